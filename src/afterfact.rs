@@ -1,10 +1,10 @@
 use std::cmp::{self, min, Ordering};
 use std::error::Error;
-use std::fs::File;
 use std::io::{self, BufWriter, Write};
 use std::path::Path;
 use std::process;
 use std::str::FromStr;
+use std::time::Duration;
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
 use chrono::{DateTime, Local, TimeZone, Utc};
@@ -59,6 +59,7 @@ pub struct AfterfactInfo {
     pub tl_endtime: Option<DateTime<Utc>>,
     pub record_cnt: u128,
     pub recover_record_cnt: u128,
+    pub suppressed_detect_cnt: u128, // --suppressで抑制した検知の数
     pub detected_record_idset: HashSet<CompactString>,
     pub total_detect_counts_by_level: Vec<u128>,
     pub unique_detect_counts_by_level: Vec<u128>,
@@ -76,6 +77,10 @@ pub struct AfterfactInfo {
     pub author_list_cache: HashMap<CompactString, Nested<String>>,
     pub prev_message: HashMap<CompactString, Profile>,
     pub prev_details_convert_map: HashMap<CompactString, Vec<CompactString>>,
+    pub detect_counts_by_rule: HashMap<CompactString, (CompactString, u128)>, // --statsで使うルールID毎の(ルールタイトル, 検知数)
+    pub detect_counts_by_eventid: HashMap<CompactString, u128>, // --statsで使うEventID毎の検知数
+    pub total_detect_cnt: u128, // 処理速度などの統計情報の出力で使う、出力した検知の総数
+    pub is_truncated_by_limit: bool, // --limitで指定した数を超えた検知結果を出力しなかったかどうか
 }
 
 struct InitLevelMapResult(
@@ -125,6 +130,7 @@ impl Default for AfterfactInfo {
             tl_endtime: Option::None,
             record_cnt: 0,
             recover_record_cnt: 0,
+            suppressed_detect_cnt: 0,
             detected_record_idset: HashSet::new(),
             total_detect_counts_by_level: vec![0; 6],
             unique_detect_counts_by_level: vec![0; 6],
@@ -142,6 +148,10 @@ impl Default for AfterfactInfo {
             author_list_cache: HashMap::new(),
             prev_message: HashMap::new(),
             prev_details_convert_map: HashMap::new(),
+            detect_counts_by_rule: HashMap::new(),
+            total_detect_cnt: 0,
+            is_truncated_by_limit: false,
+            detect_counts_by_eventid: HashMap::new(),
         }
     }
 }
@@ -151,6 +161,7 @@ pub struct AfterfactWriter {
     disp_wtr_buf: Buffer,
     csv_writer: Writer<Box<dyn io::Write>>,
     pub display_flag: bool,
+    json_array_started: bool, // --json-prettyでJSON配列の先頭の"["を出力済みかどうか
 }
 
 pub fn init_writer(stored_static: &StoredStatic) -> AfterfactWriter {
@@ -162,10 +173,20 @@ pub fn init_writer(stored_static: &StoredStatic) -> AfterfactWriter {
     let mut display_flag = false;
     let target: Box<dyn io::Write> = if let Some(path) = &stored_static.output_path {
         // output to file
-        match File::create(path) {
+        let is_append = matches!(
+            &stored_static.config.action,
+            Some(Action::JsonTimeline(option)) if option.append
+        );
+        let file = if is_append {
+            utils::append_output_file(path)
+        } else {
+            utils::create_output_file(path)
+        };
+        match file {
             Ok(file) => Box::new(BufWriter::new(file)),
             Err(err) => {
-                AlertMessage::alert(&format!("Failed to open file. {err}")).ok();
+                AlertMessage::alert(&format!("Failed to open file. {} : {err}", path.display()))
+                    .ok();
                 process::exit(1);
             }
         }
@@ -193,6 +214,7 @@ pub fn init_writer(stored_static: &StoredStatic) -> AfterfactWriter {
         disp_wtr_buf,
         csv_writer: writer,
         display_flag,
+        json_array_started: false,
     }
 }
 
@@ -220,6 +242,8 @@ pub fn emit_csv(
     afterfact_writer: &mut AfterfactWriter,
     afterfact_info: &mut AfterfactInfo,
 ) {
+    let limited_len = get_limited_len(detect_infos, duplicate_idxes, stored_static, afterfact_info);
+    let detect_infos = &detect_infos[..limited_len];
     if detect_infos.is_empty() {
         return;
     }
@@ -238,11 +262,102 @@ pub fn emit_csv(
     calc_statistic_info(detect_infos, duplicate_idxes, afterfact_info, stored_static);
 }
 
+/// --limitが指定された場合に、これまでに出力した検知結果と合わせて指定した数を超えないように、出力する検知結果の数を返す。
+/// 重複として出力しない検知結果は数に含めない
+fn get_limited_len(
+    detect_infos: &[DetectInfo],
+    duplicate_idxes: &HashSet<usize>,
+    stored_static: &StoredStatic,
+    afterfact_info: &mut AfterfactInfo,
+) -> usize {
+    let limit = match stored_static.output_option.as_ref().unwrap().limit {
+        Some(limit) => limit as u128,
+        None => return detect_infos.len(),
+    };
+    let mut output_cnt = afterfact_info.total_detect_cnt;
+    for i in 0..detect_infos.len() {
+        if duplicate_idxes.contains(&i) {
+            continue;
+        }
+        if output_cnt >= limit {
+            afterfact_info.is_truncated_by_limit = true;
+            return i;
+        }
+        output_cnt += 1;
+    }
+    detect_infos.len()
+}
+
+/// --limitで検知結果を打ち切ったことを知らせるメッセージを作成する
+fn create_truncated_msg(stored_static: &StoredStatic) -> String {
+    let limit = stored_static
+        .output_option
+        .as_ref()
+        .and_then(|option| option.limit)
+        .unwrap_or_default();
+    format!(
+        "Results were truncated to the first {} detections because of --limit.",
+        limit.to_formatted_string(&Locale::en)
+    )
+}
+
+/// --limitで指定した数の検知結果を出力済みかどうかを返す。低メモリモードでは、これ以降のイベントの解析を省略するために使う
+pub fn is_limit_reached(stored_static: &StoredStatic, afterfact_info: &AfterfactInfo) -> bool {
+    stored_static.output_option.as_ref().is_some_and(|option| {
+        option
+            .limit
+            .is_some_and(|limit| afterfact_info.total_detect_cnt >= limit as u128)
+    })
+}
+
 fn output_afterfact_err(err: Box<dyn Error>) {
     AlertMessage::alert(&format!("Failed to write CSV. {err}")).ok();
     process::exit(1);
 }
 
+/// 時刻順に並び替えた検知結果と、-Xで除外する重複した検知結果のインデックス
+pub struct SortedDetectInfos<'a> {
+    pub detect_infos: &'a [DetectInfo],
+    pub duplicate_idxes: HashSet<usize>,
+}
+
+impl<'a> SortedDetectInfos<'a> {
+    /// 検知結果をsort_detect_infoの順に並び替え、remove_duplicate_detectionsがtrueの場合は重複した検知結果を除外対象にする
+    pub fn new(
+        detect_infos: &'a mut [DetectInfo],
+        remove_duplicate_detections: bool,
+    ) -> SortedDetectInfos<'a> {
+        sort_detect_info(detect_infos);
+        let duplicate_idxes = if remove_duplicate_detections {
+            get_duplicate_idxes(detect_infos)
+        } else {
+            HashSet::new()
+        };
+        SortedDetectInfos {
+            detect_infos,
+            duplicate_idxes,
+        }
+    }
+
+    /// 重複として除外したものを除いた検知結果の数を返す
+    pub fn len(&self) -> usize {
+        self.detect_infos.len() - self.duplicate_idxes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 重複として除外したものを除いた検知結果を時刻順に返す
+    pub fn iter(&self) -> impl Iterator<Item = &DetectInfo> {
+        self.detect_infos
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.duplicate_idxes.contains(i))
+            .map(|(_, detect_info)| detect_info)
+    }
+}
+
 fn output_afterfact_inner(
     detect_infos: &mut [DetectInfo],
     afterfact_writer: &mut AfterfactWriter,
@@ -254,17 +369,26 @@ fn output_afterfact_inner(
     }
 
     // sort and filter detect infos
-    sort_detect_info(detect_infos);
-    let duplicate_idxes = if stored_static
-        .output_option
-        .as_ref()
-        .unwrap()
-        .remove_duplicate_detections
-    {
-        get_duplicate_idxes(detect_infos)
-    } else {
-        HashSet::new()
-    };
+    let sorted_detect_infos = SortedDetectInfos::new(
+        detect_infos,
+        stored_static
+            .output_option
+            .as_ref()
+            .unwrap()
+            .remove_duplicate_detections,
+    );
+    let (detect_infos, duplicate_idxes) = (
+        sorted_detect_infos.detect_infos,
+        sorted_detect_infos.duplicate_idxes,
+    );
+    // --limitはソートと重複の削除を行った後の検知結果に適用する
+    let limited_len = get_limited_len(
+        detect_infos,
+        &duplicate_idxes,
+        stored_static,
+        afterfact_info,
+    );
+    let detect_infos = &detect_infos[..limited_len];
 
     emit_csv_inner(
         detect_infos,
@@ -295,6 +419,10 @@ fn emit_csv_inner(
     afterfact_writer: &mut AfterfactWriter,
     afterfact_info: &mut AfterfactInfo,
 ) -> io::Result<()> {
+    // --statsの場合は検知結果を出力せず、集計のみを行う
+    if stored_static.output_option.as_ref().unwrap().stats {
+        return Ok(());
+    }
     let output_replaced_maps: HashMap<&str, &str> =
         HashMap::from_iter(vec![("🛂r", "\r"), ("🛂n", "\n"), ("🛂t", "\t")]);
     let mut removed_replaced_maps: HashMap<&str, &str> =
@@ -314,17 +442,21 @@ fn emit_csv_inner(
 
     // remove duplicate dataのための前レコード分の情報を保持する変数
     let color_map = create_output_color_map(stored_static.common_options.no_color);
-    let (json_output_flag, jsonl_output_flag, remove_duplicate_data) =
+    let (json_output_flag, jsonl_output_flag, json_pretty_flag, remove_duplicate_data) =
         match &stored_static.config.action.as_ref().unwrap() {
             Action::JsonTimeline(option) => (
                 true,
                 option.jsonl_timeline,
+                option.json_pretty,
                 option.output_options.remove_duplicate_data,
             ),
-            Action::CsvTimeline(option) => {
-                (false, false, option.output_options.remove_duplicate_data)
-            }
-            _ => (false, false, false),
+            Action::CsvTimeline(option) => (
+                false,
+                false,
+                false,
+                option.output_options.remove_duplicate_data,
+            ),
+            _ => (false, false, false, false),
         };
 
     let profile = stored_static.profiles.as_ref().unwrap();
@@ -407,7 +539,28 @@ fn emit_csv_inner(
             afterfact_info
                 .prev_details_convert_map
                 .clone_from(&detect_info.details_convert_map);
-            if afterfact_writer.display_flag {
+            if json_pretty_flag {
+                // --json-prettyの場合は、全ての検知結果を1つのJSON配列として出力する。
+                // 検知結果はメモリに溜めずに1件ずつ出力し、配列の末尾はfinish_json_arrayで出力する
+                let open_bracket = if afterfact_writer.json_array_started {
+                    "}, {"
+                } else {
+                    "[{"
+                };
+                afterfact_writer.json_array_started = true;
+                if afterfact_writer.display_flag {
+                    write_color_buffer(
+                        &afterfact_writer.disp_wtr,
+                        None,
+                        &format!("{open_bracket}\n{}", &result.0),
+                        true,
+                    )
+                    .ok();
+                } else {
+                    afterfact_writer.csv_writer.write_field(open_bracket)?;
+                    afterfact_writer.csv_writer.write_field(&result.0)?;
+                }
+            } else if afterfact_writer.display_flag {
                 write_color_buffer(
                     &afterfact_writer.disp_wtr,
                     None,
@@ -495,6 +648,7 @@ fn calc_statistic_info(
         afterfact_info
             .timestamps
             .push(detect_info.detected_time.timestamp());
+        afterfact_info.total_detect_cnt += 1;
         if !detect_info.is_condition {
             afterfact_info
                 .detected_record_idset
@@ -504,6 +658,19 @@ fn calc_statistic_info(
                 )));
         }
 
+        // --show-unused-rulesでも、検知しなかったルールの判定に--statsのルール毎の検知数を使う
+        if output_option.stats || output_option.show_unused_rules {
+            afterfact_info
+                .detect_counts_by_rule
+                .entry(detect_info.ruleid.to_owned())
+                .or_insert_with(|| (detect_info.ruletitle.to_owned(), 0))
+                .1 += 1;
+            *afterfact_info
+                .detect_counts_by_eventid
+                .entry(detect_info.eventid.to_owned())
+                .or_insert(0) += 1;
+        }
+
         if !output_option.no_summary {
             let level_suffix = get_level_suffix(detect_info.level.as_str());
             let author_list = afterfact_info
@@ -577,11 +744,52 @@ fn calc_statistic_info(
     }
 }
 
+/// --json-prettyの場合に、JSON配列の末尾を出力する。検知結果が1件もない場合は空の配列を出力する
+fn finish_json_array(
+    stored_static: &StoredStatic,
+    afterfact_writer: &mut AfterfactWriter,
+) -> io::Result<()> {
+    let is_json_pretty = matches!(
+        stored_static.config.action.as_ref(),
+        Some(Action::JsonTimeline(option)) if option.json_pretty
+    );
+    if !is_json_pretty || stored_static.output_option.as_ref().unwrap().stats {
+        return Ok(());
+    }
+    let close_bracket = if afterfact_writer.json_array_started {
+        "}]"
+    } else {
+        "[]"
+    };
+    if afterfact_writer.display_flag {
+        write_color_buffer(&afterfact_writer.disp_wtr, None, close_bracket, true).ok();
+    } else {
+        afterfact_writer.csv_writer.write_field(close_bracket)?;
+        afterfact_writer.csv_writer.flush()?;
+    }
+    Ok(())
+}
+
 pub fn output_additional_afterfact(
     stored_static: &StoredStatic,
     afterfact_writer: &mut AfterfactWriter,
     afterfact_info: &AfterfactInfo,
 ) {
+    if let Err(err) = finish_json_array(stored_static, afterfact_writer) {
+        output_afterfact_err(Box::new(err));
+    }
+    if afterfact_info.is_truncated_by_limit {
+        write_color_buffer(
+            &afterfact_writer.disp_wtr,
+            get_writable_color(
+                Some(Color::Rgb(255, 255, 0)),
+                stored_static.common_options.no_color,
+            ),
+            &create_truncated_msg(stored_static),
+            true,
+        )
+        .ok();
+    }
     if afterfact_writer.display_flag {
         println!();
     }
@@ -605,6 +813,10 @@ pub fn output_additional_afterfact(
         .iter(),
     );
     let output_option = stored_static.output_option.as_ref().unwrap();
+    if output_option.stats {
+        output_rule_hit_stats(stored_static, afterfact_writer, afterfact_info);
+        return;
+    }
     if !output_option.no_summary && !afterfact_info.rule_author_counter.is_empty() {
         write_color_buffer(
             &afterfact_writer.disp_wtr,
@@ -823,6 +1035,37 @@ pub fn output_additional_afterfact(
             )
             .ok();
         }
+        if stored_static.suppression_list.is_some() {
+            write_color_buffer(
+                &afterfact_writer.disp_wtr,
+                get_writable_color(
+                    Some(Color::Rgb(0, 255, 255)),
+                    stored_static.common_options.no_color,
+                ),
+                "Suppressed detections",
+                false,
+            )
+            .ok();
+            write_color_buffer(
+                &afterfact_writer.disp_wtr,
+                get_writable_color(None, stored_static.common_options.no_color),
+                ": ",
+                false,
+            )
+            .ok();
+            write_color_buffer(
+                &afterfact_writer.disp_wtr,
+                get_writable_color(
+                    Some(Color::Rgb(0, 255, 255)),
+                    stored_static.common_options.no_color,
+                ),
+                &afterfact_info
+                    .suppressed_detect_cnt
+                    .to_formatted_string(&Locale::en),
+                true,
+            )
+            .ok();
+        }
         println!();
 
         if stored_static.html_report_flag {
@@ -835,6 +1078,17 @@ pub fn output_additional_afterfact(
                     .recover_record_cnt
                     .to_formatted_string(&Locale::en)
             ));
+            if stored_static.suppression_list.is_some() {
+                html_output_stock.push(format!(
+                    "- Suppressed detections: {}",
+                    &afterfact_info
+                        .suppressed_detect_cnt
+                        .to_formatted_string(&Locale::en)
+                ));
+            }
+            if afterfact_info.is_truncated_by_limit {
+                html_output_stock.push(format!("- {}", create_truncated_msg(stored_static)));
+            }
         }
 
         let color_map = create_output_color_map(stored_static.common_options.no_color);
@@ -900,8 +1154,158 @@ pub fn output_additional_afterfact(
     }
 }
 
+/// --statsで表示するルール毎とEventID毎の検知数を、検知数の多い順に並べて返す
+fn get_rule_hit_stats(
+    afterfact_info: &AfterfactInfo,
+) -> (
+    Vec<(&CompactString, &CompactString, u128)>,
+    Vec<(&CompactString, u128)>,
+) {
+    let rule_stats = afterfact_info
+        .detect_counts_by_rule
+        .iter()
+        .map(|(id, (title, cnt))| (id, title, *cnt))
+        .sorted_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.cmp(b.1)))
+        .collect();
+    let eventid_stats = afterfact_info
+        .detect_counts_by_eventid
+        .iter()
+        .map(|(eventid, cnt)| (eventid, *cnt))
+        .sorted_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)))
+        .collect();
+    (rule_stats, eventid_stats)
+}
+
+/// --statsで指定された場合に、検知結果の代わりにルール毎とEventID毎の検知数を出力する
+fn output_rule_hit_stats(
+    stored_static: &StoredStatic,
+    afterfact_writer: &AfterfactWriter,
+    afterfact_info: &AfterfactInfo,
+) {
+    let (rule_stats, eventid_stats) = get_rule_hit_stats(afterfact_info);
+    let total_detections: u128 = rule_stats.iter().map(|(_, _, cnt)| cnt).sum();
+    let percent = |cnt: u128| {
+        if total_detections == 0 {
+            0 as f64
+        } else {
+            (cnt as f64) / (total_detections as f64) * 100.0
+        }
+    };
+
+    write_color_buffer(
+        &afterfact_writer.disp_wtr,
+        get_writable_color(
+            Some(Color::Rgb(0, 255, 0)),
+            stored_static.common_options.no_color,
+        ),
+        "Rule Hit Statistics:\n",
+        true,
+    )
+    .ok();
+    println!(
+        "Total events: {}",
+        afterfact_info.record_cnt.to_formatted_string(&Locale::en)
+    );
+    println!(
+        "Total detections: {}",
+        total_detections.to_formatted_string(&Locale::en)
+    );
+    println!();
+
+    write_color_buffer(
+        &afterfact_writer.disp_wtr,
+        get_writable_color(
+            Some(Color::Rgb(255, 255, 0)),
+            stored_static.common_options.no_color,
+        ),
+        "Detections by rule:",
+        true,
+    )
+    .ok();
+    for (id, title, cnt) in rule_stats.iter() {
+        println!(
+            "{} ({:.2}%) | {} ({})",
+            cnt.to_formatted_string(&Locale::en),
+            percent(*cnt),
+            title,
+            id
+        );
+    }
+    println!();
+
+    write_color_buffer(
+        &afterfact_writer.disp_wtr,
+        get_writable_color(
+            Some(Color::Rgb(255, 255, 0)),
+            stored_static.common_options.no_color,
+        ),
+        "Detections by EventID:",
+        true,
+    )
+    .ok();
+    for (eventid, cnt) in eventid_stats.iter() {
+        println!(
+            "{} ({:.2}%) | {}",
+            cnt.to_formatted_string(&Locale::en),
+            percent(*cnt),
+            eventid
+        );
+    }
+    println!();
+}
+
+/// 読み込まれたルールの一覧(id, title, level, status, ルールファイルのパス)から、1件も検知しなかったルールを返す
+fn get_unused_rules<'a>(
+    rule_list: &'a [[String; 5]],
+    afterfact_info: &AfterfactInfo,
+) -> Vec<&'a [String; 5]> {
+    rule_list
+        .iter()
+        .filter(|rule| {
+            !afterfact_info
+                .detect_counts_by_rule
+                .contains_key(rule[0].as_str())
+        })
+        .collect()
+}
+
+/// --show-unused-rulesが指定された場合に、スキャン後に1件も検知しなかったルールを出力する
+pub fn output_unused_rules(
+    rule_list: &[[String; 5]],
+    stored_static: &StoredStatic,
+    afterfact_writer: &AfterfactWriter,
+    afterfact_info: &AfterfactInfo,
+) {
+    let unused_rules = get_unused_rules(rule_list, afterfact_info);
+    write_color_buffer(
+        &afterfact_writer.disp_wtr,
+        get_writable_color(
+            Some(Color::Rgb(0, 255, 0)),
+            stored_static.common_options.no_color,
+        ),
+        "Unused Rules:\n",
+        true,
+    )
+    .ok();
+    println!(
+        "Loaded rules: {}",
+        rule_list.len().to_formatted_string(&Locale::en)
+    );
+    println!(
+        "Rules with no detections: {}",
+        unused_rules.len().to_formatted_string(&Locale::en)
+    );
+    println!();
+    for [id, title, level, _, rulepath] in unused_rules {
+        println!("{title} ({id}) | {level} | {rulepath}");
+    }
+    println!();
+}
+
+/// 検知結果を時刻順に並び替える。同一時刻の場合はlevel(低い順)、EventID、ルールファイルのパス、コンピュータ名の順で比較する。
+/// 全て同じ場合は安定ソートにより検知した順番を維持するので、実行ごとに出力順が変わることはない
 pub fn sort_detect_info(detect_infos: &mut [DetectInfo]) {
-    detect_infos.sort_unstable_by(|a, b| {
+    detect_infos.sort_by(|a, b| {
         let cmp_time = a.detected_time.cmp(&b.detected_time);
         if cmp_time != Ordering::Equal {
             return cmp_time;
@@ -945,18 +1349,14 @@ pub fn get_duplicate_idxes(detect_infos: &mut [DetectInfo]) -> HashSet<usize> {
     let mut filtered_detect_infos = HashSet::new();
     let mut prev_detect_infos = HashSet::new();
     for (i, detect_info) in detect_infos.iter().enumerate() {
-        if i == 0 {
-            continue;
-        }
-
-        let prev_detect_info = &detect_infos[i - 1];
-        if prev_detect_info
-            .detected_time
-            .cmp(&detect_info.detected_time)
-            != Ordering::Equal
+        // 時刻が変わった場合はそれまでの比較対象をクリアする。同一時刻の先頭の検知結果も比較対象に含める
+        if i > 0
+            && detect_infos[i - 1]
+                .detected_time
+                .cmp(&detect_info.detected_time)
+                != Ordering::Equal
         {
             prev_detect_infos.clear();
-            continue;
         }
 
         let fields: Vec<&(CompactString, Profile)> = detect_info
@@ -974,6 +1374,27 @@ pub fn get_duplicate_idxes(detect_infos: &mut [DetectInfo]) -> HashSet<usize> {
     filtered_detect_infos
 }
 
+/// スキャンしたイベント数と処理時間から、1秒あたりの処理イベント数を含む統計情報のメッセージを作成する関数
+pub fn create_processing_stats_msg(
+    record_cnt: u128,
+    elapsed: Duration,
+    detect_cnt: u128,
+) -> String {
+    let elapsed_secs = elapsed.as_secs_f64();
+    let events_per_sec = if elapsed_secs > 0.0 {
+        (record_cnt as f64 / elapsed_secs) as u128
+    } else {
+        record_cnt
+    };
+    format!(
+        "Processed {} events in {:.1}s ({} events/s), {} detections",
+        record_cnt.to_formatted_string(&Locale::en),
+        elapsed_secs,
+        events_per_sec.to_formatted_string(&Locale::en),
+        detect_cnt.to_formatted_string(&Locale::en)
+    )
+}
+
 /// level_color.txtファイルを読み込み対応する文字色のマッピングを返却する関数
 pub fn create_output_color_map(no_color_flag: bool) -> HashMap<CompactString, Colors> {
     let read_result = utils::read_csv(
@@ -1634,6 +2055,7 @@ fn _get_json_vec(profile: &Profile, target_data: &String) -> Vec<String> {
         Profile::MitreTactics(_) | Profile::MitreTags(_) | Profile::OtherTags(_) => {
             target_data.split(": ").map(|x| x.to_string()).collect()
         }
+        Profile::MatchedFields(_) => target_data.split(" ¦ ").map(|x| x.to_string()).collect(),
         Profile::Details(_) | Profile::AllFieldInfo(_) | Profile::ExtraFieldInfo(_) => {
             let ret: Vec<String> = target_data.split(" ¦ ").map(|x| x.to_string()).collect();
             if target_data == &ret[0] && !utils::contains_str(target_data, ": ") {
@@ -1869,6 +2291,30 @@ pub fn output_json_str(
                         4,
                     ));
                 }
+                Profile::MatchedFields(_) => {
+                    // 一致したフィールドは「selection名.キー: 値」の文字列の配列として出力する
+                    let fmted_val = if val == "-" {
+                        "\"-\"".to_string()
+                    } else {
+                        format!(
+                            "[{}]",
+                            vec_data
+                                .iter()
+                                .map(|x| format!(
+                                    "\"{}\"",
+                                    _convert_valid_json_str(&[x.as_str()], false)
+                                ))
+                                .join(", ")
+                        )
+                    };
+                    target.push(_create_json_output_format(
+                        key,
+                        &fmted_val,
+                        key.starts_with('\"'),
+                        true,
+                        4,
+                    ));
+                }
                 Profile::Details(_) | Profile::AllFieldInfo(_) | Profile::ExtraFieldInfo(_) => {
                     let mut output_stock: Vec<String> = vec![];
                     let details_key = match profile {
@@ -2204,14 +2650,19 @@ fn _output_html_computer_by_mitre_attck(html_output_stock: &mut Nested<String>)
 mod tests {
     use std::fs::{read_to_string, remove_file};
     use std::path::Path;
+    use std::time::Duration;
 
     use chrono::NaiveDateTime;
     use chrono::{Local, TimeZone, Utc};
+    use clap::Parser;
     use compact_str::CompactString;
-    use hashbrown::HashMap;
+    use hashbrown::{HashMap, HashSet};
     use serde_json::Value;
 
+    use crate::afterfact::create_processing_stats_msg;
+    use crate::afterfact::emit_csv;
     use crate::afterfact::format_time;
+    use crate::afterfact::get_rule_hit_stats;
     use crate::afterfact::init_writer;
     use crate::afterfact::output_afterfact_inner;
     use crate::afterfact::AfterfactInfo;
@@ -2233,7 +2684,9 @@ mod tests {
     use crate::detections::utils;
     use crate::options::profile::{load_profile, Profile};
 
-    use super::create_output_color_map;
+    use super::{
+        create_output_color_map, get_duplicate_idxes, sort_detect_info, SortedDetectInfos,
+    };
 
     #[test]
     fn test_emit_csv_output() {
@@ -2266,6 +2719,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2286,7 +2740,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -2299,10 +2753,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -2311,6 +2768,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2359,6 +2832,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2379,7 +2853,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -2392,10 +2866,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -2404,6 +2881,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2606,6 +3099,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2626,7 +3120,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -2639,10 +3133,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -2651,6 +3148,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2709,6 +3222,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2729,7 +3243,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -2742,10 +3256,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -2754,6 +3271,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2940,6 +3473,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2960,7 +3494,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -2973,10 +3507,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -2985,6 +3522,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -3033,6 +3586,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -3053,7 +3607,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -3066,10 +3620,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -3078,6 +3635,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -3275,6 +3848,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -3295,7 +3869,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -3308,10 +3882,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -3320,6 +3897,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -3335,6 +3928,8 @@ mod tests {
             geo_ip: None,
             output: Some(Path::new("./test_emit_csv_remove_duplicate.json").to_path_buf()),
             jsonl_timeline: false,
+            json_pretty: false,
+            append: false,
         });
         let dummy_config = Some(Config {
             action: Some(dummy_action),
@@ -3368,6 +3963,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -3388,7 +3984,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -3401,10 +3997,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -3413,6 +4012,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -3683,6 +4298,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -3703,7 +4319,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -3716,10 +4332,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -3728,6 +4347,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -3743,6 +4378,8 @@ mod tests {
             geo_ip: None,
             output: Some(Path::new("./test_multiple_data_in_details.json").to_path_buf()),
             jsonl_timeline: false,
+            json_pretty: false,
+            append: false,
         });
         let dummy_config = Some(Config {
             action: Some(dummy_action),
@@ -3777,6 +4414,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -3797,7 +4435,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -3810,10 +4448,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -3822,6 +4463,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -4008,43 +4665,427 @@ mod tests {
     }
 
     #[test]
-    fn test_emit_csv_json_output() {
-        let mut additional_afterfact = AfterfactInfo::default();
-        let mut detect_infos = vec![];
-        let mock_ch_filter = message::create_output_filter_config(
-            "test_files/config/channel_abbreviations.txt",
-            true,
+    /// 検知結果が時刻順に並び、同一時刻の重複した検知結果が先頭のもの以外除外対象となることを確認する
+    fn test_sort_and_get_duplicate_idxes() {
+        let create_detect_info = |time: &str, level: &str, rulepath: &str, evtx: &str| {
+            let detected_time = Utc
+                .from_local_datetime(
+                    &NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%SZ").unwrap(),
+                )
+                .unwrap();
+            DetectInfo {
+                detected_time,
+                rulepath: rulepath.into(),
+                level: level.into(),
+                eventid: "4624".into(),
+                ext_field: vec![
+                    (
+                        "Timestamp".into(),
+                        Profile::Timestamp(CompactString::from(time)),
+                    ),
+                    ("RuleTitle".into(), Profile::RuleTitle(rulepath.into())),
+                    ("EvtxFile".into(), Profile::EvtxFile(evtx.into())),
+                ],
+                ..Default::default()
+            }
+        };
+        let mut detect_infos = vec![
+            create_detect_info("2021-12-23T00:00:01Z", "high", "b.yml", "1.evtx"),
+            create_detect_info("2021-12-23T00:00:00Z", "high", "a.yml", "1.evtx"),
+            create_detect_info("2021-12-23T00:00:01Z", "crit", "c.yml", "1.evtx"),
+            // 別のevtxファイルに含まれる同じイベントは重複として扱う
+            create_detect_info("2021-12-23T00:00:01Z", "high", "b.yml", "2.evtx"),
+            create_detect_info("2021-12-23T00:00:02Z", "high", "b.yml", "1.evtx"),
+        ];
+        sort_detect_info(&mut detect_infos);
+        let actual: Vec<(&str, &str)> = detect_infos
+            .iter()
+            .map(|d| (d.rulepath.as_str(), d.level.as_str()))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                ("a.yml", "high"),
+                ("b.yml", "high"),
+                ("b.yml", "high"),
+                ("c.yml", "crit"),
+                ("b.yml", "high"),
+            ]
         );
-        let test_filepath: &str = "test.evtx";
-        let test_rulepath: &str = "test-rule.yml";
-        let test_rule_id: &str = "00000000-0000-0000-0000-000000000000";
-        let test_title = "test_title";
-        let test_level = "high";
-        let test_computername = "testcomputer";
-        let test_computername2 = "testcomputer";
-        let test_eventid = "1111";
-        let output = "pokepoke";
-        let test_attack = "execution/txxxx.yyy";
-        let test_recinfo = "CommandRLine: hoge";
-        let test_record_id = "11111";
-        let expect_naivetime =
-            NaiveDateTime::parse_from_str("1996-02-27T01:05:01Z", "%Y-%m-%dT%H:%M:%SZ").unwrap();
-        let expect_time = Utc.from_local_datetime(&expect_naivetime).unwrap();
-        let expect_tz = expect_time.with_timezone(&Utc);
-        let json_dummy_action = Action::JsonTimeline(JSONOutputOption {
-            output_options: OutputOption {
-                input_args: InputOption {
-                    directory: None,
-                    filepath: None,
-                    live_analysis: false,
-                    recover_records: false,
-                    timeline_offset: None,
-                },
-                profile: None,
-                enable_deprecated_rules: false,
-                exclude_status: None,
-                min_level: "informational".to_string(),
-                exact_level: None,
+
+        let duplicate_idxes = get_duplicate_idxes(&mut detect_infos);
+        assert_eq!(duplicate_idxes, HashSet::from([2]));
+    }
+
+    #[test]
+    /// SortedDetectInfosで、重複を除いた検知結果の数と時刻順の検知結果が取得でき、同一の検知結果は入力順が維持されることを確認する
+    fn test_sorted_detect_infos() {
+        let create_detect_info = |time: &str, rulepath: &str, evtx: &str| {
+            let detected_time = Utc
+                .from_local_datetime(
+                    &NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%SZ").unwrap(),
+                )
+                .unwrap();
+            DetectInfo {
+                detected_time,
+                rulepath: rulepath.into(),
+                level: "high".into(),
+                eventid: "4624".into(),
+                ext_field: vec![
+                    (
+                        "Timestamp".into(),
+                        Profile::Timestamp(CompactString::from(time)),
+                    ),
+                    ("RuleTitle".into(), Profile::RuleTitle(rulepath.into())),
+                    ("EvtxFile".into(), Profile::EvtxFile(evtx.into())),
+                ],
+                ..Default::default()
+            }
+        };
+        let evtx_files = |sorted: &SortedDetectInfos| {
+            sorted
+                .detect_infos
+                .iter()
+                .map(|detect_info| detect_info.ext_field[2].1.to_value())
+                .collect::<Vec<String>>()
+        };
+        let mut detect_infos = vec![
+            create_detect_info("2021-12-23T00:00:01Z", "b.yml", "3.evtx"),
+            create_detect_info("2021-12-23T00:00:01Z", "b.yml", "1.evtx"),
+            create_detect_info("2021-12-23T00:00:00Z", "a.yml", "1.evtx"),
+            create_detect_info("2021-12-23T00:00:01Z", "b.yml", "2.evtx"),
+        ];
+        let sorted = SortedDetectInfos::new(&mut detect_infos, false);
+        assert_eq!(sorted.len(), 4);
+        assert_eq!(
+            evtx_files(&sorted),
+            vec!["1.evtx", "3.evtx", "1.evtx", "2.evtx"]
+        );
+
+        let sorted = SortedDetectInfos::new(&mut detect_infos, true);
+        assert_eq!(sorted.len(), 2);
+        assert!(!sorted.is_empty());
+        let actual: Vec<(&str, String)> = sorted
+            .iter()
+            .map(|detect_info| {
+                (
+                    detect_info.rulepath.as_str(),
+                    detect_info.ext_field[2].1.to_value(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                ("a.yml", "1.evtx".to_string()),
+                ("b.yml", "3.evtx".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    /// Results Summaryのレベル別、ルール別の検知数が出力した検知結果から集計されることを確認する
+    fn test_emit_csv_summary_counts() {
+        let create_detect_info =
+            |level: &str, rulepath: &str, ruletitle: &str, computer: &str| DetectInfo {
+                detected_time: Utc.with_ymd_and_hms(2021, 12, 23, 0, 0, 0).unwrap(),
+                rulepath: rulepath.into(),
+                ruletitle: ruletitle.into(),
+                ruleid: rulepath.into(),
+                level: level.into(),
+                computername: computer.into(),
+                eventid: "4624".into(),
+                ext_field: vec![("RuleTitle".into(), Profile::RuleTitle(ruletitle.into()))],
+                ..Default::default()
+            };
+        let detect_infos = vec![
+            create_detect_info(
+                "crit",
+                "test_files/rules/level_yaml/critical.yml",
+                "Critical Rule",
+                "PC1",
+            ),
+            create_detect_info(
+                "crit",
+                "test_files/rules/level_yaml/critical.yml",
+                "Critical Rule",
+                "PC2",
+            ),
+            create_detect_info(
+                "high",
+                "test_files/rules/level_yaml/high.yml",
+                "High Rule",
+                "PC1",
+            ),
+        ];
+        let get_afterfact_info = |output_path: &str, options: &[&str]| {
+            let mut args = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w", "-o"];
+            args.push(output_path);
+            args.extend(options);
+            let stored_static =
+                StoredStatic::create_static_data(Some(Config::try_parse_from(args).unwrap()));
+            let mut afterfact_info = AfterfactInfo::default();
+            {
+                let mut writer = init_writer(&stored_static);
+                emit_csv(
+                    &detect_infos,
+                    &HashSet::new(),
+                    &stored_static,
+                    &mut writer,
+                    &mut afterfact_info,
+                );
+            }
+            assert!(remove_file(output_path).is_ok());
+            afterfact_info
+        };
+
+        let afterfact_info = get_afterfact_info("./test_emit_csv_summary_counts.csv", &[]);
+        assert_eq!(
+            afterfact_info.total_detect_counts_by_level,
+            vec![0, 0, 0, 0, 1, 2]
+        );
+        assert_eq!(
+            afterfact_info.unique_detect_counts_by_level,
+            vec![0, 0, 0, 0, 1, 1]
+        );
+        assert_eq!(
+            afterfact_info.detect_counts_by_rule_and_level["crit"]["Critical Rule"],
+            2
+        );
+        assert_eq!(
+            afterfact_info.detect_counts_by_rule_and_level["high"]["High Rule"],
+            1
+        );
+        assert_eq!(
+            afterfact_info.detect_counts_by_computer_and_level["crit"].len(),
+            2
+        );
+
+        // --no-summaryが指定された場合は集計しない
+        let afterfact_info =
+            get_afterfact_info("./test_emit_csv_summary_counts_no_summary.csv", &["-N"]);
+        assert_eq!(afterfact_info.total_detect_counts_by_level, vec![0; 6]);
+        assert!(afterfact_info.detect_counts_by_rule_and_level["crit"].is_empty());
+    }
+
+    #[test]
+    fn test_emit_csv_stats() {
+        // --statsの場合は検知結果を出力せず、ルール毎とEventID毎の検知数のみを集計することを確認
+        let create_detect_info = |ruleid: &str, ruletitle: &str, eventid: &str| DetectInfo {
+            detected_time: Utc.with_ymd_and_hms(2021, 12, 23, 0, 0, 0).unwrap(),
+            rulepath: "test_files/rules/level_yaml/high.yml".into(),
+            ruletitle: ruletitle.into(),
+            ruleid: ruleid.into(),
+            level: "high".into(),
+            computername: "PC1".into(),
+            eventid: eventid.into(),
+            ext_field: vec![("RuleTitle".into(), Profile::RuleTitle(ruletitle.into()))],
+            ..Default::default()
+        };
+        let detect_infos = vec![
+            create_detect_info("rule-a", "Rule A", "4624"),
+            create_detect_info("rule-b", "Rule B", "4624"),
+            create_detect_info("rule-a", "Rule A", "4688"),
+            create_detect_info("rule-a", "Rule A", "4624"),
+        ];
+        let output_path = "./test_emit_csv_stats.csv";
+        let stored_static = StoredStatic::create_static_data(Some(
+            Config::try_parse_from([
+                "hayabusa",
+                "csv-timeline",
+                "-f",
+                "test.evtx",
+                "-w",
+                "-o",
+                output_path,
+                "--stats",
+            ])
+            .unwrap(),
+        ));
+        let mut afterfact_info = AfterfactInfo::default();
+        {
+            let mut writer = init_writer(&stored_static);
+            emit_csv(
+                &detect_infos,
+                &HashSet::new(),
+                &stored_static,
+                &mut writer,
+                &mut afterfact_info,
+            );
+        }
+        // 検知結果は出力されない
+        assert_eq!(read_to_string(output_path).unwrap(), "");
+        assert!(remove_file(output_path).is_ok());
+
+        let (rule_stats, eventid_stats) = get_rule_hit_stats(&afterfact_info);
+        assert_eq!(
+            rule_stats,
+            vec![
+                (
+                    &CompactString::from("rule-a"),
+                    &CompactString::from("Rule A"),
+                    3
+                ),
+                (
+                    &CompactString::from("rule-b"),
+                    &CompactString::from("Rule B"),
+                    1
+                )
+            ]
+        );
+        assert_eq!(
+            eventid_stats,
+            vec![
+                (&CompactString::from("4624"), 3),
+                (&CompactString::from("4688"), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_unused_rules() {
+        // --show-unused-rulesの場合は--statsを指定しなくてもルール毎の検知数を集計し、検知しなかったルールを判定できることを確認
+        let detect_info = DetectInfo {
+            detected_time: Utc.with_ymd_and_hms(2021, 12, 23, 0, 0, 0).unwrap(),
+            rulepath: "rules/a.yml".into(),
+            ruletitle: "Rule A".into(),
+            ruleid: "rule-a".into(),
+            level: "high".into(),
+            computername: "PC1".into(),
+            eventid: "4624".into(),
+            ext_field: vec![("RuleTitle".into(), Profile::RuleTitle("Rule A".into()))],
+            ..Default::default()
+        };
+        let output_path = "./test_get_unused_rules.csv";
+        let stored_static = StoredStatic::create_static_data(Some(
+            Config::try_parse_from([
+                "hayabusa",
+                "csv-timeline",
+                "-f",
+                "test.evtx",
+                "-w",
+                "-o",
+                output_path,
+                "--show-unused-rules",
+            ])
+            .unwrap(),
+        ));
+        let mut afterfact_info = AfterfactInfo::default();
+        {
+            let mut writer = init_writer(&stored_static);
+            emit_csv(
+                &[detect_info],
+                &HashSet::new(),
+                &stored_static,
+                &mut writer,
+                &mut afterfact_info,
+            );
+        }
+        assert!(remove_file(output_path).is_ok());
+
+        let create_rule_row = |id: &str, title: &str, path: &str| {
+            [
+                id.to_string(),
+                title.to_string(),
+                "high".to_string(),
+                "test".to_string(),
+                path.to_string(),
+            ]
+        };
+        let rule_list = vec![
+            create_rule_row("rule-a", "Rule A", "rules/a.yml"),
+            create_rule_row("rule-b", "Rule B", "rules/b.yml"),
+        ];
+        assert_eq!(
+            get_unused_rules(&rule_list, &afterfact_info),
+            vec![&create_rule_row("rule-b", "Rule B", "rules/b.yml")]
+        );
+    }
+
+    #[test]
+    /// csv-timelineの出力でカンマ、ダブルクォート、改行を含む値が正しくエスケープされることを確認する
+    fn test_csv_writer_escape() {
+        let output_path = "./test_csv_writer_escape.csv";
+        let config = Config::try_parse_from([
+            "hayabusa",
+            "csv-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "-o",
+            output_path,
+        ])
+        .unwrap();
+        let stored_static = StoredStatic::create_static_data(Some(config));
+        {
+            let mut writer = init_writer(&stored_static);
+            assert!(!writer.display_flag);
+            writer
+                .csv_writer
+                .write_record(["Timestamp", "EventID", "RecordInformation"])
+                .unwrap();
+            writer
+                .csv_writer
+                .write_record([
+                    "2021-12-23 00:00:00.000 +00:00",
+                    "4688",
+                    "Cmd: cmd.exe /c \"echo a,b\"\nUser: test",
+                ])
+                .unwrap();
+            writer.csv_writer.flush().unwrap();
+        }
+        let expect = "\"Timestamp\",\"EventID\",\"RecordInformation\"\n\"2021-12-23 00:00:00.000 +00:00\",4688,\"Cmd: cmd.exe /c \"\"echo a,b\"\"\nUser: test\"\n";
+        match read_to_string(output_path) {
+            Err(_) => panic!("Failed to open file."),
+            Ok(s) => {
+                assert_eq!(s, expect);
+                let mut rdr = csv::Reader::from_reader(s.as_bytes());
+                let record = rdr.records().next().unwrap().unwrap();
+                assert_eq!(&record[2], "Cmd: cmd.exe /c \"echo a,b\"\nUser: test");
+            }
+        };
+        assert!(remove_file(output_path).is_ok());
+    }
+
+    #[test]
+    fn test_emit_csv_json_output() {
+        let mut additional_afterfact = AfterfactInfo::default();
+        let mut detect_infos = vec![];
+        let mock_ch_filter = message::create_output_filter_config(
+            "test_files/config/channel_abbreviations.txt",
+            true,
+        );
+        let test_filepath: &str = "test.evtx";
+        let test_rulepath: &str = "test-rule.yml";
+        let test_rule_id: &str = "00000000-0000-0000-0000-000000000000";
+        let test_title = "test_title";
+        let test_level = "high";
+        let test_computername = "testcomputer";
+        let test_computername2 = "testcomputer";
+        let test_eventid = "1111";
+        let output = "pokepoke";
+        let test_attack = "execution/txxxx.yyy";
+        let test_recinfo = "CommandRLine: hoge";
+        let test_record_id = "11111";
+        let expect_naivetime =
+            NaiveDateTime::parse_from_str("1996-02-27T01:05:01Z", "%Y-%m-%dT%H:%M:%SZ").unwrap();
+        let expect_time = Utc.from_local_datetime(&expect_naivetime).unwrap();
+        let expect_tz = expect_time.with_timezone(&Utc);
+        let json_dummy_action = Action::JsonTimeline(JSONOutputOption {
+            output_options: OutputOption {
+                input_args: InputOption {
+                    directory: None,
+                    filepath: None,
+                    live_analysis: false,
+                    live_channel: None,
+                    recover_records: false,
+                    timeline_offset: None,
+                },
+                profile: None,
+                enable_deprecated_rules: false,
+                exclude_status: None,
+                min_level: "informational".to_string(),
+                exact_level: None,
                 enable_noisy_rules: false,
                 end_timeline: None,
                 start_timeline: None,
@@ -4057,7 +5098,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -4070,10 +5111,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -4082,6 +5126,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -4097,6 +5157,8 @@ mod tests {
             geo_ip: None,
             output: Some(Path::new("./test_emit_csv_json.json").to_path_buf()),
             jsonl_timeline: false,
+            json_pretty: false,
+            append: false,
         });
 
         let dummy_config = Some(Config {
@@ -4131,6 +5193,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -4151,7 +5214,7 @@ mod tests {
                 us_time: false,
                 utc: true,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -4164,10 +5227,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -4176,6 +5242,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -4316,6 +5398,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -4336,7 +5419,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -4349,10 +5432,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -4361,6 +5447,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -4376,6 +5478,8 @@ mod tests {
             geo_ip: None,
             output: Some(Path::new("./test_emit_csv_jsonl.jsonl").to_path_buf()),
             jsonl_timeline: true,
+            json_pretty: false,
+            append: false,
         });
 
         let dummy_config = Some(Config {
@@ -4410,6 +5514,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -4430,7 +5535,7 @@ mod tests {
                 us_time: false,
                 utc: true,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -4443,10 +5548,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -4455,6 +5563,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -4561,8 +5685,336 @@ mod tests {
             Err(_) => panic!("Failed to open file."),
             Ok(s) => {
                 assert_eq!(s, format!("{} }}", expect.join("")));
+                // JSONLとして1行ごとに単独のJSONオブジェクトとしてパースできること
+                assert_eq!(s.lines().count(), 1);
+                let parsed: Value = serde_json::from_str(&s).unwrap();
+                assert_eq!(parsed["RuleTitle"], "test_title");
+                assert_eq!(parsed["EventID"], 1111);
             }
         };
         assert!(remove_file("./test_emit_csv_jsonl.jsonl").is_ok());
     }
+
+    #[test]
+    fn test_emit_csv_json_pretty_output() {
+        let mut additional_afterfact = AfterfactInfo::default();
+        let mut detect_infos = vec![];
+        let mock_ch_filter = message::create_output_filter_config(
+            "test_files/config/channel_abbreviations.txt",
+            true,
+        );
+        let test_filepath: &str = "test.evtx";
+        let test_rulepath: &str = "test-rule.yml";
+        let test_rule_id: &str = "00000000-0000-0000-0000-000000000000";
+        let test_title = "test_title";
+        let test_level = "high";
+        let test_computername = "testcomputer";
+        let test_computername2 = "testcomputer";
+        let test_eventid = "1111";
+        let output = "pokepoke";
+        let test_attack = "execution/txxxx.yyy";
+        let test_recinfo = "CommandRLine: hoge";
+        let test_record_id = "11111";
+        let expect_naivetime =
+            NaiveDateTime::parse_from_str("1996-02-27T01:05:01Z", "%Y-%m-%dT%H:%M:%SZ").unwrap();
+        let expect_time = Utc.from_local_datetime(&expect_naivetime).unwrap();
+        let expect_tz = expect_time.with_timezone(&Utc);
+        let json_dummy_action = Action::JsonTimeline(JSONOutputOption {
+            output_options: OutputOption {
+                input_args: InputOption {
+                    directory: None,
+                    filepath: None,
+                    live_analysis: false,
+                    live_channel: None,
+                    recover_records: false,
+                    timeline_offset: None,
+                },
+                profile: None,
+                enable_deprecated_rules: false,
+                exclude_status: None,
+                min_level: "informational".to_string(),
+                exact_level: None,
+                enable_noisy_rules: false,
+                end_timeline: None,
+                start_timeline: None,
+                eid_filter: false,
+                european_time: false,
+                iso_8601: false,
+                rfc_2822: false,
+                rfc_3339: false,
+                us_military_time: false,
+                us_time: false,
+                utc: false,
+                visualize_timeline: false,
+                rules: vec![Path::new("./rules").to_path_buf()],
+                html_report: None,
+                no_summary: true,
+                common_options: CommonOptions {
+                    no_color: false,
+                    quiet: false,
+                    help: None,
+                },
+                detect_common_options: DetectCommonOption {
+                    evtx_file_ext: None,
+                    thread_number: None,
+                    quiet_errors: false,
+                    config: Path::new("./rules/config").to_path_buf(),
+                    verbose: 0,
+                    no_progress: false,
+                    json_input: false,
+                    include_computer: None,
+                    exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
+                },
+                enable_unsupported_rules: false,
+                clobber: false,
+                proven_rules: false,
+                include_tag: None,
+                exclude_tag: None,
+                include_category: None,
+                exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
+                include_eid: None,
+                exclude_eid: None,
+                no_field: false,
+                no_pwsh_field_extraction: false,
+                remove_duplicate_data: false,
+                remove_duplicate_detections: false,
+                no_wizard: true,
+                include_status: None,
+                sort_events: false,
+                enable_all_rules: false,
+                scan_all_evtx_files: false,
+            },
+            geo_ip: None,
+            output: Some(Path::new("./test_emit_csv_json_pretty.json").to_path_buf()),
+            jsonl_timeline: false,
+            json_pretty: true,
+            append: false,
+        });
+
+        let dummy_config = Some(Config {
+            action: Some(json_dummy_action),
+            debug: false,
+        });
+        let stored_static = StoredStatic::create_static_data(dummy_config);
+        let output_profile: Vec<(CompactString, Profile)> = load_profile(
+            "test_files/config/default_profile.yaml",
+            "test_files/config/profiles.yaml",
+            Some(&stored_static),
+        )
+        .unwrap_or_default();
+        {
+            let val = r#"
+                {
+                    "Event": {
+                        "EventData": {
+                            "CommandRLine": "hoge"
+                        },
+                        "System": {
+                            "TimeCreated_attributes": {
+                                "SystemTime": "1996-02-27T01:05:01Z"
+                            }
+                        }
+                    }
+                }
+            "#;
+            let event: Value = serde_json::from_str(val).unwrap();
+            let output_option = OutputOption {
+                input_args: InputOption {
+                    directory: None,
+                    filepath: None,
+                    live_analysis: false,
+                    live_channel: None,
+                    recover_records: false,
+                    timeline_offset: None,
+                },
+                profile: None,
+                enable_deprecated_rules: false,
+                exclude_status: None,
+                min_level: "informational".to_string(),
+                exact_level: None,
+                enable_noisy_rules: false,
+                end_timeline: None,
+                start_timeline: None,
+                eid_filter: false,
+                european_time: false,
+                iso_8601: false,
+                rfc_2822: false,
+                rfc_3339: false,
+                us_military_time: false,
+                us_time: false,
+                utc: true,
+                visualize_timeline: false,
+                rules: vec![Path::new("./rules").to_path_buf()],
+                html_report: None,
+                no_summary: false,
+                common_options: CommonOptions {
+                    no_color: false,
+                    quiet: false,
+                    help: None,
+                },
+                detect_common_options: DetectCommonOption {
+                    evtx_file_ext: None,
+                    thread_number: None,
+                    quiet_errors: false,
+                    config: Path::new("./rules/config").to_path_buf(),
+                    verbose: 0,
+                    no_progress: false,
+                    json_input: false,
+                    include_computer: None,
+                    exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
+                },
+                enable_unsupported_rules: false,
+                clobber: false,
+                proven_rules: false,
+                include_tag: None,
+                exclude_tag: None,
+                include_category: None,
+                exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
+                include_eid: None,
+                exclude_eid: None,
+                no_field: false,
+                no_pwsh_field_extraction: false,
+                remove_duplicate_data: false,
+                remove_duplicate_detections: false,
+                no_wizard: true,
+                include_status: None,
+                sort_events: false,
+                enable_all_rules: false,
+                scan_all_evtx_files: false,
+            };
+            let ch = mock_ch_filter
+                .get(&CompactString::from("security"))
+                .unwrap_or(&CompactString::default())
+                .clone();
+            let mut profile_converter: HashMap<&str, Profile> = HashMap::from([
+                (
+                    "Timestamp",
+                    Profile::Timestamp(format_time(&expect_time, false, &output_option).into()),
+                ),
+                ("Computer", Profile::Computer(test_computername2.into())),
+                ("Channel", Profile::Channel(ch.into())),
+                ("Level", Profile::Level(test_level.into())),
+                ("EventID", Profile::EventID(test_eventid.into())),
+                ("MitreAttack", Profile::MitreTactics(test_attack.into())),
+                ("RecordID", Profile::RecordID(test_record_id.into())),
+                ("RuleTitle", Profile::RuleTitle(test_title.into())),
+                (
+                    "RecordInformation",
+                    Profile::AllFieldInfo(test_recinfo.into()),
+                ),
+                ("RuleFile", Profile::RuleFile(test_rulepath.into())),
+                ("EvtxFile", Profile::EvtxFile(test_filepath.into())),
+                ("Tags", Profile::MitreTags(test_attack.into())),
+            ]);
+            let details_convert_map: HashMap<CompactString, Vec<CompactString>> =
+                HashMap::from_iter([("#AllFieldInfo".into(), vec![test_recinfo.into()])]);
+            let eventkey_alias = load_eventkey_alias(
+                utils::check_setting_path(
+                    &CURRENT_EXE_PATH.to_path_buf(),
+                    "rules/config/eventkey_alias.txt",
+                    true,
+                )
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            );
+
+            let message_detect_info = message::create_message(
+                &event,
+                CompactString::new(output),
+                DetectInfo {
+                    detected_time: expect_time,
+                    rulepath: CompactString::from(test_rulepath),
+                    ruleid: test_rule_id.into(),
+                    ruletitle: CompactString::from(test_title),
+                    level: CompactString::from(test_level),
+                    computername: CompactString::from(test_computername2),
+                    eventid: CompactString::from(test_eventid),
+                    detail: CompactString::default(),
+                    ext_field: output_profile.to_owned(),
+                    is_condition: false,
+                    details_convert_map,
+                },
+                &profile_converter,
+                (false, true),
+                (&eventkey_alias, &FieldDataMapKey::default(), &None),
+            );
+            detect_infos.push(message_detect_info.clone());
+            detect_infos.push(message_detect_info);
+            *profile_converter.get_mut("Computer").unwrap() =
+                Profile::Computer(test_computername.into());
+        }
+        additional_afterfact.record_cnt = 1;
+        additional_afterfact.recover_record_cnt = 0;
+        additional_afterfact.tl_starttime = Some(expect_tz);
+        additional_afterfact.tl_endtime = Some(expect_tz);
+        let mut writer = init_writer(&stored_static);
+        assert!(output_afterfact_inner(
+            &mut detect_infos,
+            &mut writer,
+            &stored_static,
+            &mut additional_afterfact,
+        )
+        .is_ok());
+        match read_to_string("./test_emit_csv_json_pretty.json") {
+            Err(_) => panic!("Failed to open file."),
+            Ok(s) => {
+                // 全ての検知結果が1つのJSON配列としてパースできること
+                let parsed: Vec<Value> = serde_json::from_str(&s).unwrap();
+                assert_eq!(parsed.len(), 2);
+                assert_eq!(parsed[0]["RuleTitle"], "test_title");
+                assert_eq!(parsed[1]["EventID"], 1111);
+            }
+        };
+        assert!(remove_file("./test_emit_csv_json_pretty.json").is_ok());
+    }
+
+    #[test]
+    fn test_create_processing_stats_msg() {
+        assert_eq!(
+            create_processing_stats_msg(1_234_567, Duration::from_millis(12_300), 42),
+            "Processed 1,234,567 events in 12.3s (100,371 events/s), 42 detections"
+        );
+        // 処理時間が0の場合も0除算にならない
+        assert_eq!(
+            create_processing_stats_msg(10, Duration::ZERO, 0),
+            "Processed 10 events in 0.0s (10 events/s), 0 detections"
+        );
+    }
 }
@@ -5,15 +5,20 @@ use crate::options::geoip_search::GeoIPSearch;
 use crate::options::htmlreport;
 use crate::options::pivot::PIVOT_KEYWORD;
 use crate::options::profile::{load_profile, Profile};
+use crate::options::suppression::SuppressionList;
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
 use chrono::{DateTime, Days, Duration, Local, Months, Utc};
+use chrono_tz::Tz;
 use clap::{ArgAction, ArgGroup, Args, ColorChoice, Command, CommandFactory, Parser, Subcommand};
 use compact_str::CompactString;
-use hashbrown::{HashMap, HashSet};
+use hashbrown::{Equivalent, HashMap, HashSet};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde_json::Value;
 use std::env::current_exe;
+use std::hash::{Hash, Hasher};
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 use std::{fs, process};
@@ -62,7 +67,9 @@ pub struct StoredStatic {
     pub disp_abbr_general_values: Vec<CompactString>,
     pub provider_abbr_config: HashMap<CompactString, CompactString>,
     pub quiet_errors_flag: bool,
+    pub strict_flag: bool,
     pub verbose_flag: bool,
+    pub no_progress_flag: bool,
     pub metrics_flag: bool,
     pub logon_summary_flag: bool,
     pub search_flag: bool,
@@ -93,10 +100,38 @@ pub struct StoredStatic {
     pub is_low_memory: bool,
     pub enable_all_rules: bool,
     pub scan_all_evtx_files: bool,
+    pub logsource_channel_config: HashMap<CompactString, Vec<CompactString>>, // logsourceのserviceと対象となるChannel(小文字)のマップ
+    pub suppression_list: Option<SuppressionList>, // --suppressで指定した既知の誤検知のリスト
+    pub global_selections: HashMap<String, Yaml>, // ルールのconditionから名前で参照できる共通のselection
 }
 impl StoredStatic {
     /// main.rsでパースした情報からデータを格納する関数
     pub fn create_static_data(input_config: Option<Config>) -> StoredStatic {
+        // json-timelineで-oに.jsonlファイルが指定された場合は、-Lを指定しなくてもJSONL形式で出力する(--json-prettyの場合を除く)
+        // --appendは既存のファイルに追記するので、常にJSONL形式で出力する
+        let mut input_config = input_config;
+        if let Some(Action::JsonTimeline(opt)) = input_config.as_mut().unwrap().action.as_mut() {
+            opt.jsonl_timeline |= opt.append
+                || !opt.json_pretty
+                    && opt
+                        .output
+                        .as_ref()
+                        .is_some_and(|path| is_jsonl_output_file(path));
+        }
+        // -oで指定されたファイル名の{timestamp}を実行時の日時に置き換える
+        let output = match input_config.as_mut().unwrap().action.as_mut() {
+            Some(Action::CsvTimeline(opt)) => opt.output.as_mut(),
+            Some(Action::JsonTimeline(opt)) => opt.output.as_mut(),
+            Some(Action::EidMetrics(opt)) => opt.output.as_mut(),
+            Some(Action::PivotKeywordsList(opt)) => opt.output.as_mut(),
+            Some(Action::LogonSummary(opt)) => opt.output.as_mut(),
+            Some(Action::Search(opt)) => opt.output.as_mut(),
+            Some(Action::ComputerMetrics(opt)) => opt.output.as_mut(),
+            _ => None,
+        };
+        if let Some(path) = output {
+            *path = expand_output_timestamp(path, &Local::now());
+        }
         let action_id = Action::to_usize(input_config.as_ref().unwrap().action.as_ref());
         let quiet_errors_flag = match &input_config.as_ref().unwrap().action {
             Some(Action::CsvTimeline(opt)) => opt.output_options.detect_common_options.quiet_errors,
@@ -110,7 +145,15 @@ impl StoredStatic {
             Some(Action::ComputerMetrics(opt)) => opt.quiet_errors,
             _ => false,
         };
-        let common_options = match &input_config.as_ref().unwrap().action {
+        let strict_flag = match &input_config.as_ref().unwrap().action {
+            Some(Action::CsvTimeline(opt)) => opt.output_options.detect_common_options.strict,
+            Some(Action::JsonTimeline(opt)) => opt.output_options.detect_common_options.strict,
+            Some(Action::LogonSummary(opt)) => opt.detect_common_options.strict,
+            Some(Action::EidMetrics(opt)) => opt.detect_common_options.strict,
+            Some(Action::PivotKeywordsList(opt)) => opt.detect_common_options.strict,
+            _ => false,
+        };
+        let mut common_options = match &input_config.as_ref().unwrap().action {
             Some(Action::CsvTimeline(opt)) => opt.output_options.common_options,
             Some(Action::JsonTimeline(opt)) => opt.output_options.common_options,
             Some(Action::LevelTuning(opt)) => opt.common_options,
@@ -122,12 +165,17 @@ impl StoredStatic {
             Some(Action::UpdateRules(opt)) => opt.common_options,
             Some(Action::Search(opt)) => opt.common_options,
             Some(Action::ComputerMetrics(opt)) => opt.common_options,
+            Some(Action::ValidateRules(opt)) => opt.common_options,
             None => CommonOptions {
                 no_color: false,
                 quiet: false,
                 help: None,
             },
         };
+        // 標準出力がターミナルではない(パイプやリダイレクトされている)場合は、ANSIエスケープシーケンスが混ざらないように色を付けない
+        if !std::io::stdout().is_terminal() {
+            common_options.no_color = true;
+        }
         let binding = Path::new("./rules/config").to_path_buf();
         let config_path = match &input_config.as_ref().unwrap().action {
             Some(Action::CsvTimeline(opt)) => &opt.output_options.detect_common_options.config,
@@ -139,14 +187,31 @@ impl StoredStatic {
             Some(Action::ComputerMetrics(opt)) => &opt.config,
             _ => &binding,
         };
-        let verbose_flag = match &input_config.as_ref().unwrap().action {
-            Some(Action::CsvTimeline(opt)) => opt.output_options.detect_common_options.verbose,
-            Some(Action::JsonTimeline(opt)) => opt.output_options.detect_common_options.verbose,
-            Some(Action::LogonSummary(opt)) => opt.detect_common_options.verbose,
-            Some(Action::EidMetrics(opt)) => opt.detect_common_options.verbose,
-            Some(Action::PivotKeywordsList(opt)) => opt.detect_common_options.verbose,
-            Some(Action::Search(opt)) => opt.verbose,
-            Some(Action::ComputerMetrics(opt)) => opt.verbose,
+        let alias_config_path = match &input_config.as_ref().unwrap().action {
+            Some(Action::CsvTimeline(opt)) => opt
+                .output_options
+                .detect_common_options
+                .alias_config
+                .as_ref(),
+            Some(Action::JsonTimeline(opt)) => opt
+                .output_options
+                .detect_common_options
+                .alias_config
+                .as_ref(),
+            Some(Action::LogonSummary(opt)) => opt.detect_common_options.alias_config.as_ref(),
+            Some(Action::EidMetrics(opt)) => opt.detect_common_options.alias_config.as_ref(),
+            Some(Action::PivotKeywordsList(opt)) => opt.detect_common_options.alias_config.as_ref(),
+            _ => None,
+        };
+        let verbose_flag = get_verbose_level(&input_config.as_ref().unwrap().action) > 0;
+        let no_progress_flag = match &input_config.as_ref().unwrap().action {
+            Some(Action::CsvTimeline(opt)) => opt.output_options.detect_common_options.no_progress,
+            Some(Action::JsonTimeline(opt)) => opt.output_options.detect_common_options.no_progress,
+            Some(Action::LogonSummary(opt)) => opt.detect_common_options.no_progress,
+            Some(Action::EidMetrics(opt)) => opt.detect_common_options.no_progress,
+            Some(Action::PivotKeywordsList(opt)) => opt.detect_common_options.no_progress,
+            Some(Action::Search(opt)) => opt.no_progress,
+            Some(Action::ComputerMetrics(opt)) => opt.no_progress,
             _ => false,
         };
         let json_input_flag = match &input_config.as_ref().unwrap().action {
@@ -158,6 +223,18 @@ impl StoredStatic {
             Some(Action::ComputerMetrics(opt)) => opt.json_input,
             _ => false,
         };
+        // -fで.json/.jsonlファイルが指定された場合は、-Jの指定がなくてもJSON形式のイベントログとして読み込む
+        let input_filepath = match &input_config.as_ref().unwrap().action {
+            Some(Action::CsvTimeline(opt)) => opt.output_options.input_args.filepath.as_ref(),
+            Some(Action::JsonTimeline(opt)) => opt.output_options.input_args.filepath.as_ref(),
+            Some(Action::LogonSummary(opt)) => opt.input_args.filepath.as_ref(),
+            Some(Action::EidMetrics(opt)) => opt.input_args.filepath.as_ref(),
+            Some(Action::PivotKeywordsList(opt)) => opt.input_args.filepath.as_ref(),
+            Some(Action::ComputerMetrics(opt)) => opt.input_args.filepath.as_ref(),
+            _ => None,
+        };
+        let json_input_flag =
+            json_input_flag || input_filepath.is_some_and(|path| is_json_input_file(path));
         let is_valid_min_level = match &input_config.as_ref().unwrap().action {
             Some(Action::CsvTimeline(opt)) => LEVEL_ABBR_MAP
                 .keys()
@@ -230,14 +307,25 @@ impl StoredStatic {
             process::exit(1);
         }
         if let Some(geo_ip_db_path) = geo_ip_db_result.unwrap() {
-            *GEOIP_DB_PARSER.write().unwrap() = Some(GeoIPSearch::new(
-                &geo_ip_db_path,
-                vec![
-                    "GeoLite2-ASN.mmdb",
-                    "GeoLite2-Country.mmdb",
-                    "GeoLite2-City.mmdb",
-                ],
-            ));
+            let geo_ip = if geo_ip_db_path.is_file() {
+                match GeoIPSearch::open_mmdb_file(&geo_ip_db_path) {
+                    Ok(geo_ip) => geo_ip,
+                    Err(err_msg) => {
+                        AlertMessage::alert(&err_msg).ok();
+                        process::exit(1);
+                    }
+                }
+            } else {
+                GeoIPSearch::new(
+                    &geo_ip_db_path,
+                    vec![
+                        "GeoLite2-ASN.mmdb",
+                        "GeoLite2-Country.mmdb",
+                        "GeoLite2-City.mmdb",
+                    ],
+                )
+            };
+            *GEOIP_DB_PARSER.write().unwrap() = Some(geo_ip);
             let geo_ip_file_path =
                 utils::check_setting_path(config_path, "geoip_field_mapping", false)
                     .unwrap_or_else(|| {
@@ -645,7 +733,9 @@ impl StoredStatic {
             output_option: extract_output_options(input_config.as_ref().unwrap()),
             pivot_keyword_list_flag: action_id == 4,
             quiet_errors_flag,
+            strict_flag,
             verbose_flag,
+            no_progress_flag,
             html_report_flag: htmlreport::check_html_flag(input_config.as_ref().unwrap()),
             profiles: None,
             thread_number: check_thread_number(input_config.as_ref().unwrap()),
@@ -676,6 +766,19 @@ impl StoredStatic {
                     .unwrap(),
             ),
             target_ruleids,
+            logsource_channel_config: load_logsource_channel_config(
+                utils::check_setting_path(config_path, "logsource_channel.txt", false)
+                    .unwrap_or_else(|| {
+                        utils::check_setting_path(
+                            &CURRENT_EXE_PATH.to_path_buf(),
+                            "config/logsource_channel.txt",
+                            true,
+                        )
+                        .unwrap()
+                    })
+                    .to_str()
+                    .unwrap(),
+            ),
             json_input_flag,
             output_path: output_path.cloned(),
             common_options,
@@ -692,6 +795,12 @@ impl StoredStatic {
             is_low_memory,
             enable_all_rules,
             scan_all_evtx_files,
+            suppression_list: None,
+            global_selections: load_global_selections(utils::check_setting_path(
+                config_path,
+                "global_selections.yaml",
+                false,
+            )),
         };
         ret.profiles = load_profile(
             check_setting_path(
@@ -712,6 +821,29 @@ impl StoredStatic {
             .unwrap(),
             Some(&ret),
         );
+        if let Some(suppress_path) = ret
+            .output_option
+            .as_ref()
+            .and_then(|opt| opt.suppress.as_ref())
+        {
+            match SuppressionList::load(&suppress_path.to_string_lossy()) {
+                Ok(suppression_list) => ret.suppression_list = Some(suppression_list),
+                Err(errmsgs) => {
+                    errmsgs.iter().for_each(|errmsg| {
+                        AlertMessage::alert(errmsg).ok();
+                    });
+                    process::exit(1);
+                }
+            }
+        }
+        if let Some(alias_config_path) = alias_config_path {
+            if let Err(errmsgs) = merge_eventkey_alias(&mut ret.eventkey_alias, alias_config_path) {
+                errmsgs.iter().for_each(|errmsg| {
+                    AlertMessage::alert(errmsg).ok();
+                });
+                process::exit(1);
+            }
+        }
         ret
     }
     /// detailsのdefault値をファイルから読み取る関数
@@ -866,6 +998,16 @@ pub enum Action {
     /// Tune alert levels (default: ./rules/config/level_tuning.txt)
     LevelTuning(LevelTuningOption),
 
+    #[clap(
+        author = "Yamato Security (https://github.com/Yamato-Security/hayabusa - @SecurityYamato)",
+        help_template = "\nHayabusa v2.17.0 - Dev Build\n{author-with-newline}\n{usage-heading}\n  {usage}\n\n{all-args}",
+        term_width = 400,
+        display_order = 480,
+        disable_help_flag = true
+    )]
+    /// Validate rules without scanning event logs
+    ValidateRules(ValidateRulesOption),
+
     #[clap(
         author = "Yamato Security (https://github.com/Yamato-Security/hayabusa - @SecurityYamato)",
         help_template = "\nHayabusa v2.17.0 - Dev Build\n{author-with-newline}\n{usage-heading}\n  {usage}\n\n{all-args}",
@@ -911,6 +1053,7 @@ impl Action {
                 Action::ListProfiles(_) => 9,
                 Action::Search(_) => 10,
                 Action::ComputerMetrics(_) => 11,
+                Action::ValidateRules(_) => 12,
             }
         } else {
             100
@@ -931,6 +1074,7 @@ impl Action {
                 Action::ListProfiles(_) => "list-profiles",
                 Action::Search(_) => "search",
                 Action::ComputerMetrics(_) => "computer-metrics",
+                Action::ValidateRules(_) => "validate-rules",
             }
         } else {
             ""
@@ -974,9 +1118,17 @@ pub struct DetectCommonOption {
     )]
     pub config: PathBuf,
 
-    /// Output verbose information
-    #[arg(help_heading = Some("Display Settings"), short = 'v', long, display_order = 480)]
-    pub verbose: bool,
+    /// Specify additional field alias config file (alias,event_key pairs in CSV or YAML)
+    #[arg(help_heading = Some("General Options"), long = "alias-config", value_name = "FILE", display_order = 443)]
+    pub alias_config: Option<PathBuf>,
+
+    /// Output verbose information (-vv: also output debug information)
+    #[arg(help_heading = Some("Display Settings"), short = 'v', long, action = ArgAction::Count, display_order = 480)]
+    pub verbose: u8,
+
+    /// Do not display the progress bar
+    #[arg(help_heading = Some("Display Settings"), long = "no-progress", display_order = 405)]
+    pub no_progress: bool,
 
     /// Scan only specified computer names (ex: ComputerA) (ex: ComputerA,ComputerB)
     #[arg(help_heading = Some("Filtering"), long = "include-computer", value_name = "COMPUTER...", conflicts_with = "exclude-computer", use_value_delimiter = true, value_delimiter = ',', display_order = 352)]
@@ -985,6 +1137,10 @@ pub struct DetectCommonOption {
     /// Do not scan specified computer names (ex: ComputerA) (ex: ComputerA,ComputerB)
     #[arg(help_heading = Some("Filtering"), long = "exclude-computer", value_name = "COMPUTER...", conflicts_with = "include_computer",use_value_delimiter = true, value_delimiter = ',', display_order = 314)]
     pub exclude_computer: Option<Vec<String>>,
+
+    /// Exit with an error code when event records failed to be parsed or rules have no title
+    #[arg(help_heading = Some("General Options"), long = "strict", display_order = 461)]
+    pub strict: bool,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -1099,9 +1255,13 @@ pub struct SearchOption {
         )]
     pub config: PathBuf,
 
-    /// Output verbose information
-    #[arg(help_heading = Some("Display Settings"), short = 'v', long, display_order = 480)]
-    pub verbose: bool,
+    /// Output verbose information (-vv: also output debug information)
+    #[arg(help_heading = Some("Display Settings"), short = 'v', long, action = ArgAction::Count, display_order = 480)]
+    pub verbose: u8,
+
+    /// Do not display the progress bar
+    #[arg(help_heading = Some("Display Settings"), long = "no-progress", display_order = 405)]
+    pub no_progress: bool,
 
     /// Output event field information in multiple rows for CSV output
     #[arg(help_heading = Some("Output"), short = 'M', long="multiline", display_order = 390)]
@@ -1185,6 +1345,24 @@ pub struct LevelTuningOption {
     pub level_tuning: PathBuf,
 }
 
+#[derive(Args, Clone, Debug)]
+pub struct ValidateRulesOption {
+    #[clap(flatten)]
+    pub common_options: CommonOptions,
+
+    /// Specify a custom rule directory or file (default: ./rules)
+    #[arg(
+        help_heading = Some("General Options"),
+        short = 'r',
+        long,
+        default_value = "./rules",
+        hide_default_value = true,
+        value_name = "DIR/FILE",
+        display_order = 441
+    )]
+    pub rules: PathBuf,
+}
+
 #[derive(Args, Clone, Debug)]
 pub struct EidMetricsOption {
     #[clap(flatten)]
@@ -1234,7 +1412,7 @@ pub struct EidMetricsOption {
 }
 
 #[derive(Args, Clone, Debug)]
-#[clap(group(ArgGroup::new("input_filtering").args(["directory", "filepath", "live_analysis"]).required(true)))]
+#[clap(group(ArgGroup::new("input_filtering").args(["directory", "filepath", "live_analysis", "live_channel"]).required(true)))]
 #[clap(group(ArgGroup::new("level_rule_filtering").args(["min_level", "exact_level"]).multiple(false)))]
 pub struct PivotKeywordOption {
     #[clap(flatten)]
@@ -1431,6 +1609,14 @@ pub struct OutputOption {
     #[arg(help_heading = Some("Filtering"), long = "exclude-category", value_name = "CATEGORY...", conflicts_with = "include_category", requires = "no_wizard", use_value_delimiter = true, value_delimiter = ',', display_order = 314)]
     pub exclude_category: Option<Vec<String>>,
 
+    /// Only load rules with specified rule IDs or file names (wildcards allowed) (ex: *powershell*.yml)
+    #[arg(help_heading = Some("Filtering"), long = "enable-rule", value_name = "ID/FILE...", use_value_delimiter = true, value_delimiter = ',', display_order = 313)]
+    pub enable_rule: Option<Vec<String>>,
+
+    /// Do not load rules with specified rule IDs or file names (wildcards allowed) (ex: *powershell*.yml)
+    #[arg(help_heading = Some("Filtering"), long = "exclude-rule", value_name = "ID/FILE...", use_value_delimiter = true, value_delimiter = ',', display_order = 316)]
+    pub exclude_rule: Option<Vec<String>>,
+
     /// Minimum level for rules to load (default: informational)
     #[arg(
         help_heading = Some("Filtering"),
@@ -1519,22 +1705,44 @@ pub struct OutputOption {
     #[arg(help_heading = Some("Time Format"), short = 'U', long = "UTC", display_order = 210)]
     pub utc: bool,
 
+    /// Load rules with the same content from different paths instead of skipping them (only warn)
+    #[arg(help_heading = Some("Filtering"), long = "keep-duplicate-rules", display_order = 326)]
+    pub keep_duplicate_rules: bool,
+
+    /// Add a MatchedFields column with the selection keys and field values that matched the rule
+    #[arg(help_heading = Some("Output"), long = "matched-fields", display_order = 395)]
+    pub matched_fields: bool,
+
+    /// Cache parsed rules in a file to speed up loading unchanged rules on the next run
+    #[arg(help_heading = Some("General Options"), long = "rule-cache", value_name = "FILE", display_order = 444)]
+    pub rule_cache: Option<PathBuf>,
+
+    /// Output time in the specified timezone (ex: Asia/Tokyo) (ex: America/New_York)
+    #[arg(help_heading = Some("Time Format"), long = "timezone", value_name = "TIMEZONE", conflicts_with_all = ["utc", "iso_8601"], display_order = 211)]
+    pub timezone: Option<Tz>,
+
+    /// Timestamp used for detected events without a readable timestamp (default: "1970-01-01 00:00:00 +00:00")
+    #[arg(help_heading = Some("Time Format"), long = "default-timestamp", value_name = "DATE", value_parser = parse_default_timestamp, display_order = 212)]
+    pub default_timestamp: Option<DateTime<Utc>>,
+
     /// Output event frequency timeline (terminal needs to support unicode)
     #[arg(help_heading = Some("Display Settings"), short = 'T', long = "visualize-timeline", display_order = 490)]
     pub visualize_timeline: bool,
 
-    /// Specify a custom rule directory or file (default: ./rules)
+    /// Specify custom rule directories or files (default: ./rules) (ex: ./rules,./internal-rules)
     #[arg(
         help_heading = Some("General Options"),
         short = 'r',
         long,
         default_value = "./rules",
         hide_default_value = true,
-        value_name = "DIR/FILE",
+        value_name = "DIR/FILE...",
         requires = "no_wizard",
+        use_value_delimiter = true,
+        value_delimiter = ',',
         display_order = 441
     )]
-    pub rules: PathBuf,
+    pub rules: Vec<PathBuf>,
 
     /// Save Results Summary details to an HTML report (ex: results.html)
     #[arg(help_heading = Some("Output"), short = 'H', long="HTML-report", conflicts_with = "no_summary", value_name = "FILE", display_order = 80, requires = "output")]
@@ -1544,6 +1752,38 @@ pub struct OutputOption {
     #[arg(help_heading = Some("Display Settings"), short = 'N', long = "no-summary", conflicts_with = "html_report", display_order = 401)]
     pub no_summary: bool,
 
+    /// Only display hit counts per rule and EventID instead of the detection results
+    #[arg(help_heading = Some("Display Settings"), long = "stats", conflicts_with_all = ["html_report", "no_summary"], display_order = 450)]
+    pub stats: bool,
+
+    /// Display the loaded rules after filtering and exit without scanning
+    #[arg(help_heading = Some("Display Settings"), long = "list-rules", conflicts_with_all = ["html_report", "stats"], display_order = 398)]
+    pub list_rules: bool,
+
+    /// Display the input files, number of loaded rules and effective options and exit without scanning
+    #[arg(help_heading = Some("Display Settings"), long = "dry-run", conflicts_with_all = ["list_rules", "debug_rule", "html_report"], display_order = 300)]
+    pub dry_run: bool,
+
+    /// Display the rules that did not detect any events after the scan
+    #[arg(help_heading = Some("Display Settings"), long = "show-unused-rules", conflicts_with = "list_rules", display_order = 452)]
+    pub show_unused_rules: bool,
+
+    /// Only load rules tagged with specific MITRE ATT&CK tactics or techniques (ex: execution,t1059)
+    #[arg(help_heading = Some("Filtering"), long = "mitre", visible_alias = "tags", value_name = "TACTIC/TECHNIQUE...", requires = "no_wizard", use_value_delimiter = true, value_delimiter = ',', display_order = 355)]
+    pub mitre: Option<Vec<String>>,
+
+    /// Only load the rule with the specified ID and print the evaluation result of each selection for every event
+    #[arg(help_heading = Some("Display Settings"), long = "debug-rule", value_name = "ID", conflicts_with_all = ["list_rules", "enable_rule"], display_order = 396)]
+    pub debug_rule: Option<String>,
+
+    /// Suppress detections of known benign events listed in a file (Field:Value or event hash per line)
+    #[arg(help_heading = Some("Filtering"), long = "suppress", value_name = "FILE", display_order = 455)]
+    pub suppress: Option<PathBuf>,
+
+    /// Only output the first N detections (applied after sorting with -s)
+    #[arg(help_heading = Some("Output"), long = "limit", value_name = "NUMBER", conflicts_with = "stats", display_order = 395)]
+    pub limit: Option<usize>,
+
     /// Overwrite files when saving
     #[arg(help_heading = Some("General Options"), short='C', long = "clobber", display_order = 290, requires = "output")]
     pub clobber: bool,
@@ -1578,6 +1818,10 @@ pub struct OutputOption {
     #[arg(help_heading = Some("General Options"), short='s', long = "sort-events", display_order = 451)]
     pub sort_events: bool,
 
+    /// Scan 100 events at a time and output their detections after each batch (results are not sorted)
+    #[arg(help_heading = Some("General Options"), long = "stream", conflicts_with = "sort_events", display_order = 452)]
+    pub stream: bool,
+
     /// Enable all rules regardless of loaded evtx files (disable channel filter for rules)
     #[arg(help_heading = Some("Filtering"), short='A', long = "enable-all-rules", display_order = 300)]
     pub enable_all_rules: bool,
@@ -1587,6 +1831,18 @@ pub struct OutputOption {
     pub scan_all_evtx_files: bool,
 }
 
+/// --default-timestampで指定された時刻をパースする。--timeline-startと同じ形式を受け付ける
+fn parse_default_timestamp(time: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S %z")
+        .or_else(|_| DateTime::parse_from_str(time, "%Y/%m/%d %H:%M:%S %z"))
+        .or_else(|_| DateTime::parse_from_rfc3339(time))
+        .map(|dt| dt.with_timezone(&Utc))
+        .map_err(|_| {
+            "Please specify the timestamp in the following format: \"2020-02-22 00:00:00 +09:00\""
+                .to_string()
+        })
+}
+
 #[derive(Copy, Args, Clone, Debug)]
 pub struct CommonOptions {
     /// Disable color output
@@ -1603,20 +1859,24 @@ pub struct CommonOptions {
 }
 
 #[derive(Args, Clone, Debug)]
-#[clap(group(ArgGroup::new("input_filtering").args(["directory", "filepath", "live_analysis"]).required(true)))]
+#[clap(group(ArgGroup::new("input_filtering").args(["directory", "filepath", "live_analysis", "live_channel"]).required(true)))]
 pub struct InputOption {
     /// Directory of multiple .evtx files
-    #[arg(help_heading = Some("Input"), short = 'd', long, value_name = "DIR", conflicts_with_all = ["filepath", "live_analysis"], display_order = 300)]
+    #[arg(help_heading = Some("Input"), short = 'd', long, value_name = "DIR", conflicts_with_all = ["filepath", "live_analysis", "live_channel"], display_order = 300)]
     pub directory: Option<Vec<PathBuf>>,
 
     /// File path to one .evtx file
-    #[arg(help_heading = Some("Input"), short = 'f', long = "file", value_name = "FILE", conflicts_with_all = ["directory", "live_analysis"], display_order = 320)]
+    #[arg(help_heading = Some("Input"), short = 'f', long = "file", value_name = "FILE", conflicts_with_all = ["directory", "live_analysis", "live_channel"], display_order = 320)]
     pub filepath: Option<PathBuf>,
 
     /// Analyze the local C:\Windows\System32\winevt\Logs folder
-    #[arg(help_heading = Some("Input"), short = 'l', long = "live-analysis", conflicts_with_all = ["filepath", "directory", "json_input"], display_order = 380)]
+    #[arg(help_heading = Some("Input"), short = 'l', long = "live-analysis", conflicts_with_all = ["filepath", "directory", "json_input", "live_channel"], display_order = 380)]
     pub live_analysis: bool,
 
+    /// Analyze events of the specified channels from the live Windows event log (ex: Security,Microsoft-Windows-Sysmon/Operational)
+    #[arg(help_heading = Some("Input"), long = "live-channel", value_name = "CHANNEL...", conflicts_with_all = ["filepath", "directory", "live_analysis", "json_input"], use_value_delimiter = true, value_delimiter = ',', display_order = 381)]
+    pub live_channel: Option<Vec<String>>,
+
     /// Carve evtx records from slack space (default: disabled)
     #[arg(help_heading = Some("General Options"), short = 'x', long = "recover-records", conflicts_with = "json_input", display_order = 440)]
     pub recover_records: bool,
@@ -1641,7 +1901,8 @@ pub struct CsvOutputOption {
         help_heading = Some("Output"),
         short = 'G',
         long = "GeoIP",
-        value_name = "MAXMIND-DB-DIR",
+        alias = "geoip",
+        value_name = "MAXMIND-DB-DIR/FILE",
         display_order = 70
     )]
     pub geo_ip: Option<PathBuf>,
@@ -1664,12 +1925,21 @@ pub struct JSONOutputOption {
     #[arg(help_heading = Some("Output"), short = 'L', long = "JSONL-output", requires = "output", display_order = 100)]
     pub jsonl_timeline: bool,
 
+    /// Save the timeline as a single pretty-printed JSON array (ex: --json-pretty -o results.json)
+    #[arg(help_heading = Some("Output"), long = "json-pretty", conflicts_with = "jsonl_timeline", display_order = 101)]
+    pub json_pretty: bool,
+
+    /// Append the timeline to an existing JSONL file instead of overwriting it (ex: --append -o results.jsonl)
+    #[arg(help_heading = Some("Output"), long = "append", requires = "output", conflicts_with_all = ["json_pretty", "clobber"], display_order = 102)]
+    pub append: bool,
+
     /// Add GeoIP (ASN, city, country) info to IP addresses
     #[arg(
         help_heading = Some("Output"),
         short = 'G',
         long = "GeoIP",
-        value_name = "MAXMIND-DB-DIR",
+        alias = "geoip",
+        value_name = "MAXMIND-DB-DIR/FILE",
         display_order = 70
     )]
     pub geo_ip: Option<PathBuf>,
@@ -1721,9 +1991,13 @@ pub struct ComputerMetricsOption {
     )]
     pub config: PathBuf,
 
-    /// Output verbose information
-    #[arg(help_heading = Some("Display Settings"), short = 'v', long, display_order = 480)]
-    pub verbose: bool,
+    /// Output verbose information (-vv: also output debug information)
+    #[arg(help_heading = Some("Display Settings"), short = 'v', long, action = ArgAction::Count, display_order = 480)]
+    pub verbose: u8,
+
+    /// Do not display the progress bar
+    #[arg(help_heading = Some("Display Settings"), long = "no-progress", display_order = 405)]
+    pub no_progress: bool,
 
     /// Overwrite files when saving
     #[arg(help_heading = Some("General Options"), short='C', long = "clobber", display_order = 290, requires = "output")]
@@ -1813,6 +2087,76 @@ fn load_target_ids(path: &str) -> TargetIds {
     ret
 }
 
+/// logsource_channel.txtを読み込み、logsourceのserviceと対象となるChannelのマップを返す関数
+fn load_logsource_channel_config(path: &str) -> HashMap<CompactString, Vec<CompactString>> {
+    let mut ret: HashMap<CompactString, Vec<CompactString>> = HashMap::new();
+    let read_result = match utils::read_csv(path) {
+        Ok(v) => v,
+        Err(e) => {
+            AlertMessage::alert(&e).ok();
+            return ret;
+        }
+    };
+
+    read_result.iter().for_each(|line| {
+        if line.len() != 2 {
+            return;
+        }
+
+        let service = line[0].trim().to_lowercase();
+        let channel = line[1].trim().to_lowercase();
+        if service.is_empty() || channel.is_empty() {
+            return;
+        }
+        ret.entry(service.into()).or_default().push(channel.into());
+    });
+    ret
+}
+
+/// 共通のselectionの名前の接頭辞。ルール内のselectionと区別するために、global_selections.yamlに定義するselectionの名前はこの接頭辞から始める
+pub const GLOBAL_SELECTION_PREFIX: &str = "global_";
+
+/// global_selections.yamlを読み込み、共通のselectionの名前と定義のマップを返す関数。ファイルが存在しない場合は空のマップを返す
+fn load_global_selections(path: Option<PathBuf>) -> HashMap<String, Yaml> {
+    let mut ret = HashMap::new();
+    let path = match path {
+        Some(path) => path,
+        None => return ret,
+    };
+    let loaded_yaml = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| YamlLoader::load_from_str(&contents).map_err(|e| e.to_string()));
+    let docs = match loaded_yaml {
+        Ok(docs) => docs,
+        Err(e) => {
+            AlertMessage::alert(&format!(
+                "Failed to load the global selections. (FilePath : {}) {e}",
+                path.display()
+            ))
+            .ok();
+            return ret;
+        }
+    };
+    let selections = match docs.first().and_then(|doc| doc.as_hash()) {
+        Some(selections) => selections,
+        None => return ret,
+    };
+    for (name, selection) in selections {
+        let name = name.as_str().unwrap_or_default();
+        if name.len() <= GLOBAL_SELECTION_PREFIX.len() || !name.starts_with(GLOBAL_SELECTION_PREFIX)
+        {
+            AlertMessage::alert(&format!(
+                "The name of a global selection must start with {GLOBAL_SELECTION_PREFIX}. (Name : {name}, FilePath : {})",
+                path.display()
+            ))
+            .ok();
+            continue;
+        }
+        ret.insert(name.to_string(), selection.clone());
+    }
+    ret
+}
+
 #[derive(Debug, Clone)]
 pub struct TargetEventTime {
     parse_success_flag: bool,
@@ -1827,6 +2171,7 @@ impl TargetEventTime {
                 if let Some(time) = input_time {
                     match DateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S %z") // 2014-11-28 21:00:09 +09:00
                     .or_else(|_| DateTime::parse_from_str(time, "%Y/%m/%d %H:%M:%S %z")) // 2014/11/28 21:00:09 +09:00
+                    .or_else(|_| DateTime::parse_from_rfc3339(time)) // 2014-11-28T12:00:09Z
                 {
                     Ok(dt) => Some(dt.with_timezone(&Utc)),
                     Err(_) => {
@@ -2074,10 +2419,52 @@ impl TargetEventTime {
     }
 }
 
+/// ASCIIの大文字小文字を区別せずに比較するHashMapのキー。
+/// 検索時はIgnoreCaseStrを使うので、検索の度に小文字に変換した文字列を作成する必要がない
+#[derive(Debug, Clone)]
+struct IgnoreCaseKey(String);
+
+impl PartialEq for IgnoreCaseKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl Eq for IgnoreCaseKey {}
+
+impl Hash for IgnoreCaseKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        IgnoreCaseStr(&self.0).hash(state);
+    }
+}
+
+/// IgnoreCaseKeyのHashMapを&strで検索するためのキー
+struct IgnoreCaseStr<'a>(&'a str);
+
+impl Hash for IgnoreCaseStr<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // IgnoreCaseKeyと同じハッシュ値になるよう、小文字に変換しながら1バイトずつハッシュ化する
+        for b in self.0.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        state.write_u8(0xff);
+    }
+}
+
+impl Equivalent<IgnoreCaseKey> for IgnoreCaseStr<'_> {
+    fn equivalent(&self, key: &IgnoreCaseKey) -> bool {
+        self.0.eq_ignore_ascii_case(&key.0)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct EventKeyAliasConfig {
     key_to_eventkey: HashMap<String, String>,
     key_to_split_eventkey: HashMap<String, Vec<usize>>,
+    /// 大文字小文字を区別しないaliasから設定ファイルに記載されたaliasへのマップ。大文字小文字のみが異なるaliasが複数定義されている場合はNoneとし、大文字小文字を区別して扱う
+    ignore_case_key_to_key: HashMap<IgnoreCaseKey, Option<String>>,
+    /// 大文字小文字を区別しないChannel名またはプロバイダ名から、そのChannel/プロバイダのイベントにのみ適用するaliasへのマップ
+    scoped_aliases: HashMap<IgnoreCaseKey, EventKeyAliasConfig>,
 }
 
 impl EventKeyAliasConfig {
@@ -2085,15 +2472,88 @@ impl EventKeyAliasConfig {
         EventKeyAliasConfig {
             key_to_eventkey: HashMap::new(),
             key_to_split_eventkey: HashMap::new(),
+            ignore_case_key_to_key: HashMap::new(),
+            scoped_aliases: HashMap::new(),
         }
     }
 
+    /// イベントのChannelまたはプロバイダ名に対してaliasが定義されている場合は、そのaliasの設定を返す。
+    /// 定義されていない場合はNoneを返すので、呼び出し元は全体のaliasの設定を使う
+    pub fn get_scoped_alias(
+        &self,
+        alias: &str,
+        event_value: &Value,
+    ) -> Option<&EventKeyAliasConfig> {
+        if self.scoped_aliases.is_empty() {
+            return None;
+        }
+        let system = &event_value["Event"]["System"];
+        [
+            &system["Channel"],
+            &system["Provider_attributes"]["Name"],
+            &system["Provider"]["#attributes"]["Name"],
+            &event_value["Event"]["EventData"]["Channel"],
+        ]
+        .into_iter()
+        .filter_map(|scope| utils::get_text_value(scope).as_str())
+        .find_map(|scope| {
+            self.scoped_aliases
+                .get(&IgnoreCaseStr(scope))
+                .filter(|config| config.get_event_key(alias).is_some())
+        })
+    }
+
     pub fn get_event_key(&self, alias: &str) -> Option<&String> {
-        self.key_to_eventkey.get(alias)
+        self.key_to_eventkey
+            .get(alias)
+            .or_else(|| self.key_to_eventkey.get(self.get_canonical_key(alias)?))
     }
 
     pub fn get_event_key_split(&self, alias: &str) -> Option<&Vec<usize>> {
-        self.key_to_split_eventkey.get(alias)
+        self.key_to_split_eventkey.get(alias).or_else(|| {
+            self.key_to_split_eventkey
+                .get(self.get_canonical_key(alias)?)
+        })
+    }
+
+    /// 大文字小文字を区別せずにaliasを検索し、設定ファイルに記載されたaliasを返す
+    fn get_canonical_key(&self, alias: &str) -> Option<&String> {
+        self.ignore_case_key_to_key
+            .get(&IgnoreCaseStr(alias))?
+            .as_ref()
+    }
+
+    fn insert(&mut self, alias: &str, event_key: &str) {
+        self.key_to_eventkey
+            .insert(alias.to_owned(), event_key.to_owned());
+        self.key_to_split_eventkey.insert(
+            alias.to_owned(),
+            event_key.split('.').map(|s| s.len()).collect(),
+        );
+        self.ignore_case_key_to_key
+            .entry(IgnoreCaseKey(alias.to_owned()))
+            .and_modify(|key| {
+                if key.as_deref() != Some(alias) {
+                    *key = None;
+                }
+            })
+            .or_insert_with(|| Some(alias.to_owned()));
+    }
+
+    /// 指定したChannelまたはプロバイダ名のイベントにのみ適用するaliasを追加する
+    fn insert_scoped(&mut self, scope: &str, alias: &str, event_key: &str) {
+        self.scoped_aliases
+            .entry(IgnoreCaseKey(scope.to_owned()))
+            .or_default()
+            .insert(alias, event_key);
+    }
+
+    /// aliasの設定ファイルの1行分を追加する。scopeが指定された場合はそのChannel/プロバイダのイベントにのみ適用する
+    fn insert_line(&mut self, alias: &str, event_key: &str, scope: Option<&str>) {
+        match scope {
+            Some(scope) => self.insert_scoped(scope, alias, event_key),
+            None => self.insert(alias, event_key),
+        }
     }
 }
 
@@ -2103,6 +2563,44 @@ impl Default for EventKeyAliasConfig {
     }
 }
 
+/// eventkey_aliasに定義がない場合でも、フルパスを書かずに取得できるようにするEvent.System配下のフィールドのalias
+/// RenderedMessageは転送されたイベントなどに含まれる、表示用に展開されたメッセージ(Event.RenderingInfo.Message)を表す。
+/// EventData配下にMessageというフィールドを持つイベントがあるため、Messageではなくプロファイルと同じRenderedMessageという名前にしている
+const DEFAULT_SYSTEM_EVENTKEY_ALIAS: [(&str, &str); 7] = [
+    ("Channel", "Event.System.Channel"),
+    ("Computer", "Event.System.Computer"),
+    ("EventID", "Event.System.EventID"),
+    ("Provider", "Event.System.Provider_attributes.Name"),
+    ("Provider_Name", "Event.System.Provider_attributes.Name"),
+    (
+        "TimeCreated",
+        "Event.System.TimeCreated_attributes.SystemTime",
+    ),
+    ("RenderedMessage", "Event.RenderingInfo.Message"),
+];
+
+/// 大文字小文字を区別せずに、Event.System配下のフィールドのデフォルトのaliasからイベントのキーを取得する
+pub fn get_default_system_event_key(alias: &str) -> Option<&'static str> {
+    DEFAULT_SYSTEM_EVENTKEY_ALIAS
+        .iter()
+        .find(|(default_alias, _)| default_alias.eq_ignore_ascii_case(alias))
+        .map(|(_, event_key)| *event_key)
+}
+
+/// -v/--verboseが指定された回数を取得する。-vで詳細情報を、-vvでデバッグ情報を出力する
+pub fn get_verbose_level(action: &Option<Action>) -> u8 {
+    match action {
+        Some(Action::CsvTimeline(opt)) => opt.output_options.detect_common_options.verbose,
+        Some(Action::JsonTimeline(opt)) => opt.output_options.detect_common_options.verbose,
+        Some(Action::LogonSummary(opt)) => opt.detect_common_options.verbose,
+        Some(Action::EidMetrics(opt)) => opt.detect_common_options.verbose,
+        Some(Action::PivotKeywordsList(opt)) => opt.detect_common_options.verbose,
+        Some(Action::Search(opt)) => opt.verbose,
+        Some(Action::ComputerMetrics(opt)) => opt.verbose,
+        _ => 0,
+    }
+}
+
 pub fn load_eventkey_alias(path: &str) -> EventKeyAliasConfig {
     let mut config = EventKeyAliasConfig::new();
 
@@ -2114,7 +2612,8 @@ pub fn load_eventkey_alias(path: &str) -> EventKeyAliasConfig {
     }
 
     read_result.unwrap().iter().for_each(|line| {
-        if line.len() != 2 {
+        // 3列目にはaliasを適用するChannelまたはプロバイダ名を指定できる
+        if line.len() != 2 && line.len() != 3 {
             return;
         }
 
@@ -2125,18 +2624,196 @@ pub fn load_eventkey_alias(path: &str) -> EventKeyAliasConfig {
             return;
         }
 
-        config
-            .key_to_eventkey
-            .insert(alias.to_owned(), event_key.to_owned());
-        config.key_to_split_eventkey.insert(
-            alias.to_owned(),
-            event_key.split('.').map(|s| s.len()).collect(),
-        );
+        let scope = line
+            .get(2)
+            .map(|scope| scope.trim())
+            .filter(|s| !s.is_empty());
+        config.insert_line(alias, event_key, scope);
     });
     config.key_to_eventkey.shrink_to_fit();
     config
 }
 
+/// ユーザが指定したaliasの設定ファイル(alias,event_keyのCSVまたはYAMLの連想配列)を読み込み、既存のaliasの設定に上書きする。
+/// CSVの3列目またはYAMLのChannel名/プロバイダ名の連想配列で、特定のChannel/プロバイダのイベントにのみ適用するaliasを指定できる。
+/// 不正な行がある場合は行番号を含むエラーメッセージを返し、設定は変更しない
+pub fn merge_eventkey_alias(
+    config: &mut EventKeyAliasConfig,
+    path: &Path,
+) -> Result<(), Vec<String>> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        vec![format!(
+            "Failed to open the alias config file. (FilePath : {}, Error : {e})",
+            path.display()
+        )]
+    })?;
+    let is_yaml = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("yml") || ext.eq_ignore_ascii_case("yaml"));
+    let aliases = if is_yaml {
+        parse_yaml_eventkey_alias(&contents)
+    } else {
+        parse_csv_eventkey_alias(&contents)
+    }
+    .map_err(|errs| {
+        errs.into_iter()
+            .map(|(line_no, errmsg)| {
+                format!(
+                    "Invalid alias config. {errmsg} (FilePath : {}, Line : {line_no})",
+                    path.display()
+                )
+            })
+            .collect::<Vec<_>>()
+    })?;
+    aliases.iter().for_each(|(alias, event_key, scope)| {
+        config.insert_line(alias, event_key, scope.as_deref())
+    });
+    Ok(())
+}
+
+/// aliasとevent_keyの組み合わせが正しいかを確認する
+fn validate_eventkey_alias(alias: &str, event_key: &str) -> Result<(), String> {
+    if alias.is_empty() || event_key.is_empty() {
+        return Err("The alias and event key must not be empty.".to_string());
+    }
+    if event_key.split('.').any(|key| key.is_empty()) {
+        return Err(format!("The event key is malformed: {event_key}"));
+    }
+    Ok(())
+}
+
+/// aliasの設定ファイルから読み込んだ(alias, event_key, 適用するChannel/プロバイダ名)の組
+type EventKeyAliasLine = (String, String, Option<String>);
+
+/// alias,event_key[,channel]形式のCSVを読み込む。1行目がヘッダ(alias,event_key)の場合と#から始まる行は読み飛ばす
+fn parse_csv_eventkey_alias(contents: &str) -> Result<Vec<EventKeyAliasLine>, Vec<(u64, String)>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .comment(Some(b'#'))
+        .from_reader(contents.as_bytes());
+    let mut aliases = vec![];
+    let mut errors = vec![];
+    for (i, record) in rdr.records().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line_no = e.position().map(|pos| pos.line()).unwrap_or_default();
+                errors.push((line_no, e.to_string()));
+                continue;
+            }
+        };
+        let line_no = record.position().map(|pos| pos.line()).unwrap_or_default();
+        if record.len() != 2 && record.len() != 3 {
+            errors.push((
+                line_no,
+                "Each line must be in the format of alias,event_key or alias,event_key,channel."
+                    .to_string(),
+            ));
+            continue;
+        }
+        let alias = record[0].trim();
+        let event_key = record[1].trim();
+        if i == 0 && alias == "alias" && event_key == "event_key" {
+            continue;
+        }
+        let scope = record
+            .get(2)
+            .map(|scope| scope.trim())
+            .filter(|scope| !scope.is_empty())
+            .map(|scope| scope.to_string());
+        match validate_eventkey_alias(alias, event_key) {
+            Ok(_) => aliases.push((alias.to_string(), event_key.to_string(), scope)),
+            Err(errmsg) => errors.push((line_no, errmsg)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(aliases)
+    } else {
+        Err(errors)
+    }
+}
+
+/// alias: event_key形式のYAMLの連想配列を読み込む。
+/// 値が連想配列の場合はキーをChannel名またはプロバイダ名とし、そのChannel/プロバイダのイベントにのみ適用するaliasとして読み込む
+fn parse_yaml_eventkey_alias(contents: &str) -> Result<Vec<EventKeyAliasLine>, Vec<(u64, String)>> {
+    let docs = YamlLoader::load_from_str(contents)
+        .map_err(|e| vec![(e.marker().line() as u64, e.to_string())])?;
+    let hash = match docs.first() {
+        Some(Yaml::Hash(hash)) => hash,
+        None | Some(Yaml::Null) => return Ok(vec![]),
+        _ => {
+            return Err(vec![(
+                1,
+                "The alias config must be a mapping of alias: event_key.".to_string(),
+            )])
+        }
+    };
+    // YAMLのパース結果からは行番号が取得できないため、キーが記載されている行を検索する
+    let find_line_no = |alias: &str| {
+        contents
+            .lines()
+            .position(|line| line.trim_start().starts_with(alias))
+            .map(|idx| idx as u64 + 1)
+            .unwrap_or_default()
+    };
+    let mut aliases = vec![];
+    let mut errors = vec![];
+    let to_alias = |key: &Yaml| match key {
+        Yaml::String(s) => Some(s.trim().to_string()),
+        Yaml::Integer(i) => Some(i.to_string()),
+        _ => None,
+    };
+    // (alias, event_keyの値, 適用するChannel/プロバイダ名)の組に展開する
+    let mut entries = vec![];
+    for (key, val) in hash {
+        let alias = match to_alias(key) {
+            Some(alias) => alias,
+            None => {
+                errors.push((0, "The alias must be a string.".to_string()));
+                continue;
+            }
+        };
+        match val.as_hash() {
+            Some(scoped_hash) => {
+                for (scoped_key, scoped_val) in scoped_hash {
+                    match to_alias(scoped_key) {
+                        Some(scoped_alias) => {
+                            entries.push((scoped_alias, scoped_val, Some(alias.clone())))
+                        }
+                        None => errors.push((
+                            find_line_no(&alias),
+                            format!("The alias under {alias} must be a string."),
+                        )),
+                    }
+                }
+            }
+            None => entries.push((alias, val, None)),
+        }
+    }
+    for (alias, val, scope) in entries {
+        let event_key = match val.as_str() {
+            Some(event_key) => event_key.trim(),
+            None => {
+                errors.push((
+                    find_line_no(&alias),
+                    format!("The event key of {alias} must be a string."),
+                ));
+                continue;
+            }
+        };
+        match validate_eventkey_alias(&alias, event_key) {
+            Ok(_) => aliases.push((alias, event_key.to_string(), scope)),
+            Err(errmsg) => errors.push((find_line_no(&alias), errmsg)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(aliases)
+    } else {
+        Err(errors)
+    }
+}
+
 ///設定ファイルを読み込み、keyとfieldsのマップをPIVOT_KEYWORD大域変数にロードする。
 pub fn load_pivot_keywords(path: &str) {
     let read_result = match utils::read_txt(path) {
@@ -2176,11 +2853,45 @@ pub fn load_pivot_keywords(path: &str) {
     });
 }
 
-/// --target-file-extで追加された拡張子から、調査対象ファイルの拡張子セットを返す関数。--json-inputがtrueの場合はjsonのみを対象とする
+/// -oで指定したファイル名のうち、実行時の日時に置き換える文字列
+pub const OUTPUT_TIMESTAMP_TOKEN: &str = "{timestamp}";
+
+/// JSON形式のイベントログとして読み込むファイルの拡張子
+pub const JSON_INPUT_EXTENSIONS: [&str; 2] = ["json", "jsonl"];
+
+/// 拡張子からJSON形式のイベントログとして読み込むファイルかどうかを判定する関数
+pub fn is_json_input_file(path: &Path) -> bool {
+    // results.jsonl.gzのようにgzipで圧縮されたファイルは、.gzを除いた拡張子で判定する
+    utils::strip_gz_extension(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| JSON_INPUT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// 拡張子からJSONL形式で出力するファイルかどうかを判定する関数
+pub fn is_jsonl_output_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("jsonl"))
+}
+
+/// 出力ファイルのパスに含まれる{timestamp}を、指定した日時(YYYYMMDD_HHMMSS)に置き換える関数
+pub fn expand_output_timestamp(path: &Path, now: &DateTime<Local>) -> PathBuf {
+    let path_str = path.to_string_lossy();
+    if !path_str.contains(OUTPUT_TIMESTAMP_TOKEN) {
+        return path.to_path_buf();
+    }
+    PathBuf::from(path_str.replace(
+        OUTPUT_TIMESTAMP_TOKEN,
+        &now.format("%Y%m%d_%H%M%S").to_string(),
+    ))
+}
+
+/// --target-file-extで追加された拡張子から、調査対象ファイルの拡張子セットを返す関数。--json-inputがtrueの場合はjson/jsonlのみを対象とする
 pub fn get_target_extensions(arg: Option<&Vec<String>>, json_input_flag: bool) -> HashSet<String> {
     let mut target_file_extensions: HashSet<String> = convert_option_vecs_to_hs(arg);
     if json_input_flag {
-        target_file_extensions.insert(String::from("json"));
+        target_file_extensions.extend(JSON_INPUT_EXTENSIONS.iter().map(|ext| ext.to_string()));
     } else {
         target_file_extensions.insert(String::from("evtx"));
     }
@@ -2208,6 +2919,7 @@ fn extract_search_options(config: &Config) -> Option<SearchOption> {
             quiet_errors: option.quiet_errors,
             config: option.config.clone(),
             verbose: option.verbose,
+            no_progress: option.no_progress,
             multiline: option.multiline,
             clobber: option.clobber,
             json_output: option.json_output,
@@ -2249,7 +2961,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             us_time: false,
             utc: false,
             visualize_timeline: false,
-            rules: Path::new("./rules").to_path_buf(),
+            rules: vec![Path::new("./rules").to_path_buf()],
             html_report: None,
             no_summary: false,
             common_options: option.common_options,
@@ -2261,6 +2973,22 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             exclude_tag: option.exclude_tag.clone(),
             include_category: None,
             exclude_category: None,
+            stats: false,
+            timezone: None,
+            default_timestamp: None,
+            keep_duplicate_rules: false,
+            matched_fields: false,
+            rule_cache: None,
+            list_rules: false,
+            show_unused_rules: false,
+            mitre: None,
+            debug_rule: None,
+            suppress: None,
+            limit: None,
+            stream: false,
+            dry_run: false,
+            enable_rule: None,
+            exclude_rule: None,
             include_eid: option.include_eid.clone(),
             exclude_eid: option.exclude_eid.clone(),
             no_field: false,
@@ -2292,7 +3020,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             us_time: option.us_time,
             utc: option.utc,
             visualize_timeline: false,
-            rules: Path::new("./rules").to_path_buf(),
+            rules: vec![Path::new("./rules").to_path_buf()],
             html_report: None,
             no_summary: false,
             common_options: option.common_options,
@@ -2304,6 +3032,22 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             exclude_tag: None,
             include_category: None,
             exclude_category: None,
+            stats: false,
+            timezone: None,
+            default_timestamp: None,
+            keep_duplicate_rules: false,
+            matched_fields: false,
+            rule_cache: None,
+            list_rules: false,
+            show_unused_rules: false,
+            mitre: None,
+            debug_rule: None,
+            suppress: None,
+            limit: None,
+            stream: false,
+            dry_run: false,
+            enable_rule: None,
+            exclude_rule: None,
             include_eid: None,
             exclude_eid: None,
             no_field: false,
@@ -2335,7 +3079,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             us_time: option.us_time,
             utc: option.utc,
             visualize_timeline: false,
-            rules: Path::new("./rules").to_path_buf(),
+            rules: vec![Path::new("./rules").to_path_buf()],
             html_report: None,
             no_summary: false,
             common_options: option.common_options,
@@ -2347,6 +3091,22 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             exclude_tag: None,
             include_category: None,
             exclude_category: None,
+            stats: false,
+            timezone: None,
+            default_timestamp: None,
+            keep_duplicate_rules: false,
+            matched_fields: false,
+            rule_cache: None,
+            list_rules: false,
+            show_unused_rules: false,
+            mitre: None,
+            debug_rule: None,
+            suppress: None,
+            limit: None,
+            stream: false,
+            dry_run: false,
+            enable_rule: None,
+            exclude_rule: None,
             include_eid: None,
             exclude_eid: None,
             no_field: false,
@@ -2369,6 +3129,22 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             include_tag: None,
             include_category: None,
             exclude_category: None,
+            stats: false,
+            timezone: None,
+            default_timestamp: None,
+            keep_duplicate_rules: false,
+            matched_fields: false,
+            rule_cache: None,
+            list_rules: false,
+            show_unused_rules: false,
+            mitre: None,
+            debug_rule: None,
+            suppress: None,
+            limit: None,
+            stream: false,
+            dry_run: false,
+            enable_rule: None,
+            exclude_rule: None,
             min_level: String::default(),
             exact_level: None,
             enable_noisy_rules: false,
@@ -2384,8 +3160,11 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
                 quiet_errors: option.quiet_errors,
                 config: option.config.clone(),
                 verbose: option.verbose,
+                no_progress: option.no_progress,
                 include_computer: None,
                 exclude_computer: None,
+                strict: false,
+                alias_config: None,
             },
             european_time: false,
             iso_8601: false,
@@ -2395,7 +3174,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             us_time: false,
             utc: false,
             visualize_timeline: false,
-            rules: Path::new("./rules").to_path_buf(),
+            rules: vec![Path::new("./rules").to_path_buf()],
             html_report: None,
             no_summary: false,
             clobber: option.clobber,
@@ -2429,7 +3208,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             us_time: option.us_time,
             utc: option.utc,
             visualize_timeline: false,
-            rules: Path::new("./rules").to_path_buf(),
+            rules: vec![Path::new("./rules").to_path_buf()],
             html_report: None,
             no_summary: false,
             common_options: option.common_options,
@@ -2440,8 +3219,11 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
                 quiet_errors: option.quiet_errors,
                 config: option.config.clone(),
                 verbose: option.verbose,
+                no_progress: option.no_progress,
                 include_computer: None,
                 exclude_computer: None,
+                strict: false,
+                alias_config: None,
             },
             exact_level: None,
             enable_unsupported_rules: false,
@@ -2451,6 +3233,22 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             exclude_tag: None,
             include_category: None,
             exclude_category: None,
+            stats: false,
+            timezone: None,
+            default_timestamp: None,
+            keep_duplicate_rules: false,
+            matched_fields: false,
+            rule_cache: None,
+            list_rules: false,
+            show_unused_rules: false,
+            mitre: None,
+            debug_rule: None,
+            suppress: None,
+            limit: None,
+            stream: false,
+            dry_run: false,
+            enable_rule: None,
+            exclude_rule: None,
             include_eid: None,
             exclude_eid: None,
             no_field: false,
@@ -2468,6 +3266,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
                 directory: None,
                 filepath: None,
                 live_analysis: false,
+                live_channel: None,
                 recover_records: false,
                 timeline_offset: None,
             },
@@ -2488,7 +3287,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             us_time: false,
             utc: false,
             visualize_timeline: false,
-            rules: Path::new("./rules").to_path_buf(),
+            rules: vec![Path::new("./rules").to_path_buf()],
             html_report: None,
             no_summary: false,
             common_options: option.common_options,
@@ -2497,10 +3296,13 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
                 thread_number: None,
                 quiet_errors: false,
                 config: Path::new("./rules/config").to_path_buf(),
-                verbose: false,
+                verbose: 0,
+                no_progress: false,
                 json_input: false,
                 include_computer: None,
                 exclude_computer: None,
+                strict: false,
+                alias_config: None,
             },
             enable_unsupported_rules: false,
             clobber: false,
@@ -2509,6 +3311,22 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             exclude_tag: None,
             include_category: None,
             exclude_category: None,
+            stats: false,
+            timezone: None,
+            default_timestamp: None,
+            keep_duplicate_rules: false,
+            matched_fields: false,
+            rule_cache: None,
+            list_rules: false,
+            show_unused_rules: false,
+            mitre: None,
+            debug_rule: None,
+            suppress: None,
+            limit: None,
+            stream: false,
+            dry_run: false,
+            enable_rule: None,
+            exclude_rule: None,
             include_eid: None,
             exclude_eid: None,
             no_field: false,
@@ -2526,6 +3344,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
                 directory: None,
                 filepath: None,
                 live_analysis: false,
+                live_channel: None,
                 recover_records: false,
                 timeline_offset: None,
             },
@@ -2546,7 +3365,7 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             us_time: false,
             utc: false,
             visualize_timeline: false,
-            rules: Path::new("./rules").to_path_buf(),
+            rules: vec![Path::new("./rules").to_path_buf()],
             html_report: None,
             no_summary: false,
             common_options: option.common_options,
@@ -2555,10 +3374,13 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
                 thread_number: None,
                 quiet_errors: false,
                 config: Path::new("./rules/config").to_path_buf(),
-                verbose: false,
+                verbose: 0,
+                no_progress: false,
                 json_input: false,
                 include_computer: None,
                 exclude_computer: None,
+                strict: false,
+                alias_config: None,
             },
             enable_unsupported_rules: true,
             clobber: false,
@@ -2567,6 +3389,22 @@ fn extract_output_options(config: &Config) -> Option<OutputOption> {
             exclude_tag: None,
             include_category: None,
             exclude_category: None,
+            stats: false,
+            timezone: None,
+            default_timestamp: None,
+            keep_duplicate_rules: false,
+            matched_fields: false,
+            rule_cache: None,
+            list_rules: false,
+            show_unused_rules: false,
+            mitre: None,
+            debug_rule: None,
+            suppress: None,
+            limit: None,
+            stream: false,
+            dry_run: false,
+            enable_rule: None,
+            exclude_rule: None,
             include_eid: None,
             exclude_eid: None,
             no_field: false,
@@ -2672,19 +3510,22 @@ fn create_control_chat_replace_map() -> HashMap<char, CompactString> {
 
 #[cfg(test)]
 mod tests {
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     use super::{
-        create_control_chat_replace_map, Action, CommonOptions, Config, CsvOutputOption,
-        DetectCommonOption, InputOption, JSONOutputOption, OutputOption, StoredStatic,
-        TargetEventTime,
+        create_control_chat_replace_map, load_eventkey_alias, load_global_selections,
+        load_logsource_channel_config, merge_eventkey_alias, Action, CommonOptions, Config,
+        CsvOutputOption, DetectCommonOption, InputOption, JSONOutputOption, OutputOption,
+        StoredStatic, TargetEventTime,
     };
     use crate::detections::configs::{
         self, EidMetricsOption, LogonSummaryOption, PivotKeywordOption, SearchOption,
     };
-    use chrono::{DateTime, Utc};
+    use chrono::{DateTime, Local, TimeZone, Utc};
+    use clap::Parser;
     use compact_str::CompactString;
     use hashbrown::{HashMap, HashSet};
+    use itertools::Itertools;
 
     //     #[test]
     //     #[ignore]
@@ -2729,6 +3570,369 @@ mod tests {
         assert!(time_filter.is_target(&end_time));
     }
 
+    #[test]
+    fn target_event_time_filter_iso_8601() {
+        // --timeline-start/--timeline-endにISO-8601形式(UTC)の時刻を指定できることを確認
+        let config = Config::try_parse_from([
+            "hayabusa",
+            "csv-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "--timeline-start",
+            "2018-02-20T12:00:09Z",
+            "--timeline-end",
+            "2020-03-30 21:00:09 +09:00",
+        ])
+        .unwrap();
+        let stored_static = StoredStatic::create_static_data(Some(config));
+        let time_filter = TargetEventTime::new(&stored_static);
+        assert!(time_filter.is_parse_success());
+        assert_eq!(
+            time_filter.start_time,
+            Some("2018-02-20T12:00:09Z".parse::<DateTime<Utc>>().unwrap())
+        );
+        assert_eq!(
+            time_filter.end_time,
+            Some("2020-03-30T12:00:09Z".parse::<DateTime<Utc>>().unwrap())
+        );
+
+        // 時刻が取得できないイベントは絞り込みの対象外とする
+        assert!(time_filter.is_target(&None));
+    }
+
+    #[test]
+    fn test_rules_option() {
+        // -rを指定しない場合は./rulesを使い、指定した場合はフォルダもファイルも複数指定できることを確認
+        let get_rules_path = |args: Vec<&str>| match Config::try_parse_from(args).unwrap().action {
+            Some(Action::CsvTimeline(opt)) => opt.output_options.rules,
+            _ => panic!("csv-timeline should be parsed."),
+        };
+
+        let default_args = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w"];
+        assert_eq!(get_rules_path(default_args), [Path::new("./rules")]);
+
+        let dir_args = vec![
+            "hayabusa",
+            "csv-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "-r",
+            "test_files/rules/level_yaml",
+        ];
+        assert_eq!(
+            get_rules_path(dir_args),
+            [Path::new("test_files/rules/level_yaml")]
+        );
+
+        let file_args = vec![
+            "hayabusa",
+            "csv-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "--rules",
+            "test_files/rules/yaml/1.yml",
+        ];
+        assert_eq!(
+            get_rules_path(file_args),
+            [Path::new("test_files/rules/yaml/1.yml")]
+        );
+
+        // 複数回の指定とカンマ区切りの指定を組み合わせた場合は、指定した順に並ぶ
+        let multi_args = vec![
+            "hayabusa",
+            "csv-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "-r",
+            "test_files/rules/level_yaml",
+            "-r",
+            "test_files/rules/yaml,test_files/rules/nested_yaml",
+        ];
+        assert_eq!(
+            get_rules_path(multi_args),
+            [
+                Path::new("test_files/rules/level_yaml"),
+                Path::new("test_files/rules/yaml"),
+                Path::new("test_files/rules/nested_yaml")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_eventkey_alias_case_insensitive() {
+        // aliasは大文字小文字を区別せずに検索でき、大文字小文字のみが異なるaliasが定義されている場合は区別されることを確認
+        let actual = load_eventkey_alias("test_files/config/eventkey_alias_case.txt");
+        let command_line = "Event.EventData.CommandLine".to_string();
+        assert_eq!(actual.get_event_key("CommandLine"), Some(&command_line));
+        assert_eq!(actual.get_event_key("commandLine"), Some(&command_line));
+        assert_eq!(actual.get_event_key("COMMANDLINE"), Some(&command_line));
+        assert_eq!(
+            actual.get_event_key_split("commandline"),
+            Some(&vec![5, 9, 11])
+        );
+        assert_eq!(
+            actual.get_event_key("TargetUser"),
+            Some(&"Event.EventData.TargetUserName".to_string())
+        );
+        assert_eq!(
+            actual.get_event_key("targetuser"),
+            Some(&"Event.EventData.SubjectUserName".to_string())
+        );
+        assert!(actual.get_event_key("TARGETUSER").is_none());
+        assert!(actual.get_event_key_split("TARGETUSER").is_none());
+    }
+
+    #[test]
+    fn test_merge_eventkey_alias() {
+        // ユーザが指定したaliasの設定ファイル(CSV/YAML)が既存の設定にマージされることを確認
+        let mut csv_config = load_eventkey_alias("test_files/config/eventkey_alias_case.txt");
+        merge_eventkey_alias(
+            &mut csv_config,
+            Path::new("test_files/config/alias_config/user_alias.csv"),
+        )
+        .unwrap();
+        assert_eq!(
+            csv_config.get_event_key("ProcessName"),
+            Some(&"Event.UserData.Process.Name".to_string())
+        );
+        assert_eq!(
+            csv_config.get_event_key_split("processname"),
+            Some(&vec![5, 8, 7, 4])
+        );
+        assert_eq!(
+            csv_config.get_event_key("CommandLine"),
+            Some(&"Event.EventData.CommandLine".to_string())
+        );
+
+        let mut yaml_config = load_eventkey_alias("test_files/config/eventkey_alias_case.txt");
+        merge_eventkey_alias(
+            &mut yaml_config,
+            Path::new("test_files/config/alias_config/user_alias.yml"),
+        )
+        .unwrap();
+        assert_eq!(
+            yaml_config.get_event_key("SessionId"),
+            Some(&"Event.UserData.Session.Id".to_string())
+        );
+        assert_eq!(
+            yaml_config.get_event_key("EventID"),
+            Some(&"Event.System.EventID".to_string())
+        );
+    }
+
+    #[test]
+    fn test_merge_scoped_eventkey_alias() {
+        // YAMLのChannel名の連想配列に記載したaliasは、そのChannelのイベントにのみ適用されることを確認
+        let mut config = load_eventkey_alias("test_files/config/eventkey_alias_case.txt");
+        merge_eventkey_alias(
+            &mut config,
+            Path::new("test_files/config/alias_config/channel_alias.yml"),
+        )
+        .unwrap();
+        assert_eq!(
+            config.get_event_key("ProcessId"),
+            Some(&"Event.EventData.ProcessId".to_string())
+        );
+        // Channel毎のaliasは全体のaliasには追加されない
+        assert!(config.get_event_key("Image").is_none());
+
+        let security_record = serde_json::json!({"Event": {"System": {"Channel": "Security"}}});
+        let scoped_config = config
+            .get_scoped_alias("ProcessId", &security_record)
+            .unwrap();
+        assert_eq!(
+            scoped_config.get_event_key("ProcessId"),
+            Some(&"Event.EventData.NewProcessId".to_string())
+        );
+        assert!(config.get_scoped_alias("Image", &security_record).is_some());
+        // Channel名も大文字小文字を区別せずに検索する
+        let upper_security_record =
+            serde_json::json!({"Event": {"System": {"Channel": "SECURITY"}}});
+        assert!(config
+            .get_scoped_alias("Image", &upper_security_record)
+            .is_some());
+        assert!(config
+            .get_scoped_alias("EventID", &security_record)
+            .is_none());
+
+        let sysmon_record = serde_json::json!({"Event": {"System": {"Channel": "Microsoft-Windows-Sysmon/Operational"}}});
+        assert!(config
+            .get_scoped_alias("ProcessId", &sysmon_record)
+            .is_none());
+    }
+
+    #[test]
+    fn test_merge_eventkey_alias_invalid() {
+        // 不正な行が行番号付きで報告され、設定が変更されないことを確認
+        let mut config = load_eventkey_alias("test_files/config/eventkey_alias_case.txt");
+        let errmsgs = merge_eventkey_alias(
+            &mut config,
+            Path::new("test_files/config/alias_config/invalid_alias.csv"),
+        )
+        .unwrap_err();
+        assert_eq!(errmsgs.len(), 3);
+        assert!(errmsgs[0].contains("Line : 3"));
+        assert!(errmsgs[1].contains("Line : 4"));
+        assert!(errmsgs[2].contains("Line : 5"));
+        assert!(config.get_event_key("ProcessName").is_none());
+
+        let errmsgs = merge_eventkey_alias(
+            &mut config,
+            Path::new("test_files/config/alias_config/invalid_alias.yml"),
+        )
+        .unwrap_err();
+        assert_eq!(errmsgs.len(), 1);
+        assert!(errmsgs[0].contains("Line : 2"));
+
+        let errmsgs = merge_eventkey_alias(
+            &mut config,
+            Path::new("test_files/config/alias_config/not_exist.csv"),
+        )
+        .unwrap_err();
+        assert_eq!(errmsgs.len(), 1);
+    }
+
+    #[test]
+    fn test_alias_config_option() {
+        // --alias-configで指定したファイルのaliasが読み込まれることを確認
+        let config = Config::try_parse_from([
+            "hayabusa",
+            "csv-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "--alias-config",
+            "test_files/config/alias_config/user_alias.yml",
+        ])
+        .unwrap();
+        let stored_static = StoredStatic::create_static_data(Some(config));
+        assert_eq!(
+            stored_static.eventkey_alias.get_event_key("SessionId"),
+            Some(&"Event.UserData.Session.Id".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_logsource_channel_config() {
+        // 1つのserviceに複数のChannelを設定でき、小文字で読み込まれることを確認
+        let actual = load_logsource_channel_config("config/logsource_channel.txt");
+        assert_eq!(
+            actual.get("security").unwrap(),
+            &vec![CompactString::from("security")]
+        );
+        assert_eq!(
+            actual.get("powershell").unwrap(),
+            &vec![
+                CompactString::from("microsoft-windows-powershell/operational"),
+                CompactString::from("powershellcore/operational")
+            ]
+        );
+        assert!(actual.get("service").is_none());
+    }
+
+    #[test]
+    fn test_load_global_selections() {
+        // global_から始まる名前のselectionのみ読み込まれることを確認
+        let actual = load_global_selections(Some(PathBuf::from(
+            "test_files/config/global_selections.yaml",
+        )));
+        assert_eq!(
+            actual.keys().sorted().collect::<Vec<_>>(),
+            vec!["global_filter_admin_tools", "global_filter_system_account"]
+        );
+        assert_eq!(
+            actual["global_filter_system_account"]["SubjectUserSid"].as_str(),
+            Some("S-1-5-18")
+        );
+        assert!(load_global_selections(None).is_empty());
+    }
+
+    #[test]
+    fn test_no_progress_option() {
+        // --no-progressを指定した場合のみno_progress_flagが有効になることを確認
+        let get_no_progress_flag = |args: Vec<&str>| {
+            StoredStatic::create_static_data(Some(Config::try_parse_from(args).unwrap()))
+                .no_progress_flag
+        };
+
+        let default_args = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w"];
+        assert!(!get_no_progress_flag(default_args));
+
+        let timeline_args = vec![
+            "hayabusa",
+            "json-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "--no-progress",
+        ];
+        assert!(get_no_progress_flag(timeline_args));
+
+        let search_args = vec![
+            "hayabusa",
+            "search",
+            "-f",
+            "test.evtx",
+            "-k",
+            "mimikatz",
+            "--no-progress",
+        ];
+        assert!(get_no_progress_flag(search_args));
+    }
+
+    #[test]
+    fn test_verbose_option() {
+        // -v/--verboseを指定した場合のみverbose_flagが有効になることを確認
+        let get_verbose_flag = |args: Vec<&str>| {
+            StoredStatic::create_static_data(Some(Config::try_parse_from(args).unwrap()))
+                .verbose_flag
+        };
+
+        let default_args = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w"];
+        assert!(!get_verbose_flag(default_args));
+
+        let short_args = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w", "-v"];
+        assert!(get_verbose_flag(short_args));
+
+        let long_args = vec![
+            "hayabusa",
+            "json-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "--verbose",
+        ];
+        assert!(get_verbose_flag(long_args));
+    }
+
+    #[test]
+    fn test_get_verbose_level() {
+        // -vの指定回数がログのレベルに使う値として取得できることを確認
+        let get_verbose_level = |args: Vec<&str>| {
+            configs::get_verbose_level(&Config::try_parse_from(args).unwrap().action)
+        };
+        let args = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w"];
+        assert_eq!(get_verbose_level(args), 0);
+        let args = vec![
+            "hayabusa",
+            "search",
+            "-f",
+            "test.evtx",
+            "-k",
+            "mimikatz",
+            "-v",
+        ];
+        assert_eq!(get_verbose_level(args), 1);
+        let args = vec!["hayabusa", "json-timeline", "-f", "test.evtx", "-w", "-vv"];
+        assert_eq!(get_verbose_level(args), 2);
+        let args = vec!["hayabusa", "update-rules"];
+        assert_eq!(get_verbose_level(args), 0);
+    }
+
     #[test]
     fn test_get_target_extensions() {
         let data = vec!["evtx_data".to_string(), "evtx_stars".to_string()];
@@ -2741,6 +3945,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_target_extensions_json_input() {
+        let ret = configs::get_target_extensions(None, true);
+        let expect: HashSet<String> = HashSet::from(["json".to_string(), "jsonl".to_string()]);
+        assert_eq!(ret, expect);
+    }
+
+    #[test]
+    fn test_json_input_detected_by_extension() {
+        // -fで.json/.jsonlファイルを指定した場合は-Jを指定しなくてもJSON形式の入力として扱うことを確認
+        let get_json_input_flag = |filepath: &str| {
+            let args = vec!["hayabusa", "csv-timeline", "-f", filepath, "-w"];
+            StoredStatic::create_static_data(Some(Config::try_parse_from(args).unwrap()))
+                .json_input_flag
+        };
+        assert!(!get_json_input_flag("test_files/evtx/test.evtx"));
+        assert!(get_json_input_flag("test_files/evtx/test.json"));
+        assert!(get_json_input_flag("test_files/evtx/test.jsonl"));
+        assert!(get_json_input_flag("test_files/evtx/TEST.JSONL"));
+        assert!(get_json_input_flag("test_files/evtx/test.jsonl.gz"));
+        assert!(get_json_input_flag("test_files/evtx/test.JSON.GZ"));
+        assert!(!get_json_input_flag("test_files/evtx/test.evtx.gz"));
+    }
+
+    #[test]
+    fn test_jsonl_output_detected_by_extension() {
+        // json-timelineで-oに.jsonlファイルを指定した場合は-Lを指定しなくてもJSONL形式の出力として扱うことを確認
+        let get_jsonl_timeline_flag = |output: &str| {
+            let args = vec!["hayabusa", "json-timeline", "-f", "test.evtx", "-o", output];
+            let stored_static =
+                StoredStatic::create_static_data(Some(Config::try_parse_from(args).unwrap()));
+            match stored_static.config.action {
+                Some(Action::JsonTimeline(opt)) => opt.jsonl_timeline,
+                _ => panic!("json-timeline was not parsed."),
+            }
+        };
+        assert!(!get_jsonl_timeline_flag("results.json"));
+        assert!(get_jsonl_timeline_flag("results.jsonl"));
+        assert!(get_jsonl_timeline_flag("results.JSONL"));
+
+        // --appendを指定した場合は拡張子に関わらずJSONL形式で出力する
+        let args = vec![
+            "hayabusa",
+            "json-timeline",
+            "-f",
+            "test.evtx",
+            "--append",
+            "-o",
+            "results.json",
+        ];
+        let stored_static =
+            StoredStatic::create_static_data(Some(Config::try_parse_from(args).unwrap()));
+        assert!(matches!(
+            stored_static.config.action,
+            Some(Action::JsonTimeline(opt)) if opt.append && opt.jsonl_timeline
+        ));
+
+        // --appendは--clobberと同時に指定できない
+        let args = vec![
+            "hayabusa",
+            "json-timeline",
+            "-f",
+            "test.evtx",
+            "--append",
+            "-C",
+            "-o",
+            "results.jsonl",
+        ];
+        assert!(Config::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_expand_output_timestamp() {
+        let now = Local.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        assert_eq!(
+            configs::expand_output_timestamp(Path::new("results-{timestamp}.csv"), &now),
+            PathBuf::from("results-20240102_030405.csv")
+        );
+        assert_eq!(
+            configs::expand_output_timestamp(Path::new("{timestamp}/results.jsonl"), &now),
+            PathBuf::from("20240102_030405/results.jsonl")
+        );
+        assert_eq!(
+            configs::expand_output_timestamp(Path::new("results.csv"), &now),
+            PathBuf::from("results.csv")
+        );
+    }
+
     #[test]
     fn no_target_extensions() {
         let ret = configs::get_target_extensions(None, false);
@@ -2777,6 +4069,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: Some("1d".to_string()),
                     },
@@ -2797,7 +4090,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -2810,10 +4103,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: true,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -2822,6 +4118,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -2855,6 +4167,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: Some("1y".to_string()),
                     },
@@ -2875,7 +4188,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -2888,10 +4201,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: true,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -2900,6 +4216,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -2915,6 +4247,8 @@ mod tests {
                 geo_ip: None,
                 output: None,
                 jsonl_timeline: false,
+                json_pretty: false,
+                append: false,
             })),
             debug: false,
         }));
@@ -2938,6 +4272,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: Some("1h".to_string()),
                 },
@@ -2950,7 +4285,8 @@ mod tests {
                 thread_number: None,
                 quiet_errors: false,
                 config: Path::new("./rules/config").to_path_buf(),
-                verbose: false,
+                verbose: 0,
+                no_progress: false,
                 multiline: false,
                 clobber: true,
                 json_output: false,
@@ -2985,6 +4321,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: Some("1h1m".to_string()),
                 },
@@ -3001,10 +4338,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: true,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
             })),
             debug: false,
@@ -3029,6 +4369,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: Some("1y1d1h".to_string()),
                 },
@@ -3045,10 +4386,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: true,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 end_timeline: None,
                 start_timeline: None,
@@ -3079,6 +4423,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: Some("1y1M1s".to_string()),
                 },
@@ -3088,10 +4433,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: true,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 end_timeline: None,
                 start_timeline: None,
@@ -1,14 +1,18 @@
 extern crate csv;
 
 use std::default::Default;
-use std::fmt::Write;
-use std::path::Path;
-use std::sync::Arc;
-
-use chrono::{TimeZone, Utc};
+use std::fmt::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, TimeZone, Utc};
+use comfy_table::modifiers::UTF8_ROUND_CORNERS;
+use comfy_table::presets::UTF8_FULL;
+use comfy_table::Table;
 use compact_str::CompactString;
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
+use lazy_static::lazy_static;
 use nested::Nested;
 use num_format::{Locale, ToFormattedString};
 use serde_json::Value;
@@ -21,22 +25,27 @@ use crate::detections::configs::STORED_EKEY_ALIAS;
 use crate::detections::field_data_map::FieldDataMapKey;
 use crate::detections::message::{AlertMessage, DetectInfo, ERROR_LOG_STACK, TAGS_CONFIG};
 use crate::detections::rule::correlation_parser::parse_correlation_rules;
-use crate::detections::rule::{self, AggResult, RuleNode};
-use crate::detections::utils::{create_recordinfos, format_time, write_color_buffer};
-use crate::detections::utils::{get_serde_number_to_string, make_ascii_titlecase};
+use crate::detections::rule::{self, AggResult, RuleNode, SelectReason};
+use crate::detections::utils::{
+    create_rec_info, create_recordinfos, format_time, get_event_id_key, get_event_value,
+    is_filtered_by_computer_name, write_color_buffer,
+};
+use crate::detections::utils::{get_serde_number_to_string, get_text_value, make_ascii_titlecase};
 use crate::filter;
 use crate::options::htmlreport;
 use crate::options::pivot::insert_pivot_keyword;
 use crate::options::profile::Profile::{
-    self, Channel, Computer, EventID, EvtxFile, Level, MitreTactics, MitreTags, OtherTags,
-    Provider, RecordID, RecoveredRecord, RenderedMessage, RuleAuthor, RuleCreationDate, RuleFile,
-    RuleID, RuleModifiedDate, RuleTitle, SrcASN, SrcCity, SrcCountry, Status, TgtASN, TgtCity,
-    TgtCountry, Timestamp,
+    self, Channel, Computer, EventHash, EventID, EvtxFile, Level, MatchedFields, MitreTactics,
+    MitreTags, OtherTags, Provider, RecordID, RecoveredRecord, RenderedMessage, RuleAuthor,
+    RuleCreationDate, RuleFile, RuleID, RuleModifiedDate, RuleTitle, SrcASN, SrcCity, SrcCountry,
+    Status, TgtASN, TgtCity, TgtCountry, Timestamp,
 };
+use crate::options::suppression;
 use crate::yaml::ParseYaml;
 
 use super::configs::{
-    EventKeyAliasConfig, StoredStatic, GEOIP_DB_PARSER, GEOIP_DB_YAML, GEOIP_FILTER, STORED_STATIC,
+    EventKeyAliasConfig, StoredStatic, TargetEventTime, TargetIds, GEOIP_DB_PARSER, GEOIP_DB_YAML,
+    GEOIP_FILTER, STORED_STATIC,
 };
 use super::message::{self, COMPUTER_MITRE_ATTCK_MAP, LEVEL_ABBR_MAP};
 
@@ -56,37 +65,503 @@ impl EvtxRecordInfo {
     }
 }
 
+lazy_static! {
+    pub static ref DETECTION_ERROR_REPORT: Mutex<DetectionErrorReport> =
+        Mutex::new(DetectionErrorReport::default());
+}
+
+/// 解析中に発生したエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DetectionError {
+    /// evtxファイルのレコードのパースに失敗した場合
+    EvtxParse { filepath: String, message: String },
+    /// JSONファイルの読み込みに失敗した場合
+    Json { filepath: String, message: String },
+    /// ルールファイルのパースに失敗した場合
+    Rule { rulepath: String, title: String },
+}
+
+impl fmt::Display for DetectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DetectionError::EvtxParse { filepath, message } => write!(
+                f,
+                "Failed to parse event file.\nEventFile: {filepath}\nError: {message}\n"
+            ),
+            DetectionError::Json { filepath, message } => write!(
+                f,
+                "Failed to parse JSON file.\nJSONFile: {filepath}\nError: {message}\n"
+            ),
+            DetectionError::Rule { rulepath, title } => write!(
+                f,
+                "Failed to parse rule file. (FilePath : {rulepath}, Title : {title})"
+            ),
+        }
+    }
+}
+
+/// 解析中に発生したエラーの種類ごとの件数。呼び出し側で件数に応じて処理を中断するかなどを判断するために使う
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DetectionErrorReport {
+    pub evtx_parse_error_cnt: usize,
+    pub json_error_cnt: usize,
+    pub rule_error_cnt: usize,
+}
+
+impl DetectionErrorReport {
+    pub fn add(&mut self, err: &DetectionError) {
+        match err {
+            DetectionError::EvtxParse { .. } => self.evtx_parse_error_cnt += 1,
+            DetectionError::Json { .. } => self.json_error_cnt += 1,
+            DetectionError::Rule { .. } => self.rule_error_cnt += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.evtx_parse_error_cnt + self.json_error_cnt + self.rule_error_cnt
+    }
+}
+
+impl fmt::Display for DetectionErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Event file parse errors: {} / JSON file errors: {} / Rule parse errors: {}",
+            self.evtx_parse_error_cnt.to_formatted_string(&Locale::en),
+            self.json_error_cnt.to_formatted_string(&Locale::en),
+            self.rule_error_cnt.to_formatted_string(&Locale::en)
+        )
+    }
+}
+
+/// レコードのパース結果のイテレータをラップし、パースに失敗したレコードはエラーとして記録した上で読み飛ばすイテレータ。
+/// 破損したレコードやチャンクがあっても、ファイル内の残りのレコードの解析は継続する
+pub struct SkipCorruptRecords<I> {
+    records: I,
+    filepath: String,
+    verbose_flag: bool,
+    quiet_errors_flag: bool,
+    pub read_cnt: usize,    // パースに失敗したものも含めた読み込んだレコード数
+    pub corrupt_cnt: usize, // パースに失敗して読み飛ばしたレコード数
+}
+
+impl<I> SkipCorruptRecords<I> {
+    pub fn new(
+        records: I,
+        filepath: &str,
+        verbose_flag: bool,
+        quiet_errors_flag: bool,
+    ) -> SkipCorruptRecords<I> {
+        SkipCorruptRecords {
+            records,
+            filepath: filepath.to_string(),
+            verbose_flag,
+            quiet_errors_flag,
+            read_cnt: 0,
+            corrupt_cnt: 0,
+        }
+    }
+}
+
+impl<I, T, E> Iterator for SkipCorruptRecords<I>
+where
+    I: Iterator<Item = Result<T, E>>,
+    E: fmt::Display,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        for record in self.records.by_ref() {
+            self.read_cnt += 1;
+            match record {
+                Ok(record) => return Some(record),
+                Err(e) => {
+                    self.corrupt_cnt += 1;
+                    let err = DetectionError::EvtxParse {
+                        filepath: self.filepath.clone(),
+                        message: e.to_string(),
+                    };
+                    DETECTION_ERROR_REPORT.lock().unwrap().add(&err);
+                    let errmsg = err.to_string();
+                    if self.verbose_flag {
+                        AlertMessage::alert(&errmsg).ok();
+                    }
+                    if !self.quiet_errors_flag {
+                        ERROR_LOG_STACK
+                            .lock()
+                            .unwrap()
+                            .push(format!("[ERROR] {errmsg}"));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// scan_recordsに渡す1レコード分のイベント
+#[derive(Debug, Clone)]
+pub struct ScanRecord {
+    pub event: Value,                     // JSON形式のイベント
+    pub timestamp: Option<DateTime<Utc>>, // イベントの発生時刻。時刻によるフィルタリングに使う
+    pub recovered: bool,                  // レコードが復元されたかどうか
+}
+
+impl ScanRecord {
+    /// イベントの発生時刻をイベント自体から取得してScanRecordを作成します。
+    pub fn from_event(event: Value, json_input_flag: bool) -> ScanRecord {
+        ScanRecord {
+            timestamp: message::get_event_time(&event, json_input_flag),
+            event,
+            recovered: false,
+        }
+    }
+}
+
+/// scan_recordsで検知したイベントの情報
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub timestamp: Option<DateTime<Utc>>, // イベントの発生時刻
+    pub rule_index: usize,                // 検知したルールのDetection内でのインデックス
+    pub record_index: usize,              // 検知したレコードのScanResult::records内でのインデックス
+    pub detect_info: DetectInfo,          // 出力用の検知結果
+}
+
+/// scan_recordsの結果
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    pub records: Vec<EvtxRecordInfo>, // フィルタリング後のレコード。timeline機能の集計に使う
+    pub hits: Vec<Hit>,
+}
+
+/// 検知対象のレコードを絞り込む条件。EVTXファイルとJSONファイルのどちらから読み込んだレコードも同じ条件で絞り込む
+pub struct RecordFilter<'a> {
+    pub time_filter: &'a TargetEventTime,
+    pub target_event_ids: &'a TargetIds,
+    pub channel_key: &'a str, // Channelの値を取得するキー。JSON形式のイベントログではEvent.EventData.Channelを参照する
+}
+
+impl RecordFilter<'_> {
+    /// Computer名、EventID、Channel、時刻によるフィルタリングを行い、検知対象のレコードであればtrueを返す。
+    pub fn is_target(&self, record: &ScanRecord, stored_static: &StoredStatic) -> bool {
+        let data = &record.event;
+        let eventkey_alias = &stored_static.eventkey_alias;
+        // Searchならすべてのフィルタを無視
+        if !stored_static.search_flag {
+            // Computer名がinclude_computerで指定されたものに合致しないまたはexclude_computerで指定されたものに合致した場合はフィルタリングする。
+            if is_filtered_by_computer_name(
+                get_event_value("Event.System.Computer", data, eventkey_alias),
+                (
+                    &stored_static.include_computer,
+                    &stored_static.exclude_computer,
+                ),
+            ) {
+                return false;
+            }
+
+            // EventIDがinclude_eidで指定されたものに合致しないまたはexclude_eidで指定されたものに合致した場合、target_eventids.txtで指定されたEventIDではない場合はフィルタリングする。
+            let eid_filter = stored_static
+                .output_option
+                .as_ref()
+                .is_some_and(|opt| opt.eid_filter);
+            if self.is_filtered_by_eid(
+                data,
+                eventkey_alias,
+                (&stored_static.include_eid, &stored_static.exclude_eid),
+                eid_filter,
+            ) {
+                return false;
+            }
+
+            // channelがnullである場合はフィルタリングする。
+            if !self.is_valid_channel(data, eventkey_alias) {
+                return false;
+            }
+        }
+        // EventID側の条件との条件の混同を防ぐため時間でのフィルタリングの条件分岐を分離した
+        self.time_filter.is_target(&record.timestamp)
+    }
+
+    /// target_eventids.txtの設定を元にフィルタする。 trueであれば検知確認対象のEventIDであることを意味する。
+    fn is_target_event_id(&self, data: &Value, eventkey_alias: &EventKeyAliasConfig) -> bool {
+        match get_event_value(&get_event_id_key(), data, eventkey_alias) {
+            Some(Value::String(s)) => self.target_event_ids.is_target(&s.replace('\"', ""), true),
+            Some(Value::Number(n)) => self
+                .target_event_ids
+                .is_target(&n.to_string().replace('\"', ""), true),
+            _ => true, // レコードからEventIdが取得できない場合は、特にフィルタしない
+        }
+    }
+
+    /// レコードのチャンネルの値が正しい(Stringの形でありnullでないもの)ことを判定する関数
+    fn is_valid_channel(&self, data: &Value, eventkey_alias: &EventKeyAliasConfig) -> bool {
+        match get_event_value(self.channel_key, data, eventkey_alias) {
+            Some(Value::String(s)) => s != "null",
+            _ => false, // channelの値は文字列を想定しているため、それ以外のデータが来た場合はfalseを返す
+        }
+    }
+
+    fn is_filtered_by_eid(
+        &self,
+        data: &Value,
+        eventkey_alias: &EventKeyAliasConfig,
+        (include_eid, exclude_eid): (&HashSet<CompactString>, &HashSet<CompactString>),
+        eid_filter: bool,
+    ) -> bool {
+        let target_eid = if !include_eid.is_empty() || !exclude_eid.is_empty() {
+            get_event_value(&get_event_id_key(), data, eventkey_alias)
+                .and_then(|eid_record| get_serde_number_to_string(eid_record, false))
+                .unwrap_or_default()
+        } else {
+            CompactString::default()
+        };
+        // 以下の場合はフィルタリングする。
+        // 1. include_eidが指定されているが、include_eidに含まれていない場合
+        // 2. exclude_eidが指定されていて、exclude_eidに含まれている場合
+        // 3. eid_filterが指定されていて、target_eventids.txtで指定されたEventIDでない場合
+        (!include_eid.is_empty() && !include_eid.contains(&target_eid))
+            || (!exclude_eid.is_empty() && exclude_eid.contains(&target_eid))
+            || (eid_filter && !self.is_target_event_id(data, eventkey_alias))
+    }
+}
+
+/// ルールが検知に必須とするEventIDごとに、ルールのインデックスをまとめたもの。
+/// レコードのEventIDに対応するルールとcatch_allのルールだけを評価することで、関係のないルールの評価を省略する
+#[derive(Debug, Default)]
+struct RuleEventIdIndex {
+    by_eventid: HashMap<String, Vec<usize>>,
+    catch_all: Vec<usize>, // 必須となるEventIDを判定できないルール。全てのレコードを評価する
+}
+
+impl RuleEventIdIndex {
+    fn new(rules: &[RuleNode]) -> RuleEventIdIndex {
+        let mut index = RuleEventIdIndex::default();
+        for (rule_index, rule) in rules.iter().enumerate() {
+            let eventids = rule.get_target_eventids();
+            if eventids.is_empty() {
+                index.catch_all.push(rule_index);
+                continue;
+            }
+            for eventid in eventids {
+                index
+                    .by_eventid
+                    .entry(eventid.to_owned())
+                    .or_default()
+                    .push(rule_index);
+            }
+        }
+        index
+    }
+
+    /// レコードごとにEventIDに対応するルールを引き、ルールごとに評価するレコードのインデックスを返す。
+    /// catch_allのルールは全てのレコードを評価するのでNoneを返す。EventIDが取得できないレコードは全てのルールで評価する
+    fn dispatch(&self, rule_cnt: usize, records: &[EvtxRecordInfo]) -> Vec<Option<Vec<usize>>> {
+        let mut targets = vec![Some(vec![]); rule_cnt];
+        for rule_index in &self.catch_all {
+            targets[*rule_index] = None;
+        }
+        for (record_index, record) in records.iter().enumerate() {
+            match record.get_value("EventID") {
+                Some(eventid) => {
+                    for rule_index in self.by_eventid.get(eventid).into_iter().flatten() {
+                        if let Some(target) = targets[*rule_index].as_mut() {
+                            target.push(record_index);
+                        }
+                    }
+                }
+                None => targets
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|target| target.push(record_index)),
+            }
+        }
+        targets
+    }
+}
+
 #[derive(Debug)]
 pub struct Detection {
     rules: Vec<RuleNode>,
+    rule_keys: Nested<String>, // ルールの検知で参照するキーの一覧。EvtxRecordInfoの作成に使う
+    eventid_index: Option<RuleEventIdIndex>,
+    pub suppressed_cnt: u128, // --suppressで抑制した検知の数
 }
 
 impl Detection {
     pub fn new(rule_nodes: Vec<RuleNode>) -> Detection {
-        Detection { rules: rule_nodes }
+        Detection {
+            rule_keys: Detection::get_all_keys(&rule_nodes),
+            rules: rule_nodes,
+            eventid_index: None,
+            suppressed_cnt: 0,
+        }
+    }
+
+    /// ルールの検知で参照するキーの一覧を作成します。
+    fn get_all_keys(rules: &[RuleNode]) -> Nested<String> {
+        let mut key_set = HashSet::new();
+        for rule in rules {
+            key_set.extend(rule::get_detection_keys(rule).iter().map(|x| x.to_string()));
+        }
+        key_set.into_iter().collect::<Nested<String>>()
     }
 
-    pub fn start(self, rt: &Runtime, records: Vec<EvtxRecordInfo>) -> (Self, Vec<DetectInfo>) {
-        rt.block_on(self.execute_rules(records))
+    /// レコードのフィルタリング、EvtxRecordInfoの作成、ルールの実行をまとめて行います。
+    /// EVTXファイルとJSONファイルのどちらから読み込んだレコードも、この関数を経由して同じ条件で絞り込んだ上で検知を行います。
+    /// metrics、logon-summary、search、computer-metricsコマンドではルールを実行せず、フィルタリング後のレコードのみを返します。
+    /// aggregation conditionを持つルールはここでは検知結果を返さず、add_aggcondition_msgesで集計結果を出力します。
+    /// レコードの値の取得とルールの実行ではSTORED_EKEY_ALIASとSTORED_STATICを参照するので、呼び出し元で事前にstored_staticと同じ設定を格納しておく必要があります。
+    /// 格納されていない場合はpanicします。
+    pub fn scan_records<I>(
+        mut self,
+        rt: &Runtime,
+        records: I,
+        filepath: &str,
+        record_filter: &RecordFilter,
+        stored_static: &StoredStatic,
+    ) -> (Self, ScanResult)
+    where
+        I: IntoIterator<Item = ScanRecord>,
+    {
+        assert!(
+            STORED_EKEY_ALIAS.read().unwrap().is_some() && STORED_STATIC.read().unwrap().is_some(),
+            "STORED_EKEY_ALIAS and STORED_STATIC must be initialized before calling Detection::scan_records."
+        );
+        let (target_records, timestamps): (Vec<_>, Vec<_>) = records
+            .into_iter()
+            .filter(|record| record_filter.is_target(record, stored_static))
+            .map(|record| ((record.event, record.recovered), record.timestamp))
+            .unzip();
+        let records = rt.block_on(Detection::create_rec_infos(
+            target_records,
+            filepath,
+            self.rule_keys.to_owned(),
+            stored_static.no_pwsh_field_extraction,
+        ));
+        // 以下のコマンドの際にはルールにかけない
+        if stored_static.metrics_flag
+            || stored_static.logon_summary_flag
+            || stored_static.search_flag
+            || stored_static.computer_metrics_flag
+        {
+            return (
+                self,
+                ScanResult {
+                    records,
+                    hits: vec![],
+                },
+            );
+        }
+
+        // ルールのEventIDのインデックスは、最初に呼び出された時に一度だけ作成する
+        if self.eventid_index.is_none() {
+            self.eventid_index = Some(RuleEventIdIndex::new(&self.rules));
+        }
+        let (mut detection, records, detected) = rt.block_on(self.execute_rules(records));
+        let mut hits = vec![];
+        for (rule_index, record_index, detect_info) in detected {
+            // --suppressで指定した既知の誤検知に一致する場合は出力せず、抑制した数だけ数える
+            if Detection::is_suppressed(&records[record_index], stored_static) {
+                detection.suppressed_cnt += 1;
+                continue;
+            }
+            hits.push(Hit {
+                timestamp: timestamps[record_index],
+                rule_index,
+                record_index,
+                detect_info,
+            });
+        }
+        Detection::warn_missing_event_time(&hits, &records, stored_static);
+        (detection, ScanResult { records, hits })
+    }
+
+    async fn create_rec_infos(
+        records: Vec<(Value, bool)>,
+        filepath: &str,
+        rule_keys: Nested<String>,
+        no_pwsh_field_extraction: bool,
+    ) -> Vec<EvtxRecordInfo> {
+        let filepath = Arc::new(filepath.to_string());
+        let rule_keys = Arc::new(rule_keys);
+        let threads: Vec<JoinHandle<EvtxRecordInfo>> = records
+            .into_iter()
+            .map(|(rec, recovered_record_flag)| {
+                let arc_rule_keys = Arc::clone(&rule_keys);
+                let arc_filepath = Arc::clone(&filepath);
+                spawn(async move {
+                    create_rec_info(
+                        rec,
+                        arc_filepath.to_string(),
+                        &arc_rule_keys,
+                        &recovered_record_flag,
+                        &no_pwsh_field_extraction,
+                    )
+                })
+            })
+            .collect();
+
+        let mut ret = vec![];
+        for thread in threads.into_iter() {
+            ret.push(thread.await.unwrap());
+        }
+        ret
+    }
+
+    /// ルールが検知したイベントレコードが、--suppressで指定した既知の誤検知に一致するかどうかを判定します。
+    fn is_suppressed(record_info: &EvtxRecordInfo, stored_static: &StoredStatic) -> bool {
+        stored_static.suppression_list.as_ref().is_some_and(|list| {
+            list.is_suppressed(&record_info.record, &stored_static.eventkey_alias)
+        })
+    }
+
+    /// イベントレコードがルールに一致するかどうかを判定します。
+    fn select_record(
+        rule: &mut RuleNode,
+        record_info: &EvtxRecordInfo,
+        stored_static: &StoredStatic,
+    ) -> bool {
+        rule.select(
+            record_info,
+            stored_static.verbose_flag,
+            stored_static.quiet_errors_flag,
+            stored_static.json_input_flag,
+            &stored_static.eventkey_alias,
+        )
     }
 
     // ルールファイルをパースします。
     pub fn parse_rule_files(
         min_level: &str,
         target_level: &str,
-        rulespath: &Path,
+        rulespaths: &[PathBuf],
         exclude_ids: &filter::RuleExclude,
         stored_static: &StoredStatic,
     ) -> Vec<RuleNode> {
-        // ルールファイルのパースを実行
+        // ルールファイルのパースを実行。-rで複数のフォルダが指定された場合は、指定された順に読み込む
         let mut rulefile_loader = ParseYaml::new(stored_static);
-        let result_readdir = rulefile_loader.read_dir(
-            rulespath,
+        let result_readdir = rulefile_loader.read_dirs(
+            rulespaths,
             min_level,
             target_level,
             exclude_ids,
             stored_static,
         );
+        // 次回以降の実行でパースを省略できるように、ルールのパース結果をキャッシュファイルに保存する
+        if let Some(rule_cache) = rulefile_loader.rule_cache.as_mut() {
+            if let Err(e) = rule_cache.save() {
+                let errmsg = format!("Failed to save the rule cache file. {e}");
+                if stored_static.verbose_flag {
+                    AlertMessage::warn(&errmsg).ok();
+                }
+                if !stored_static.quiet_errors_flag {
+                    ERROR_LOG_STACK
+                        .lock()
+                        .unwrap()
+                        .push(format!("[WARN] {errmsg}"));
+                }
+            }
+        }
         if result_readdir.is_err() {
             let errmsg = format!("{}", result_readdir.unwrap_err());
             if stored_static.verbose_flag {
@@ -101,6 +576,9 @@ impl Detection {
             return vec![];
         }
         let mut parseerror_count = rulefile_loader.errorrule_count;
+        // ルールファイルの読み込み時に発生したエラーの件数を加算する
+        DETECTION_ERROR_REPORT.lock().unwrap().rule_error_cnt +=
+            rulefile_loader.errorrule_count as usize;
         let return_if_success = |mut rule: RuleNode| {
             let err_msgs_result = rule.init(stored_static);
             if err_msgs_result.is_ok() {
@@ -109,8 +587,12 @@ impl Detection {
 
             // ruleファイルのパースに失敗した場合はエラー出力
             err_msgs_result.err().iter().for_each(|err_msgs| {
-                let errmsg_body =
-                    format!("Failed to parse rule file. (FilePath : {})", rule.rulepath);
+                let err = DetectionError::Rule {
+                    rulepath: rule.get_rulepath_with_doc_index(),
+                    title: rule.title(),
+                };
+                DETECTION_ERROR_REPORT.lock().unwrap().add(&err);
+                let errmsg_body = err.to_string();
                 if stored_static.verbose_flag {
                     AlertMessage::warn(&errmsg_body).ok();
                     err_msgs.iter().for_each(|err_msg| {
@@ -138,15 +620,73 @@ impl Detection {
         let mut ret = rulefile_loader
             .files
             .into_iter()
-            .map(|rule_file_tuple| rule::create_rule(rule_file_tuple.0, rule_file_tuple.1))
+            .map(|(filepath, doc_index, yaml)| {
+                let mut rule = rule::create_rule(filepath, yaml);
+                rule.doc_index = doc_index;
+                rule
+            })
             .filter_map(return_if_success)
             .collect();
+        let before_correlation_parseerror_count = parseerror_count;
         ret = parse_correlation_rules(ret, stored_static, &mut parseerror_count);
+        DETECTION_ERROR_REPORT.lock().unwrap().rule_error_cnt +=
+            (parseerror_count - before_correlation_parseerror_count) as usize;
         if !(stored_static.logon_summary_flag
             || stored_static.search_flag
             || stored_static.metrics_flag
             || stored_static.computer_metrics_flag)
         {
+            if !stored_static.common_options.quiet
+                && stored_static
+                    .output_option
+                    .as_ref()
+                    .is_some_and(|opt| opt.mitre.is_some())
+            {
+                write_color_buffer(
+                    &BufferWriter::stdout(ColorChoice::Always),
+                    None,
+                    &format!(
+                        "Rules matching the MITRE ATT&CK filter: {}",
+                        rulefile_loader
+                            .mitre_match_cnt
+                            .to_formatted_string(&Locale::en)
+                    ),
+                    true,
+                )
+                .ok();
+            }
+            if let Some(rule_cache) = rulefile_loader
+                .rule_cache
+                .as_ref()
+                .filter(|_| !stored_static.common_options.quiet)
+            {
+                write_color_buffer(
+                    &BufferWriter::stdout(ColorChoice::Always),
+                    None,
+                    &format!(
+                        "Rule files loaded from the rule cache: {}",
+                        rule_cache.hit_cnt.to_formatted_string(&Locale::en)
+                    ),
+                    true,
+                )
+                .ok();
+            }
+            if let Some(suppression_list) = stored_static
+                .suppression_list
+                .as_ref()
+                .filter(|_| !stored_static.common_options.quiet)
+            {
+                write_color_buffer(
+                    &BufferWriter::stdout(ColorChoice::Always),
+                    None,
+                    &format!(
+                        "Suppression entries loaded: {}",
+                        suppression_list.len().to_formatted_string(&Locale::en)
+                    ),
+                    true,
+                )
+                .ok();
+            }
             Detection::print_rule_load_info(
                 &rulefile_loader.rulecounter,
                 &rulefile_loader.rule_load_cnt,
@@ -159,26 +699,39 @@ impl Detection {
     }
 
     // 複数のイベントレコードに対して、複数のルールを1個実行します。
-    async fn execute_rules(mut self, records: Vec<EvtxRecordInfo>) -> (Self, Vec<DetectInfo>) {
+    // ルール毎のスレッドで並列に判定しますが、検知結果は各スレッドの結果をルールの読み込み順に結合するため、
+    // スレッドの実行順に関わらず「ルールの読み込み順、同じルール内ではレコードの順」で常に同じ順番になります。
+    async fn execute_rules(
+        mut self,
+        records: Vec<EvtxRecordInfo>,
+    ) -> (Self, Vec<EvtxRecordInfo>, Vec<(usize, usize, DetectInfo)>) {
+        // レコードのEventIDから評価が必要なルールを引き、ルールごとに評価するレコードを振り分ける
+        let target_records = match &self.eventid_index {
+            Some(index) => index.dispatch(self.rules.len(), &records),
+            None => vec![None; self.rules.len()],
+        };
         let records_arc = Arc::new(records);
         // // 各rule毎にスレッドを作成して、スレッドを起動する。
         let rules = self.rules;
-        let handles: Vec<JoinHandle<(RuleNode, Vec<DetectInfo>)>> = rules
+        let handles: Vec<JoinHandle<(RuleNode, Vec<(usize, DetectInfo)>)>> = rules
             .into_iter()
-            .map(|rule| {
+            .zip(target_records)
+            .map(|(rule, target_record_indices)| {
                 let records_cloned = Arc::clone(&records_arc);
-                spawn(async move { Detection::execute_rule(rule, records_cloned) })
+                spawn(async move {
+                    Detection::execute_rule(rule, records_cloned, target_record_indices)
+                })
             })
             .collect();
 
         // 全スレッドの実行完了を待機
         let mut rules = vec![];
         let mut all_log_records = vec![];
-        for handle in handles {
+        for (rule_index, handle) in handles.into_iter().enumerate() {
             let (ret_rule, log_records) = handle.await.unwrap();
             rules.push(ret_rule);
-            for log_record in log_records {
-                all_log_records.push(log_record);
+            for (record_index, log_record) in log_records {
+                all_log_records.push((rule_index, record_index, log_record));
             }
         }
 
@@ -187,7 +740,9 @@ impl Detection {
         // self.rulesが再度所有権を取り戻せるように、Detection::execute_ruleで引数に渡したruleを戻り値として返すようにしている。
         self.rules = rules;
 
-        (self, all_log_records)
+        // 全スレッドの終了後はレコードの参照が残っていないので、timeline機能などで使うためにレコードの所有権を取り戻す
+        let records = Arc::try_unwrap(records_arc).unwrap_or_else(|records| records.to_vec());
+        (self, records, all_log_records)
     }
 
     pub fn add_aggcondition_msges(
@@ -214,23 +769,39 @@ impl Detection {
     }
 
     // 複数のイベントレコードに対して、ルールを1個実行します。
+    // target_record_indicesが指定されている場合は、EventIDのインデックスで振り分けたレコードのみを評価します。
     fn execute_rule(
         mut rule: RuleNode,
         records: Arc<Vec<EvtxRecordInfo>>,
-    ) -> (RuleNode, Vec<DetectInfo>) {
+        target_record_indices: Option<Vec<usize>>,
+    ) -> (RuleNode, Vec<(usize, DetectInfo)>) {
         let agg_condition = rule.has_agg_condition();
         let binding = STORED_STATIC.read().unwrap();
         let stored_static = binding.as_ref().unwrap();
         let mut ret = vec![];
-        for record_info in records.as_ref() {
-            let result = rule.select(
-                record_info,
-                stored_static.verbose_flag,
-                stored_static.quiet_errors_flag,
-                stored_static.json_input_flag,
-                &stored_static.eventkey_alias,
-            );
-            if !result {
+        let debug_rule = stored_static
+            .output_option
+            .as_ref()
+            .is_some_and(|opt| opt.debug_rule.is_some());
+        // --debug-ruleの場合は全てのレコードに対する評価結果を表示するので、振り分けたレコードに限定しない
+        let target_record_indices: Vec<usize> = match target_record_indices {
+            Some(indices) if !debug_rule => indices,
+            _ => (0..records.len()).collect(),
+        };
+        for record_index in target_record_indices {
+            let record_info = &records[record_index];
+            // --debug-ruleが指定された場合は、検知結果とは別に全てのレコードに対する評価結果を標準エラー出力に表示する
+            if debug_rule {
+                let reason = rule.select_with_reason(record_info, &stored_static.eventkey_alias);
+                write_color_buffer(
+                    &BufferWriter::stderr(ColorChoice::Always),
+                    None,
+                    &Detection::create_debug_rule_line(record_info, &reason),
+                    true,
+                )
+                .ok();
+            }
+            if !Detection::select_record(&mut rule, record_info, stored_static) {
                 continue;
             }
 
@@ -241,10 +812,9 @@ impl Detection {
 
             // aggregation conditionが存在しない場合はそのまま出力対応を行う
             if !agg_condition {
-                ret.push(Detection::create_log_record(
-                    &rule,
-                    record_info,
-                    stored_static,
+                ret.push((
+                    record_index,
+                    Detection::create_log_record(&rule, record_info, stored_static),
                 ));
             }
         }
@@ -252,6 +822,71 @@ impl Detection {
         (rule, ret)
     }
 
+    /// イベントの時刻が取得できないレコードに使用する時刻を返す。--default-timestampが指定されていない場合は1970-01-01 00:00:00 UTC
+    fn get_default_timestamp(stored_static: &StoredStatic) -> DateTime<Utc> {
+        stored_static
+            .output_option
+            .as_ref()
+            .and_then(|opt| opt.default_timestamp)
+            .unwrap_or_else(|| Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0).unwrap())
+    }
+
+    /// 検知したレコードのイベントの時刻が取得できない場合に、デフォルトの時刻を使用する旨の警告をレコードごとに1回だけ出力する
+    fn warn_missing_event_time(
+        hits: &[Hit],
+        records: &[EvtxRecordInfo],
+        stored_static: &StoredStatic,
+    ) {
+        for record_index in hits.iter().map(|hit| hit.record_index).unique() {
+            let record_info = &records[record_index];
+            if message::get_event_time(&record_info.record, stored_static.json_input_flag).is_some()
+            {
+                continue;
+            }
+            let errmsg = format!(
+                "Failed to get the event timestamp. The default time is used instead. (EventFile : {}, EventRecordID : {})",
+                record_info.evtx_filepath,
+                get_serde_number_to_string(
+                    &record_info.record["Event"]["System"]["EventRecordID"],
+                    false
+                )
+                .unwrap_or_else(|| "-".into())
+            );
+            if stored_static.verbose_flag {
+                AlertMessage::warn(&errmsg).ok();
+            }
+            if !stored_static.quiet_errors_flag {
+                ERROR_LOG_STACK
+                    .lock()
+                    .unwrap()
+                    .push(format!("[WARN] {errmsg}"));
+            }
+        }
+    }
+
+    /// --debug-ruleで表示する、1レコードに対するルールの評価結果の文字列を作成する
+    pub fn create_debug_rule_line(record_info: &EvtxRecordInfo, reason: &SelectReason) -> String {
+        let system = &record_info.record["Event"]["System"];
+        let get_system_value = |key: &str| {
+            get_serde_number_to_string(&system[key], false).unwrap_or_else(|| "-".into())
+        };
+        let selections = reason
+            .selections
+            .iter()
+            .map(|(name, result)| format!("{name}:{result}"))
+            .join(" ¦ ");
+        format!(
+            "[Rule Debug] {} ¦ EventRecordID:{} ¦ EventID:{} ¦ TargetChannel:{} ¦ TargetEventID:{} ¦ {} ¦ Result:{}",
+            record_info.evtx_filepath,
+            get_system_value("EventRecordID"),
+            get_system_value("EventID"),
+            reason.is_target_channel,
+            reason.is_target_eventid,
+            selections,
+            reason.result
+        )
+    }
+
     /// create log record
     fn create_log_record(
         rule: &RuleNode,
@@ -283,18 +918,20 @@ impl Detection {
         )
         .unwrap_or_default()
         .replace('\'', "");
-        let eid =
-            get_serde_number_to_string(&record_info.record["Event"]["System"]["EventID"], false)
-                .unwrap_or_else(|| "-".into());
+        let eid = get_serde_number_to_string(
+            get_text_value(&record_info.record["Event"]["System"]["EventID"]),
+            false,
+        )
+        .unwrap_or_else(|| "-".into());
         let recovered_record = if record_info.recovered_record {
             "Y"
         } else {
             ""
         };
 
-        let default_time = Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0).unwrap();
+        // イベントの時刻が取得できない場合は--default-timestampで指定された時刻を使用する。警告はscan_recordsでレコードごとに出力する
         let time = message::get_event_time(&record_info.record, stored_static.json_input_flag)
-            .unwrap_or(default_time);
+            .unwrap_or_else(|| Detection::get_default_timestamp(stored_static));
         let level = rule.yaml["level"].as_str().unwrap_or("-").to_string();
 
         let mut profile_converter: HashMap<&str, Profile> = HashMap::new();
@@ -366,16 +1003,7 @@ impl Detection {
                     profile_converter.insert(key.as_str(), RecordID(rec_id.to_string().into()));
                 }
                 RuleTitle(_) => {
-                    profile_converter.insert(
-                        key.as_str(),
-                        RuleTitle(
-                            rule.yaml["title"]
-                                .as_str()
-                                .unwrap_or_default()
-                                .to_string()
-                                .into(),
-                        ),
-                    );
+                    profile_converter.insert(key.as_str(), RuleTitle(rule.title().into()));
                 }
                 RuleFile(_) => {
                     let rule_file_path = CompactString::from(
@@ -538,6 +1166,24 @@ impl Detection {
                     profile_converter
                         .insert("RecoveredRecord", RecoveredRecord(recovered_record.into()));
                 }
+                MatchedFields(_) => {
+                    // 検知の根拠となったselectionのキーとレコードの値を出力する
+                    // 検知したレコードのみ再度selectionを評価するので、スキャン中の負荷は増えない
+                    let matched_fields = rule.get_matched_fields(record_info, eventkey_alias);
+                    let matched_fields = if matched_fields.is_empty() {
+                        "-".to_string()
+                    } else {
+                        matched_fields.join(" ¦ ")
+                    };
+                    profile_converter.insert(key.as_str(), MatchedFields(matched_fields.into()));
+                }
+                EventHash(_) => {
+                    // --suppressで指定するためのレコード全体のハッシュ値を出力する
+                    profile_converter.insert(
+                        key.as_str(),
+                        EventHash(suppression::event_hash(&record_info.record).into()),
+                    );
+                }
                 RenderedMessage(_) => {
                     let convert_value = if let Some(message) =
                         record_info.record["Event"]["RenderingInfo"]["Message"].as_str()
@@ -726,7 +1372,7 @@ impl Detection {
             detected_time: time,
             rulepath: CompactString::from(&rule.rulepath),
             ruleid: CompactString::from(rule.yaml["id"].as_str().unwrap_or("-")),
-            ruletitle: CompactString::from(rule.yaml["title"].as_str().unwrap_or("-")),
+            ruletitle: CompactString::from(rule.title()),
             level: CompactString::from(
                 LEVEL_ABBR_MAP
                     .get(&level.as_str())
@@ -811,16 +1457,7 @@ impl Detection {
                     profile_converter.insert(key.as_str(), RecordID("-".into()));
                 }
                 RuleTitle(_) => {
-                    profile_converter.insert(
-                        key.as_str(),
-                        RuleTitle(
-                            rule.yaml["title"]
-                                .as_str()
-                                .unwrap_or_default()
-                                .to_owned()
-                                .into(),
-                        ),
-                    );
+                    profile_converter.insert(key.as_str(), RuleTitle(rule.title().into()));
                 }
                 RuleFile(_) => {
                     let rule_path = Path::new(&rule.rulepath)
@@ -927,6 +1564,12 @@ impl Detection {
                 RecoveredRecord(_) => {
                     profile_converter.insert("RecoveredRecord", RenderedMessage("".into()));
                 }
+                MatchedFields(_) => {
+                    profile_converter.insert(key.as_str(), MatchedFields("-".into()));
+                }
+                EventHash(_) => {
+                    profile_converter.insert(key.as_str(), EventHash("-".into()));
+                }
                 RenderedMessage(_) => {
                     profile_converter.insert(key.as_str(), RenderedMessage("-".into()));
                 }
@@ -954,7 +1597,7 @@ impl Detection {
             detected_time: agg_result.start_timedate,
             rulepath: CompactString::from(&rule.rulepath),
             ruleid: CompactString::from(rule.yaml["id"].as_str().unwrap_or("-")),
-            ruletitle: CompactString::from(rule.yaml["title"].as_str().unwrap_or("-")),
+            ruletitle: CompactString::from(rule.title()),
             level: CompactString::from(
                 LEVEL_ABBR_MAP
                     .get(str_level)
@@ -1029,6 +1672,15 @@ impl Detection {
             } else {
                 write!(ret, " ¦ {}:{}", field_name, agg_result.key).ok();
             }
+        } else if !rule.yaml["detection"]["timeframe"].is_badvalue() {
+            // byの指定がない場合は、timeframe毎に検知されるので検知したtimeframeの範囲を出力する
+            write!(
+                ret,
+                " ¦ Start:{} ¦ End:{}",
+                agg_result.start_timedate.format("%Y-%m-%d %H:%M:%S%.3f"),
+                agg_result.end_timedate.format("%Y-%m-%d %H:%M:%S%.3f")
+            )
+            .ok();
         }
 
         CompactString::from(ret)
@@ -1044,6 +1696,46 @@ impl Detection {
             .join(" ¦ ")
     }
 
+    /// --list-rulesで表示する、ルールのid, title, level, status, ルールファイルのパスの一覧をルールファイルのパス順で作成する
+    pub fn create_rule_list(rules: &[RuleNode]) -> Vec<[String; 5]> {
+        rules
+            .iter()
+            .map(|rule| {
+                let get_value = |key: &str| rule.yaml[key].as_str().unwrap_or("-").to_string();
+                [
+                    get_value("id"),
+                    rule.title(),
+                    get_value("level"),
+                    get_value("status"),
+                    rule.get_rulepath_with_doc_index(),
+                ]
+            })
+            .sorted_by(|a, b| a[4].cmp(&b[4]))
+            .collect()
+    }
+
+    /// 読み込まれたルールの一覧を作成する。--show-unused-rulesで検知しなかったルールを判定するために使う
+    pub fn get_rule_list(&self) -> Vec<[String; 5]> {
+        Self::create_rule_list(&self.rules)
+    }
+
+    /// --list-rulesが指定された場合に、スキャン対象となるルールの一覧を表形式で表示する
+    pub fn print_rule_list(rules: &[RuleNode]) {
+        let mut tb = Table::new();
+        tb.load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_header(vec!["ID", "Title", "Level", "Status", "Rule Path"]);
+        for row in Self::create_rule_list(rules) {
+            tb.add_row(row.to_vec());
+        }
+        println!("{tb}");
+        println!(
+            "Total rules: {}",
+            rules.len().to_formatted_string(&Locale::en)
+        );
+        println!();
+    }
+
     pub fn print_rule_load_info(
         rc: &HashMap<CompactString, u128>,
         ld_rc: &HashMap<CompactString, u128>,
@@ -1197,7 +1889,9 @@ mod tests {
     use chrono::TimeZone;
     use chrono::Utc;
     use compact_str::CompactString;
+    use nested::Nested;
     use serde_json::Value;
+    use tokio::runtime::Runtime;
     use yaml_rust::Yaml;
     use yaml_rust::YamlLoader;
 
@@ -1211,15 +1905,27 @@ mod tests {
     use crate::detections::configs::InputOption;
     use crate::detections::configs::OutputOption;
     use crate::detections::configs::StoredStatic;
+    use crate::detections::configs::TargetEventTime;
+    use crate::detections::configs::TargetIds;
     use crate::detections::configs::CURRENT_EXE_PATH;
     use crate::detections::configs::STORED_EKEY_ALIAS;
+    use crate::detections::configs::STORED_STATIC;
     use crate::detections::detection::Detection;
+    use crate::detections::detection::EvtxRecordInfo;
+    use crate::detections::detection::{
+        DetectionError, DetectionErrorReport, RecordFilter, RuleEventIdIndex, ScanRecord,
+        SkipCorruptRecords,
+    };
+    use crate::detections::message::DetectInfo;
+    use crate::detections::message::ERROR_LOG_STACK;
     use crate::detections::rule::create_rule;
+    use crate::detections::rule::get_detection_keys;
     use crate::detections::rule::AggResult;
     use crate::detections::rule::RuleNode;
     use crate::detections::utils;
     use crate::filter;
     use crate::options::profile::Profile;
+    use crate::options::suppression::SuppressionList;
 
     fn create_dummy_stored_static() -> StoredStatic {
         StoredStatic::create_static_data(Some(Config {
@@ -1229,6 +1935,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -1249,7 +1956,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -1262,10 +1969,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -1274,6 +1984,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -1294,21 +2020,773 @@ mod tests {
         }))
     }
 
+    fn create_record_filter<'a>(
+        time_filter: &'a TargetEventTime,
+        target_event_ids: &'a TargetIds,
+    ) -> RecordFilter<'a> {
+        RecordFilter {
+            time_filter,
+            target_event_ids,
+            channel_key: "Event.System.Channel",
+        }
+    }
+
+    /// 作成済みのEvtxRecordInfoのイベントをscan_recordsに渡し、検知結果を返す
+    fn scan_all(
+        detection: Detection,
+        rt: &Runtime,
+        records: Vec<EvtxRecordInfo>,
+        stored_static: &StoredStatic,
+    ) -> (Detection, Vec<DetectInfo>) {
+        let time_filter = TargetEventTime::new(stored_static);
+        let target_event_ids = TargetIds::default();
+        let records = records
+            .into_iter()
+            .map(|record| ScanRecord::from_event(record.record, stored_static.json_input_flag));
+        let (detection, result) = detection.scan_records(
+            rt,
+            records,
+            "testpath",
+            &create_record_filter(&time_filter, &target_event_ids),
+            stored_static,
+        );
+        let detect_infos = result.hits.into_iter().map(|hit| hit.detect_info);
+        (detection, detect_infos.collect())
+    }
+
+    /// selectionだけを条件に持つテスト用のルールを作成して初期化する
+    fn create_test_rule(
+        rulepath: String,
+        selection: &str,
+        stored_static: &StoredStatic,
+    ) -> RuleNode {
+        let rule_str = format!(
+            r#"
+        enabled: true
+        detection:
+            selection:
+                {selection}
+            condition: selection
+        details: testdata
+        "#
+        );
+        let rule_yaml = YamlLoader::load_from_str(&rule_str).unwrap();
+        let mut rule = create_rule(rulepath, rule_yaml.into_iter().next().unwrap());
+        assert!(rule.init(stored_static).is_ok());
+        rule
+    }
+
+    /// JSON文字列のイベントから、keysの値を保持したEvtxRecordInfoを作成する
+    fn create_test_rec_info(record_json_str: &str, keys: &Nested<String>) -> EvtxRecordInfo {
+        let record: Value = serde_json::from_str(record_json_str).unwrap();
+        utils::create_rec_info(record, "testpath".to_string(), keys, &false, &false)
+    }
+
     #[test]
     fn test_parse_rule_files() {
         let level = "informational";
-        let opt_rule_path = Path::new("./test_files/rules/level_yaml");
+        let opt_rule_path = vec![Path::new("./test_files/rules/level_yaml").to_path_buf()];
         let dummy_stored_static = create_dummy_stored_static();
         let cole = Detection::parse_rule_files(
             level,
             "",
-            opt_rule_path,
+            &opt_rule_path,
             &filter::exclude_ids(&dummy_stored_static),
             &dummy_stored_static,
         );
         assert_eq!(5, cole.len());
     }
 
+    #[test]
+    fn test_create_rule_list() {
+        // ルールファイルのパス順に並べられ、記載がない項目は-になることを確認
+        let rule_yaml1 = YamlLoader::load_from_str(
+            "id: 5bc9a8e1-0f5b\ntitle: Rule B\nlevel: high\nstatus: test\ndetection:\n    selection:\n        EventID: 1",
+        )
+        .unwrap();
+        let rule_yaml2 = YamlLoader::load_from_str(
+            "title: Rule A\nlevel: low\ndetection:\n    selection:\n        EventID: 2",
+        )
+        .unwrap();
+        let rules = vec![
+            create_rule("rules/b.yml".to_string(), rule_yaml1[0].clone()),
+            create_rule("rules/a.yml".to_string(), rule_yaml2[0].clone()),
+        ];
+        assert_eq!(
+            Detection::create_rule_list(&rules),
+            vec![
+                [
+                    "-".to_string(),
+                    "Rule A".to_string(),
+                    "low".to_string(),
+                    "-".to_string(),
+                    "rules/a.yml".to_string(),
+                ],
+                [
+                    "5bc9a8e1-0f5b".to_string(),
+                    "Rule B".to_string(),
+                    "high".to_string(),
+                    "test".to_string(),
+                    "rules/b.yml".to_string(),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_corrupt_records() {
+        // 途中のレコードと末尾の切り詰められたレコードのパースに失敗するイテレータ
+        let records: Vec<Result<Value, String>> = vec![
+            Ok(serde_json::json!({"Event": {"System": {"EventRecordID": 1}}})),
+            Err("Failed to deserialize record 2".to_string()),
+            Ok(serde_json::json!({"Event": {"System": {"EventRecordID": 3}}})),
+            Err("Unexpected EOF while reading record 4".to_string()),
+        ];
+        let mut records = SkipCorruptRecords::new(records.into_iter(), "test.evtx", false, true);
+        let ids: Vec<i64> = records
+            .by_ref()
+            .map(|rec| rec["Event"]["System"]["EventRecordID"].as_i64().unwrap())
+            .collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(records.read_cnt, 4);
+        assert_eq!(records.corrupt_cnt, 2);
+
+        // すべてのレコードのパースに失敗しても途中で中断しない
+        let records: Vec<Result<Value, String>> =
+            vec![Err("bad chunk".to_string()), Err("bad chunk".to_string())];
+        let mut records = SkipCorruptRecords::new(records.into_iter(), "test.evtx", false, true);
+        assert!(records.next().is_none());
+        assert_eq!(records.read_cnt, 2);
+        assert_eq!(records.corrupt_cnt, 2);
+    }
+
+    #[test]
+    fn test_scan_records() {
+        // EVTXファイルを使わずにJSON形式のイベントに対してルールを実行できることを確認
+        let stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+
+        let rules = ["Channel: Security", "CommandLine|contains: 'whoami'"]
+            .iter()
+            .enumerate()
+            .map(|(i, selection)| {
+                create_test_rule(format!("rule{i}.yml"), selection, &stored_static)
+            })
+            .collect::<Vec<RuleNode>>();
+
+        let records = [
+            r#"{"Event": {"System": {"EventID": 4624, "Channel": "Security", "TimeCreated_attributes": {"SystemTime": "2021-12-23T00:00:00.000000Z"}}}}"#,
+            r#"{"Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational", "TimeCreated_attributes": {"SystemTime": "2021-12-24T00:00:00.000000Z"}}, "EventData": {"CommandLine": "cmd.exe /c whoami"}}}"#,
+            r#"{"Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "notepad.exe"}}}"#,
+        ]
+        .iter()
+        .map(|record| ScanRecord::from_event(serde_json::from_str(record).unwrap(), false));
+
+        let time_filter = TargetEventTime::new(&stored_static);
+        let target_event_ids = TargetIds::default();
+        let rt = Runtime::new().unwrap();
+        let (_, result) = Detection::new(rules).scan_records(
+            &rt,
+            records,
+            "-",
+            &create_record_filter(&time_filter, &target_event_ids),
+            &stored_static,
+        );
+        assert_eq!(result.records.len(), 3);
+        let hits = result.hits;
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].rule_index, 0);
+        assert_eq!(
+            hits[0].timestamp,
+            Some(Utc.with_ymd_and_hms(2021, 12, 23, 0, 0, 0).unwrap())
+        );
+        assert_eq!(
+            result.records[hits[0].record_index].record["Event"]["System"]["EventID"],
+            4624
+        );
+        assert_eq!(hits[1].rule_index, 1);
+        assert_eq!(hits[1].record_index, 1);
+        assert_eq!(
+            hits[1].timestamp,
+            Some(Utc.with_ymd_and_hms(2021, 12, 24, 0, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_scan_records_with_suppression() {
+        // --suppressで指定した既知の誤検知に一致するイベントは検知しないことを確認
+        let mut stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+        stored_static.suppression_list = Some(
+            SuppressionList::parse(["CommandLine: cmd.exe /c whoami"].into_iter(), "test.txt")
+                .unwrap(),
+        );
+
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine|contains: 'whoami'
+            condition: selection
+        details: testdata
+        "#;
+        let rule_yaml = YamlLoader::load_from_str(rule_str).unwrap();
+        let mut rule = create_rule(
+            "rule.yml".to_string(),
+            rule_yaml.into_iter().next().unwrap(),
+        );
+        assert!(rule.init(&stored_static).is_ok());
+
+        let records = [
+            r#"{"Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "CMD.exe /c whoami"}}}"#,
+            r#"{"Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "powershell.exe whoami /priv"}}}"#,
+        ]
+        .iter()
+        .map(|record| ScanRecord::from_event(serde_json::from_str(record).unwrap(), false));
+
+        let time_filter = TargetEventTime::new(&stored_static);
+        let target_event_ids = TargetIds::default();
+        let rt = Runtime::new().unwrap();
+        let (detection, result) = Detection::new(vec![rule]).scan_records(
+            &rt,
+            records,
+            "-",
+            &create_record_filter(&time_filter, &target_event_ids),
+            &stored_static,
+        );
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(detection.suppressed_cnt, 1);
+        assert_eq!(
+            result.records[result.hits[0].record_index].record["Event"]["EventData"]["CommandLine"],
+            "powershell.exe whoami /priv"
+        );
+    }
+
+    #[test]
+    fn test_scan_records_filters_records() {
+        // Computer名、EventID、Channelによるフィルタリングが、ルールの実行前に適用されることを確認
+        let mut stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+        stored_static
+            .exclude_computer
+            .insert(CompactString::from("excluded-pc"));
+        stored_static
+            .exclude_eid
+            .insert(CompactString::from("4625"));
+
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel: Security
+            condition: selection
+        details: testdata
+        "#;
+        let rule_yaml = YamlLoader::load_from_str(rule_str).unwrap();
+        let mut rule = create_rule(
+            "rule.yml".to_string(),
+            rule_yaml.into_iter().next().unwrap(),
+        );
+        assert!(rule.init(&stored_static).is_ok());
+
+        let records = [
+            r#"{"Event": {"System": {"EventID": 4624, "Channel": "Security", "Computer": "target-pc"}}}"#,
+            r#"{"Event": {"System": {"EventID": 4624, "Channel": "Security", "Computer": "excluded-pc"}}}"#,
+            r#"{"Event": {"System": {"EventID": 4625, "Channel": "Security", "Computer": "target-pc"}}}"#,
+            r#"{"Event": {"System": {"EventID": 4624, "Channel": null, "Computer": "target-pc"}}}"#,
+        ]
+        .iter()
+        .map(|record| ScanRecord::from_event(serde_json::from_str(record).unwrap(), false));
+
+        let time_filter = TargetEventTime::new(&stored_static);
+        let target_event_ids = TargetIds::default();
+        let rt = Runtime::new().unwrap();
+        let (_, result) = Detection::new(vec![rule]).scan_records(
+            &rt,
+            records,
+            "-",
+            &create_record_filter(&time_filter, &target_event_ids),
+            &stored_static,
+        );
+        assert_eq!(result.records.len(), 1);
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(
+            result.records[0].record["Event"]["System"]["Computer"],
+            "target-pc"
+        );
+    }
+
+    #[test]
+    fn test_missing_event_time_warned_once_per_record() {
+        // 時刻が取得できないレコードが複数のルールで検知されても、警告はレコードごとに1回だけ出力されることを確認
+        let stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+
+        let rules: Vec<RuleNode> = ["EventID: 4688", "Channel: Security"]
+            .iter()
+            .enumerate()
+            .map(|(i, selection)| {
+                create_test_rule(
+                    format!("missing_time_rule{i}.yml"),
+                    selection,
+                    &stored_static,
+                )
+            })
+            .collect();
+        let keys = Detection::get_all_keys(&rules);
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4688, "Channel": "Security", "EventRecordID": 918273645}}}"#;
+        let records = vec![create_test_rec_info(record_json_str, &keys)];
+
+        let rt = Runtime::new().unwrap();
+        let (_, detect_infos) = scan_all(Detection::new(rules), &rt, records, &stored_static);
+        assert_eq!(detect_infos.len(), 2);
+        let warn_cnt = ERROR_LOG_STACK
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|msg| {
+                msg.starts_with("[WARN] Failed to get the event timestamp.")
+                    && msg.contains("EventRecordID : 918273645")
+            })
+            .count();
+        assert_eq!(warn_cnt, 1);
+    }
+
+    #[test]
+    fn test_create_log_record_default_timestamp() {
+        // 時刻が取得できないレコードには、--default-timestampで指定した時刻が使用されることを確認
+        let mut stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 4688
+            condition: selection
+        details: testdata
+        "#;
+        let rule_yaml = YamlLoader::load_from_str(rule_str).unwrap();
+        let mut rule = create_rule(
+            "testpath".to_string(),
+            rule_yaml.into_iter().next().unwrap(),
+        );
+        assert!(rule.init(&stored_static).is_ok());
+        let record: Value =
+            serde_json::from_str(r#"{"Event": {"System": {"EventID": 4688}}}"#).unwrap();
+        let keys = get_detection_keys(&rule);
+        let record_info =
+            utils::create_rec_info(record, "testpath".to_string(), &keys, &false, &false);
+
+        let detect_info = Detection::create_log_record(&rule, &record_info, &stored_static);
+        assert_eq!(
+            detect_info.detected_time,
+            Utc.with_ymd_and_hms(1970, 1, 1, 0, 0, 0).unwrap()
+        );
+
+        let default_timestamp = Utc.with_ymd_and_hms(2020, 2, 22, 0, 0, 0).unwrap();
+        stored_static
+            .output_option
+            .as_mut()
+            .unwrap()
+            .default_timestamp = Some(default_timestamp);
+        let detect_info = Detection::create_log_record(&rule, &record_info, &stored_static);
+        assert_eq!(detect_info.detected_time, default_timestamp);
+    }
+
+    #[test]
+    fn test_parallel_detection_same_as_serial() {
+        // ルール毎にスレッドで並列実行した結果が、1つずつ順番に判定した結果と一致することを確認
+        let stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+
+        let rule_strs = [
+            "EventID: 1",
+            "Channel: Security",
+            "CommandLine|contains: 'cmd.exe'",
+        ];
+        let create_rules = || {
+            rule_strs
+                .iter()
+                .enumerate()
+                .map(|(i, selection)| {
+                    create_test_rule(format!("rule{i}.yml"), selection, &stored_static)
+                })
+                .collect::<Vec<RuleNode>>()
+        };
+
+        let mut rules = create_rules();
+        let keys = Detection::get_all_keys(&rules);
+        let create_records = || {
+            (0..300)
+                .map(|i| {
+                    let channel = if i % 3 == 0 { "Security" } else { "System" };
+                    let record_json_str = format!(
+                        r#"{{"Event": {{"System": {{"EventID": {}, "Channel": "{channel}", "EventRecordID": {i}}}, "EventData": {{"CommandLine": "{}"}}}}}}"#,
+                        i % 5,
+                        if i % 7 == 0 { "C:\\\\Windows\\\\cmd.exe /c whoami" } else { "notepad.exe" }
+                    );
+                    create_test_rec_info(&record_json_str, &keys)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut expected = vec![];
+        let records = create_records();
+        for rule in rules.iter_mut() {
+            let detected_cnt = records
+                .iter()
+                .filter(|record| {
+                    rule.select(
+                        record,
+                        stored_static.verbose_flag,
+                        stored_static.quiet_errors_flag,
+                        stored_static.json_input_flag,
+                        &stored_static.eventkey_alias,
+                    )
+                })
+                .count();
+            expected.push((rule.rulepath.clone(), detected_cnt));
+        }
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (_, detect_infos) = scan_all(
+            Detection::new(create_rules()),
+            &rt,
+            create_records(),
+            &stored_static,
+        );
+        let actual: Vec<(String, usize)> = rule_strs
+            .iter()
+            .enumerate()
+            .map(|(i, _)| {
+                let rulepath = format!("rule{i}.yml");
+                let detected_cnt = detect_infos
+                    .iter()
+                    .filter(|detect_info| detect_info.rulepath == rulepath)
+                    .count();
+                (rulepath, detected_cnt)
+            })
+            .collect();
+        assert_eq!(actual, expected);
+        assert!(expected.iter().all(|(_, cnt)| *cnt > 0));
+    }
+
+    #[test]
+    fn test_parallel_detection_stable_order() {
+        // 多数のルールを並列に実行しても、--threadsで指定したスレッド数に関わらず、
+        // 検知結果がルールの読み込み順、レコードの順で毎回同じ順番になることを確認
+        let stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+
+        let rule_cnt = 64;
+        let create_rules = || {
+            (0..rule_cnt)
+                .map(|i| {
+                    let selection = format!("EventID: {}", i % 8);
+                    create_test_rule(format!("rule{i:02}.yml"), &selection, &stored_static)
+                })
+                .collect::<Vec<RuleNode>>()
+        };
+
+        let keys = Detection::get_all_keys(&create_rules());
+        let create_records = || {
+            (0..400)
+                .map(|i| {
+                    let record_json_str = format!(
+                        r#"{{"Event": {{"System": {{"EventID": {}, "Channel": "Security", "Computer": "computer{i}", "EventRecordID": {i}}}}}}}"#,
+                        i % 10
+                    );
+                    create_test_rec_info(&record_json_str, &keys)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // ルールの読み込み順に並び、同じルール内ではレコードの順に並ぶ
+        let expected: Vec<(String, String)> = (0..rule_cnt)
+            .flat_map(|i| {
+                (0..400)
+                    .filter(move |j| j % 10 == i % 8)
+                    .map(move |j| (format!("rule{i:02}.yml"), format!("computer{j}")))
+            })
+            .collect();
+        for thread_number in [Some(1), Some(4), None] {
+            let rt = utils::create_tokio_runtime(thread_number);
+            for _ in 0..3 {
+                let (_, detect_infos) = scan_all(
+                    Detection::new(create_rules()),
+                    &rt,
+                    create_records(),
+                    &stored_static,
+                );
+                let actual: Vec<(String, String)> = detect_infos
+                    .iter()
+                    .map(|detect_info| {
+                        (
+                            detect_info.rulepath.to_string(),
+                            detect_info.computername.to_string(),
+                        )
+                    })
+                    .collect();
+                assert_eq!(actual, expected, "threads: {thread_number:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_chunked_detection_same_as_single_pass() {
+        // レコードを一定件数ずつ区切って検知した結果が、全件をまとめて検知した結果と一致することを確認
+        let stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+
+        let create_rules = || {
+            vec![create_test_rule(
+                "testpath".to_string(),
+                "{Channel: Security, EventID: 4624}",
+                &stored_static,
+            )]
+        };
+        let keys = Detection::get_all_keys(&create_rules());
+
+        // 全件を保持しないイテレータとしてレコードを生成する
+        let record_cnt = 2500;
+        let records_iter = || {
+            (0..record_cnt).map(|i| {
+                let record_json_str = format!(
+                    r#"{{"Event": {{"System": {{"EventID": {}, "Channel": "Security", "EventRecordID": {i}}}}}}}"#,
+                    if i % 4 == 0 { 4624 } else { 4625 }
+                );
+                create_test_rec_info(&record_json_str, &keys)
+            })
+        };
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (_, single_pass) = scan_all(
+            Detection::new(create_rules()),
+            &rt,
+            records_iter().collect(),
+            &stored_static,
+        );
+
+        let chunk_size = 1000;
+        let mut detection = Detection::new(create_rules());
+        let mut chunked_cnt = 0;
+        let mut records = records_iter();
+        loop {
+            let chunk: Vec<_> = records.by_ref().take(chunk_size).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            assert!(chunk.len() <= chunk_size);
+            let (detection_tmp, detect_infos) = scan_all(detection, &rt, chunk, &stored_static);
+            chunked_cnt += detect_infos.len();
+            detection = detection_tmp;
+        }
+        assert_eq!(single_pass.len(), record_cnt / 4);
+        assert_eq!(chunked_cnt, single_pass.len());
+    }
+
+    #[test]
+    fn test_rule_eventid_index() {
+        // レコードのEventIDに対応するルールと、EventIDを判定できないルールだけに振り分けることを確認
+        let stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+
+        let rules: Vec<RuleNode> = [
+            "EventID: 4624",
+            "EventID: [4624, 4625]",
+            "Channel: Security",
+        ]
+        .iter()
+        .map(|selection| create_test_rule("testpath".to_string(), selection, &stored_static))
+        .collect();
+        let index = RuleEventIdIndex::new(&rules);
+        assert_eq!(index.catch_all, vec![2]);
+
+        let keys = Detection::get_all_keys(&rules);
+        let records: Vec<_> = [
+            r#"{"Event": {"System": {"EventID": 4624, "Channel": "Security"}}}"#,
+            r#"{"Event": {"System": {"EventID": 4625, "Channel": "Security"}}}"#,
+            r#"{"Event": {"System": {"EventID": 4688, "Channel": "Security"}}}"#,
+            r#"{"Event": {"System": {"Channel": "Security"}}}"#,
+        ]
+        .iter()
+        .map(|record_json_str| create_test_rec_info(record_json_str, &keys))
+        .collect();
+        assert_eq!(
+            index.dispatch(rules.len(), &records),
+            vec![Some(vec![0, 3]), Some(vec![0, 1, 3]), None]
+        );
+    }
+
+    #[test]
+    fn test_create_debug_rule_line() {
+        // --debug-ruleで表示する評価結果に、レコードの情報と各selectionの評価結果が含まれることを確認
+        let stored_static = create_dummy_stored_static();
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 4688
+            filter:
+                CommandLine|contains: 'whoami'
+            condition: selection and not filter
+        details: testdata
+        "#;
+        let rule_yaml = YamlLoader::load_from_str(rule_str).unwrap();
+        let mut rule_node = create_rule(
+            "testpath".to_string(),
+            rule_yaml.into_iter().next().unwrap(),
+        );
+        assert!(rule_node.init(&stored_static).is_ok());
+        let keys = get_detection_keys(&rule_node);
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4688, "EventRecordID": 12}, "EventData": {"CommandLine": "whoami /all"}}}"#;
+        let record: Value = serde_json::from_str(record_json_str).unwrap();
+        let record_info =
+            utils::create_rec_info(record, "test.evtx".to_string(), &keys, &false, &false);
+        let reason = rule_node.select_with_reason(&record_info, &stored_static.eventkey_alias);
+        assert_eq!(
+            Detection::create_debug_rule_line(&record_info, &reason),
+            "[Rule Debug] test.evtx ¦ EventRecordID:12 ¦ EventID:4688 ¦ TargetChannel:true ¦ TargetEventID:true ¦ filter:true ¦ selection:true ¦ Result:false"
+        );
+    }
+
+    #[test]
+    fn test_create_log_record_rule_metadata() {
+        // ルールのtitle, id, levelが検知結果に含まれ、存在しない場合はデフォルト値(titleはファイル名)になることを確認
+        let stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        let record_json_str = r#"
+        {
+            "Event": {
+                "System": {
+                    "TimeCreated_attributes": {
+                        "SystemTime": "1996-02-27T01:05:01Z"
+                    },
+                    "Computer": "testcomputer",
+                    "Channel": "Security",
+                    "EventID": 4688
+                }
+            }
+        }"#;
+        let create_detect_info = |rule_str: &str| {
+            let rule_yaml = YamlLoader::load_from_str(rule_str).unwrap();
+            let mut rule_node = create_rule(
+                "testpath".to_string(),
+                rule_yaml.into_iter().next().unwrap(),
+            );
+            assert!(rule_node.init(&stored_static).is_ok());
+            let keys = get_detection_keys(&rule_node);
+            let record: Value = serde_json::from_str(record_json_str).unwrap();
+            let record_info =
+                utils::create_rec_info(record, "testpath".to_string(), &keys, &false, &false);
+            Detection::create_log_record(&rule_node, &record_info, &stored_static)
+        };
+
+        let detect_info = create_detect_info(
+            r#"
+        title: Suspicious Process
+        id: 00000000-0000-0000-0000-000000000001
+        level: high
+        detection:
+            selection:
+                Channel: Security
+            condition: selection
+        details: testdata
+        "#,
+        );
+        assert_eq!(detect_info.ruletitle, "Suspicious Process");
+        assert_eq!(detect_info.ruleid, "00000000-0000-0000-0000-000000000001");
+        assert_eq!(detect_info.level, "high");
+        assert_eq!(detect_info.computername, "testcomputer");
+        assert_eq!(detect_info.eventid, "4688");
+        assert_eq!(
+            detect_info.detected_time,
+            Utc.with_ymd_and_hms(1996, 2, 27, 1, 5, 1).unwrap()
+        );
+
+        let detect_info = create_detect_info(
+            r#"
+        detection:
+            selection:
+                Channel: Security
+            condition: selection
+        details: testdata
+        "#,
+        );
+        assert_eq!(detect_info.ruletitle, "testpath");
+        assert_eq!(detect_info.ruleid, "-");
+    }
+
+    #[test]
+    fn test_create_log_record_mitre_attack() {
+        // ルールのtagsからMITRE ATT&CKの戦術とテクニックが分けて出力されることを確認
+        let mut stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        let profiles = stored_static.profiles.as_mut().unwrap();
+        profiles.push((
+            "MitreTactics".into(),
+            Profile::MitreTactics(Default::default()),
+        ));
+        profiles.push(("MitreTags".into(), Profile::MitreTags(Default::default())));
+        profiles.push(("OtherTags".into(), Profile::OtherTags(Default::default())));
+
+        let rule_str = r#"
+        title: Suspicious PowerShell
+        tags:
+            - attack.execution
+            - attack.t1059.001
+            - attack.g0016
+            - car.2016-04-005
+        detection:
+            selection:
+                Channel: Security
+            condition: selection
+        details: testdata
+        "#;
+        let rule_yaml = YamlLoader::load_from_str(rule_str).unwrap();
+        let mut rule_node = create_rule(
+            "testpath".to_string(),
+            rule_yaml.into_iter().next().unwrap(),
+        );
+        assert!(rule_node.init(&stored_static).is_ok());
+        let keys = get_detection_keys(&rule_node);
+        let record_json_str = r#"{"Event": {"System": {"Channel": "Security", "EventID": 4688}}}"#;
+        let record: Value = serde_json::from_str(record_json_str).unwrap();
+        let record_info =
+            utils::create_rec_info(record, "testpath".to_string(), &keys, &false, &false);
+        let detect_info = Detection::create_log_record(&rule_node, &record_info, &stored_static);
+
+        let expected = [
+            (
+                CompactString::from("MitreTactics"),
+                Profile::MitreTactics("Exec".into()),
+            ),
+            (
+                CompactString::from("MitreTags"),
+                Profile::MitreTags("T1059.001 ¦ G0016".into()),
+            ),
+            (
+                CompactString::from("OtherTags"),
+                Profile::OtherTags("car.2016-04-005".into()),
+            ),
+        ];
+        for field in expected.iter() {
+            assert!(
+                detect_info.ext_field.contains(field),
+                "{:?} is not found in {:?}",
+                field,
+                detect_info.ext_field
+            );
+        }
+    }
+
     #[test]
     fn test_output_aggregation_output_with_output() {
         let default_time = Utc.with_ymd_and_hms(1977, 1, 1, 0, 0, 0).unwrap();
@@ -1367,8 +2845,9 @@ mod tests {
     #[test]
     fn test_output_aggregation_output_with_timeframe() {
         let default_time = Utc.with_ymd_and_hms(1977, 1, 1, 0, 0, 0).unwrap();
-        let agg_result: AggResult =
+        let mut agg_result: AggResult =
             AggResult::new(2, "_".to_string(), vec![], default_time, ">= 1".to_string());
+        agg_result.end_timedate = Utc.with_ymd_and_hms(1977, 1, 1, 0, 10, 0).unwrap();
         let rule_str = r#"
         enabled: true
         detection:
@@ -1385,7 +2864,9 @@ mod tests {
         let test = rule_yaml.next().unwrap();
         let mut rule_node = create_rule("testpath".to_string(), test);
         rule_node.init(&create_dummy_stored_static()).ok();
-        let expected_output = "Count:2";
+        // byの指定がない場合は、検知したtimeframeの範囲も出力する
+        let expected_output =
+            "Count:2 ¦ Start:1977-01-01 00:00:00.000 ¦ End:1977-01-01 00:10:00.000";
         assert_eq!(
             Detection::create_count_output(&rule_node, &agg_result),
             expected_output
@@ -1491,6 +2972,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -1511,7 +2993,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -1524,10 +3006,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -1536,6 +3021,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -1630,6 +3131,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -1650,7 +3152,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -1663,10 +3165,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -1675,6 +3180,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -1764,6 +3285,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -1784,7 +3306,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -1797,10 +3319,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -1809,6 +3334,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -1914,6 +3455,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -1934,7 +3476,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -1947,10 +3489,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -1959,6 +3504,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2051,4 +3612,44 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_detection_error_report() {
+        // エラーの種類ごとに件数が集計され、人が読める形式で出力できることを確認
+        let evtx_err = DetectionError::EvtxParse {
+            filepath: "test.evtx".to_string(),
+            message: "invalid record".to_string(),
+        };
+        let json_err = DetectionError::Json {
+            filepath: "test.json".to_string(),
+            message: "Cannot open file.".to_string(),
+        };
+        let rule_err = DetectionError::Rule {
+            rulepath: "test.yml".to_string(),
+            title: "Test Rule".to_string(),
+        };
+        assert_eq!(
+            evtx_err.to_string(),
+            "Failed to parse event file.\nEventFile: test.evtx\nError: invalid record\n"
+        );
+        assert_eq!(
+            rule_err.to_string(),
+            "Failed to parse rule file. (FilePath : test.yml, Title : Test Rule)"
+        );
+
+        let mut report = DetectionErrorReport::default();
+        assert_eq!(report.total(), 0);
+        report.add(&evtx_err);
+        report.add(&evtx_err);
+        report.add(&json_err);
+        report.add(&rule_err);
+        assert_eq!(report.evtx_parse_error_cnt, 2);
+        assert_eq!(report.json_error_cnt, 1);
+        assert_eq!(report.rule_error_cnt, 1);
+        assert_eq!(report.total(), 4);
+        assert_eq!(
+            report.to_string(),
+            "Event file parse errors: 2 / JSON file errors: 1 / Rule parse errors: 1"
+        );
+    }
 }
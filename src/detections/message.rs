@@ -1,10 +1,10 @@
 extern crate lazy_static;
 use crate::detections::configs::CURRENT_EXE_PATH;
 use crate::detections::field_data_map::{convert_field_data, FieldDataMap, FieldDataMapKey};
-use crate::detections::utils::{self, get_serde_number_to_string, write_color_buffer};
+use crate::detections::utils::{self, get_serde_number_to_string};
 use crate::options::profile::Profile::{
-    self, AllFieldInfo, Details, ExtraFieldInfo, Literal, SrcASN, SrcCity, SrcCountry, TgtASN,
-    TgtCity, TgtCountry,
+    self, AllFieldInfo, Details, ExtraFieldInfo, Literal, MatchedFields, SrcASN, SrcCity,
+    SrcCountry, TgtASN, TgtCity, TgtCountry,
 };
 use chrono::{DateTime, Local, Utc};
 use compact_str::CompactString;
@@ -13,6 +13,7 @@ use hashbrown::HashMap;
 use hashbrown::HashSet;
 use itertools::Itertools;
 use lazy_static::lazy_static;
+use log::LevelFilter;
 use nested::Nested;
 use regex::Regex;
 use serde_json::Value;
@@ -21,7 +22,6 @@ use std::fs::{create_dir, File};
 use std::io::{self, BufWriter, Write};
 use std::path::Path;
 use std::sync::Mutex;
-use termcolor::{BufferWriter, ColorChoice};
 
 use super::configs::EventKeyAliasConfig;
 use super::utils::remove_sp_char;
@@ -246,6 +246,12 @@ pub fn create_message(
                     profile_converter.get(key.as_str()).unwrap().to_owned(),
                 ))
             }
+            // レコードの値に%で囲まれた文字列が含まれていてもaliasとして置き換えないように、そのままの値を使う
+            MatchedFields(_) => {
+                if let Some(p) = profile_converter.get(key.as_str()) {
+                    replaced_profiles.push((key.to_owned(), p.to_owned()));
+                }
+            }
             _ => {
                 if let Some(p) = profile_converter.get(key.as_str()) {
                     let (parsed_message, _) = &parse_message(
@@ -408,24 +414,34 @@ impl AlertMessage {
         println!();
     }
 
+    /// ログの出力を初期化する関数
+    /// 検知結果は標準出力に出力するので、ログは標準エラー出力に出力する。
+    /// -vの指定回数に応じて出力するレベルを変更し、RUST_LOG環境変数が指定された場合はそちらを優先する
+    pub fn init_logger(verbose_level: u8) {
+        let level = match verbose_level {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            _ => LevelFilter::Debug,
+        };
+        env_logger::Builder::new()
+            .filter_level(level)
+            .parse_default_env()
+            .target(env_logger::Target::Stderr)
+            .format(|buf, record| writeln!(buf, "[{}] {}", record.level(), record.args()))
+            .try_init()
+            .ok();
+    }
+
     /// ERRORメッセージを表示する関数
     pub fn alert(contents: &str) -> io::Result<()> {
-        write_color_buffer(
-            &BufferWriter::stderr(ColorChoice::Always),
-            None,
-            &format!("[ERROR] {contents}"),
-            true,
-        )
+        log::error!("{contents}");
+        Ok(())
     }
 
     /// WARNメッセージを表示する関数
     pub fn warn(contents: &str) -> io::Result<()> {
-        write_color_buffer(
-            &BufferWriter::stderr(ColorChoice::Always),
-            None,
-            &format!("[WARN] {contents}"),
-            true,
-        )
+        log::warn!("{contents}");
+        Ok(())
     }
 }
 
@@ -433,9 +449,10 @@ impl AlertMessage {
 mod tests {
     use crate::detections::configs::{load_eventkey_alias, StoredStatic, CURRENT_EXE_PATH};
     use crate::detections::field_data_map::FieldDataMapKey;
-    use crate::detections::message::{parse_message, AlertMessage};
+    use crate::detections::message::{get_event_time, parse_message, AlertMessage};
     use crate::detections::utils;
 
+    use chrono::{TimeZone, Utc};
     use compact_str::CompactString;
     use hashbrown::HashMap;
     use serde_json::Value;
@@ -454,6 +471,63 @@ mod tests {
         AlertMessage::warn(input).expect("[WARN] TESTWarn!");
     }
 
+    #[test]
+    /// レコードのSystemTimeからイベントの時刻を取得できることを確認する
+    fn test_get_event_time() {
+        let json_str = r#"
+        {
+            "Event": {
+                "System": {
+                    "TimeCreated_attributes": {
+                        "SystemTime": "2021-12-23T00:00:00.123456Z"
+                    }
+                }
+            }
+        }
+        "#;
+        let event_record: Value = serde_json::from_str(json_str).unwrap();
+        let expected = Utc
+            .with_ymd_and_hms(2021, 12, 23, 0, 0, 0)
+            .unwrap()
+            .checked_add_signed(chrono::Duration::microseconds(123456))
+            .unwrap();
+        assert_eq!(get_event_time(&event_record, false), Some(expected));
+    }
+
+    #[test]
+    /// JSON入力の場合は@timestampからイベントの時刻を取得できることを確認する
+    fn test_get_event_time_json_input() {
+        let json_str = r#"
+        {
+            "Event": {
+                "System": {
+                    "@timestamp": "2021-12-23T00:00:00Z"
+                }
+            }
+        }
+        "#;
+        let event_record: Value = serde_json::from_str(json_str).unwrap();
+        let expected = Utc.with_ymd_and_hms(2021, 12, 23, 0, 0, 0).unwrap();
+        assert_eq!(get_event_time(&event_record, true), Some(expected));
+        assert_eq!(get_event_time(&event_record, false), None);
+    }
+
+    #[test]
+    /// 時刻が存在しないレコードの場合はNoneが返ることを確認する
+    fn test_get_event_time_not_exist() {
+        let json_str = r#"
+        {
+            "Event": {
+                "System": {
+                    "EventID": 4624
+                }
+            }
+        }
+        "#;
+        let event_record: Value = serde_json::from_str(json_str).unwrap();
+        assert_eq!(get_event_time(&event_record, false), None);
+    }
+
     #[test]
     /// outputで指定されているキー(eventkey_alias.txt内で設定済み)から対象のレコード内の情報でメッセージをパースしているか確認する関数
     fn test_parse_message() {
@@ -16,6 +16,9 @@ lazy_static! {
         Regex::new(r"^(\s*\w+\s*,)+\s*\w+|^\w+").unwrap(),
     ];
     pub static ref RE_PIPE: Regex = Regex::new(r"\|.*").unwrap();
+    // Sigmaの集計関数のうち未対応のもの
+    pub static ref RE_UNSUPPORTED_AGGREGATION: Regex =
+        Regex::new(r"^\s*(min|max|avg|sum|near)\b").unwrap();
 }
 
 #[derive(Debug)]
@@ -78,6 +81,14 @@ impl AggegationConditionCompiler {
             .as_str()
             .replacen('|', "", 1);
 
+        // count以外の集計関数は未対応であることを明示する
+        if let Some(captured) = RE_UNSUPPORTED_AGGREGATION.captures(&aggregation_str) {
+            return Result::Err(format!(
+                "The {} aggregation function is not supported. Only count() can be used.",
+                &captured[1]
+            ));
+        }
+
         let tokens = self.tokenize(aggregation_str)?;
 
         self.parse(tokens)
@@ -405,6 +416,23 @@ mod tests {
         assert_eq!("An aggregation condition parse error has occurred. The aggregation condition can only use count.".to_string(),result.unwrap_err());
     }
 
+    #[test]
+    fn test_aggegation_condition_compiler_unsupported_function() {
+        // count以外の集計関数は未対応であることがわかるエラーとする
+        let compiler = AggegationConditionCompiler::new();
+        for func in ["min", "max", "avg", "sum"] {
+            let result = compiler.compile(&format!(
+                "selection | {func}(Duration) by TargetUserName > 5"
+            ));
+            assert_eq!(
+                format!("An aggregation condition parse error has occurred. The {func} aggregation function is not supported. Only count() can be used."),
+                result.unwrap_err()
+            );
+        }
+        let result = compiler.compile("selection1 | near selection2");
+        assert_eq!("An aggregation condition parse error has occurred. The near aggregation function is not supported. Only count() can be used.".to_string(), result.unwrap_err());
+    }
+
     #[test]
     fn test_aggegation_condition_compiler_no_ope() {
         // 比較演算子がない
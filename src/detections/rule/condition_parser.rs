@@ -1,10 +1,11 @@
 use lazy_static::lazy_static;
-use regex::Regex;
+use regex::{Captures, Regex};
 
 use self::selectionnodes::{
     AndSelectionNode, NotSelectionNode, OrSelectionNode, RefSelectionNode, SelectionNode,
 };
 use super::selectionnodes;
+use crate::detections::configs::GLOBAL_SELECTION_PREFIX;
 use hashbrown::HashMap;
 use itertools::Itertools;
 use std::{sync::Arc, vec::IntoIter};
@@ -17,8 +18,11 @@ lazy_static! {
         Regex::new(r"^\w+").unwrap(),
     ];
     pub static ref RE_PIPE: Regex = Regex::new(r"\|.*").unwrap();
-    // all of selection* と 1 of selection* にマッチする正規表現
-    pub static ref OF_SELECTION: Regex = Regex::new(r"(all|1) of ([^*]+)\*").unwrap();
+    // all of selection* と 1 of selection*、all of them と 1 of them にマッチする正規表現
+    pub static ref OF_SELECTION: Regex = Regex::new(r"\b(all|1) of ([^\s()*]+)(\*?)").unwrap();
+    // conditionで参照されている共通のselection(global_filter、global_filter*等)にマッチする正規表現
+    pub static ref GLOBAL_SELECTION_REF: Regex =
+        Regex::new(&format!(r"\b({GLOBAL_SELECTION_PREFIX}\w*)(\*?)")).unwrap();
 }
 
 #[derive(Debug, Clone)]
@@ -67,6 +71,10 @@ impl ConditionToken {
                     let sub_node = sub_token.into_selection_node(name_2_node)?;
                     select_and_node.child_nodes.push(sub_node);
                 }
+                // selection and not filterのような条件で、先に肯定的な条件を評価して一致しない場合はfilterの評価を省略できるように、Notのノードを後ろに並べる
+                select_and_node
+                    .child_nodes
+                    .sort_by_key(|node| node.is::<NotSelectionNode>());
                 return Result::Ok(Box::new(select_and_node));
             }
             ConditionToken::OrContainer(sub_tokens) => {
@@ -91,6 +99,31 @@ impl ConditionToken {
         };
     }
 
+    /// 否定されたselectionのみで構成された条件であるかを判定する。negatedは上位のNotで否定されている場合にtrueになる
+    fn is_negation_only(&self, negated: bool) -> bool {
+        match self {
+            ConditionToken::SelectionReference(_) => negated,
+            ConditionToken::ParenthesisContainer(sub_token) => sub_token.is_negation_only(negated),
+            ConditionToken::NotContainer(sub_token) => sub_token.is_negation_only(!negated),
+            // ド・モルガンの法則により、否定されたAndはOrとして、否定されたOrはAndとして判定する
+            ConditionToken::AndContainer(sub_tokens) if !negated => sub_tokens
+                .as_slice()
+                .iter()
+                .all(|sub_token| sub_token.is_negation_only(negated)),
+            ConditionToken::OrContainer(sub_tokens) if negated => sub_tokens
+                .as_slice()
+                .iter()
+                .all(|sub_token| sub_token.is_negation_only(negated)),
+            ConditionToken::AndContainer(sub_tokens) | ConditionToken::OrContainer(sub_tokens) => {
+                sub_tokens
+                    .as_slice()
+                    .iter()
+                    .any(|sub_token| sub_token.is_negation_only(negated))
+            }
+            _ => false,
+        }
+    }
+
     pub fn to_condition_token(token: &str) -> ConditionToken {
         if token == "(" {
             ConditionToken::LeftParenthesis
@@ -125,15 +158,8 @@ impl ConditionCompiler {
         name_2_node: &HashMap<String, Arc<Box<dyn SelectionNode>>>,
     ) -> Result<Box<dyn SelectionNode>, String> {
         let node_keys: Vec<String> = name_2_node.keys().cloned().collect();
-        let condition_str = Self::convert_condition(condition_str, &node_keys);
-        // パイプはここでは処理しない
-        let captured = self::RE_PIPE.captures(condition_str.as_str());
-        let replaced_condition = if let Some(cap) = captured {
-            let captured = cap.get(0).unwrap().as_str();
-            condition_str.replace(captured, "")
-        } else {
-            condition_str.to_string()
-        };
+        let replaced_condition =
+            Self::remove_pipe(&Self::convert_condition(condition_str, &node_keys));
 
         let result = self.compile_condition_body(&replaced_condition, name_2_node);
         if let Result::Err(msg) = result {
@@ -143,28 +169,50 @@ impl ConditionCompiler {
         }
     }
 
-    // all of selection* と 1 of selection* を通常のand/orに変換する
-    pub fn convert_condition(condition_str: &str, node_keys: &[String]) -> String {
-        let mut converted_str = condition_str.to_string();
-        for matched in OF_SELECTION.find_iter(condition_str) {
-            let match_str: &str = matched.as_str();
-            let sep = if match_str.starts_with("all") {
-                " and "
-            } else {
-                " or "
-            };
-            let target_node_key_prefix = match_str
-                .replace('*', "")
-                .replace("all of ", "")
-                .replace("1 of ", "");
-            let replaced_condition = node_keys
-                .iter()
-                .filter(|x| x.starts_with(target_node_key_prefix.as_str()))
-                .join(sep);
-            converted_str =
-                converted_str.replace(match_str, format!("({})", replaced_condition).as_str())
+    /// conditionが否定されたselectionのみで構成されている(not filterのように肯定的なselectionが無い)かを判定する。
+    /// このようなconditionはfilterに一致しない全てのイベントに一致する。パースできない場合はfalseを返す
+    pub fn is_negation_only_condition(&self, condition_str: &str, node_keys: &[String]) -> bool {
+        let replaced_condition =
+            Self::remove_pipe(&Self::convert_condition(condition_str, node_keys));
+        self.tokenize(&replaced_condition)
+            .and_then(|tokens| self.parse(tokens.into_iter()))
+            .is_ok_and(|parsed| parsed.is_negation_only(false))
+    }
+
+    // パイプ以降のaggregation conditionはここでは処理しないので取り除く
+    fn remove_pipe(condition_str: &str) -> String {
+        let captured = self::RE_PIPE.captures(condition_str);
+        if let Some(cap) = captured {
+            let captured = cap.get(0).unwrap().as_str();
+            condition_str.replace(captured, "")
+        } else {
+            condition_str.to_string()
         }
-        converted_str
+    }
+
+    // all of selection* と 1 of selection*、all of them と 1 of them を通常のand/orに変換する
+    pub fn convert_condition(condition_str: &str, node_keys: &[String]) -> String {
+        OF_SELECTION
+            .replace_all(condition_str, |caps: &Captures| {
+                let sep = if &caps[1] == "all" { " and " } else { " or " };
+                let target = &caps[2];
+                let is_wildcard = !caps[3].is_empty();
+                let replaced_condition = node_keys
+                    .iter()
+                    .filter(|x| {
+                        if is_wildcard {
+                            x.starts_with(target)
+                        } else if target == "them" {
+                            // Sigmaの仕様に合わせて、_から始まるselectionはthemの対象外にする。共通のselectionもルール内のselectionではないので対象外にする
+                            !x.starts_with('_') && !x.starts_with(GLOBAL_SELECTION_PREFIX)
+                        } else {
+                            x.as_str() == target
+                        }
+                    })
+                    .join(sep);
+                format!("({replaced_condition})")
+            })
+            .to_string()
     }
 
     /// 与えたConditionからSelectionNodeを作る
@@ -455,6 +503,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -475,7 +524,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -488,10 +537,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -500,6 +552,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -921,6 +989,29 @@ mod tests {
         check_select(rule_str, SIMPLE_RECORD_STR, false);
     }
 
+    #[test]
+    fn test_condition_selection_and_not_filter() {
+        // Sigmaルールでよく使われる「selection and not filter」のパターンのテスト
+        let rule_str = |filter_value: &str| {
+            format!(
+                r#"
+        enabled: true
+        detection:
+            selection:
+                Channel: 'System'
+                EventID: 7040
+            filter:
+                param2: '{filter_value}'
+            condition: selection and not filter
+        details: 'Service name : %param1%'
+        "#
+            )
+        };
+
+        check_select(rule_str("demand start").as_str(), SIMPLE_RECORD_STR, true);
+        check_select(rule_str("auto start").as_str(), SIMPLE_RECORD_STR, false);
+    }
+
     #[test]
     fn test_condition_parenthesis_detect() {
         // conditionに括弧を使ったテスト
@@ -1484,6 +1575,33 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_convert_condition_of_them() {
+        let keys = vec![
+            "selection_foo".to_string(),
+            "selection_bar".to_string(),
+            "_internal".to_string(),
+        ];
+        let result = ConditionCompiler::convert_condition("1 of them", &keys);
+        assert_eq!(result, "(selection_foo or selection_bar)");
+        let result = ConditionCompiler::convert_condition("all of them", &keys);
+        assert_eq!(result, "(selection_foo and selection_bar)");
+    }
+
+    #[test]
+    fn test_convert_condition_of_selection_without_wildcard() {
+        // ワイルドカードがない場合は名前が完全一致したselectionのみを対象にする
+        let keys = vec!["selection".to_string(), "selection_foo".to_string()];
+        let result = ConditionCompiler::convert_condition("1 of selection", &keys);
+        assert_eq!(result, "(selection)");
+        let result =
+            ConditionCompiler::convert_condition("1 of selection and 1 of selection*", &keys);
+        assert_eq!(
+            result,
+            "(selection) and (selection or selection_foo)".to_string()
+        );
+    }
+
     #[test]
     fn test_convert_condition_not_convert() {
         let condition = "selection1 and selection2";
@@ -1568,6 +1686,40 @@ mod tests {
         check_select(rule_str, SIMPLE_RECORD_STR, false);
     }
 
+    #[test]
+    fn test_condition_of_them() {
+        // conditionに 1 of them と all of them を使ったパターンのテスト
+        let rule_str = |condition: &str| {
+            format!(
+                r#"
+        enabled: true
+        detection:
+            selection_foo:
+                Channel: 'System'
+            selection_bar:
+                EventID: 7040
+            selection_baz:
+                param1: 'NOTDETECT'
+            condition: {condition}
+        details: 'Service name : %param1%'
+        "#
+            )
+        };
+
+        check_select(rule_str("1 of them").as_str(), SIMPLE_RECORD_STR, true);
+        check_select(rule_str("all of them").as_str(), SIMPLE_RECORD_STR, false);
+        check_select(
+            rule_str("all of selection_ba* and selection_foo").as_str(),
+            SIMPLE_RECORD_STR,
+            false,
+        );
+        check_select(
+            rule_str("1 of selection_ba* and selection_foo").as_str(),
+            SIMPLE_RECORD_STR,
+            true,
+        );
+    }
+
     #[test]
     fn test_condition_complex_of_selection() {
         let rule_str = |condition: &str| {
@@ -1628,4 +1780,92 @@ mod tests {
         check_select(rule_str(case4).as_str(), record_json_str, false);
         check_select(rule_str(case5).as_str(), record_json_str, false);
     }
+
+    #[test]
+    fn test_condition_selection_and_multiple_not_filters() {
+        // 肯定的なselectionと複数の否定されたfilterを組み合わせた場合、いずれかのfilterに一致すると検知しない
+        let rule_str = |condition: &str| {
+            format!(
+                r#"
+        enabled: true
+        detection:
+            selection:
+                Channel: 'System'
+                EventID: 7045
+            filter_a:
+                ImagePath|startswith: 'C:\Windows'
+            filter_b:
+                ImagePath|contains: 'defender'
+            condition: {condition}
+        "#
+            )
+        };
+        let record_json_str = |image_path: &str| {
+            format!(
+                r#"{{"Event": {{"System": {{"EventID": 7045, "Channel": "System"}}, "EventData": {{"ImagePath": "{image_path}"}}}}}}"#
+            )
+        };
+
+        for condition in [
+            "selection and not filter_a and not filter_b",
+            "not filter_a and selection and not filter_b",
+            "selection and not ( filter_a or filter_b )",
+            "selection and not 1 of filter_*",
+        ] {
+            let rule_str = rule_str(condition);
+            check_select(&rule_str, &record_json_str("C:\\\\Users\\\\evil.exe"), true);
+            check_select(
+                &rule_str,
+                &record_json_str("C:\\\\Windows\\\\svc.exe"),
+                false,
+            );
+            check_select(
+                &rule_str,
+                &record_json_str("D:\\\\defender\\\\svc.exe"),
+                false,
+            );
+        }
+    }
+
+    #[test]
+    fn test_condition_only_not_filter() {
+        // 否定されたfilterのみのconditionは、filterに一致しない全てのイベントに一致する
+        let rule_str = r#"
+        enabled: true
+        detection:
+            filter:
+                Channel: 'Security'
+            condition: not filter
+        "#;
+        check_select(rule_str, SIMPLE_RECORD_STR, true);
+
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4624, "Channel": "Security"}}}"#;
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_is_negation_only_condition() {
+        let compiler = ConditionCompiler::new();
+        let node_keys = ["selection", "filter1", "filter2"].map(|x| x.to_string());
+        let is_negation_only =
+            |condition: &str| compiler.is_negation_only_condition(condition, &node_keys);
+
+        assert!(is_negation_only("not filter1"));
+        assert!(is_negation_only("not filter1 and not filter2"));
+        assert!(is_negation_only("not 1 of filter*"));
+        assert!(is_negation_only("not ( filter1 or filter2 )"));
+        assert!(is_negation_only("selection or not filter1"));
+        assert!(is_negation_only("not filter1 | count() > 5"));
+
+        assert!(!is_negation_only("selection"));
+        assert!(!is_negation_only(
+            "selection and not filter1 and not filter2"
+        ));
+        assert!(!is_negation_only("not filter1 and selection"));
+        assert!(!is_negation_only("not ( not selection )"));
+        assert!(!is_negation_only(
+            "not ( filter1 and filter2 ) and selection"
+        ));
+        assert!(!is_negation_only("not"));
+    }
 }
@@ -541,7 +541,10 @@ pub fn judge_timeframe(
         let cnt = counter.count();
         if select_aggcon(cnt, rule) {
             // 条件を満たすtimeframeが見つかった
-            ret.push(counter.create_agg_result(left, &datas, cnt, key, rule));
+            let mut agg_result = counter.create_agg_result(left, &datas, cnt, key, rule);
+            // data[left]からdata[right-1]までが条件を満たしたtimeframeの範囲となる
+            agg_result.end_timedate = datas[(right - 1).max(left) as usize].record_time;
+            ret.push(agg_result);
             left = right;
         } else {
             // 条件を満たさなかったので、rightとleftを+1ずらす
@@ -604,6 +607,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -624,7 +628,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -637,10 +641,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -649,6 +656,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -1684,6 +1707,80 @@ mod tests {
         check_count(&rule_str, &recs, expected_count, expected_agg_result);
     }
 
+    // byの指定がないcount()で、timeframe(5m)内に集中したレコードのまとまり毎に1つずつ検知することの検査
+    #[test]
+    fn test_count_no_by_clustered_timeframe() {
+        let recs = vec![
+            test_create_recstr_std("1", "1977-01-09T00:00:00Z"),
+            test_create_recstr_std("1", "1977-01-09T00:01:00Z"),
+            test_create_recstr_std("1", "1977-01-09T00:02:00Z"),
+            test_create_recstr_std("1", "1977-01-09T00:04:00Z"),
+            test_create_recstr_std("1", "1977-01-09T01:00:00Z"),
+            test_create_recstr_std("1", "1977-01-09T01:30:00Z"),
+            test_create_recstr_std("1", "1977-01-09T03:00:00Z"),
+            test_create_recstr_std("1", "1977-01-09T03:03:00Z"),
+            test_create_recstr_std("1", "1977-01-09T03:05:00Z"),
+        ];
+        let rule_str = create_std_rule("count() > 2", "5m");
+
+        let mut expected_count = HashMap::new();
+        expected_count.insert("_".to_owned(), 9);
+        let expected_agg_result: Vec<AggResult> = vec![
+            AggResult::new(
+                4,
+                "_".to_owned(),
+                vec![],
+                Utc.with_ymd_and_hms(1977, 1, 9, 0, 0, 0).unwrap(),
+                "> 2".to_string(),
+            ),
+            AggResult::new(
+                3,
+                "_".to_owned(),
+                vec![],
+                Utc.with_ymd_and_hms(1977, 1, 9, 3, 0, 0).unwrap(),
+                "> 2".to_string(),
+            ),
+        ];
+        check_count(&rule_str, &recs, expected_count, expected_agg_result);
+
+        // 検知したtimeframeの最後のレコードの時刻が範囲の終わりとなる
+        let mut rule_node = create_rule(
+            "testpath".to_string(),
+            YamlLoader::load_from_str(&rule_str).unwrap().remove(0),
+        );
+        let dummy_stored_static = create_dummy_stored_static();
+        assert!(rule_node.init(&dummy_stored_static).is_ok());
+        let keys = detections::rule::get_detection_keys(&rule_node);
+        for rec in &recs {
+            let recinfo = utils::create_rec_info(
+                serde_json::from_str(rec).unwrap(),
+                "testpath".to_owned(),
+                &keys,
+                &false,
+                &false,
+            );
+            rule_node.select(
+                &recinfo,
+                dummy_stored_static.verbose_flag,
+                dummy_stored_static.quiet_errors_flag,
+                dummy_stored_static.json_input_flag,
+                &dummy_stored_static.eventkey_alias,
+            );
+        }
+        let end_timedates: Vec<DateTime<Utc>> = rule_node
+            .judge_satisfy_aggcondition(&dummy_stored_static)
+            .iter()
+            .map(|agg_result| agg_result.end_timedate)
+            .collect();
+        assert_eq!(
+            end_timedates,
+            vec![
+                Utc.with_ymd_and_hms(1977, 1, 9, 0, 4, 0).unwrap(),
+                Utc.with_ymd_and_hms(1977, 1, 9, 3, 5, 0).unwrap(),
+            ]
+        );
+    }
+
     fn test_create_recstr_std(event_id: &str, time: &str) -> String {
         test_create_recstr(event_id, time, "Windows Event Log")
     }
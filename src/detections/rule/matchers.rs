@@ -1,10 +1,13 @@
 use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
 use cidr_utils::cidr::errors::NetworkParseError;
 use cidr_utils::cidr::IpCidr;
+use itertools::Itertools;
 use nested::Nested;
-use regex::Regex;
+use regex::{Regex, RegexBuilder};
 use std::net::IpAddr;
 use std::str::FromStr;
+use std::string::FromUtf8Error;
 use std::{cmp::Ordering, collections::HashMap};
 use yaml_rust::Yaml;
 
@@ -31,7 +34,41 @@ pub trait LeafMatcher: Downcast + Send + Sync {
 }
 downcast_rs::impl_downcast!(LeafMatcher);
 
+/// |windashでオプションの先頭の-を置き換える文字。-、/、–(en dash)、—(em dash)、―(horizontal bar)
+const WINDASH_CHARACTERS: [char; 5] = ['-', '/', '\u{2013}', '\u{2014}', '\u{2015}'];
+/// |windashで記号の全ての組み合わせのパターンを作成するオプション数の上限。
+/// 組み合わせの数は5のオプション数乗で増えるので、上限を超える場合は1つの正規表現で判定する
+const WINDASH_MAX_COMBINATION_HEADS: usize = 2;
+/// |windashを正規表現で判定する場合に、オプションの先頭を表すために一時的に使う文字(Unicodeの私用領域の文字)
+const WINDASH_PLACEHOLDER: char = '\u{E000}';
+/// WINDASH_CHARACTERSのいずれかに一致する正規表現
+const WINDASH_REGEX_CLASS: &str = "[-/\u{2013}\u{2014}\u{2015}]";
+
+#[cfg(test)]
+thread_local! {
+    // 正規表現のコンパイル回数。select時にコンパイルが行われていないことをテストで確認するために使う
+    static REGEX_COMPILE_CNT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// LeafMatcherで使う正規表現をコンパイルする。
+/// 正規表現のコンパイルは重いため、この関数はLeafMatcher::initからのみ呼び出し、is_matchからは呼び出さないこと。
+fn compile_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    compile_regex_with_flags(pattern, &[])
+}
+
+/// |reの後に指定された|i(大文字小文字を区別しない), |m(複数行), |s(.が改行にも一致)のフラグを設定して正規表現をコンパイルする。
+fn compile_regex_with_flags(pattern: &str, flags: &[&str]) -> Result<Regex, regex::Error> {
+    #[cfg(test)]
+    REGEX_COMPILE_CNT.with(|cnt| cnt.set(cnt.get() + 1));
+    RegexBuilder::new(pattern)
+        .case_insensitive(flags.contains(&"i"))
+        .multi_line(flags.contains(&"m"))
+        .dot_matches_new_line(flags.contains(&"s"))
+        .build()
+}
+
 /// 指定された文字数以上であることをチェックするクラス。
+/// `Field: {min_length: 1000}`と`Field|minlength: 1000`のどちらの形式でも指定できる。
 pub struct MinlengthMatcher {
     min_len: i64,
 }
@@ -44,6 +81,10 @@ impl MinlengthMatcher {
 
 impl LeafMatcher for MinlengthMatcher {
     fn is_target_key(&self, key_list: &Nested<String>) -> bool {
+        // |minlengthは他のパイプと組み合わせずに指定した場合のみ対象とする
+        if key_list.len() == 1 {
+            return key_list[0].split('|').skip(1).eq(["minlength"]);
+        }
         if key_list.len() != 2 {
             return false;
         }
@@ -54,8 +95,13 @@ impl LeafMatcher for MinlengthMatcher {
     fn init(&mut self, key_list: &Nested<String>, select_value: &Yaml) -> Result<(), Vec<String>> {
         let min_length = select_value.as_i64();
         if min_length.is_none() {
+            let name = if key_list.len() == 1 {
+                "minlength"
+            } else {
+                "min_length"
+            };
             let errmsg = format!(
-                "min_length value should be an integer. [key:{}]",
+                "{name} value should be an integer. [key:{}]",
                 utils::concat_selection_key(key_list)
             );
             return Result::Err(vec![errmsg]);
@@ -114,7 +160,7 @@ impl LeafMatcher for RegexesFileMatcher {
         let regexes_strs = regexes_strs.unwrap();
         self.regexes = regexes_strs
             .iter()
-            .map(|regex_str| Regex::new(regex_str).unwrap())
+            .map(|regex_str| compile_regex(regex_str).unwrap())
             .collect();
 
         Result::Ok(())
@@ -171,7 +217,7 @@ impl LeafMatcher for AllowlistFileMatcher {
         self.regexes = regexes_strs
             .unwrap()
             .iter()
-            .map(|regex_str| Regex::new(regex_str).unwrap())
+            .map(|regex_str| compile_regex(regex_str).unwrap())
             .collect();
 
         Result::Ok(())
@@ -201,7 +247,12 @@ pub struct DefaultMatcher {
     re: Option<Vec<Regex>>,
     fast_match: Option<Vec<FastMatch>>,
     pipes: Vec<PipeElement>,
-    key_list: Nested<String>,
+    is_keyword: bool, // keywordsのようにキーが指定されていない(key_listが空)場合はtrue
+    case_sensitive: bool,
+    timestamp_compare: bool,
+    normalize_backslash: bool,
+    exact_number: Option<i128>, // ルールの値が整数の場合の値。16進数や8進数で表記された値を10進数の値と数値として比較するために使用する
+    is_radix_rule_value: bool,  // ルールの値が16進数/8進数で表記されている場合はtrue
 }
 
 impl DefaultMatcher {
@@ -210,8 +261,28 @@ impl DefaultMatcher {
             re: Option::None,
             fast_match: Option::None,
             pipes: Vec::new(),
-            key_list: Nested::<String>::new(),
+            is_keyword: false,
+            case_sensitive: false,
+            timestamp_compare: false,
+            normalize_backslash: false,
+            exact_number: None,
+            is_radix_rule_value: false,
+        }
+    }
+
+    /// 連続したバックスラッシュを1つのバックスラッシュにまとめます。
+    /// 例えば`C:\\Windows\\System32`は`C:\Windows\System32`になります。
+    fn normalize_backslash(value: &str) -> String {
+        let mut normalized = String::with_capacity(value.len());
+        let mut prev_is_backslash = false;
+        for c in value.chars() {
+            if c == '\\' && prev_is_backslash {
+                continue;
+            }
+            prev_is_backslash = c == '\\';
+            normalized.push(c);
         }
+        normalized
     }
 
     pub fn get_eqfield_key(&self) -> Option<&String> {
@@ -220,10 +291,41 @@ impl DefaultMatcher {
     }
 
     /// このmatcherの正規表現とマッチするかどうか判定します。
+    /// ワイルドカードから変換した正規表現は初期化時に^と$で囲んでいるので、値全体と一致する場合のみtrueになります。
     fn is_regex_fullmatch(&self, value: &str) -> bool {
         return self.re.as_ref().unwrap().iter().any(|x| x.is_match(value));
     }
 
+    /// FastMatchでレコードの値と比較します。FastMatchで判定できない場合はNoneを返します。
+    /// lowercase_valueには小文字に変換したレコードの値をキャッシュします。
+    fn is_fast_match(
+        &self,
+        fast_match: &FastMatch,
+        event_value_str: &str,
+        lowercase_value: &mut Option<String>,
+    ) -> Option<bool> {
+        if self.case_sensitive {
+            return match fast_match {
+                FastMatch::Exact(s) => Some(event_value_str == s),
+                FastMatch::StartsWith(s) => Some(event_value_str.starts_with(s.as_str())),
+                FastMatch::EndsWith(s) => Some(event_value_str.ends_with(s.as_str())),
+                FastMatch::Contains(s) | FastMatch::AllOnly(s) => {
+                    Some(utils::contains_str(event_value_str, s))
+                }
+            };
+        }
+        match fast_match {
+            FastMatch::Exact(s) => Some(Self::eq_ignore_case(event_value_str, s)),
+            FastMatch::StartsWith(s) => Self::starts_with_ignore_case(event_value_str, s),
+            FastMatch::EndsWith(s) => Self::ends_with_ignore_case(event_value_str, s),
+            FastMatch::Contains(s) | FastMatch::AllOnly(s) => {
+                let lowercase_value =
+                    lowercase_value.get_or_insert_with(|| event_value_str.to_lowercase());
+                Some(utils::contains_str(lowercase_value, s))
+            }
+        }
+    }
+
     /// Hayabusaのルールファイルのフィールド名とそれに続いて指定されるパイプを、正規表現形式の文字列に変換します。
     /// ワイルドカードの文字列を正規表現にする処理もこのメソッドに実装されています。patternにワイルドカードの文字列を指定して、pipesにPipeElement::Wildcardを指定すればOK!!
     fn from_pattern_to_regex_str(pattern: String, pipes: &[PipeElement]) -> String {
@@ -267,6 +369,172 @@ impl DefaultMatcher {
         None
     }
 
+    /// |windashで使うパターンを作成する。
+    /// コマンドラインのオプションの先頭の-を、-、/、–(en dash)、—(em dash)、―(horizontal bar)に置き換えた全ての組み合わせを返す。
+    /// オプションごとに別々に置き換えるので、`-a -b`から`-a /b`のように記号が混在したパターンも作成する。先頭は元のパターンになる
+    /// オプションの数がWINDASH_MAX_COMBINATION_HEADSを超える場合は、use_regexがtrueならオプションの先頭をWINDASH_PLACEHOLDERに置き換えたパターンを1つ返し、
+    /// falseなら(|wideや|base64で正規表現を使えない場合)全てのオプションの先頭を同じ記号に置き換えたパターンのみを返す
+    fn windash_patterns(pattern: &str, use_regex: bool) -> Vec<String> {
+        let chars: Vec<char> = pattern.chars().collect();
+        // 単語の途中の-(例: -test-testの2つ目の-)はオプションの先頭ではないので置き換えない
+        let option_heads: Vec<usize> = (0..chars.len())
+            .filter(|&i| {
+                chars[i] == '-'
+                    && !(i > 0 && chars[i - 1].is_alphanumeric())
+                    && chars.get(i + 1).is_some_and(|n| n.is_alphanumeric())
+            })
+            .collect();
+        if option_heads.is_empty() {
+            return vec![pattern.to_string()];
+        }
+        let replace_heads = |dashes: &[char]| -> String {
+            let mut replaced = chars.clone();
+            for (i, dash) in option_heads.iter().zip(dashes.iter().cycle()) {
+                replaced[*i] = *dash;
+            }
+            replaced.into_iter().collect()
+        };
+        if option_heads.len() > WINDASH_MAX_COMBINATION_HEADS {
+            if use_regex {
+                return vec![replace_heads(&[WINDASH_PLACEHOLDER])];
+            }
+            return WINDASH_CHARACTERS
+                .iter()
+                .map(|dash| replace_heads(&[*dash]))
+                .collect();
+        }
+        option_heads
+            .iter()
+            .map(|_| WINDASH_CHARACTERS.iter())
+            .multi_cartesian_product()
+            .map(|dashes| replace_heads(&dashes.into_iter().copied().collect::<Vec<char>>()))
+            .collect()
+    }
+
+    /// |base64offsetで使うパターンを作成する。
+    /// 値の前に0~2バイトのデータがある場合のbase64エンコード結果から、前後のデータの影響を受けない部分を取り出して返す。
+    fn base64offset_patterns(val_byte: &[u8]) -> Result<Vec<String>, FromUtf8Error> {
+        let mut b64_offset_patterns = vec![];
+        for i in 0..3 {
+            let mut b64_result = vec![];
+            let mut target_byte = vec![];
+            target_byte.resize_with(i, || 0b0);
+            target_byte.extend_from_slice(val_byte);
+            b64_result.resize_with(target_byte.len() * 4 / 3 + 4, || 0b0);
+            general_purpose::STANDARD
+                .encode_slice(target_byte, &mut b64_result)
+                .ok();
+            let b64_str = String::from_utf8(b64_result)?;
+            let filtered_null_chr = b64_str.replace('\0', "");
+            let b64_offset_contents = match b64_str.find('=').unwrap_or_default() % 4 {
+                2 => {
+                    if i == 0 {
+                        filtered_null_chr[..filtered_null_chr.len() - 3].to_string()
+                    } else {
+                        filtered_null_chr[(i + 1)..filtered_null_chr.len() - 3].to_string()
+                    }
+                }
+                3 => {
+                    if i == 0 {
+                        filtered_null_chr[..filtered_null_chr.len() - 2].to_string()
+                    } else {
+                        filtered_null_chr[(i + 1)..filtered_null_chr.len() - 2].to_string()
+                    }
+                }
+                _ => {
+                    if i == 0 {
+                        filtered_null_chr
+                    } else {
+                        filtered_null_chr[(i + 1)..].to_string()
+                    }
+                }
+            };
+            b64_offset_patterns.push(b64_offset_contents);
+        }
+        Ok(b64_offset_patterns)
+    }
+
+    /// パイプの組み合わせが正しいかを確認する。
+    /// パターンを変換するパイプ(windash, wide, base64, base64offset)は、startswith, endswith, containsのいずれかとのみ組み合わせることができ、
+    /// 値の比較方法を指定するパイプは1つだけ指定できる。
+    fn validate_pipes(pipes: &[PipeElement], key_list: &Nested<String>) -> Result<(), String> {
+        let key = utils::concat_selection_key(key_list);
+        for (i, pipe) in pipes.iter().enumerate() {
+            if pipes[..i]
+                .iter()
+                .any(|p| std::mem::discriminant(p) == std::mem::discriminant(pipe))
+            {
+                return Result::Err(format!(
+                    "The same modifier cannot be specified more than once. key:{key}"
+                ));
+            }
+        }
+        let compare_pipes: Vec<&PipeElement> = pipes
+            .iter()
+            .filter(|pipe| {
+                !matches!(
+                    pipe,
+                    PipeElement::Windash
+                        | PipeElement::Wide
+                        | PipeElement::Base64
+                        | PipeElement::Base64offset
+                        | PipeElement::All
+                )
+            })
+            .collect();
+        if compare_pipes.len() > 1 {
+            return Result::Err(format!("Multiple pipe elements cannot be used. key:{key}"));
+        }
+        let is_transformed = pipes.iter().any(|pipe| {
+            matches!(
+                pipe,
+                PipeElement::Windash
+                    | PipeElement::Wide
+                    | PipeElement::Base64
+                    | PipeElement::Base64offset
+            )
+        });
+        if is_transformed
+            && !matches!(
+                compare_pipes.as_slice(),
+                [] | [PipeElement::Startswith] | [PipeElement::Endswith] | [PipeElement::Contains]
+            )
+        {
+            return Result::Err(format!(
+                "The windash, wide, base64 and base64offset modifiers can only be combined with startswith, endswith or contains. key:{key}"
+            ));
+        }
+        if pipes.contains(&PipeElement::Base64) && pipes.contains(&PipeElement::Base64offset) {
+            return Result::Err(format!(
+                "The base64 and base64offset modifiers cannot be combined. key:{key}"
+            ));
+        }
+        if pipes.contains(&PipeElement::Base64offset) && !pipes.contains(&PipeElement::Contains) {
+            return Result::Err(format!(
+                "The base64offset modifier must be combined with contains. key:{key}"
+            ));
+        }
+        Result::Ok(())
+    }
+
+    /// 複数のパターンをそれぞれprefixとsuffixで囲んでFastMatchに変換する。
+    /// 1つでもFastMatchに変換できないパターンがある場合は、正規表現マッチのみとするためNoneを返す。
+    fn convert_patterns_to_fast_match(
+        patterns: &[String],
+        prefix: &str,
+        suffix: &str,
+        ignore_case: bool,
+    ) -> Option<Vec<FastMatch>> {
+        let mut fastmatches = vec![];
+        for pattern in patterns {
+            fastmatches.extend(Self::convert_to_fast_match(
+                format!("{prefix}{pattern}{suffix}").as_str(),
+                ignore_case,
+            )?);
+        }
+        Some(fastmatches)
+    }
+
     // ワイルドカードマッチを高速なstd::stringのlen/starts_with/ends_withに変換するための関数
     fn convert_to_fast_match(s: &str, ignore_case: bool) -> Option<Vec<FastMatch>> {
         let wildcard_count = s.chars().filter(|c| *c == '*').count();
@@ -321,12 +589,18 @@ impl LeafMatcher for DefaultMatcher {
     }
 
     fn init(&mut self, key_list: &Nested<String>, select_value: &Yaml) -> Result<(), Vec<String>> {
-        let mut tmp_key_list = Nested::<String>::new();
-        tmp_key_list.extend(key_list.iter());
-        self.key_list = tmp_key_list;
+        self.is_keyword = key_list.is_empty();
         if select_value.is_null() {
             return Result::Ok(());
         }
+        // |allに空の配列が指定された場合は、全てのイベントに一致しないようにルールの読み込みエラーにする
+        if select_value.as_vec().is_some_and(|ary| ary.is_empty()) {
+            let errmsg = format!(
+                "An empty list cannot be specified for the all modifier. key:{}",
+                utils::concat_selection_key(key_list)
+            );
+            return Result::Err(vec![errmsg]);
+        }
 
         // patternをパースする
         let yaml_value = match select_value {
@@ -362,8 +636,22 @@ impl LeafMatcher for DefaultMatcher {
 
         let keys_without_head = &keys_all[1..];
 
+        // |reの後に指定された|i, |m, |sはパイプではなく正規表現のフラグとして扱う
+        let re_pos = keys_without_head.iter().position(|key| *key == "re");
+        let mut re_flags = vec![];
         let mut err_msges = vec![];
-        for key in keys_without_head.iter() {
+        for (i, key) in keys_without_head.iter().enumerate() {
+            if re_pos.is_some_and(|pos| i > pos) && key.len() == 1 {
+                if matches!(*key, "i" | "m" | "s") {
+                    re_flags.push(*key);
+                } else {
+                    err_msges.push(format!(
+                        "An unknown regex flag was specified. flag:{key}, key:{}",
+                        utils::concat_selection_key(key_list)
+                    ));
+                }
+                continue;
+            }
             let pipe_element = PipeElement::new(key, &pattern[0], key_list);
             match pipe_element {
                 Ok(element) => {
@@ -377,146 +665,232 @@ impl LeafMatcher for DefaultMatcher {
         if !err_msges.is_empty() {
             return Err(err_msges);
         }
-        let n = self.pipes.len();
-        if n == 0 {
-            // パイプがないケース
-            self.fast_match = Self::convert_to_fast_match(&pattern[0], true);
-        } else if n == 1 {
-            // パイプがあるケース
-            self.fast_match = match &self.pipes[0] {
-                PipeElement::Startswith => {
-                    Self::convert_to_fast_match(format!("{}*", pattern[0]).as_str(), true)
-                }
-                PipeElement::Endswith => {
-                    Self::convert_to_fast_match(format!("*{}", pattern[0]).as_str(), true)
-                }
-                PipeElement::Contains => {
-                    Self::convert_to_fast_match(format!("*{}*", pattern[0]).as_str(), true)
-                }
-                PipeElement::AllOnly => {
-                    Self::convert_to_fast_match(format!("allOnly*{}*", pattern[0]).as_str(), true)
+        // |existsはフィールドの有無だけを判定するので、他のパイプと組み合わせることはできない
+        if self
+            .pipes
+            .iter()
+            .any(|pipe| matches!(pipe, PipeElement::Exists(_)))
+        {
+            if self.pipes.len() > 1 {
+                let errmsg = format!(
+                    "The exists modifier cannot be combined with other modifiers. key:{}",
+                    utils::concat_selection_key(key_list)
+                );
+                return Result::Err(vec![errmsg]);
+            }
+            return Result::Ok(());
+        }
+        // |casedは比較時に大文字小文字を区別するかどうかだけを切り替えるため、パイプの組み合わせの判定からは除外する
+        if self.pipes.contains(&PipeElement::Cased) {
+            self.case_sensitive = true;
+            self.pipes.retain(|pipe| pipe != &PipeElement::Cased);
+        }
+        // |timestampは|lt, |lte, |gt, |gteの比較方法だけを切り替えるため、パイプの組み合わせの判定からは除外する
+        if self.pipes.contains(&PipeElement::Timestamp) {
+            self.timestamp_compare = true;
+            self.pipes.retain(|pipe| pipe != &PipeElement::Timestamp);
+        }
+        // |normbackslashは比較前の値の正規化だけを切り替えるため、パイプの組み合わせの判定からは除外する
+        if self.pipes.contains(&PipeElement::NormBackslash) {
+            self.normalize_backslash = true;
+            self.pipes
+                .retain(|pipe| pipe != &PipeElement::NormBackslash);
+        }
+        if let Some(compare_pipe) = self
+            .pipes
+            .iter()
+            .find(|pipe| pipe.get_numeric_compare_value().is_some())
+        {
+            // 比較する値が数値(|timestampの場合は時刻)として解釈できない場合はルールの記載ミスなのでエラーにする
+            let compare_value = compare_pipe.get_numeric_compare_value().unwrap();
+            let (is_valid, value_type) = if self.timestamp_compare {
+                (
+                    PipeElement::parse_timestamp(compare_value).is_some(),
+                    "a timestamp",
+                )
+            } else {
+                (
+                    PipeElement::parse_number(compare_value).is_some()
+                        || PipeElement::parse_version(compare_value).is_some(),
+                    "a number or a version",
+                )
+            };
+            if !is_valid {
+                let errmsg = format!(
+                    "The value of the {} modifier should be {value_type}. key:{}",
+                    compare_pipe.get_numeric_compare_name(),
+                    utils::concat_selection_key(key_list)
+                );
+                return Result::Err(vec![errmsg]);
+            }
+        } else if self.timestamp_compare {
+            let errmsg = format!(
+                "The timestamp modifier must be combined with lt, lte, gt or gte. key:{}",
+                utils::concat_selection_key(key_list)
+            );
+            return Result::Err(vec![errmsg]);
+        }
+        // |fieldrefは値に指定されたフィールドの値と比較するので、正規表現やFastMatchへの変換は不要
+        let fieldref_key = self.pipes.iter().find_map(|pipe| match pipe {
+            PipeElement::FieldRef(key, _) => Some(key.to_owned()),
+            _ => None,
+        });
+        if let Some(fieldref_key) = fieldref_key {
+            let other_pipes: Vec<&PipeElement> = self
+                .pipes
+                .iter()
+                .filter(|pipe| !matches!(pipe, PipeElement::FieldRef(_, _)))
+                .collect();
+            let fieldref_match = match other_pipes.as_slice() {
+                [] => FieldRefMatch::Equals,
+                [PipeElement::Startswith] => FieldRefMatch::StartsWith,
+                [PipeElement::Endswith] => FieldRefMatch::EndsWith,
+                [PipeElement::Contains] => FieldRefMatch::Contains,
+                _ => {
+                    let errmsg = format!(
+                        "The fieldref modifier can only be combined with startswith, endswith, contains or cased. key:{}",
+                        utils::concat_selection_key(key_list)
+                    );
+                    return Result::Err(vec![errmsg]);
                 }
-                _ => None,
             };
-        } else if n == 2 {
-            if self.pipes[0] == PipeElement::Base64offset && self.pipes[1] == PipeElement::Contains
+            self.pipes = vec![PipeElement::FieldRef(fieldref_key, fieldref_match)];
+            return Result::Ok(());
+        }
+        if let Err(errmsg) = Self::validate_pipes(&self.pipes, key_list) {
+            return Result::Err(vec![errmsg]);
+        }
+        // パイプがなく、ルールの値が整数(YAMLの整数、または0x/0oから始まる文字列)の場合は、
+        // 0x12019fと1179551のように表記が異なる値も一致するように数値として比較できるようにしておく
+        let is_radix_rule_value =
+            matches!(select_value, Yaml::String(s) if PipeElement::has_radix_prefix(s));
+        if self.pipes.is_empty() && (select_value.as_i64().is_some() || is_radix_rule_value) {
+            if let Some(NumericValue::Integer(number)) = PipeElement::parse_number(&pattern[0]) {
+                self.exact_number = Some(number);
+                self.is_radix_rule_value = is_radix_rule_value;
+            }
+        }
+        // パターンを変換するパイプは、記載順に関わらず以下の順番で適用してから、残りのパイプで比較する
+        // 1. |windash 2. |wide 3. |base64, |base64offset
+        // |windashはパターンの-を/に置き換えたパターンを追加する
+        // |wide、|base64、|base64offsetと組み合わせる場合はエンコード後の値を比較するので、正規表現は使えない
+        if self.pipes.contains(&PipeElement::Windash) {
+            let use_regex = !self.pipes.iter().any(|pipe| {
+                matches!(
+                    pipe,
+                    PipeElement::Wide | PipeElement::Base64 | PipeElement::Base64offset
+                )
+            });
+            pattern = Self::windash_patterns(&pattern[0], use_regex);
+            self.pipes.retain(|pipe| pipe != &PipeElement::Windash);
+        }
+        let is_windash_regex = pattern.iter().any(|p| p.contains(WINDASH_PLACEHOLDER));
+        // |wideはパターンをUTF-16LEでエンコードしたバイト列に置き換える
+        // PowerShellの-EncodedCommandなどを検知するため、base64/base64offsetと組み合わせて使う
+        let mut pattern_bytes: Vec<Vec<u8>> =
+            pattern.iter().map(|p| p.as_bytes().to_vec()).collect();
+        if self.pipes.contains(&PipeElement::Wide) {
+            self.pipes.retain(|pipe| pipe != &PipeElement::Wide);
+            pattern_bytes = pattern
+                .iter()
+                .map(|p| p.encode_utf16().flat_map(|c| c.to_le_bytes()).collect())
+                .collect();
+            if !self.pipes.contains(&PipeElement::Base64)
+                && !self.pipes.contains(&PipeElement::Base64offset)
             {
-                // |base64offset|containsの場合
-                let val = pattern[0].as_str();
-                let val_byte = val.as_bytes();
-                let mut fastmatches = vec![];
-                for i in 0..3 {
-                    let mut b64_result = vec![];
-                    let mut target_byte = vec![];
-                    target_byte.resize_with(i, || 0b0);
-                    target_byte.extend_from_slice(val_byte);
-                    b64_result.resize_with(target_byte.len() * 4 / 3 + 4, || 0b0);
-                    general_purpose::STANDARD
-                        .encode_slice(target_byte, &mut b64_result)
-                        .ok();
-                    let convstr_b64 = String::from_utf8(b64_result);
-                    if let Ok(b64_str) = convstr_b64 {
-                        // ここでContainsのfastmatch対応を行う
-                        let filtered_null_chr = b64_str.replace('\0', "");
-                        let b64_offset_contents = match b64_str.find('=').unwrap_or_default() % 4 {
-                            2 => {
-                                if i == 0 {
-                                    filtered_null_chr[..filtered_null_chr.len() - 3].to_string()
-                                } else {
-                                    filtered_null_chr[(i + 1)..filtered_null_chr.len() - 3]
-                                        .to_string()
-                                }
-                            }
-                            3 => {
-                                if i == 0 {
-                                    filtered_null_chr[..filtered_null_chr.len() - 2].to_string()
-                                } else {
-                                    filtered_null_chr.replace('\0', "")
-                                        [(i + 1)..filtered_null_chr.len() - 2]
-                                        .to_string()
-                                }
-                            }
-                            _ => {
-                                if i == 0 {
-                                    filtered_null_chr
-                                } else {
-                                    filtered_null_chr[(i + 1)..].to_string()
-                                }
+                // base64と組み合わせない場合は、UTF-16LEのバイト列をそのまま文字列として比較する
+                let wide_patterns: Result<Vec<String>, _> = pattern_bytes
+                    .iter()
+                    .map(|b| String::from_utf8(b.clone()))
+                    .collect();
+                match wide_patterns {
+                    Ok(wide_patterns) => pattern = wide_patterns,
+                    Err(_) => {
+                        let errmsg = format!(
+                            "The wide modifier can be used with non-ASCII values only when combined with base64 or base64offset. key:{}",
+                            utils::concat_selection_key(key_list)
+                        );
+                        return Result::Err(vec![errmsg]);
+                    }
+                }
+            }
+        }
+        // |base64はパターンをbase64エンコードした値に置き換える
+        // base64エンコードした値は大文字小文字を区別する必要がある
+        if self.pipes.contains(&PipeElement::Base64) {
+            pattern = pattern_bytes
+                .iter()
+                .map(|b| general_purpose::STANDARD.encode(b))
+                .collect();
+            self.case_sensitive = true;
+            self.pipes.retain(|pipe| pipe != &PipeElement::Base64);
+        }
+        let ignore_case = !self.case_sensitive;
+        // |allは配列の値をAndSelectionNodeとしてパースするための指定なので、ここでの比較方法には影響しない
+        let compare_pipe = self.pipes.iter().find(|pipe| **pipe != PipeElement::All);
+        if self.pipes.contains(&PipeElement::Base64offset) {
+            // |base64offset|containsの場合
+            // base64エンコードした値は大文字小文字を区別する必要がある
+            self.case_sensitive = true;
+            let mut fastmatches = vec![];
+            for val_byte in &pattern_bytes {
+                match Self::base64offset_patterns(val_byte) {
+                    Ok(b64_offset_contents) => {
+                        for b64_offset_content in b64_offset_contents {
+                            if let Some(fm) = Self::convert_to_fast_match(
+                                &format!("*{b64_offset_content}*"),
+                                false,
+                            ) {
+                                fastmatches.extend(fm);
                             }
-                        };
-                        if let Some(fm) =
-                            Self::convert_to_fast_match(&format!("*{b64_offset_contents}*"), false)
-                        {
-                            fastmatches.extend(fm);
                         }
-                    } else {
-                        err_msges.push(format!(
-                            "Failed base64 encoding: {}",
-                            convstr_b64.unwrap_err()
-                        ));
                     }
+                    Err(e) => err_msges.push(format!("Failed base64 encoding: {e}")),
+                }
+            }
+            if !fastmatches.is_empty() {
+                self.fast_match = Some(fastmatches);
+            }
+        } else if !is_windash_regex {
+            self.fast_match = match compare_pipe {
+                // パイプがないケース
+                None => Self::convert_patterns_to_fast_match(&pattern, "", "", ignore_case),
+                Some(PipeElement::Startswith) => {
+                    Self::convert_patterns_to_fast_match(&pattern, "", "*", ignore_case)
                 }
-                if !fastmatches.is_empty() {
-                    self.fast_match = Some(fastmatches);
+                Some(PipeElement::Endswith) => {
+                    Self::convert_patterns_to_fast_match(&pattern, "*", "", ignore_case)
                 }
-            } else if self.pipes[0] == PipeElement::Contains && self.pipes[1] == PipeElement::All
-            // |contains|allの場合、事前の分岐でAndSelectionNodeとしているのでここではcontainsのみとして取り扱う
-            {
-                self.fast_match =
-                    Self::convert_to_fast_match(format!("*{}*", pattern[0]).as_str(), true);
-            } else if self.pipes[0] == PipeElement::Contains
-                && self.pipes[1] == PipeElement::Windash
-            {
-                // |contains|windashの場合
-                let mut fastmatches =
-                    Self::convert_to_fast_match(format!("*{}*", pattern[0]).as_str(), true)
-                        .unwrap_or_default();
-                pattern.push(pattern[0].replacen('-', "/", 1));
-                fastmatches.extend(
-                    Self::convert_to_fast_match(
-                        format!("*{}*", pattern[0].replacen('-', "/", 1)).as_str(),
-                        true,
-                    )
-                    .unwrap_or_default(),
-                );
-                if !fastmatches.is_empty() {
-                    self.fast_match = Some(fastmatches);
+                // |contains|allの場合、事前の分岐でAndSelectionNodeとしているのでここではcontainsのみとして取り扱う
+                Some(PipeElement::Contains) => {
+                    Self::convert_patterns_to_fast_match(&pattern, "*", "*", ignore_case)
                 }
-            }
-        } else if n == 3 {
-            if self.pipes.contains(&PipeElement::Contains)
-                && self.pipes.contains(&PipeElement::All)
-                && self.pipes.contains(&PipeElement::Windash)
-            // |contains|all|windashの場合、事前の分岐でAndSelectionNodeとしているのでここではcontainsとwindashのみとして取り扱う
-            {
-                let mut fastmatches =
-                    Self::convert_to_fast_match(format!("*{}*", pattern[0]).as_str(), true)
-                        .unwrap_or_default();
-                pattern.push(pattern[0].replacen('-', "/", 1));
-                fastmatches.extend(
-                    Self::convert_to_fast_match(
-                        format!("*{}*", pattern[0].replacen('-', "/", 1)).as_str(),
-                        true,
-                    )
-                    .unwrap_or_default(),
-                );
-                if !fastmatches.is_empty() {
-                    self.fast_match = Some(fastmatches);
+                Some(PipeElement::AllOnly) => {
+                    Self::convert_patterns_to_fast_match(&pattern, "allOnly*", "*", ignore_case)
+                }
+                _ => None,
+            };
+        }
+        // |normbackslashの場合は、ルールの値もレコードの値と同じように連続したバックスラッシュをまとめておく
+        if self.normalize_backslash {
+            if let Some(fast_matches) = self.fast_match.as_mut() {
+                for fast_match in fast_matches.iter_mut() {
+                    match fast_match {
+                        FastMatch::Exact(s)
+                        | FastMatch::StartsWith(s)
+                        | FastMatch::EndsWith(s)
+                        | FastMatch::Contains(s)
+                        | FastMatch::AllOnly(s) => *s = Self::normalize_backslash(s),
+                    }
                 }
             }
-        } else {
-            let errmsg = format!(
-                "Multiple pipe elements cannot be used. key:{}",
-                utils::concat_selection_key(key_list)
-            );
-            return Result::Err(vec![errmsg]);
         }
         if self.fast_match.is_some()
             && matches!(
                 &self.fast_match.as_ref().unwrap()[0],
                 FastMatch::Exact(_) | FastMatch::Contains(_)
             )
-            && !self.key_list.is_empty()
+            && !self.is_keyword
         {
             // FastMatch::Exact/Contains検索に置き換えられたときは正規表現は不要
             return Result::Ok(());
@@ -527,7 +901,12 @@ impl LeafMatcher for DefaultMatcher {
                 PipeElement::EqualsField(_) | PipeElement::Endswithfield(_)
             )
         });
-        if !is_eqfield {
+        // 数値の大小比較は正規表現を使わないので、正規表現への変換は不要
+        let is_numeric_compare = self
+            .pipes
+            .iter()
+            .any(|pipe_element| pipe_element.get_numeric_compare_value().is_some());
+        if !is_eqfield && !is_numeric_compare {
             // 正規表現ではない場合、ワイルドカードであることを表す。
             // ワイルドカードは正規表現でマッチングするので、ワイルドカードを正規表現に変換するPipeを内部的に追加することにする。
             let is_re = self
@@ -540,9 +919,26 @@ impl LeafMatcher for DefaultMatcher {
 
             let mut re_result_vec = vec![];
             for p in pattern {
-                let pattern = DefaultMatcher::from_pattern_to_regex_str(p, &self.pipes);
+                let pattern = DefaultMatcher::from_pattern_to_regex_str(p, &self.pipes)
+                    .replace(WINDASH_PLACEHOLDER, WINDASH_REGEX_CLASS);
+                // ワイルドカードから変換した正規表現は(?i)付きになるので、|casedの場合は取り除く
+                let pattern = if self.case_sensitive && !is_re {
+                    pattern.replacen("(?i)", "", 1)
+                } else {
+                    pattern
+                };
+                // ワイルドカードは値全体と一致する必要があるので、正規表現を^と$で囲む
+                // |reとkeywords(key_listが空)の場合は部分一致のままとする
+                let pattern = if !is_re && !self.is_keyword {
+                    match pattern.strip_prefix("(?i)") {
+                        Some(p) => format!("(?i)^(?:{p})$"),
+                        None => format!("^(?:{pattern})$"),
+                    }
+                } else {
+                    pattern
+                };
                 // Pipeで処理されたパターンを正規表現に変換
-                if let Ok(re_result) = Regex::new(&pattern) {
+                if let Ok(re_result) = compile_regex_with_flags(&pattern, &re_flags) {
                     re_result_vec.push(re_result);
                 } else {
                     let errmsg = format!(
@@ -563,11 +959,16 @@ impl LeafMatcher for DefaultMatcher {
             PipeElement::EqualsField(_) | PipeElement::Endswithfield(_) => {
                 Some(pipe.is_eqfield_match(event_value, recinfo))
             }
+            PipeElement::FieldRef(_, _) => {
+                Some(pipe.is_fieldref_match(event_value, recinfo, self.case_sensitive))
+            }
+            PipeElement::Lt(_) | PipeElement::Lte(_) | PipeElement::Gt(_) | PipeElement::Gte(_) => {
+                Some(pipe.is_numeric_compare_match(event_value, self.timestamp_compare))
+            }
+            PipeElement::Exists(is_exist) => Some(event_value.is_some() == *is_exist),
             PipeElement::Cidr(ip_result) => match ip_result {
                 Ok(matcher_ip) => {
-                    let val = String::default();
-                    let event_value_str = event_value.unwrap_or(&val);
-                    let event_ip = IpAddr::from_str(event_value_str);
+                    let event_ip = IpAddr::from_str(event_value.map_or("", |v| v.as_str()));
                     match event_ip {
                         Ok(target_ip) => Some(matcher_ip.contains(&target_ip)),
                         Err(_) => Some(false), //IPアドレス以外の形式のとき
@@ -583,19 +984,15 @@ impl LeafMatcher for DefaultMatcher {
 
         // yamlにnullが設定されていた場合
         // keylistが空(==JSONのgrep検索)の場合、無視する。
-        if self.key_list.is_empty() && self.re.is_none() && self.fast_match.is_none() {
+        if self.is_keyword && self.re.is_none() && self.fast_match.is_none() {
             return false;
         }
 
         // yamlにnullが設定されていた場合
         if self.re.is_none() && self.fast_match.is_none() {
-            // レコード内に対象のフィールドが存在しなければ検知したものとして扱う
-            for v in self.key_list.iter() {
-                if recinfo.get_value(v).is_none() {
-                    return true;
-                }
-            }
-            return false;
+            // レコード内に対象のフィールドが存在しないか、値がnullであれば検知したものとして扱う
+            // 値が空文字の場合はフィールドが存在するので検知しない
+            return event_value.is_none();
         }
 
         if event_value.is_none() {
@@ -603,7 +1000,25 @@ impl LeafMatcher for DefaultMatcher {
         }
 
         let event_value_str = event_value.unwrap();
-        if self.key_list.is_empty() {
+        let normalized_value;
+        let event_value_str = if self.normalize_backslash && event_value_str.contains("\\\\") {
+            normalized_value = Self::normalize_backslash(event_value_str);
+            &normalized_value
+        } else {
+            event_value_str
+        };
+        // ルールの値とイベントの値のどちらかが16進数/8進数で表記されている場合は、数値として一致するかを判定する
+        // 10進数同士の場合は文字列として比較すればよいので、数値への変換は行わない
+        if let Some(rule_number) = self.exact_number {
+            if self.is_radix_rule_value || PipeElement::has_radix_prefix(event_value_str) {
+                if let Some(NumericValue::Integer(event_number)) =
+                    PipeElement::parse_number(event_value_str)
+                {
+                    return event_number == rule_number;
+                }
+            }
+        }
+        if self.is_keyword {
             // この場合ただのgrep検索なので、ただ正規表現に一致するかどうか調べればよいだけ
             return self
                 .re
@@ -613,19 +1028,23 @@ impl LeafMatcher for DefaultMatcher {
                 .any(|x| x.is_match(event_value_str));
         } else if let Some(fast_matcher) = &self.fast_match {
             let fast_match_result = if fast_matcher.len() == 1 {
-                match &fast_matcher[0] {
-                    FastMatch::Exact(s) => Some(Self::eq_ignore_case(event_value_str, s)),
-                    FastMatch::StartsWith(s) => Self::starts_with_ignore_case(event_value_str, s),
-                    FastMatch::EndsWith(s) => Self::ends_with_ignore_case(event_value_str, s),
-                    FastMatch::Contains(s) | FastMatch::AllOnly(s) => {
-                        Some(utils::contains_str(&event_value_str.to_lowercase(), s))
+                self.is_fast_match(&fast_matcher[0], event_value_str, &mut None)
+            } else {
+                // FastMatchが複数ある場合(|windashなど)は、いずれかに一致すれば検知したものとして扱う
+                // 小文字に変換したレコードの値はFastMatch間で使いまわす
+                let mut lowercase_value = None;
+                let mut result = Some(false);
+                for fm in fast_matcher {
+                    match self.is_fast_match(fm, event_value_str, &mut lowercase_value) {
+                        Some(true) => {
+                            result = Some(true);
+                            break;
+                        }
+                        Some(false) => {}
+                        None => result = None,
                     }
                 }
-            } else {
-                Some(fast_matcher.iter().any(|fm| match fm {
-                    FastMatch::Contains(s) => utils::contains_str(event_value_str, s),
-                    _ => false,
-                }))
+                result
             };
             if let Some(is_match) = fast_match_result {
                 return is_match;
@@ -636,6 +1055,73 @@ impl LeafMatcher for DefaultMatcher {
     }
 }
 
+/// FILETIMEの起点(1601/01/01)からUNIX時間の起点(1970/01/01)までの100ナノ秒単位の差
+const FILETIME_UNIX_EPOCH_DIFF: i128 = 116_444_736_000_000_000;
+
+/// |lt, |lte, |gt, |gteで比較するための数値を表すenum
+/// 64bitのLogonIdなどを精度を落とさずに比較できるよう、整数同士の場合は整数として比較する
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum NumericValue {
+    Integer(i128),
+    Float(f64),
+}
+
+impl NumericValue {
+    fn as_f64(&self) -> f64 {
+        match self {
+            NumericValue::Integer(i) => *i as f64,
+            NumericValue::Float(f) => *f,
+        }
+    }
+}
+
+/// |fieldrefと組み合わせて指定された比較方法を表すenum
+#[derive(PartialEq, Debug, Clone, Copy)]
+enum FieldRefMatch {
+    Equals,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+impl FieldRefMatch {
+    fn is_match(&self, event_value: &str, ref_value: &str) -> bool {
+        match self {
+            FieldRefMatch::Equals => event_value == ref_value,
+            FieldRefMatch::StartsWith => event_value.starts_with(ref_value),
+            FieldRefMatch::EndsWith => event_value.ends_with(ref_value),
+            FieldRefMatch::Contains => utils::contains_str(event_value, ref_value),
+        }
+    }
+
+    /// 大文字小文字を区別せずに比較します。
+    /// 検知処理の度に文字列を確保しないよう、ASCII文字のみの場合は小文字に変換せずに比較します。
+    fn is_match_ignore_case(&self, event_value: &str, ref_value: &str) -> bool {
+        if !event_value.is_ascii() || !ref_value.is_ascii() {
+            return self.is_match(&event_value.to_lowercase(), &ref_value.to_lowercase());
+        }
+        let (event_value, ref_value) = (event_value.as_bytes(), ref_value.as_bytes());
+        if ref_value.len() > event_value.len() {
+            return false;
+        }
+        match self {
+            FieldRefMatch::Equals => event_value.eq_ignore_ascii_case(ref_value),
+            FieldRefMatch::StartsWith => {
+                event_value[..ref_value.len()].eq_ignore_ascii_case(ref_value)
+            }
+            FieldRefMatch::EndsWith => {
+                event_value[event_value.len() - ref_value.len()..].eq_ignore_ascii_case(ref_value)
+            }
+            FieldRefMatch::Contains => {
+                ref_value.is_empty()
+                    || event_value
+                        .windows(ref_value.len())
+                        .any(|window| window.eq_ignore_ascii_case(ref_value))
+            }
+        }
+    }
+}
+
 /// パイプ(|)で指定される要素を表すクラス。
 /// 要リファクタリング
 #[derive(PartialEq)]
@@ -647,11 +1133,37 @@ enum PipeElement {
     Wildcard,
     EqualsField(String),
     Endswithfield(String),
+    Base64,
     Base64offset,
     Windash,
     Cidr(Result<IpCidr, NetworkParseError>),
     All,
     AllOnly,
+    Cased,
+    Wide,
+    FieldRef(String, FieldRefMatch),
+    Lt(String),
+    Lte(String),
+    Gt(String),
+    Gte(String),
+    /// |lt, |lte, |gt, |gteの比較を数値ではなく時刻として行う。
+    /// ISO-8601形式の文字列と、FILETIME(1601/01/01からの100ナノ秒単位の整数)を時刻として解釈する。
+    Timestamp,
+    /// フィールドの有無を判定する。値の内容は問わない。
+    /// nullの値はフィールドが存在しないものとして扱う。
+    ///
+    /// | ルール | フィールドなし | null | 空文字 | 空文字以外 |
+    /// |---|---|---|---|---|
+    /// | `Field: null` | 検知 | 検知 | - | - |
+    /// | `Field: ''` | - | - | 検知 | - |
+    /// | `Field\|exists: true` | - | - | 検知 | 検知 |
+    /// | `Field\|exists: false` | 検知 | 検知 | - | - |
+    Exists(bool),
+    /// 比較する前に、レコードの値とルールの値の連続したバックスラッシュ(`\\`, `\\\`など)を1つの`\`にまとめる。
+    /// ルールの値はワイルドカードのエスケープ(`\\`は`\`を表す)を解釈した後の文字列を正規化するので、
+    /// `C:\Windows\`や`C:\\\\Windows\\\\`と指定したルールは、`C:\Windows\`と`C:\\Windows\\`のどちらのレコードの値とも一致する。
+    /// 正規表現(|re)のパターンは正規化しないが、レコードの値は正規化してから比較する。
+    NormBackslash,
 }
 
 impl PipeElement {
@@ -663,11 +1175,35 @@ impl PipeElement {
             "re" => Option::Some(PipeElement::Re),
             "equalsfield" => Option::Some(PipeElement::EqualsField(pattern.to_string())),
             "endswithfield" => Option::Some(PipeElement::Endswithfield(pattern.to_string())),
+            "base64" => Option::Some(PipeElement::Base64),
             "base64offset" => Option::Some(PipeElement::Base64offset),
             "windash" => Option::Some(PipeElement::Windash),
             "cidr" => Option::Some(PipeElement::Cidr(IpCidr::from_str(pattern))),
             "all" => Option::Some(PipeElement::All),
             "allOnly" => Option::Some(PipeElement::AllOnly),
+            "cased" => Option::Some(PipeElement::Cased),
+            "wide" | "utf16le" => Option::Some(PipeElement::Wide),
+            "fieldref" => Option::Some(PipeElement::FieldRef(
+                pattern.to_string(),
+                FieldRefMatch::Equals,
+            )),
+            "exists" => match pattern {
+                "true" => Option::Some(PipeElement::Exists(true)),
+                "false" => Option::Some(PipeElement::Exists(false)),
+                _ => {
+                    return Result::Err(format!(
+                        "The value of the exists modifier should be true or false. key:{}",
+                        utils::concat_selection_key(key_list)
+                    ));
+                }
+            },
+            // 比較する値が数値(|timestampの場合は時刻)として解釈できるかはDefaultMatcher::init()で確認する
+            "lt" => Option::Some(PipeElement::Lt(pattern.to_string())),
+            "lte" => Option::Some(PipeElement::Lte(pattern.to_string())),
+            "gt" => Option::Some(PipeElement::Gt(pattern.to_string())),
+            "gte" => Option::Some(PipeElement::Gte(pattern.to_string())),
+            "timestamp" => Option::Some(PipeElement::Timestamp),
+            "normbackslash" => Option::Some(PipeElement::NormBackslash),
             _ => Option::None,
         };
 
@@ -685,10 +1221,154 @@ impl PipeElement {
         match self {
             PipeElement::EqualsField(s) => Option::Some(s),
             PipeElement::Endswithfield(s) => Option::Some(s),
+            PipeElement::FieldRef(s, _) => Option::Some(s),
+            _ => Option::None,
+        }
+    }
+
+    fn get_numeric_compare_value(&self) -> Option<&String> {
+        match self {
+            PipeElement::Lt(s) | PipeElement::Lte(s) | PipeElement::Gt(s) | PipeElement::Gte(s) => {
+                Option::Some(s)
+            }
             _ => Option::None,
         }
     }
 
+    fn get_numeric_compare_name(&self) -> &str {
+        match self {
+            PipeElement::Lt(_) => "lt",
+            PipeElement::Lte(_) => "lte",
+            PipeElement::Gt(_) => "gt",
+            _ => "gte",
+        }
+    }
+
+    /// 10進数の整数/小数と、Windowsのイベントログでよく使われる0xから始まる16進数、0oから始まる8進数を数値として解釈します。
+    fn parse_number(value: &str) -> Option<NumericValue> {
+        let value = value.trim();
+        if Self::has_radix_prefix(value) {
+            let radix = if value[1..2].eq_ignore_ascii_case("x") {
+                16
+            } else {
+                8
+            };
+            return i128::from_str_radix(&value[2..], radix)
+                .ok()
+                .map(NumericValue::Integer);
+        }
+        if let Ok(i) = value.parse::<i128>() {
+            return Some(NumericValue::Integer(i));
+        }
+        value
+            .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
+            .map(NumericValue::Float)
+    }
+
+    /// 0x(16進数)または0o(8進数)から始まる値かどうかを判定します。
+    fn has_radix_prefix(value: &str) -> bool {
+        let bytes = value.trim().as_bytes();
+        bytes.len() > 2 && bytes[0] == b'0' && matches!(bytes[1], b'x' | b'X' | b'o' | b'O')
+    }
+
+    /// 10.0.19041のようにドットで区切られた数字をバージョンとして解釈します。
+    /// 10.5のような小数と区別するため、ルールの値は数値として解釈できない場合のみバージョンとして扱います。
+    fn parse_version(value: &str) -> Option<Vec<u64>> {
+        value
+            .trim()
+            .split('.')
+            .map(|component| {
+                if component.is_empty() || !component.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                component.parse::<u64>().ok()
+            })
+            .collect()
+    }
+
+    /// バージョンを先頭の要素から順に数値として比較します。
+    /// 要素数が異なる場合は足りない要素を0として扱うので、10.0と10.0.0は等しくなります。
+    fn cmp_version(left: &[u64], right: &[u64]) -> Ordering {
+        (0..left.len().max(right.len()))
+            .map(|i| {
+                let l = left.get(i).unwrap_or(&0);
+                let r = right.get(i).unwrap_or(&0);
+                l.cmp(r)
+            })
+            .find(|ordering| ordering != &Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    }
+
+    /// ISO-8601形式の文字列と、FILETIMEの整数を1970/01/01からのナノ秒として解釈します。
+    /// タイムゾーンの指定がない文字列はUTCとして扱います。
+    fn parse_timestamp(value: &str) -> Option<i128> {
+        let value = value.trim();
+        if let Some(NumericValue::Integer(filetime)) = PipeElement::parse_number(value) {
+            // FILETIMEは1601/01/01からの100ナノ秒単位の値なので、1970/01/01からのナノ秒に変換する
+            return Some((filetime - FILETIME_UNIX_EPOCH_DIFF) * 100);
+        }
+        let datetime = DateTime::parse_from_rfc3339(value)
+            .map(|dt| dt.naive_utc())
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+            .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f"))
+            .or_else(|_| {
+                NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            })
+            .ok()?
+            .and_utc();
+        Some(
+            datetime.timestamp() as i128 * 1_000_000_000
+                + datetime.timestamp_subsec_nanos() as i128,
+        )
+    }
+
+    /// |lt, |lte, |gt, |gteのパイプ処理です。
+    /// イベントの値とルールの値のどちらかが数値(|timestampの場合は時刻、ルールの値がバージョンの場合はバージョン)として解釈できない場合はfalseを返します。
+    fn is_numeric_compare_match(
+        &self,
+        event_value: Option<&String>,
+        timestamp_compare: bool,
+    ) -> bool {
+        let rule_value = self.get_numeric_compare_value();
+        let ordering = if timestamp_compare {
+            let rule_value = rule_value.and_then(|v| PipeElement::parse_timestamp(v));
+            let event_value = event_value.and_then(|v| PipeElement::parse_timestamp(v));
+            match (event_value, rule_value) {
+                (Some(e), Some(r)) => Some(e.cmp(&r)),
+                _ => None,
+            }
+        } else if let Some(rule_version) = rule_value
+            .filter(|v| PipeElement::parse_number(v).is_none())
+            .and_then(|v| PipeElement::parse_version(v))
+        {
+            // ルールの値がバージョンの場合は、イベントの値もバージョンとして比較する
+            event_value
+                .and_then(|v| PipeElement::parse_version(v))
+                .map(|event_version| PipeElement::cmp_version(&event_version, &rule_version))
+        } else {
+            let rule_value = rule_value.and_then(|v| PipeElement::parse_number(v));
+            let event_value = event_value.and_then(|v| PipeElement::parse_number(v));
+            match (event_value, rule_value) {
+                (Some(NumericValue::Integer(e)), Some(NumericValue::Integer(r))) => Some(e.cmp(&r)),
+                (Some(e), Some(r)) => e.as_f64().partial_cmp(&r.as_f64()),
+                _ => None,
+            }
+        };
+        match ordering {
+            Some(ordering) => match self {
+                PipeElement::Lt(_) => ordering == Ordering::Less,
+                PipeElement::Lte(_) => ordering != Ordering::Greater,
+                PipeElement::Gt(_) => ordering == Ordering::Greater,
+                PipeElement::Gte(_) => ordering != Ordering::Less,
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
     fn is_eqfield_match(&self, event_value: Option<&String>, recinfo: &EvtxRecordInfo) -> bool {
         match self {
             PipeElement::EqualsField(eq_key) => {
@@ -707,14 +1387,36 @@ impl PipeElement {
                     return false;
                 }
 
-                let event_value = &event_value.unwrap().to_lowercase();
-                let ends_value = &ends_value.unwrap().to_lowercase();
-                event_value.ends_with(ends_value)
+                FieldRefMatch::EndsWith
+                    .is_match_ignore_case(event_value.unwrap(), ends_value.unwrap())
             }
             _ => false,
         }
     }
 
+    /// |fieldrefで指定されたフィールドの値とレコードの値を比較します。
+    fn is_fieldref_match(
+        &self,
+        event_value: Option<&String>,
+        recinfo: &EvtxRecordInfo,
+        case_sensitive: bool,
+    ) -> bool {
+        let (ref_key, fieldref_match) = match self {
+            PipeElement::FieldRef(ref_key, fieldref_match) => (ref_key, fieldref_match),
+            _ => return false,
+        };
+        // どちらかのフィールドがレコードに存在しない場合はfalseにする
+        let (event_value, ref_value) = match (event_value, recinfo.get_value(ref_key)) {
+            (Some(event_value), Some(ref_value)) => (event_value, ref_value),
+            _ => return false,
+        };
+        if case_sensitive {
+            fieldref_match.is_match(event_value, ref_value)
+        } else {
+            fieldref_match.is_match_ignore_case(event_value, ref_value)
+        }
+    }
+
     /// patternをパイプ処理します
     fn pipe_pattern(&self, pattern: String) -> String {
         // enumでポリモーフィズムを実装すると、一つのメソッドに全部の型の実装をする感じになる。Java使い的にはキモイ感じがする。
@@ -752,6 +1454,8 @@ impl PipeElement {
             PipeElement::Endswith => fn_add_asterisk_begin(pattern),
             // containsの場合はpatternの前後にwildcardを足すことで対応する
             PipeElement::Contains => fn_add_asterisk_end(fn_add_asterisk_begin(pattern)),
+            // |allの場合もcontainsと同様にレコード全体への部分一致で判定する
+            PipeElement::AllOnly => fn_add_asterisk_end(fn_add_asterisk_begin(pattern)),
             // WildCardは正規表現に変換する。
             PipeElement::Wildcard => PipeElement::pipe_pattern_wildcard(pattern),
             _ => pattern,
@@ -842,6 +1546,7 @@ impl PipeElement {
 
 #[cfg(test)]
 mod tests {
+    use std::cmp::Ordering;
     use std::path::Path;
 
     use super::super::matchers::{
@@ -855,9 +1560,21 @@ mod tests {
         Action, CommonOptions, Config, CsvOutputOption, DetectCommonOption, InputOption,
         OutputOption, StoredStatic, STORED_EKEY_ALIAS,
     };
-    use crate::detections::rule::matchers::FastMatch;
-    use crate::detections::rule::tests::parse_rule_from_str;
+    use crate::detections::rule::create_rule;
+    use crate::detections::rule::matchers::{FastMatch, FieldRefMatch};
+    use crate::detections::rule::tests::{create_dummy_stored_static, parse_rule_from_str};
     use crate::detections::{self, utils};
+    use yaml_rust::YamlLoader;
+
+    /// Event.SystemとEvent.EventDataの中身を指定して、テスト用のレコードのJSON文字列を作成する
+    fn create_record_json_str(system: &str, event_data: &str) -> String {
+        format!(
+            r#"{{
+            "Event": {{"System": {{{system}}}, "EventData": {{{event_data}}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+        )
+    }
 
     fn check_select(rule_str: &str, record_str: &str, expect_select: bool) {
         let mut rule_node = parse_rule_from_str(rule_str);
@@ -868,6 +1585,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -888,7 +1606,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -901,10 +1619,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -913,6 +1634,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -1387,39 +2124,95 @@ mod tests {
     }
 
     #[test]
-    fn test_notdetect_minlen_and() {
-        // minlenが正しく検知できることを確認
+    fn test_detect_minlength_modifier() {
+        // |minlengthで指定した文字数以上の場合のみ検知することを境界値で確認
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel:
-                    min_length: 11
+                CommandLine|minlength: 10
         details: 'command=%CommandLine%'
         "#;
 
+        for (command_line, expect_select) in [
+            ("powershell", true),  // 10文字
+            ("powershell ", true), // 11文字
+            ("powershel", false),  // 9文字
+        ] {
+            let record_json_str = format!(
+                r#"
+        {{
+            "Event": {{"System": {{"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}}, "EventData": {{"CommandLine": "{command_line}"}}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            );
+            check_select(rule_str, &record_json_str, expect_select);
+        }
+
+        // フィールドが存在しない場合は検知しない
         let record_json_str = r#"
         {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security10", "Computer":"DESKTOP-ICHIICHI"}},
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"Image": "C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
-
         check_select(rule_str, record_json_str, false);
     }
 
     #[test]
-    fn test_detect_regex() {
-        // 正規表現が使えることを確認
+    fn test_detect_minlength_modifier_invalid_value() {
+        // |minlengthの値が整数でない場合はエラーになることを確認
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel|re: ^Program$
-        details: 'command=%CommandLine%'
+                CommandLine|minlength: 'long'
+        details: 'Rule parse test'
         "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "minlength value should be an integer. [key:detection -> selection -> CommandLine|minlength]"
+                    .to_string()
+            ])
+        );
+    }
 
-        let record_json_str = r#"
-        {
+    #[test]
+    fn test_notdetect_minlen_and() {
+        // minlenが正しく検知できることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel:
+                    min_length: 11
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security10", "Computer":"DESKTOP-ICHIICHI"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_detect_regex() {
+        // 正規表現が使えることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel|re: ^Program$
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
             "Event": {"System": {"EventID": 4103, "Channel": "Program", "Computer":"DESKTOP-ICHIICHI"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
@@ -1447,6 +2240,214 @@ mod tests {
         check_select(rule_str, record_json_str, true);
     }
 
+    #[test]
+    fn test_detect_regex_only_with_re_modifier() {
+        // |reを指定した場合のみ正規表現として扱い、修飾子なしや|containsの値に含まれる「.」や「+」は文字列として扱う
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Program", "Computer":"DESKTOP-ICHIICHI"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str_literal = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Program", "Computer":"DESKTOP.+"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        let rule_str_plain = r#"
+        enabled: true
+        detection:
+            selection:
+                Computer: 'DESKTOP.+'
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str_plain, record_json_str, false);
+        check_select(rule_str_plain, record_json_str_literal, true);
+
+        let rule_str_contains = r#"
+        enabled: true
+        detection:
+            selection:
+                Computer|contains: 'TOP.+'
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str_contains, record_json_str, false);
+        check_select(rule_str_contains, record_json_str_literal, true);
+
+        let rule_str_re = r#"
+        enabled: true
+        detection:
+            selection:
+                Computer|re: 'DESKTOP.+'
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str_re, record_json_str, true);
+        check_select(rule_str_re, record_json_str_literal, true);
+    }
+
+    #[test]
+    fn test_detect_regex_flags() {
+        // |reの後に|i, |m, |sを指定した場合に、正規表現のフラグとして扱われることを確認
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational", "Computer":"desktop-ichiichi"}, "EventData": {"CommandLine": "cmd.exe\nwhoami"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let cases = [
+            // |iは大文字小文字を区別しない
+            ("Computer|re: '^DESKTOP-'", false),
+            ("Computer|re|i: '^DESKTOP-'", true),
+            // |mは^と$が各行の先頭と末尾に一致する
+            ("CommandLine|re: '^whoami$'", false),
+            ("CommandLine|re|m: '^whoami$'", true),
+            // |sは.が改行にも一致する
+            ("CommandLine|re: 'cmd[.]exe.whoami'", false),
+            ("CommandLine|re|s: 'cmd[.]exe.whoami'", true),
+            // フラグは組み合わせて指定できる
+            ("CommandLine|re|i|m: '^WHOAMI$'", true),
+        ];
+        for (selection, expect_select) in cases {
+            let rule_str = format!(
+                r#"
+        enabled: true
+        detection:
+            selection:
+                {selection}
+        details: 'command=%CommandLine%'
+        "#
+            );
+            check_select(&rule_str, record_json_str, expect_select);
+        }
+    }
+
+    #[test]
+    fn test_detect_regex_unknown_flag() {
+        // |reの後に不明なフラグが指定された場合はエラーになることを確認
+        let cases = [
+            (
+                "CommandLine|re|x: 'abc'",
+                "An unknown regex flag was specified. flag:x, key:detection -> selection -> CommandLine|re|x",
+            ),
+            // |reがない場合はフラグではなく不明なパイプとして扱う
+            (
+                "CommandLine|i: 'abc'",
+                "An unknown pipe element was specified. key:detection -> selection -> CommandLine|i",
+            ),
+        ];
+        for (selection, expected) in cases {
+            let rule_str = format!(
+                r#"
+        enabled: true
+        detection:
+            selection:
+                {selection}
+        details: 'Rule parse test'
+        "#
+            );
+            let mut rule_yaml = YamlLoader::load_from_str(&rule_str).unwrap().into_iter();
+            let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+            assert_eq!(
+                rule_node.init(&create_dummy_stored_static()),
+                Err(vec![expected.to_string()]),
+                "{selection}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_not_modifier() {
+        // 同じフィールドに対して、|containsと|contains|notをAND条件で組み合わせられることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image|contains: 'powershell'
+                Image|contains|not: 'System32'
+        details: 'command=%CommandLine%'
+        "#;
+
+        let cases = [
+            (r#"{"Image": "C:\\Users\\user01\\powershell.exe"}"#, true),
+            (
+                r#"{"Image": "C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe"}"#,
+                false,
+            ),
+            (r#"{"Image": "C:\\Windows\\System32\\cmd.exe"}"#, false),
+        ];
+        for (event_data, expect_select) in cases {
+            let record_json_str = format!(
+                r#"
+        {{
+            "Event": {{"System": {{"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}}, "EventData": {event_data}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            );
+            check_select(rule_str, &record_json_str, expect_select);
+        }
+    }
+
+    #[test]
+    fn test_detect_not_modifier_list() {
+        // |notに配列を指定した場合は、いずれの値にも一致しない場合に検知することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 1
+                Image|contains|not:
+                    - 'System32'
+                    - 'SysWOW64'
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"Image": "C:\\Users\\user01\\powershell.exe"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str2 = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"Image": "C:\\Windows\\System32\\cmd.exe"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str3 = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"Image": "C:\\Windows\\SysWOW64\\cmd.exe"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+        check_select(rule_str, record_json_str3, false);
+    }
+
+    #[test]
+    fn test_detect_not_modifier_field_not_exists() {
+        // |notはフィールドが存在しない場合も一致しないものとして検知することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 1
+                ParentImage|endswith|not: 'explorer.exe'
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"Image": "C:\\Windows\\System32\\cmd.exe"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str2 = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"ParentImage": "C:\\Windows\\explorer.exe"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+    }
+
     #[test]
     fn test_detect_regexes() {
         // regexes.txtが正しく検知できることを確認
@@ -1802,164 +2803,1344 @@ mod tests {
     }
 
     #[test]
-    fn test_detect_wildcard_multibyte() {
-        // multi byteの確認
+    fn test_exact_match_without_regex() {
+        // 修飾子とワイルドカードがない値は正規表現を使わずに文字列の完全一致で比較することを確認
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel: ホストアプリケーション
+                Image: 'C:\Program Files (x86)\app+1.exe'
         details: 'command=%CommandLine%'
         "#;
+        let rule_node = parse_rule_from_str(rule_str);
+        let selection_node = &rule_node.detection.name_to_selection["selection"];
+        let child_node = selection_node.get_childs()[0];
+        let child_node = child_node.downcast_ref::<LeafSelectionNode>().unwrap();
+        let matcher = child_node.matcher.as_ref().unwrap();
+        let matcher = matcher.downcast_ref::<DefaultMatcher>().unwrap();
+        assert!(matcher.re.is_none());
+        assert_eq!(
+            *matcher.fast_match.as_ref().unwrap(),
+            vec![FastMatch::Exact(
+                r"C:\Program Files (x86)\app+1.exe".to_string()
+            )]
+        );
 
         let record_json_str = r#"
         {
-            "Event": {"System": {"EventID": 4103, "Channel": "ホストアプリケーション"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "c:\\program files (x86)\\APP+1.exe"
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\Program Files (x86)\\app+1.exe.bak"
+            }
+          }
+        }"#;
+
+        let record_json_str3 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\Program Files x86\\app1.exe"
+            }
+          }
         }"#;
 
         check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+        check_select(rule_str, record_json_str3, false);
     }
 
     #[test]
-    fn test_detect_wildcard_multibyte_notdetect() {
-        // multi byteの確認
+    fn test_detect_cased() {
+        // |casedを指定した場合は大文字小文字を区別して比較することを確認
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel: ホスとアプリケーション
+                CommandLine|cased: 'Invoke-Mimikatz'
         details: 'command=%CommandLine%'
         "#;
 
         let record_json_str = r#"
         {
-            "Event": {"System": {"EventID": 4103, "Channel": "ホストアプリケーション"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "Invoke-Mimikatz"
+            }
+          }
         }"#;
-        check_select(rule_str, record_json_str, false);
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "invoke-mimikatz"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
     }
 
     #[test]
-    fn test_wildcard_case_insensitive() {
-        // wildcardは大文字小文字関係なくマッチする。
+    fn test_detect_contains_cased() {
+        // |contains|casedの場合も大文字小文字を区別することを確認
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel: Security
+                CommandLine|contains|cased: 'Invoke-Mimikatz'
         details: 'command=%CommandLine%'
         "#;
 
         let record_json_str = r#"
         {
-            "Event": {"System": {"EventID": 4103, "Channel": "security", "Computer":"DESKTOP-ICHIICHI"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "powershell -c Invoke-Mimikatz -DumpCreds"
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "powershell -c INVOKE-MIMIKATZ -DumpCreds"
+            }
+          }
         }"#;
 
         check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
     }
 
     #[test]
-    fn test_pipe_pattern_wildcard_asterisk() {
-        let value = PipeElement::pipe_pattern_wildcard(r"*ho*ge*".to_string());
-        assert_eq!("(?i)(.|\\a|\\f|\\t|\\n|\\r|\\v)*ho(.|\\a|\\f|\\t|\\n|\\r|\\v)*ge(.|\\a|\\f|\\t|\\n|\\r|\\v)*", value);
-    }
+    fn test_detect_startswith_cased() {
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image|startswith|cased: 'C:\Windows\'
+        details: 'command=%CommandLine%'
+        "#;
 
-    #[test]
-    fn test_pipe_pattern_wildcard_asterisk2() {
-        let value = PipeElement::pipe_pattern_wildcard(r"\*ho\*\*ge\*".to_string());
-        // wildcardの「\*」は文字列としての「*」を表す。
-        // 正規表現で「*」はエスケープする必要があるので、\*が正解
-        assert_eq!(r"(?i)\*ho\*\*ge\*", value);
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\Windows\\System32\\cmd.exe"
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\WINDOWS\\System32\\cmd.exe"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
     }
 
     #[test]
-    fn test_pipe_pattern_wildcard_asterisk3() {
-        // wildcardの「\\*」は文字列としての「\」と正規表現の「.*」を表す。
-        // 文字列としての「\」はエスケープされるので、「\\.*」が正解
-        let value = PipeElement::pipe_pattern_wildcard(r"\\*ho\\*ge\\*".to_string());
+    fn test_detect_wildcard_cased() {
+        // 正規表現に変換されるワイルドカードでも|casedが効くことを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine|cased: 'net*User'
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "net localUser"
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "NET localuser"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+    }
+
+    #[test]
+    fn test_detect_rendered_message() {
+        // RenderedMessageで表示用に展開されたメッセージ(Event.RenderingInfo.Message)と比較できることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                RenderedMessage|contains: 'An account was successfully logged on'
+        details: 'message=%RenderedMessage%'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 4624,
+              "Channel": "Security"
+            },
+            "EventData": {
+              "TargetUserName": "user01"
+            },
+            "RenderingInfo": {
+              "Message": "An account was successfully logged on.\r\n\r\nSubject:\r\n\tSecurity ID:\t\tS-1-5-18",
+              "Level": "Information"
+            }
+          }
+        }"#;
+
+        // RenderingInfoを含まないレコードは検知しない
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 4624,
+              "Channel": "Security"
+            },
+            "EventData": {
+              "TargetUserName": "user01"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+    }
+
+    #[test]
+    fn test_detect_normbackslash() {
+        let record_single = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\Windows\\System32\\cmd.exe"
+            }
+          }
+        }"#;
+        let record_double = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\\\Windows\\\\System32\\\\cmd.exe"
+            }
+          }
+        }"#;
+
+        // ルールの値の\\はエスケープとして\と解釈されるので、修飾子がなくても\区切りのパスと一致することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image: 'C:\\Windows\\System32\\cmd.exe'
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_single, true);
+        check_select(rule_str, record_double, false);
+
+        // |normbackslashを指定すると、レコードの値の連続したバックスラッシュをまとめて比較することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image|normbackslash: 'C:\\Windows\\System32\\cmd.exe'
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_single, true);
+        check_select(rule_str, record_double, true);
+
+        // ルールの値の連続したバックスラッシュもまとめて比較することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image|normbackslash: 'C:\\\\Windows\\\\System32\\\\cmd.exe'
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_single, true);
+        check_select(rule_str, record_double, true);
+
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image|contains|normbackslash: '\Windows\System32\'
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_single, true);
+        check_select(rule_str, record_double, true);
+
+        // 正規表現に変換されるワイルドカードの場合もレコードの値を正規化して比較することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image|normbackslash: 'C:\Windows\System32*.exe'
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_single, true);
+        check_select(rule_str, record_double, true);
+    }
+
+    #[test]
+    fn test_normalize_backslash() {
         assert_eq!(
-            r"(?i)\\(.|\a|\f|\t|\n|\r|\v)*ho\\(.|\a|\f|\t|\n|\r|\v)*ge\\(.|\a|\f|\t|\n|\r|\v)*",
-            value
+            DefaultMatcher::normalize_backslash(r"C:\\Windows\\\System32\cmd.exe"),
+            r"C:\Windows\System32\cmd.exe"
+        );
+        assert_eq!(
+            DefaultMatcher::normalize_backslash(r"\\server\share"),
+            r"\server\share"
         );
+        assert_eq!(DefaultMatcher::normalize_backslash("cmd.exe"), "cmd.exe");
     }
 
     #[test]
-    fn test_pipe_pattern_wildcard_question() {
-        let value = PipeElement::pipe_pattern_wildcard(r"?ho?ge?".to_string());
-        assert_eq!(r"(?i).ho.ge.", value);
+    fn test_detect_wildcard_fullmatch() {
+        // 正規表現に変換されるワイルドカードは値全体と一致する場合のみ検知することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine: 'ab?'
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "ABC"
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "abcd"
+            }
+          }
+        }"#;
+
+        let record_json_str3 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "xabc"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+        check_select(rule_str, record_json_str3, false);
+    }
+
+    #[test]
+    fn test_detect_wildcard_fullmatch_alternation() {
+        // 値に含まれる|は正規表現の選択としては扱われず、値全体との一致で判定されることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine: 'ab|ab?'
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "ab|abc"
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "abc"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+    }
+
+    #[test]
+    fn test_detect_contains_regex_metachar() {
+        // containsの値に含まれる正規表現のメタ文字がそのままの文字として扱われることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image|contains: 'Program Files (x86)'
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\Program Files (x86)\\test.exe"
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\Program Files x86\\test.exe"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+    }
+
+    #[test]
+    fn test_notdetect_contains_null_or_missing_field() {
+        // containsの対象フィールドがnullまたは存在しない場合は検知しないことを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                TargetUserName|contains: "Admin"
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 4732,
+              "Channel": "Security"
+            },
+            "EventData": {
+              "TargetUserName": null
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 4732,
+              "Channel": "Security"
+            },
+            "EventData": {
+              "MemberName": "Administrator"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, false);
+        check_select(rule_str, record_json_str2, false);
+    }
+
+    #[test]
+    fn test_detect_wildcard_windows_path() {
+        // バックスラッシュを含むWindowsのパスでも「*」と「?」がワイルドカードとして扱われることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image: 'C:\Windows\\*\cmd?.exe'
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\Windows\\System32\\cmd2.exe"
+            }
+          }
+        }"#;
+
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:\\Windows\\System32\\cmd.exe"
+            }
+          }
+        }"#;
+
+        let record_json_str3 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "Image": "C:%indows\\System32\\cmd2.exe"
+            }
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+        check_select(rule_str, record_json_str3, false);
+    }
+
+    #[test]
+    fn test_detect_wildcard_multibyte() {
+        // multi byteの確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel: ホストアプリケーション
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "ホストアプリケーション"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_detect_wildcard_multibyte_notdetect() {
+        // multi byteの確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel: ホスとアプリケーション
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "ホストアプリケーション"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_wildcard_case_insensitive() {
+        // wildcardは大文字小文字関係なくマッチする。
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel: Security
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "security", "Computer":"DESKTOP-ICHIICHI"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_asterisk() {
+        let value = PipeElement::pipe_pattern_wildcard(r"*ho*ge*".to_string());
+        assert_eq!("(?i)(.|\\a|\\f|\\t|\\n|\\r|\\v)*ho(.|\\a|\\f|\\t|\\n|\\r|\\v)*ge(.|\\a|\\f|\\t|\\n|\\r|\\v)*", value);
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_asterisk2() {
+        let value = PipeElement::pipe_pattern_wildcard(r"\*ho\*\*ge\*".to_string());
+        // wildcardの「\*」は文字列としての「*」を表す。
+        // 正規表現で「*」はエスケープする必要があるので、\*が正解
+        assert_eq!(r"(?i)\*ho\*\*ge\*", value);
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_asterisk3() {
+        // wildcardの「\\*」は文字列としての「\」と正規表現の「.*」を表す。
+        // 文字列としての「\」はエスケープされるので、「\\.*」が正解
+        let value = PipeElement::pipe_pattern_wildcard(r"\\*ho\\*ge\\*".to_string());
+        assert_eq!(
+            r"(?i)\\(.|\a|\f|\t|\n|\r|\v)*ho\\(.|\a|\f|\t|\n|\r|\v)*ge\\(.|\a|\f|\t|\n|\r|\v)*",
+            value
+        );
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_windows_path() {
+        // ワイルドカード以外の文字は正規表現としてエスケープされるので、「\W」などは正規表現として解釈されない
+        // 「\\*」は文字列としての「\」とワイルドカードの「*」を表す
+        let value = PipeElement::pipe_pattern_wildcard(r"C:\Windows\\*\cmd?.exe".to_string());
+        assert_eq!(r"(?i)C:\\Windows\\(.|\a|\f|\t|\n|\r|\v)*\\cmd.\.exe", value);
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_question() {
+        let value = PipeElement::pipe_pattern_wildcard(r"?ho?ge?".to_string());
+        assert_eq!(r"(?i).ho.ge.", value);
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_question2() {
+        let value = PipeElement::pipe_pattern_wildcard(r"\?ho\?ge\?".to_string());
+        assert_eq!(r"(?i)\?ho\?ge\?", value);
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_question3() {
+        let value = PipeElement::pipe_pattern_wildcard(r"\\?ho\\?ge\\?".to_string());
+        assert_eq!(r"(?i)\\.ho\\.ge\\.", value);
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_backshash() {
+        let value = PipeElement::pipe_pattern_wildcard(r"\\ho\\ge\\".to_string());
+        assert_eq!(r"(?i)\\\\ho\\\\ge\\\\", value);
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_mixed() {
+        let value = PipeElement::pipe_pattern_wildcard(r"\\*\****\*\\*".to_string());
+        assert_eq!(
+            r"(?i)\\(.|\a|\f|\t|\n|\r|\v)*\*(.|\a|\f|\t|\n|\r|\v)*(.|\a|\f|\t|\n|\r|\v)*(.|\a|\f|\t|\n|\r|\v)*\*\\(.|\a|\f|\t|\n|\r|\v)*",
+            value
+        );
+    }
+
+    #[test]
+    fn test_pipe_pattern_wildcard_many_backshashs() {
+        let value = PipeElement::pipe_pattern_wildcard(r"\\\*ho\\\*ge\\\".to_string());
+        assert_eq!(
+            r"(?i)\\\\(.|\a|\f|\t|\n|\r|\v)*ho\\\\(.|\a|\f|\t|\n|\r|\v)*ge\\\\\\",
+            value
+        );
+    }
+
+    #[test]
+    fn test_grep_match() {
+        // wildcardは大文字小文字関係なくマッチする。
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                - 4103
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "security", "Computer":"DESKTOP-ICHIICHI"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_grep_not_match() {
+        // wildcardは大文字小文字関係なくマッチする。
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                - 4104
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "security", "Computer":"DESKTOP-ICHIICHI"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_grep_keywords_any_field() {
+        // keywordsのリストのいずれかの値がレコードのいずれかのフィールドに部分一致すれば検知する
+        let rule_str = r#"
+        enabled: true
+        detection:
+            keywords:
+                - 'sekurlsa::'
+                - 'Invoke-Mimikatz'
+            condition: keywords
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "mimikatz.exe SEKURLSA::logonpasswords exit"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str2 = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"ParentCommandLine": "powershell.exe -c invoke-mimikatz"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str3 = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "whoami /all"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, true);
+        check_select(rule_str, record_json_str3, false);
+    }
+
+    #[test]
+    fn test_grep_keywords_with_selection() {
+        // keywordsを他のselectionと組み合わせてconditionで参照できることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 1
+            keywords:
+                - 'mimikatz'
+            condition: selection and keywords
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "mimikatz.exe"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str2 = r#"
+        {
+            "Event": {"System": {"EventID": 4688, "Channel": "Security"}, "EventData": {"CommandLine": "mimikatz.exe"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+    }
+
+    #[test]
+    fn test_grep_keywords_list_and_map() {
+        // 同じconditionで参照していても、keywordsが文字列のリストの場合はイベント全体への部分一致、
+        // 連想配列の場合はフィールドの条件として判定することを確認
+        let list_rule_str = r#"
+        enabled: true
+        detection:
+            keywords:
+                - 'mimikatz'
+            condition: keywords
+        details: 'command=%CommandLine%'
+        "#;
+        let map_rule_str = r#"
+        enabled: true
+        detection:
+            keywords:
+                CommandLine: 'mimikatz'
+            condition: keywords
+        details: 'command=%CommandLine%'
+        "#;
+        let mixed_rule_str = r#"
+        enabled: true
+        detection:
+            keywords:
+                - 'mimikatz'
+                - CommandLine: 'whoami'
+            condition: keywords
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "mimikatz.exe privilege::debug"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str2 = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "mimikatz"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str3 = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "whoami"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        // 文字列のリストはどのフィールドに含まれていても検知する
+        check_select(list_rule_str, record_json_str, true);
+        check_select(list_rule_str, record_json_str2, true);
+        check_select(list_rule_str, record_json_str3, false);
+        // 連想配列はフィールドの値と完全一致した場合のみ検知する
+        check_select(map_rule_str, record_json_str, false);
+        check_select(map_rule_str, record_json_str2, true);
+        check_select(map_rule_str, record_json_str3, false);
+        // 文字列と連想配列が混在するリストは、それぞれの形式で判定したOR条件になる
+        check_select(mixed_rule_str, record_json_str, true);
+        check_select(mixed_rule_str, record_json_str3, true);
+    }
+
+    #[test]
+    fn test_detect_value_keyword() {
+        // 文字列っぽいデータでも確認
+        // 完全一致なので、前方一致しないことを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel:
+                    value: Security
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_notdetect_value_keyword() {
+        // 文字列っぽいデータでも確認
+        // 完全一致なので、前方一致しないことを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel:
+                    value: Securiteen
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_endswith_field() {
+        // endswithfieldで正しく検知できることを確認
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|endswithfield: Computer
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "rity" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_endswith_field2() {
+        // endswithfieldで正しく検知できることを確認
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|endswithfield: Computer
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Security" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_endswith_field_caseinsensitive() {
+        // endswithfieldでcaseinsensitiveで検知することを確認
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|endswithfield: Computer
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "iTy" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_endswith_field_caseinsensitive2() {
+        // endswithfieldでcaseinsensitiveで検知することを確認
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|endswithfield: Computer
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "SecuriTy", "Computer": "ity" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_endswith_field_notdetect() {
+        // endswithfieldで正しく検知しないパターン
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|endswithfield: Computer
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "rity", "Computer": "Security" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_endswith_field_notdetect2() {
+        // endswithfieldで正しく検知しないパターン
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|endswithfield: Computer
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Sec" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_eq_field() {
+        // equalsfieldsで正しく検知できることを確認
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|equalsfield: Computer
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Security" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_eq_field_notdetect() {
+        // equalsfieldsの検知できないパターン
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|equalsfield: Computer
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Powershell" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_eq_field_emptyfield() {
+        // 存在しないフィールドを指定した場合は検知しない
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|equalsfield: NoField
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Securiti" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, false);
+
+        let rule_str = r#"
+        detection:
+            selection:
+                NoField|equalsfield: Channel
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_json_str, false);
+
+        let rule_str = r#"
+        detection:
+            selection:
+                NoField|equalsfield: NoField1
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_fieldref() {
+        // fieldrefで同じレコードの2つのフィールドの値を比較できることを確認
+        let rule_str = r#"
+        detection:
+            selection:
+                TargetUserName|fieldref: SubjectUserName
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = |subject: &str, target: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4720, "Channel": "Security"}}, "EventData": {{"SubjectUserName": "{subject}", "TargetUserName": "{target}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
+        check_select(rule_str, &record_json_str("Admin", "Admin"), true);
+        check_select(rule_str, &record_json_str("Admin", "ADMIN"), true);
+        check_select(rule_str, &record_json_str("Admin", "Admin2"), false);
+
+        let rule_str = r#"
+        detection:
+            selection:
+                TargetUserName|cased|fieldref: SubjectUserName
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, &record_json_str("Admin", "Admin"), true);
+        check_select(rule_str, &record_json_str("Admin", "ADMIN"), false);
+    }
+
+    #[test]
+    fn test_fieldref_contains() {
+        // contains|fieldrefで他のフィールドの値を含むかどうかを比較できることを確認
+        let rule_str = r#"
+        detection:
+            selection:
+                CommandLine|contains|fieldref: User
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = |cmd: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}}, "EventData": {{"CommandLine": "{cmd}", "User": "Tester"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
+        check_select(rule_str, &record_json_str("net user tester /add"), true);
+        check_select(rule_str, &record_json_str("net user admin /add"), false);
+    }
+
+    #[test]
+    fn test_fieldref_match_ignore_case() {
+        // ASCII文字のみの場合と、マルチバイト文字を含む場合で同じ結果になることを確認
+        let cases = [
+            (FieldRefMatch::Equals, "Admin", "ADMIN", true),
+            (FieldRefMatch::Equals, "Admin", "Admin2", false),
+            (
+                FieldRefMatch::StartsWith,
+                "C:\\Windows\\cmd.exe",
+                "c:\\windows",
+                true,
+            ),
+            (FieldRefMatch::StartsWith, "cmd", "cmd.exe", false),
+            (
+                FieldRefMatch::EndsWith,
+                "C:\\Windows\\CMD.EXE",
+                "cmd.exe",
+                true,
+            ),
+            (FieldRefMatch::EndsWith, "cmd.exe", "powershell.exe", false),
+            (
+                FieldRefMatch::Contains,
+                "net user Admin /add",
+                "ADMIN",
+                true,
+            ),
+            (FieldRefMatch::Contains, "net user Admin /add", "", true),
+            (
+                FieldRefMatch::Contains,
+                "net user Admin /add",
+                "guest",
+                false,
+            ),
+            (FieldRefMatch::Equals, "ÄDMIN", "ädmin", true),
+            (FieldRefMatch::EndsWith, "C:\\Users\\ÜSER", "üser", true),
+            (FieldRefMatch::Contains, "ÜSER", "admin", false),
+        ];
+        for (fieldref_match, event_value, ref_value, expected) in cases {
+            assert_eq!(
+                fieldref_match.is_match_ignore_case(event_value, ref_value),
+                expected,
+                "{fieldref_match:?} {event_value} {ref_value}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_fieldref_missing_field() {
+        // どちらかのフィールドが存在しない場合は検知しないことを確認
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Security" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|fieldref: NoField
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_json_str, false);
+
+        let rule_str = r#"
+        detection:
+            selection:
+                NoField|fieldref: Channel
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_json_str, false);
+
+        let rule_str = r#"
+        detection:
+            selection:
+                Channel|fieldref: Computer
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, record_json_str, true);
     }
 
     #[test]
-    fn test_pipe_pattern_wildcard_question2() {
-        let value = PipeElement::pipe_pattern_wildcard(r"\?ho\?ge\?".to_string());
-        assert_eq!(r"(?i)\?ho\?ge\?", value);
-    }
+    fn test_field_null() {
+        // 値でnullであった場合に対象のフィールドが存在しないことを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel:
+                    value: Security
+                Takoyaki:
+                    value: null
+        details: 'command=%CommandLine%'
+        "#;
 
-    #[test]
-    fn test_pipe_pattern_wildcard_question3() {
-        let value = PipeElement::pipe_pattern_wildcard(r"\\?ho\\?ge\\?".to_string());
-        assert_eq!(r"(?i)\\.ho\\.ge\\.", value);
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Powershell" }},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        check_select(rule_str, record_json_str, true);
     }
 
     #[test]
-    fn test_pipe_pattern_wildcard_backshash() {
-        let value = PipeElement::pipe_pattern_wildcard(r"\\ho\\ge\\".to_string());
-        assert_eq!(r"(?i)\\\\ho\\\\ge\\\\", value);
-    }
+    fn test_field_null_not_detect() {
+        // 値でnullであった場合に対象のフィールドが存在しないことを確認するテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: null
+        details: 'command=%CommandLine%'
+        "#;
 
-    #[test]
-    fn test_pipe_pattern_wildcard_mixed() {
-        let value = PipeElement::pipe_pattern_wildcard(r"\\*\****\*\\*".to_string());
-        assert_eq!(
-            r"(?i)\\(.|\a|\f|\t|\n|\r|\v)*\*(.|\a|\f|\t|\n|\r|\v)*(.|\a|\f|\t|\n|\r|\v)*(.|\a|\f|\t|\n|\r|\v)*\*\\(.|\a|\f|\t|\n|\r|\v)*",
-            value
-        );
-    }
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Powershell"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
 
-    #[test]
-    fn test_pipe_pattern_wildcard_many_backshashs() {
-        let value = PipeElement::pipe_pattern_wildcard(r"\\\*ho\\\*ge\\\".to_string());
-        assert_eq!(
-            r"(?i)\\\\(.|\a|\f|\t|\n|\r|\v)*ho\\\\(.|\a|\f|\t|\n|\r|\v)*ge\\\\\\",
-            value
-        );
+        check_select(rule_str, record_json_str, false);
     }
 
     #[test]
-    fn test_grep_match() {
-        // wildcardは大文字小文字関係なくマッチする。
+    fn test_wildcard_converted_starts_with() {
+        // ワイルドカード1文字を末尾に含む場合、stars_with相当のマッチ
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                - 4103
+                Computer: A-*
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "security", "Computer":"DESKTOP-ICHIICHI"}},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
+
         check_select(rule_str, record_json_str, true);
     }
 
     #[test]
-    fn test_grep_not_match() {
-        // wildcardは大文字小文字関係なくマッチする。
+    fn test_wildcard_converted_starts_with_notdetect() {
+        // ワイルドカード1文字を末尾に含む場合、stars_with相当のマッチ
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                - 4104
+                Computer: AA-*
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "security", "Computer":"DESKTOP-ICHIICHI"}},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -1967,21 +4148,18 @@ mod tests {
     }
 
     #[test]
-    fn test_detect_value_keyword() {
-        // 文字列っぽいデータでも確認
-        // 完全一致なので、前方一致しないことを確認
+    fn test_wildcard_converted_starts_with_exact_val() {
+        // ワイルドカード1文字を末尾に含みかつ、＊を除く比較対象文字がちょうど一致する場合
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel:
-                    value: Security
+                Computer: A-HOST*
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security"}},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -1989,21 +4167,18 @@ mod tests {
     }
 
     #[test]
-    fn test_notdetect_value_keyword() {
-        // 文字列っぽいデータでも確認
-        // 完全一致なので、前方一致しないことを確認
+    fn test_wildcard_converted_starts_with_shorter_val_notdetect() {
+        // ワイルドカード1文字を末尾に含みかつ、比較対象文字のほうが文字数が少ない場合はマッチしない
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel:
-                    value: Securiteen
+                Computer: A-HOST-*
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security"}},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2011,18 +4186,18 @@ mod tests {
     }
 
     #[test]
-    fn test_endswith_field() {
-        // endswithfieldで正しく検知できることを確認
+    fn test_wildcard_converted_starts_with_multibytes() {
+        //ワイルドカードを含むかつascii以外のパターンは正規表現マッチ
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|endswithfield: Computer
+                Computer: 社員端末*
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "rity" }},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "社員端末A"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2030,18 +4205,18 @@ mod tests {
     }
 
     #[test]
-    fn test_endswith_field2() {
-        // endswithfieldで正しく検知できることを確認
+    fn test_wildcard_converted_ends_with() {
+        // ワイルドカード1文字を先頭に含む場合、ends_with相当のマッチ
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|endswithfield: Computer
+                Computer: '*-HOST'
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Security" }},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2049,18 +4224,18 @@ mod tests {
     }
 
     #[test]
-    fn test_endswith_field_caseinsensitive() {
-        // endswithfieldでcaseinsensitiveで検知することを確認
+    fn test_wildcard_converted_ends_with_starts_with_exact_val() {
+        // ワイルドカード1文字を先頭に含みかつ、＊を除く比較対象文字がちょうど一致する場合
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|endswithfield: Computer
+                Computer: '*A-HOST'
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "iTy" }},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2068,75 +4243,138 @@ mod tests {
     }
 
     #[test]
-    fn test_endswith_field_caseinsensitive2() {
-        // endswithfieldでcaseinsensitiveで検知することを確認
+    fn test_wildcard_converted_ends_with_shorter_val_notdetect() {
+        // ワイルドカード1文字を先頭に含みかつ、比較対象文字のほうが文字数が少ない場合はマッチしない
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|endswithfield: Computer
+                Computer: '*-HOSTA'
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "SecuriTy", "Computer": "ity" }},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
-        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str, false);
     }
 
     #[test]
-    fn test_endswith_field_notdetect() {
-        // endswithfieldで正しく検知しないパターン
+    fn test_only_wildcard() {
+        // ワイルドカードだけの場合、ends_with相当のマッチ
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|endswithfield: Computer
+                Computer: '*'
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "rity", "Computer": "Security" }},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
-        check_select(rule_str, record_json_str, false);
+        check_select(rule_str, record_json_str, true);
     }
 
     #[test]
-    fn test_endswith_field_notdetect2() {
-        // endswithfieldで正しく検知しないパターン
+    fn test_two_wildcards() {
+        // ワイルドカード2文字以上を含む場合、正規表現マッチ
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|endswithfield: Computer
+                Computer: '*-HOST-*'
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Sec" }},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST-1"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
-        check_select(rule_str, record_json_str, false);
+        check_select(rule_str, record_json_str, true);
     }
 
     #[test]
-    fn test_eq_field() {
-        // equalsfieldsで正しく検知できることを確認
+    fn test_eq_ignore_case() {
+        assert!(DefaultMatcher::eq_ignore_case("abc", "abc"));
+        assert!(DefaultMatcher::eq_ignore_case("AbC", "abc"));
+        assert!(!DefaultMatcher::eq_ignore_case("abc", "ab"));
+        assert!(!DefaultMatcher::eq_ignore_case("ab", "abc"));
+    }
+
+    #[test]
+    fn test_starts_with_ignore_case() {
+        assert!(DefaultMatcher::starts_with_ignore_case("abc", "ab").unwrap(),);
+        assert!(DefaultMatcher::starts_with_ignore_case("AbC", "ab").unwrap(),);
+        assert!(!DefaultMatcher::starts_with_ignore_case("abc", "abcd").unwrap(),);
+        assert!(!DefaultMatcher::starts_with_ignore_case("aab", "ab").unwrap(),);
+    }
+
+    #[test]
+    fn test_ends_with_ignore_case() {
+        assert!(DefaultMatcher::ends_with_ignore_case("abc", "bc").unwrap());
+        assert!(DefaultMatcher::ends_with_ignore_case("AbC", "bc").unwrap());
+        assert!(!DefaultMatcher::ends_with_ignore_case("bc", "bcd").unwrap());
+        assert!(!DefaultMatcher::ends_with_ignore_case("bcd", "abc").unwrap());
+    }
+
+    #[test]
+    fn test_convert_to_fast_match() {
+        assert_eq!(DefaultMatcher::convert_to_fast_match("ab?", true), None);
+        assert_eq!(DefaultMatcher::convert_to_fast_match("a*c", true), None);
+        assert_eq!(DefaultMatcher::convert_to_fast_match("*a*b", true), None);
+        assert_eq!(DefaultMatcher::convert_to_fast_match("*a*b*", true), None);
+        assert_eq!(DefaultMatcher::convert_to_fast_match(r"a\*", true), None);
+        assert_eq!(DefaultMatcher::convert_to_fast_match(r"a\\\*", true), None);
+        assert_eq!(
+            DefaultMatcher::convert_to_fast_match("abc*", true).unwrap(),
+            vec![FastMatch::StartsWith("abc".to_string())]
+        );
+        assert_eq!(
+            DefaultMatcher::convert_to_fast_match(r"abc\\*", true).unwrap(),
+            vec![FastMatch::StartsWith(r"abc\".to_string())]
+        );
+        assert_eq!(
+            DefaultMatcher::convert_to_fast_match("*abc", true).unwrap(),
+            vec![FastMatch::EndsWith("abc".to_string())]
+        );
+        assert_eq!(
+            DefaultMatcher::convert_to_fast_match("*abc*", true).unwrap(),
+            vec![FastMatch::Contains("abc".to_string())]
+        );
+        assert_eq!(
+            DefaultMatcher::convert_to_fast_match("abc", true).unwrap(),
+            vec![FastMatch::Exact("abc".to_string())]
+        );
+        assert_eq!(
+            DefaultMatcher::convert_to_fast_match("あいう", true).unwrap(),
+            vec![FastMatch::Exact("あいう".to_string())]
+        );
+        assert_eq!(
+            DefaultMatcher::convert_to_fast_match(r"\\\\127.0.0.1\\", true).unwrap(),
+            vec![FastMatch::Exact(r"\\127.0.0.1\".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_base64offset_contains() {
+        // base64offset|containsのマッチ
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|equalsfield: Computer
+                Payload|base64offset|contains:
+                    - "http://"
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Security" }},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Tester"}, "EventData":{"Payload": "aHR0cDovL"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2144,112 +4382,216 @@ mod tests {
     }
 
     #[test]
-    fn test_eq_field_notdetect() {
-        // equalsfieldsの検知できないパターン
+    fn test_base64offset_contains_not_match() {
+        // base64offset|containsのマッチしないパターン
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|equalsfield: Computer
+                Payload|base64offset|contains:
+                    - "test"
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Powershell" }},
+        let record_json_str = r#"{
+            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Tester"}, "EventData":{"Payload": "aHR0cDovL"}},
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
+
         check_select(rule_str, record_json_str, false);
     }
 
     #[test]
-    fn test_eq_field_emptyfield() {
-        // 存在しないフィールドを指定した場合は検知しない
+    fn test_base64offset_contains_all_offsets() {
+        // base64offset|containsは3パターンのオフセットのいずれでもマッチすることを確認
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                Channel|equalsfield: NoField
+                Payload|base64offset|contains: "Invoke-Mimikatz"
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Securiti" }},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |payload: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4103, "Channel": "Security", "Computer": "Tester"}}, "EventData":{{"Payload": "{payload}"}}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
 
-        check_select(rule_str, record_json_str, false);
+        // "Invoke-Mimikatz -DumpCreds"
+        check_select(
+            rule_str,
+            &record_json_str("SW52b2tlLU1pbWlrYXR6IC1EdW1wQ3JlZHM="),
+            true,
+        );
+        // "xInvoke-Mimikatz -DumpCreds"
+        check_select(
+            rule_str,
+            &record_json_str("eEludm9rZS1NaW1pa2F0eiAtRHVtcENyZWRz"),
+            true,
+        );
+        // "xxInvoke-Mimikatz -DumpCreds"
+        check_select(
+            rule_str,
+            &record_json_str("eHhJbnZva2UtTWltaWthdHogLUR1bXBDcmVkcw=="),
+            true,
+        );
+        // base64offsetは大文字小文字を区別する
+        check_select(
+            rule_str,
+            &record_json_str("sw52b2tllu1pbwlryxr6ic1edw1wq3jlzhm="),
+            false,
+        );
+    }
 
+    #[test]
+    fn test_wide_base64offset_contains() {
+        // wideはUTF-16LEでエンコードした値をbase64offset|containsで比較することを確認
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                NoField|equalsfield: Channel
+                Payload|wide|base64offset|contains: "New-Object"
         details: 'command=%CommandLine%'
         "#;
-        check_select(rule_str, record_json_str, false);
 
+        let record_json_str = |payload: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4103, "Channel": "Security", "Computer": "Tester"}}, "EventData":{{"Payload": "{payload}"}}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
+        // "IEX (New-Object Net.WebClient)"をUTF-16LEでエンコードした値
+        check_select(
+            rule_str,
+            &record_json_str(
+                "SQBFAFgAIAAoAE4AZQB3AC0ATwBiAGoAZQBjAHQAIABOAGUAdAAuAFcAZQBiAEMAbABpAGUAbgB0ACkA",
+            ),
+            true,
+        );
+        // "xIEX (New-Object Net.WebClient)"
+        check_select(
+            rule_str,
+            &record_json_str(
+                "eABJAEUAWAAgACgATgBlAHcALQBPAGIAagBlAGMAdAAgAE4AZQB0AC4AVwBlAGIAQwBsAGkAZQBuAHQAKQA=",
+            ),
+            true,
+        );
+        // "xxIEX (New-Object Net.WebClient)"
+        check_select(
+            rule_str,
+            &record_json_str(
+                "eAB4AEkARQBYACAAKABOAGUAdwAtAE8AYgBqAGUAYwB0ACAATgBlAHQALgBXAGUAYgBDAGwAaQBlAG4AdAApAA==",
+            ),
+            true,
+        );
+        // UTF-8でエンコードした値にはマッチしない
+        check_select(
+            rule_str,
+            &record_json_str("SUVYIChOZXctT2JqZWN0IE5ldC5XZWJDbGllbnQp"),
+            false,
+        );
+    }
+
+    #[test]
+    fn test_wide_base64() {
+        // wide|base64はUTF-16LEでエンコードした値のbase64と完全一致した場合にマッチする
         let rule_str = r#"
+        enabled: true
         detection:
             selection:
-                NoField|equalsfield: NoField1
+                Payload|wide|base64: "whoami"
         details: 'command=%CommandLine%'
         "#;
-        check_select(rule_str, record_json_str, false);
+
+        let record_json_str = |payload: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4103, "Channel": "Security", "Computer": "Tester"}}, "EventData":{{"Payload": "{payload}"}}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
+        check_select(rule_str, &record_json_str("dwBoAG8AYQBtAGkA"), true);
+        check_select(rule_str, &record_json_str("d2hvYW1p"), false);
     }
 
     #[test]
-    fn test_field_null() {
-        // 値でnullであった場合に対象のフィールドが存在しないことを確認
+    fn test_base64() {
+        // base64はエンコードした値と完全一致した場合にマッチする
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel:
-                    value: Security
-                Takoyaki:
-                    value: null
+                Payload|base64: "whoami /all"
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"
-        {
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Powershell" }},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
-        check_select(rule_str, record_json_str, true);
+        let record_json_str = |payload: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4103, "Channel": "Security", "Computer": "Tester"}}, "EventData":{{"Payload": "{payload}"}}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
+        check_select(rule_str, &record_json_str("d2hvYW1pIC9hbGw="), true);
+        check_select(rule_str, &record_json_str("D2HVYW1PIC9HBGW="), false);
+        check_select(rule_str, &record_json_str("-e d2hvYW1pIC9hbGw="), false);
     }
 
     #[test]
-    fn test_field_null_not_detect() {
-        // 値でnullであった場合に対象のフィールドが存在しないことを確認するテスト
+    fn test_base64_contains() {
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                EventID: null
+                Payload|base64|contains: "Invoke-Mimikatz"
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Powershell"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |payload: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4103, "Channel": "Security", "Computer": "Tester"}}, "EventData":{{"Payload": "{payload}"}}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
 
-        check_select(rule_str, record_json_str, false);
+        check_select(
+            rule_str,
+            &record_json_str("powershell -enc SW52b2tlLU1pbWlrYXR6IC1EdW1wQ3JlZHM="),
+            true,
+        );
+        check_select(
+            rule_str,
+            &record_json_str("powershell -enc eEludm9rZS1NaW1pa2F0eiAtRHVtcENyZWRz"),
+            false,
+        );
     }
 
     #[test]
-    fn test_wildcard_converted_starts_with() {
-        // ワイルドカード1文字を末尾に含む場合、stars_with相当のマッチ
+    fn test_cidr_ipv4_detect() {
+        // cidrにマッチするIP
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: A-*
+                IpAddress|cidr: 192.168.0.0/16
         details: 'command=%CommandLine%'
         "#;
 
         let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
+            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "192.168.0.1"} },
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2257,18 +4599,18 @@ mod tests {
     }
 
     #[test]
-    fn test_wildcard_converted_starts_with_notdetect() {
-        // ワイルドカード1文字を末尾に含む場合、stars_with相当のマッチ
+    fn test_cidr_ipv4_not_detect() {
+        // cidrにマッチしないIP
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: AA-*
+                IpAddress|cidr: 2600:1f18:130c:d900::/56
         details: 'command=%CommandLine%'
         "#;
 
         let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
+            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "8.8.8.8"} },
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2276,18 +4618,18 @@ mod tests {
     }
 
     #[test]
-    fn test_wildcard_converted_starts_with_exact_val() {
-        // ワイルドカード1文字を末尾に含みかつ、＊を除く比較対象文字がちょうど一致する場合
+    fn test_cidr_ipv6_detect() {
+        // cidrにマッチするIP
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: A-HOST*
+                IpAddress|cidr: 2001:db8:1234::/48
         details: 'command=%CommandLine%'
         "#;
 
         let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
+            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "2001:db8:1234:ffff:ffff:ffff:ffff:ffff"} },
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2295,18 +4637,18 @@ mod tests {
     }
 
     #[test]
-    fn test_wildcard_converted_starts_with_shorter_val_notdetect() {
-        // ワイルドカード1文字を末尾に含みかつ、比較対象文字のほうが文字数が少ない場合はマッチしない
+    fn test_cidr_ipv6_not_detect() {
+        // cidrにマッチしないIP
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: A-HOST-*
+                IpAddress|cidr: 2001:db8:1234::/48
         details: 'command=%CommandLine%'
         "#;
 
         let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
+            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "2001:db8:1111:ffff:ffff:ffff:ffff:ffff"} },
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
@@ -2314,314 +4656,374 @@ mod tests {
     }
 
     #[test]
-    fn test_wildcard_converted_starts_with_multibytes() {
-        //ワイルドカードを含むかつascii以外のパターンは正規表現マッチ
+    fn test_cidr_ip_field_not_exists_not_detect() {
+        // cidrにマッチしないIP
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: 社員端末*
+                IpAddress|cidr: 192.168.0.0/16
         details: 'command=%CommandLine%'
         "#;
 
         let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "社員端末A"}},
+            "Event": {"System": {"EventID": 4624} },
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
-        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str, false);
     }
 
     #[test]
-    fn test_wildcard_converted_ends_with() {
-        // ワイルドカード1文字を先頭に含む場合、ends_with相当のマッチ
-        let rule_str = r#"
+    fn test_cidr_boundary_address() {
+        // cidrの範囲の境界となるIPアドレスが正しく判定されることを確認
+        let rule_str_v4 = r#"
         enabled: true
         detection:
             selection:
-                Computer: '*-HOST'
+                DestinationIp|cidr: 10.0.0.0/8
+        details: 'command=%CommandLine%'
+        "#;
+        let rule_str_v6 = r#"
+        enabled: true
+        detection:
+            selection:
+                DestinationIp|cidr: 2001:db8:1234::/48
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |ip: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 3}}, "EventData": {{"DestinationIp": "{ip}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
 
-        check_select(rule_str, record_json_str, true);
+        check_select(rule_str_v4, &record_json_str("10.0.0.0"), true);
+        check_select(rule_str_v4, &record_json_str("10.255.255.255"), true);
+        check_select(rule_str_v4, &record_json_str("9.255.255.255"), false);
+        check_select(rule_str_v4, &record_json_str("11.0.0.0"), false);
+        check_select(rule_str_v4, &record_json_str("-"), false);
+
+        check_select(rule_str_v6, &record_json_str("2001:db8:1234::"), true);
+        check_select(
+            rule_str_v6,
+            &record_json_str("2001:db8:1234:ffff:ffff:ffff:ffff:ffff"),
+            true,
+        );
+        check_select(rule_str_v6, &record_json_str("2001:db8:1235::"), false);
+        check_select(rule_str_v6, &record_json_str("10.0.0.1"), false);
     }
 
     #[test]
-    fn test_wildcard_converted_ends_with_starts_with_exact_val() {
-        // ワイルドカード1文字を先頭に含みかつ、＊を除く比較対象文字がちょうど一致する場合
+    fn test_numeric_compare_decimal() {
+        // EventIDのようにJSONの数値として格納されている値を大小比較できることを確認
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: '*A-HOST'
+                EventID|gte: 4624
+                EventID|lt: 4700
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |event_id: i64| {
+            create_record_json_str(
+                &format!(r#""EventID": {event_id}"#),
+                r#""IpAddress": "192.168.0.1""#,
+            )
+        };
 
-        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, &record_json_str(4624), true);
+        check_select(rule_str, &record_json_str(4699), true);
+        check_select(rule_str, &record_json_str(4623), false);
+        check_select(rule_str, &record_json_str(4700), false);
     }
 
     #[test]
-    fn test_wildcard_converted_ends_with_shorter_val_notdetect() {
-        // ワイルドカード1文字を先頭に含みかつ、比較対象文字のほうが文字数が少ない場合はマッチしない
+    fn test_numeric_compare_hex() {
+        // Windowsのイベントログでよく使われる16進数の文字列を大小比較できることを確認
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: '*-HOSTA'
+                TargetLogonId|gt: 0x3e7
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |logon_id: &str| {
+            create_record_json_str(
+                r#""EventID": 4624"#,
+                &format!(r#""TargetLogonId": "{logon_id}""#),
+            )
+        };
 
-        check_select(rule_str, record_json_str, false);
+        check_select(rule_str, &record_json_str("0x3e8"), true);
+        check_select(rule_str, &record_json_str("0X1A2B3C4D5E6F"), true);
+        check_select(rule_str, &record_json_str("1000"), true);
+        check_select(rule_str, &record_json_str("0x3e7"), false);
+        check_select(rule_str, &record_json_str("999"), false);
+        check_select(rule_str, &record_json_str("0x1"), false);
     }
 
     #[test]
-    fn test_only_wildcard() {
-        // ワイルドカードだけの場合、ends_with相当のマッチ
+    fn test_numeric_compare_hex_and_octal_rule_value() {
+        // ルールの値が16進数/8進数で、イベントの値が10進数の場合も比較できることを確認
+        let record_json_str = |access_mask: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4663}}, "EventData": {{"AccessMask": "{access_mask}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: '*'
+                AccessMask|gte: 0x10
         details: 'command=%CommandLine%'
         "#;
+        check_select(rule_str, &record_json_str("16"), true);
+        check_select(rule_str, &record_json_str("0x100"), true);
+        check_select(rule_str, &record_json_str("15"), false);
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
-
-        check_select(rule_str, record_json_str, true);
-    }
-
-    #[test]
-    fn test_two_wildcards() {
-        // ワイルドカード2文字以上を含む場合、正規表現マッチ
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Computer: '*-HOST-*'
+                AccessMask|lt: '0o20'
         details: 'command=%CommandLine%'
         "#;
-
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "A-HOST-1"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
-
-        check_select(rule_str, record_json_str, true);
-    }
-
-    #[test]
-    fn test_eq_ignore_case() {
-        assert!(DefaultMatcher::eq_ignore_case("abc", "abc"));
-        assert!(DefaultMatcher::eq_ignore_case("AbC", "abc"));
-        assert!(!DefaultMatcher::eq_ignore_case("abc", "ab"));
-        assert!(!DefaultMatcher::eq_ignore_case("ab", "abc"));
-    }
-
-    #[test]
-    fn test_starts_with_ignore_case() {
-        assert!(DefaultMatcher::starts_with_ignore_case("abc", "ab").unwrap(),);
-        assert!(DefaultMatcher::starts_with_ignore_case("AbC", "ab").unwrap(),);
-        assert!(!DefaultMatcher::starts_with_ignore_case("abc", "abcd").unwrap(),);
-        assert!(!DefaultMatcher::starts_with_ignore_case("aab", "ab").unwrap(),);
+        check_select(rule_str, &record_json_str("15"), true);
+        check_select(rule_str, &record_json_str("0xf"), true);
+        check_select(rule_str, &record_json_str("0o20"), false);
+        check_select(rule_str, &record_json_str("16"), false);
     }
 
     #[test]
-    fn test_ends_with_ignore_case() {
-        assert!(DefaultMatcher::ends_with_ignore_case("abc", "bc").unwrap());
-        assert!(DefaultMatcher::ends_with_ignore_case("AbC", "bc").unwrap());
-        assert!(!DefaultMatcher::ends_with_ignore_case("bc", "bcd").unwrap());
-        assert!(!DefaultMatcher::ends_with_ignore_case("bcd", "abc").unwrap());
-    }
+    fn test_exact_match_hex_and_decimal() {
+        // 0x12019fと1179551のように、16進数/8進数と10進数で表記が異なる値も一致することを確認
+        let record_json_str = |access_mask: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4663}}, "EventData": {{"AccessMask": "{access_mask}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
 
-    #[test]
-    fn test_convert_to_fast_match() {
-        assert_eq!(DefaultMatcher::convert_to_fast_match("ab?", true), None);
-        assert_eq!(DefaultMatcher::convert_to_fast_match("a*c", true), None);
-        assert_eq!(DefaultMatcher::convert_to_fast_match("*a*b", true), None);
-        assert_eq!(DefaultMatcher::convert_to_fast_match("*a*b*", true), None);
-        assert_eq!(DefaultMatcher::convert_to_fast_match(r"a\*", true), None);
-        assert_eq!(DefaultMatcher::convert_to_fast_match(r"a\\\*", true), None);
-        assert_eq!(
-            DefaultMatcher::convert_to_fast_match("abc*", true).unwrap(),
-            vec![FastMatch::StartsWith("abc".to_string())]
-        );
-        assert_eq!(
-            DefaultMatcher::convert_to_fast_match(r"abc\\*", true).unwrap(),
-            vec![FastMatch::StartsWith(r"abc\".to_string())]
-        );
-        assert_eq!(
-            DefaultMatcher::convert_to_fast_match("*abc", true).unwrap(),
-            vec![FastMatch::EndsWith("abc".to_string())]
-        );
-        assert_eq!(
-            DefaultMatcher::convert_to_fast_match("*abc*", true).unwrap(),
-            vec![FastMatch::Contains("abc".to_string())]
-        );
-        assert_eq!(
-            DefaultMatcher::convert_to_fast_match("abc", true).unwrap(),
-            vec![FastMatch::Exact("abc".to_string())]
-        );
-        assert_eq!(
-            DefaultMatcher::convert_to_fast_match("あいう", true).unwrap(),
-            vec![FastMatch::Exact("あいう".to_string())]
-        );
-        assert_eq!(
-            DefaultMatcher::convert_to_fast_match(r"\\\\127.0.0.1\\", true).unwrap(),
-            vec![FastMatch::Exact(r"\\127.0.0.1\".to_string())]
-        );
-    }
+        // YAMLの16進数の値は整数として読み込まれる
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                AccessMask: 0x12019f
+        details: 'command=%CommandLine%'
+        "#;
+        check_select(rule_str, &record_json_str("0x12019f"), true);
+        check_select(rule_str, &record_json_str("0X12019F"), true);
+        check_select(rule_str, &record_json_str("1179551"), true);
+        check_select(rule_str, &record_json_str("0x120189"), false);
 
-    #[test]
-    fn test_base64offset_contains() {
-        // base64offset|containsのマッチ
+        // 文字列で指定した16進数の値も数値として比較する
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Payload|base64offset|contains:
-                    - "http://"
+                AccessMask: '0x12019f'
         details: 'command=%CommandLine%'
         "#;
+        check_select(rule_str, &record_json_str("1179551"), true);
+        check_select(rule_str, &record_json_str("0x0012019f"), true);
+        check_select(rule_str, &record_json_str("1179550"), false);
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Tester"}, "EventData":{"Payload": "aHR0cDovL"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
-
-        check_select(rule_str, record_json_str, true);
-    }
-
-    #[test]
-    fn test_base64offset_contains_not_match() {
-        // base64offset|containsのマッチしないパターン
+        // ルールの値が10進数で、イベントの値が16進数/8進数の場合
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Payload|base64offset|contains:
-                    - "test"
+                AccessMask: 1179551
         details: 'command=%CommandLine%'
         "#;
-
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4103, "Channel": "Security", "Computer": "Tester"}, "EventData":{"Payload": "aHR0cDovL"}},
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
-
-        check_select(rule_str, record_json_str, false);
+        check_select(rule_str, &record_json_str("0x12019f"), true);
+        check_select(rule_str, &record_json_str("0o4377637"), true);
+        check_select(rule_str, &record_json_str("1179551"), true);
+        check_select(rule_str, &record_json_str("0x12019e"), false);
     }
 
     #[test]
-    fn test_cidr_ipv4_detect() {
-        // cidrにマッチするIP
+    fn test_numeric_compare_lte_float() {
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                IpAddress|cidr: 192.168.0.0/16
+                Duration|lte: 1.5
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "192.168.0.1"} },
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |duration: &str| {
+            create_record_json_str(r#""EventID": 1"#, &format!(r#""Duration": "{duration}""#))
+        };
 
-        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, &record_json_str("1.5"), true);
+        check_select(rule_str, &record_json_str("1"), true);
+        check_select(rule_str, &record_json_str("1.51"), false);
     }
 
     #[test]
-    fn test_cidr_ipv4_not_detect() {
-        // cidrにマッチしないIP
+    fn test_numeric_compare_version() {
+        // ドットで区切られたバージョンは、文字列や小数ではなく要素ごとに数値として比較する
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                IpAddress|cidr: 2600:1f18:130c:d900::/56
+                Version|gte: 10.0.19041
+        details: 'command=%CommandLine%'
+        "#;
+        let rule_str_lte = r#"
+        enabled: true
+        detection:
+            selection:
+                Version|lte: 10.0.19041
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "8.8.8.8"} },
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |version: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 1}}, "EventData": {{"Version": "{version}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
 
-        check_select(rule_str, record_json_str, false);
+        check_select(rule_str, &record_json_str("10.0.19043"), true);
+        check_select(rule_str, &record_json_str("10.0.19041"), true);
+        check_select(rule_str, &record_json_str("10.0.9200"), false);
+        check_select(rule_str, &record_json_str("6.3.9600"), false);
+        check_select(rule_str, &record_json_str("11"), true);
+        check_select(rule_str, &record_json_str("10.0.19041.1"), true);
+        check_select(rule_str, &record_json_str("10.0.x"), false);
+        check_select(rule_str_lte, &record_json_str("10.0.19043"), false);
+        check_select(rule_str_lte, &record_json_str("10.0.19041.0"), true);
+        check_select(rule_str_lte, &record_json_str("10.0"), true);
     }
 
     #[test]
-    fn test_cidr_ipv6_detect() {
-        // cidrにマッチするIP
+    fn test_cmp_version() {
+        assert_eq!(
+            PipeElement::cmp_version(&[10, 0, 19041], &[10, 0, 19043]),
+            Ordering::Less
+        );
+        assert_eq!(
+            PipeElement::cmp_version(&[10, 0], &[10, 0, 0]),
+            Ordering::Equal
+        );
+        assert_eq!(
+            PipeElement::cmp_version(&[10, 0, 1], &[10, 0]),
+            Ordering::Greater
+        );
+        assert_eq!(
+            PipeElement::parse_version(" 10.0.19041 "),
+            Some(vec![10, 0, 19041])
+        );
+        assert_eq!(PipeElement::parse_version("10..1"), None);
+        assert_eq!(PipeElement::parse_version("10.0.+1"), None);
+    }
+
+    #[test]
+    fn test_numeric_compare_not_number_not_detect() {
+        // イベントの値が数値として解釈できない場合や、フィールドが存在しない場合は検知しない
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                IpAddress|cidr: 2001:db8:1234::/48
+                TargetLogonId|lt: 0x3e7
         details: 'command=%CommandLine%'
         "#;
 
         let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "2001:db8:1234:ffff:ffff:ffff:ffff:ffff"} },
+            "Event": {"System": {"EventID": 4624}, "EventData": {"TargetLogonId": "-"} },
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let record_json_str2 = r#"{
+            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "192.168.0.1"} },
             "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
         }"#;
 
-        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str, false);
+        check_select(rule_str, record_json_str2, false);
     }
 
     #[test]
-    fn test_cidr_ipv6_not_detect() {
-        // cidrにマッチしないIP
+    fn test_timestamp_compare_iso() {
+        // |timestampを指定した場合、ISO-8601形式の文字列を文字列の順序ではなく時刻として比較する
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                IpAddress|cidr: 2001:db8:1234::/48
+                CreationTime|timestamp|gt: '2023-01-01T00:00:00Z'
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4624}, "EventData": {"IpAddress": "2001:db8:1111:ffff:ffff:ffff:ffff:ffff"} },
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |creation_time: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 1}}, "EventData": {{"CreationTime": "{creation_time}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
 
-        check_select(rule_str, record_json_str, false);
+        check_select(
+            rule_str,
+            &record_json_str("2023-01-01T00:00:00.1234567Z"),
+            true,
+        );
+        check_select(rule_str, &record_json_str("2023-01-02"), true);
+        check_select(
+            rule_str,
+            &record_json_str("2022-12-31T23:00:00-02:00"),
+            true,
+        );
+        check_select(rule_str, &record_json_str("2022-12-31 23:59:59"), false);
+        // 文字列としては大きいが、時刻としては同じ値
+        check_select(
+            rule_str,
+            &record_json_str("2023-01-01T09:00:00+09:00"),
+            false,
+        );
+        check_select(rule_str, &record_json_str("-"), false);
     }
 
     #[test]
-    fn test_cidr_ip_field_not_exists_not_detect() {
-        // cidrにマッチしないIP
+    fn test_timestamp_compare_filetime() {
+        // |timestampを指定した場合、FILETIMEの整数も時刻として比較できる
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                IpAddress|cidr: 192.168.0.0/16
+                PasswordLastSet|timestamp|lt: 133170048000000000
         details: 'command=%CommandLine%'
         "#;
 
-        let record_json_str = r#"{
-            "Event": {"System": {"EventID": 4624} },
-            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
-        }"#;
+        let record_json_str = |password_last_set: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4738}}, "EventData": {{"PasswordLastSet": "{password_last_set}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
 
-        check_select(rule_str, record_json_str, false);
+        check_select(rule_str, &record_json_str("133170047999999999"), true);
+        check_select(rule_str, &record_json_str("2022-12-31T23:59:59Z"), true);
+        check_select(rule_str, &record_json_str("133170048000000000"), false);
+        check_select(rule_str, &record_json_str("2023-01-01T00:00:00Z"), false);
     }
 
     #[test]
@@ -3011,4 +5413,469 @@ mod tests {
         check_select(rule_str, record_json_str, true);
         check_select(rule_str, record_json_str2, false);
     }
+
+    #[test]
+    fn test_contains_windash_all_powershell() {
+        // |contains|windash|allは、windashで変換した各値がcontainsで部分一致し、かつ全ての値が一致した場合のみ検知することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                'CommandLine|contains|windash|all':
+                    - ' -nop'
+                    - ' -w hidden'
+                    - ' -enc '
+            condition: selection
+        "#;
+        let create_record = |command_line: &str| {
+            format!(
+                r#"{{
+                  "Event": {{
+                    "System": {{ "EventID": 4688, "Channel": "Security" }},
+                    "EventData": {{ "CommandLine": "{command_line}" }}
+                  }}
+                }}"#
+            )
+        };
+
+        // -と/の表記や大文字小文字が混在していても、全ての値が含まれていれば検知する
+        check_select(
+            rule_str,
+            &create_record(
+                "powershell.exe -NoP /w hidden -enc SQBFAFgAIAAoAE4AZQB3AC0ATwBiAGoAZQBjAHQAKQA=",
+            ),
+            true,
+        );
+        check_select(
+            rule_str,
+            &create_record(
+                "powershell.exe /nop /w hidden /enc SQBFAFgAIAAoAE4AZQB3AC0ATwBiAGoAZQBjAHQAKQA=",
+            ),
+            true,
+        );
+        // 1つでも含まれていない値があれば検知しない
+        check_select(
+            rule_str,
+            &create_record("powershell.exe /nop /enc SQBFAFgAIAAoAE4AZQB3AC0ATwBiAGoAZQBjAHQAKQA="),
+            false,
+        );
+        // 修飾子の順番が異なっても同じ結果になる
+        let rule_str2 = rule_str.replace("contains|windash|all", "windash|all|contains");
+        check_select(
+            &rule_str2,
+            &create_record(
+                "powershell.exe /nop -w hidden /enc SQBFAFgAIAAoAE4AZQB3AC0ATwBiAGoAZQBjAHQAKQA=",
+            ),
+            true,
+        );
+        check_select(
+            &rule_str2,
+            &create_record("powershell.exe /nop -w hidden"),
+            false,
+        );
+    }
+
+    #[test]
+    fn test_contains_windash_enc() {
+        // |windashは-と/のどちらのオプション表記にも大文字小文字を区別せずマッチすることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection1:
+                'CommandLine|windash|contains': '-enc'
+            condition: selection1
+        "#;
+
+        let record_json_str = |cmd: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}}, "EventData": {{"CommandLine": "{cmd}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
+        check_select(rule_str, &record_json_str("powershell -enc AAAA"), true);
+        check_select(rule_str, &record_json_str("powershell /enc AAAA"), true);
+        check_select(rule_str, &record_json_str("powershell /ENC AAAA"), true);
+        check_select(rule_str, &record_json_str("powershell enc AAAA"), false);
+    }
+
+    #[test]
+    fn test_windash_without_contains() {
+        // containsを指定しない|windashは値全体と比較することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection1:
+                'CommandLine|windash': 'whoami -all'
+            condition: selection1
+        "#;
+
+        let record_json_str = |cmd: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}}, "EventData": {{"CommandLine": "{cmd}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
+        check_select(rule_str, &record_json_str("whoami -all"), true);
+        check_select(rule_str, &record_json_str("WHOAMI /ALL"), true);
+        check_select(rule_str, &record_json_str("whoami /all /fo list"), false);
+    }
+
+    #[test]
+    fn test_windash_patterns() {
+        // オプションの先頭の-のみを、-、/、–、—、―に置き換えることを確認
+        assert_eq!(
+            DefaultMatcher::windash_patterns("-test-test", true),
+            vec![
+                "-test-test".to_string(),
+                "/test-test".to_string(),
+                "\u{2013}test-test".to_string(),
+                "\u{2014}test-test".to_string(),
+                "\u{2015}test-test".to_string()
+            ]
+        );
+        assert_eq!(
+            DefaultMatcher::windash_patterns("a - b", true),
+            vec!["a - b".to_string()]
+        );
+        // オプションごとに別々に置き換えた全ての組み合わせを作成する
+        let patterns = DefaultMatcher::windash_patterns("certutil -urlcache -f", true);
+        assert_eq!(patterns.len(), 25);
+        assert_eq!(patterns[0], "certutil -urlcache -f");
+        assert!(patterns.contains(&"certutil /urlcache /f".to_string()));
+        assert!(patterns.contains(&"certutil -urlcache /f".to_string()));
+        assert!(patterns.contains(&"certutil /urlcache -f".to_string()));
+        assert!(patterns.contains(&"certutil \u{2014}urlcache \u{2013}f".to_string()));
+
+        // オプションの数が上限を超える場合は組み合わせを作成せず、正規表現に変換するパターンを1つだけ返す
+        assert_eq!(
+            DefaultMatcher::windash_patterns("-a -b -c", true),
+            vec!["\u{E000}a \u{E000}b \u{E000}c".to_string()]
+        );
+        // 正規表現を使えない場合は、全てのオプションを同じ記号に置き換えたパターンのみを返す
+        assert_eq!(
+            DefaultMatcher::windash_patterns("-a -b -c", false),
+            vec![
+                "-a -b -c".to_string(),
+                "/a /b /c".to_string(),
+                "\u{2013}a \u{2013}b \u{2013}c".to_string(),
+                "\u{2014}a \u{2014}b \u{2014}c".to_string(),
+                "\u{2015}a \u{2015}b \u{2015}c".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contains_windash_mixed_dashes() {
+        // |windashはオプションごとに-、/、–、—、―のいずれの記号が使われていてもマッチすることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection1:
+                'CommandLine|contains|windash': '-a -b'
+            condition: selection1
+        "#;
+
+        let record_json_str = |cmd: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}}, "EventData": {{"CommandLine": "{cmd}"}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+
+        check_select(rule_str, &record_json_str("test.exe -a /b"), true);
+        check_select(rule_str, &record_json_str("test.exe /a -b"), true);
+        check_select(
+            rule_str,
+            &record_json_str("test.exe \u{2013}a \u{2014}b"),
+            true,
+        );
+        check_select(rule_str, &record_json_str("test.exe \u{2015}a /b"), true);
+        check_select(rule_str, &record_json_str("test.exe a b"), false);
+    }
+
+    #[test]
+    fn test_windash_many_options_regex() {
+        // オプションの数が多い場合も、正規表現でオプションごとに異なる記号が使われていてもマッチすることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection1:
+                'CommandLine|contains|windash': '-a -b -c.d'
+            condition: selection1
+        "#;
+
+        let record_json_str = |cmd: &str| {
+            create_record_json_str(
+                r#""EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational""#,
+                &format!(r#""CommandLine": "{cmd}""#),
+            )
+        };
+
+        check_select(rule_str, &record_json_str("test.exe -a /b -c.d"), true);
+        check_select(
+            rule_str,
+            &record_json_str("TEST.EXE \u{2013}A \u{2014}B /C.D"),
+            true,
+        );
+        check_select(rule_str, &record_json_str("test.exe -a /b -cxd"), false);
+        check_select(rule_str, &record_json_str("test.exe -a b -c.d"), false);
+    }
+
+    #[test]
+    fn test_select_does_not_compile_regex() {
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection1:
+                Channel|re: '^Microsoft-Windows-Sysmon/.*$'
+                CommandLine: 'test*addstore'
+                Image|endswith: '\cmd.exe'
+            condition: selection1
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 1,
+              "Channel": "Microsoft-Windows-Sysmon/Operational"
+            },
+            "EventData": {
+              "CommandLine": "test -test-test /addstore",
+              "Image": "C:\\Windows\\System32\\cmd.exe"
+            }
+          }
+        }"#;
+
+        let compile_cnt = || super::REGEX_COMPILE_CNT.with(|cnt| cnt.get());
+        let before_init_cnt = compile_cnt();
+        let mut rule_node = parse_rule_from_str(rule_str);
+        let after_init_cnt = compile_cnt();
+        // 正規表現はルールの初期化時にコンパイルされる
+        assert!(after_init_cnt > before_init_cnt);
+
+        let dummy_stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(dummy_stored_static.eventkey_alias.clone());
+        let record = serde_json::from_str(record_json_str).unwrap();
+        let keys = detections::rule::get_detection_keys(&rule_node);
+        let recinfo = utils::create_rec_info(record, "testpath".to_owned(), &keys, &false, &false);
+        for _ in 0..3 {
+            assert!(rule_node.select(
+                &recinfo,
+                dummy_stored_static.verbose_flag,
+                dummy_stored_static.quiet_errors_flag,
+                dummy_stored_static.json_input_flag,
+                &dummy_stored_static.eventkey_alias
+            ));
+        }
+        // select時には正規表現のコンパイルは行われない
+        assert_eq!(compile_cnt(), after_init_cnt);
+    }
+
+    #[test]
+    fn test_detect_exists_null_empty() {
+        // フィールドなし、null、空文字、空文字以外のそれぞれの状態で、null/空文字/existsの判定結果を確認
+        let record_json_str = |event_data: &str| {
+            format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4624, "Channel": "Security"}}, "EventData": {{{event_data}}} }},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            )
+        };
+        let record_missing = record_json_str(r#""LogonType": 3"#);
+        let record_null = record_json_str(r#""TargetUserName": null"#);
+        let record_empty = record_json_str(r#""TargetUserName": """#);
+        let record_value = record_json_str(r#""TargetUserName": "Administrator""#);
+
+        let rule_str = |selection: &str| {
+            format!(
+                r#"
+        enabled: true
+        detection:
+            selection:
+                {selection}
+        details: 'Rule parse test'
+        "#
+            )
+        };
+
+        let rule_null = rule_str("TargetUserName: null");
+        check_select(&rule_null, &record_missing, true);
+        check_select(&rule_null, &record_null, true);
+        check_select(&rule_null, &record_empty, false);
+        check_select(&rule_null, &record_value, false);
+
+        let rule_empty = rule_str("TargetUserName: ''");
+        check_select(&rule_empty, &record_missing, false);
+        check_select(&rule_empty, &record_null, false);
+        check_select(&rule_empty, &record_empty, true);
+        check_select(&rule_empty, &record_value, false);
+
+        let rule_exists = rule_str("TargetUserName|exists: true");
+        check_select(&rule_exists, &record_missing, false);
+        check_select(&rule_exists, &record_null, false);
+        check_select(&rule_exists, &record_empty, true);
+        check_select(&rule_exists, &record_value, true);
+
+        let rule_not_exists = rule_str("TargetUserName|exists: false");
+        check_select(&rule_not_exists, &record_missing, true);
+        check_select(&rule_not_exists, &record_null, true);
+        check_select(&rule_not_exists, &record_empty, false);
+        check_select(&rule_not_exists, &record_value, false);
+    }
+
+    #[test]
+    fn test_exists_invalid_value() {
+        // existsの値がtrue/false以外の場合や、他のパイプと組み合わせた場合はエラーになることを確認
+        let rule_strs = [
+            r#"
+        enabled: true
+        detection:
+            selection:
+                TargetUserName|exists: 'yes'
+        details: 'Rule parse test'
+        "#,
+            r#"
+        enabled: true
+        detection:
+            selection:
+                TargetUserName|contains|exists: true
+        details: 'Rule parse test'
+        "#,
+        ];
+        let expected = [
+            "The value of the exists modifier should be true or false.",
+            "The exists modifier cannot be combined with other modifiers.",
+        ];
+        for (rule_str, expected) in rule_strs.iter().zip(expected) {
+            let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+            let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+            let result = rule_node.init(&create_dummy_stored_static());
+            assert!(result.unwrap_err()[0].starts_with(expected));
+        }
+    }
+
+    #[test]
+    fn test_windash_base64offset_contains() {
+        // windashで置き換えたパターンもbase64offset|containsで比較されることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Payload|windash|base64offset|contains: "-enc"
+        details: 'command=%CommandLine%'
+        "#;
+
+        for (payload, expect_select) in [
+            // "-enc"をbase64エンコードした値
+            ("LWVuYw==", true),
+            // "/enc"をbase64エンコードした値
+            ("L2VuYw==", true),
+            // "enc"をbase64エンコードした値
+            ("ZW5j", false),
+        ] {
+            let record_json_str = format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4103, "Channel": "Security", "Computer": "Tester"}}, "EventData":{{"Payload": "{payload}"}}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            );
+            check_select(rule_str, &record_json_str, expect_select);
+        }
+    }
+
+    #[test]
+    fn test_base64offset_contains_all() {
+        // base64offset|contains|allは、すべての値がbase64offset|containsでマッチした場合のみ検知することを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Payload|base64offset|contains|all:
+                    - "http://"
+                    - "evil"
+        details: 'command=%CommandLine%'
+        "#;
+
+        for (payload, expect_select) in [
+            // "http://evil.com"をbase64エンコードした値
+            ("aHR0cDovL2V2aWwuY29t", true),
+            // "http://example.com"をbase64エンコードした値
+            ("aHR0cDovL2V4YW1wbGUuY29t", false),
+        ] {
+            let record_json_str = format!(
+                r#"{{
+            "Event": {{"System": {{"EventID": 4103, "Channel": "Security", "Computer": "Tester"}}, "EventData":{{"Payload": "{payload}"}}}},
+            "Event_attributes": {{"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}}
+        }}"#
+            );
+            check_select(rule_str, &record_json_str, expect_select);
+        }
+    }
+
+    #[test]
+    fn test_invalid_modifier_chain() {
+        // 意味をなさないパイプの組み合わせはエラーになることを確認
+        let cases = [
+            (
+                "Payload|base64offset: 'http://'",
+                "The base64offset modifier must be combined with contains.",
+            ),
+            (
+                "Payload|base64offset|startswith: 'http://'",
+                "The base64offset modifier must be combined with contains.",
+            ),
+            (
+                "Payload|base64|base64offset|contains: 'http://'",
+                "The base64 and base64offset modifiers cannot be combined.",
+            ),
+            (
+                "Payload|base64|re: 'http://'",
+                "The windash, wide, base64 and base64offset modifiers can only be combined with startswith, endswith or contains.",
+            ),
+            (
+                "SourceIp|windash|cidr: '10.0.0.0/8'",
+                "The windash, wide, base64 and base64offset modifiers can only be combined with startswith, endswith or contains.",
+            ),
+            (
+                "CommandLine|contains|re: 'abc'",
+                "Multiple pipe elements cannot be used.",
+            ),
+            (
+                "CommandLine|startswith|endswith: 'abc'",
+                "Multiple pipe elements cannot be used.",
+            ),
+            (
+                "CommandLine|contains|contains: 'abc'",
+                "The same modifier cannot be specified more than once.",
+            ),
+        ];
+        for (selection, expected) in cases {
+            let rule_str = format!(
+                r#"
+        enabled: true
+        detection:
+            selection:
+                {selection}
+        details: 'Rule parse test'
+        "#
+            );
+            let mut rule_yaml = YamlLoader::load_from_str(&rule_str).unwrap().into_iter();
+            let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+            let result = rule_node.init(&create_dummy_stored_static());
+            assert!(
+                result.as_ref().unwrap_err()[0].starts_with(expected),
+                "{selection}: {result:?}"
+            );
+        }
+    }
 }
@@ -1,24 +1,31 @@
 extern crate regex;
 
-use std::{fmt::Debug, sync::Arc, vec};
+use std::{collections::BTreeSet, fmt::Debug, path::Path, sync::Arc, vec};
 
 use chrono::{DateTime, Utc};
-use hashbrown::HashMap;
+use compact_str::CompactString;
+use hashbrown::{HashMap, HashSet};
+use itertools::Itertools;
 use nested::Nested;
 use yaml_rust::Yaml;
 
 use super::configs::{EventKeyAliasConfig, StoredStatic};
 use super::detection::EvtxRecordInfo;
+use super::message::{AlertMessage, ERROR_LOG_STACK};
+use super::utils;
+use crate::yaml::format_rule_path;
 
 use self::aggregation_parser::AggregationParseInfo;
+use self::condition_parser::GLOBAL_SELECTION_REF;
 use self::count::{AggRecordTimeInfo, TimeFrameInfo};
-use self::selectionnodes::{LeafSelectionNode, SelectionNode};
+use self::selectionnodes::{LeafSelectionNode, NotSelectionNode, RefSelectionNode, SelectionNode};
 
 mod aggregation_parser;
 mod condition_parser;
 pub mod correlation_parser;
 mod count;
 mod matchers;
+pub mod schema;
 mod selectionnodes;
 
 pub fn create_rule(rulepath: String, yaml: Yaml) -> RuleNode {
@@ -29,9 +36,12 @@ pub fn create_rule(rulepath: String, yaml: Yaml) -> RuleNode {
 /// ード
 pub struct RuleNode {
     pub rulepath: String,
+    pub doc_index: Option<usize>, // 1つのファイルに複数のルールが記載されている場合の、ファイル内でのドキュメントの番号
     pub yaml: Yaml,
     detection: DetectionNode,
     countdata: HashMap<String, Vec<AggRecordTimeInfo>>,
+    logsource_channels: Vec<CompactString>, // logsourceのserviceから判定した検知対象のChannel(小文字)。空の場合はすべてのChannelを対象とする
+    target_eventids: HashSet<String>, // ルールが検知するために必須となるEventID。空の場合はすべてのEventIDを対象とする
 }
 
 impl Debug for RuleNode {
@@ -44,9 +54,12 @@ impl RuleNode {
     pub fn new(rule_path: String, yaml_data: Yaml) -> RuleNode {
         RuleNode {
             rulepath: rule_path,
+            doc_index: None,
             yaml: yaml_data,
             detection: DetectionNode::new(),
             countdata: HashMap::new(),
+            logsource_channels: vec![],
+            target_eventids: HashSet::new(),
         }
     }
 
@@ -57,9 +70,28 @@ impl RuleNode {
     ) -> RuleNode {
         RuleNode {
             rulepath: rule_path,
+            doc_index: None,
             yaml: yaml_data,
             detection,
             countdata: HashMap::new(),
+            logsource_channels: vec![],
+            target_eventids: HashSet::new(),
+        }
+    }
+
+    /// エラーメッセージなどに出力するために、ファイル内のドキュメントの番号を付与したルールファイルのパスを返す
+    pub fn get_rulepath_with_doc_index(&self) -> String {
+        format_rule_path(&self.rulepath, self.doc_index)
+    }
+
+    /// ルールのタイトルを返す。titleが定義されていないルールの場合はルールファイルのファイル名をタイトルとする
+    pub fn title(&self) -> String {
+        match self.yaml["title"].as_str() {
+            Some(title) if !title.is_empty() => title.to_string(),
+            _ => Path::new(&self.rulepath)
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().to_string())
+                .unwrap_or_else(|| self.rulepath.to_string()),
         }
     }
 
@@ -69,11 +101,67 @@ impl RuleNode {
             return Result::Ok(());
         }
 
+        // titleが定義されていないルールは、--strictが指定された場合はエラーとし、それ以外はファイル名をタイトルとして警告する
+        if !self.yaml["title"]
+            .as_str()
+            .is_some_and(|title| !title.is_empty())
+        {
+            if stored_static.strict_flag {
+                errmsgs.push(format!(
+                    "The title is not defined. (FilePath : {})",
+                    self.get_rulepath_with_doc_index()
+                ));
+            } else {
+                let errmsg = format!(
+                    "The title is not defined, so the file name is used as the title. (FilePath : {})",
+                    self.get_rulepath_with_doc_index()
+                );
+                if stored_static.verbose_flag {
+                    AlertMessage::warn(&errmsg).ok();
+                }
+                if !stored_static.quiet_errors_flag {
+                    ERROR_LOG_STACK
+                        .lock()
+                        .unwrap()
+                        .push(format!("[WARN] {errmsg}"));
+                }
+            }
+        }
+
+        // logsourceのserviceに対応するChannelが設定されている場合は、そのChannelのイベントのみを検知対象とする
+        if let Some(service) = self.yaml["logsource"]["service"].as_str() {
+            if let Some(channels) = stored_static
+                .logsource_channel_config
+                .get(service.to_lowercase().as_str())
+            {
+                self.logsource_channels.clone_from(channels);
+            }
+        }
+
         // detection node initialization
         let detection_result = self.detection.init(&self.yaml["detection"], stored_static);
         if let Err(err_detail) = detection_result {
             errmsgs.extend(err_detail);
+        } else if self
+            .detection
+            .is_negation_only_condition(&self.yaml["detection"])
+        {
+            // not filterのように否定されたselectionのみのconditionは、filterに一致しない全てのイベントを検知するので警告する
+            let errmsg = format!(
+                "The condition only consists of negated selections, so all events not matching them will be detected. (FilePath : {})",
+                self.get_rulepath_with_doc_index()
+            );
+            if stored_static.verbose_flag {
+                AlertMessage::warn(&errmsg).ok();
+            }
+            if !stored_static.quiet_errors_flag {
+                ERROR_LOG_STACK
+                    .lock()
+                    .unwrap()
+                    .push(format!("[WARN] {errmsg}"));
+            }
         }
+        self.target_eventids = Self::get_required_eventids(&self.yaml["detection"]);
 
         if errmsgs.is_empty() {
             Result::Ok(())
@@ -90,6 +178,10 @@ impl RuleNode {
         json_input_flag: bool,
         eventkey_alias: &EventKeyAliasConfig,
     ) -> bool {
+        // 必須となるEventIDによる絞り込みは、Detection::startでEventIDのインデックスを使って行う
+        if !self.is_target_channel(event_record) {
+            return false;
+        }
         let result = self.detection.select(event_record, eventkey_alias);
         if result && self.has_agg_condition() {
             count::count(
@@ -102,6 +194,119 @@ impl RuleNode {
         }
         result
     }
+
+    /// ルールのデバッグ用に、レコードに対する各selectionの評価結果と最終的な検知結果を返す関数。
+    /// selectと異なりaggregation conditionのカウントは行わないので、1つのレコードに対して何度でも呼び出せる
+    pub fn select_with_reason(
+        &self,
+        event_record: &EvtxRecordInfo,
+        eventkey_alias: &EventKeyAliasConfig,
+    ) -> SelectReason {
+        let is_target_channel = self.is_target_channel(event_record);
+        let is_target_eventid = self.is_target_eventid(event_record);
+        let (selections, condition_result) = self
+            .detection
+            .select_with_reason(event_record, eventkey_alias);
+        SelectReason {
+            is_target_channel,
+            is_target_eventid,
+            selections,
+            result: is_target_channel && is_target_eventid && condition_result,
+        }
+    }
+
+    /// レコードに一致したselectionのキー(パイプを含む)とレコードの値を取得する。MatchedFieldsの出力に使用する
+    pub fn get_matched_fields(
+        &self,
+        event_record: &EvtxRecordInfo,
+        eventkey_alias: &EventKeyAliasConfig,
+    ) -> Vec<String> {
+        self.detection
+            .get_matched_fields(event_record, eventkey_alias)
+    }
+
+    /// レコードのChannelがlogsourceから判定した検知対象のChannelであるかを返す関数。Channelが取得できない場合は対象とする
+    fn is_target_channel(&self, event_record: &EvtxRecordInfo) -> bool {
+        if self.logsource_channels.is_empty() {
+            return true;
+        }
+        match event_record.record["Event"]["System"]["Channel"].as_str() {
+            Some(channel) => self
+                .logsource_channels
+                .iter()
+                .any(|target| target.eq_ignore_ascii_case(channel)),
+            None => true,
+        }
+    }
+    /// ルールが検知するために必須となるEventIDを返す関数。空の場合はすべてのEventIDを対象とする
+    pub fn get_target_eventids(&self) -> &HashSet<String> {
+        &self.target_eventids
+    }
+    /// レコードのEventIDがルールの検知に必須となるEventIDに含まれるかを返す関数。EventIDが取得できない場合は対象とする
+    fn is_target_eventid(&self, event_record: &EvtxRecordInfo) -> bool {
+        if self.target_eventids.is_empty() {
+            return true;
+        }
+        match event_record.get_value("EventID") {
+            Some(eventid) => self.target_eventids.contains(eventid),
+            None => true,
+        }
+    }
+    /// conditionが1つのselectionのみ、またはそのselectionとandで結合された条件の場合に、そのselectionで指定されたEventIDを返す関数。
+    /// 検知に必須となるEventIDを判定できない場合は空のセットを返す
+    fn get_required_eventids(detection_yaml: &Yaml) -> HashSet<String> {
+        let mut ret = HashSet::new();
+        let selection_name = match detection_yaml["condition"].as_str() {
+            Some(condition) => {
+                // aggregation conditionは除いて判定する
+                let condition = condition.split('|').next().unwrap_or_default();
+                let tokens: Vec<&str> = condition.split_whitespace().collect();
+                if tokens.is_empty()
+                    || tokens[0].contains(['(', ')', '*'])
+                    || ["not", "1", "all"].contains(&tokens[0])
+                    || (tokens.len() > 1 && tokens[1] != "and")
+                    || tokens.iter().any(|token| token.eq_ignore_ascii_case("or"))
+                {
+                    return ret;
+                }
+                tokens[0]
+            }
+            None => {
+                // conditionがない場合はselectionが1つのみの場合に限る
+                let selection_names: Vec<&str> = detection_yaml
+                    .as_hash()
+                    .map(|hash| {
+                        hash.keys()
+                            .filter_map(|key| key.as_str())
+                            .filter(|key| *key != "timeframe")
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if selection_names.len() != 1 {
+                    return ret;
+                }
+                selection_names[0]
+            }
+        };
+
+        let eventids = &detection_yaml[selection_name]["EventID"];
+        let eventids = match eventids {
+            Yaml::Array(ary) => ary.iter().collect(),
+            Yaml::BadValue => vec![],
+            _ => vec![eventids],
+        };
+        for eventid in eventids {
+            let eventid = match eventid {
+                Yaml::Integer(i) => i.to_string(),
+                Yaml::String(s) if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) => {
+                    s.to_owned()
+                }
+                _ => return HashSet::new(),
+            };
+            ret.insert(eventid);
+        }
+        ret
+    }
     /// aggregation conditionが存在するかを返す関数
     pub fn has_agg_condition(&self) -> bool {
         self.detection.aggregation_condition.is_some()
@@ -157,6 +362,15 @@ pub fn get_detection_keys(node: &RuleNode) -> Nested<String> {
     ret
 }
 
+/// RuleNode::select_with_reasonの結果を表す構造体
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectReason {
+    pub is_target_channel: bool, // logsourceから判定した検知対象のChannelであるか
+    pub is_target_eventid: bool, // 検知に必須となるEventIDに含まれるか
+    pub selections: Vec<(String, bool)>, // selection名とその評価結果。selection名の昇順に並ぶ
+    pub result: bool,            // 最終的な検知結果
+}
+
 /// Ruleファイルのdetectionを表すノード
 struct DetectionNode {
     pub name_to_selection: HashMap<String, Arc<Box<dyn SelectionNode>>>,
@@ -219,9 +433,23 @@ impl DetectionNode {
                 ]);
             }
 
-            keys.next().unwrap()
+            match keys.next() {
+                Some(key) => key,
+                None => {
+                    return Result::Err(vec![
+                        "There is no selection node under detection.".to_string()
+                    ]);
+                }
+            }
         };
 
+        // conditionで参照されている共通のselectionを追加する
+        if let Err(err_detail) =
+            self.add_global_selections(condition_str, &stored_static.global_selections)
+        {
+            return Result::Err(err_detail);
+        }
+
         // conditionをパースして、SelectionNodeに変換する
         let mut err_msgs = vec![];
         let compiler = condition_parser::ConditionCompiler::new();
@@ -248,6 +476,18 @@ impl DetectionNode {
         }
     }
 
+    /// conditionが否定されたselectionのみで構成されているかを返す関数。conditionが省略されている場合は1つのselectionのみなのでfalseになる
+    fn is_negation_only_condition(&self, detection_yaml: &Yaml) -> bool {
+        match detection_yaml["condition"].as_str() {
+            Some(condition) => {
+                let node_keys: Vec<String> = self.name_to_selection.keys().cloned().collect();
+                condition_parser::ConditionCompiler::new()
+                    .is_negation_only_condition(condition, &node_keys)
+            }
+            None => false,
+        }
+    }
+
     pub fn select(
         &self,
         event_record: &EvtxRecordInfo,
@@ -261,6 +501,99 @@ impl DetectionNode {
         condition.select(event_record, eventkey_alias)
     }
 
+    /// 各selectionの評価結果とconditionの評価結果を返す
+    fn select_with_reason(
+        &self,
+        event_record: &EvtxRecordInfo,
+        eventkey_alias: &EventKeyAliasConfig,
+    ) -> (Vec<(String, bool)>, bool) {
+        let selections = self
+            .name_to_selection
+            .iter()
+            .map(|(name, selection)| {
+                (
+                    name.to_string(),
+                    selection.select(event_record, eventkey_alias),
+                )
+            })
+            .sorted()
+            .collect();
+        (selections, self.select(event_record, eventkey_alias))
+    }
+
+    /// レコードに一致したselectionについて、一致した末端ノードのキーとレコードの値を`selection名.キー: 値`の形式で取得する。
+    /// conditionでnotが付いたselectionは、一致していても検知の根拠にはならないので出力しない
+    pub fn get_matched_fields(
+        &self,
+        event_record: &EvtxRecordInfo,
+        eventkey_alias: &EventKeyAliasConfig,
+    ) -> Vec<String> {
+        let mut ret = vec![];
+        if self.condition.is_none() {
+            return ret;
+        }
+        let mut matched_names = BTreeSet::new();
+        self.collect_matched_selections(
+            self.condition.as_ref().unwrap().as_ref(),
+            event_record,
+            eventkey_alias,
+            &mut matched_names,
+        );
+        for name in matched_names {
+            let selection = self.name_to_selection[name].as_ref().as_ref();
+            Self::collect_matched_fields(name, selection, event_record, eventkey_alias, &mut ret);
+        }
+        ret
+    }
+
+    /// conditionのノードをたどり、否定されていない分岐で一致したselectionの名前を取得する
+    fn collect_matched_selections<'a>(
+        &'a self,
+        node: &dyn SelectionNode,
+        event_record: &EvtxRecordInfo,
+        eventkey_alias: &EventKeyAliasConfig,
+        ret: &mut BTreeSet<&'a str>,
+    ) {
+        if node.is::<NotSelectionNode>() || !node.select(event_record, eventkey_alias) {
+            return;
+        }
+        if let Some(ref_node) = node.downcast_ref::<RefSelectionNode>() {
+            if let Some((name, _)) = self
+                .name_to_selection
+                .iter()
+                .find(|(_, selection)| ref_node.is_ref_of(selection))
+            {
+                ret.insert(name);
+            }
+            return;
+        }
+        for child in node.get_childs() {
+            self.collect_matched_selections(child, event_record, eventkey_alias, ret);
+        }
+    }
+
+    fn collect_matched_fields(
+        name: &str,
+        node: &dyn SelectionNode,
+        event_record: &EvtxRecordInfo,
+        eventkey_alias: &EventKeyAliasConfig,
+        ret: &mut Vec<String>,
+    ) {
+        // 一致しなかったノード(ORの一致しなかった子要素など)や、|notで否定された配列の要素は出力しない
+        if node.is::<NotSelectionNode>() || !node.select(event_record, eventkey_alias) {
+            return;
+        }
+        if let Some(leaf) = node.downcast_ref::<LeafSelectionNode>() {
+            if let Some(matched_field) = leaf.get_matched_field(event_record) {
+                ret.push(format!("{name}.{matched_field}"));
+            }
+            return;
+        }
+        for child in node.get_childs() {
+            Self::collect_matched_fields(name, child, event_record, eventkey_alias, ret);
+        }
+    }
+
     /// selectionノードをパースします。
     fn parse_name_to_selection(&mut self, detection_yaml: &Yaml) -> Result<(), Vec<String>> {
         let detection_hash = detection_yaml.as_hash();
@@ -282,6 +615,14 @@ impl DetectionNode {
                 continue;
             }
 
+            // |allに配列以外の値が指定された場合は、全ての値に一致するという指定の意味をなさないのでエラーにする
+            let all_modifier_errors =
+                Self::check_all_modifier_values(&Nested::<String>::new(), &detection_hash[key]);
+            if !all_modifier_errors.is_empty() {
+                err_msgs.extend(all_modifier_errors);
+                continue;
+            }
+
             // パースして、エラーメッセージがあれば配列にためて、戻り値で返す。
             let selection_node = self.parse_selection(&detection_hash[key]);
             if let Some(node) = selection_node {
@@ -310,6 +651,87 @@ impl DetectionNode {
         Result::Ok(())
     }
 
+    /// conditionで参照されている、ルール内に定義されていないglobal_から始まるselectionをglobal_selections.yamlの定義から追加します。
+    /// `1 of global_filter*`のようにワイルドカードで指定された場合は、前方一致する全ての共通のselectionを追加します。
+    fn add_global_selections(
+        &mut self,
+        condition_str: &str,
+        global_selections: &HashMap<String, Yaml>,
+    ) -> Result<(), Vec<String>> {
+        let mut err_msgs = vec![];
+        // aggregation conditionは除いて判定する
+        let condition = condition_str.split('|').next().unwrap_or_default();
+        for (name, wildcard) in GLOBAL_SELECTION_REF
+            .captures_iter(condition)
+            .map(|caps| (caps[1].to_string(), !caps[2].is_empty()))
+            .unique()
+        {
+            let target_names: Vec<&String> = global_selections
+                .keys()
+                .filter(|key| {
+                    if wildcard {
+                        key.starts_with(&name)
+                    } else {
+                        **key == name
+                    }
+                })
+                .filter(|key| !self.name_to_selection.contains_key(key.as_str()))
+                .sorted()
+                .collect();
+            if target_names.is_empty() && !wildcard && !self.name_to_selection.contains_key(&name) {
+                err_msgs.push(format!(
+                    "The global selection {name} is not defined. Please define it in global_selections.yaml."
+                ));
+                continue;
+            }
+            for target_name in target_names {
+                let selection_node = self.parse_selection(&global_selections[target_name]);
+                if let Some(mut node) = selection_node {
+                    if let Err(err_detail) = node.init() {
+                        err_msgs.extend(err_detail.into_iter().map(|err| {
+                            format!("The global selection {target_name} is invalid. {err}")
+                        }));
+                    } else {
+                        self.name_to_selection
+                            .insert(target_name.to_string(), Arc::new(node));
+                    }
+                }
+            }
+        }
+        if err_msgs.is_empty() {
+            Result::Ok(())
+        } else {
+            Result::Err(err_msgs)
+        }
+    }
+
+    /// |allが指定されたキーの値が配列であることを確認し、配列以外の値が指定されたキーのエラーメッセージを返します。
+    fn check_all_modifier_values(key_list: &Nested<String>, yaml: &Yaml) -> Vec<String> {
+        let mut err_msgs = vec![];
+        if let Some(yaml_hash) = yaml.as_hash() {
+            for (hash_key, child_yaml) in yaml_hash {
+                let mut child_key_list = key_list.clone();
+                child_key_list.push(hash_key.as_str().unwrap_or_default());
+                let has_all_modifier = hash_key
+                    .as_str()
+                    .is_some_and(|k| k.split('|').skip(1).any(|pipe| pipe == "all"));
+                if has_all_modifier && child_yaml.as_vec().is_none() {
+                    err_msgs.push(format!(
+                        "The all modifier requires a list of values. key:{}",
+                        utils::concat_selection_key(&child_key_list)
+                    ));
+                    continue;
+                }
+                err_msgs.extend(Self::check_all_modifier_values(&child_key_list, child_yaml));
+            }
+        } else if let Some(yaml_vec) = yaml.as_vec() {
+            for child_yaml in yaml_vec {
+                err_msgs.extend(Self::check_all_modifier_values(key_list, child_yaml));
+            }
+        }
+        err_msgs
+    }
+
     /// selectionをパースします。
     fn parse_selection(&self, selection_yaml: &Yaml) -> Option<Box<dyn SelectionNode>> {
         Option::Some(Self::parse_selection_recursively(
@@ -323,6 +745,14 @@ impl DetectionNode {
         key_list: &Nested<String>,
         yaml: &Yaml,
     ) -> Box<dyn SelectionNode> {
+        if yaml.as_vec().is_some_and(|ary| !ary.is_empty()) {
+            // |notが指定されたキーに配列が指定された場合は、配列を展開したOR(|allの場合はAND)条件全体を反転させる
+            // 要素ごとに反転させると「いずれかに一致しない」になってしまうので、「いずれにも一致しない」として判定する
+            if let Some(removed_key_list) = LeafSelectionNode::remove_not_pipe(key_list) {
+                let child_node = Self::parse_selection_recursively(&removed_key_list, yaml);
+                return Box::new(selectionnodes::NotSelectionNode::new(child_node));
+            }
+        }
         if yaml.as_hash().is_some() {
             // 連想配列はAND条件と解釈する
             let yaml_hash = yaml.as_hash().unwrap();
@@ -336,6 +766,14 @@ impl DetectionNode {
                 and_node.child_nodes.push(child_node);
             });
             Box::new(and_node)
+        } else if yaml.as_vec().is_some_and(|ary| ary.is_empty())
+            && key_list.iter().any(|k: &str| k.contains("|all"))
+        {
+            // |allに空の配列が指定された場合、AND条件にすると全てのイベントに一致してしまうので、末端ノードとして初期化時にエラーにする
+            Box::new(selectionnodes::LeafSelectionNode::new(
+                key_list.clone(),
+                yaml.to_owned(),
+            ))
         } else if yaml.as_vec().is_some() && key_list.len() == 1 && key_list[0].eq("|all") {
             // |all だけの場合、
             let mut or_node = selectionnodes::AllSelectionNode::new();
@@ -362,6 +800,7 @@ impl DetectionNode {
             Box::new(or_node)
         } else {
             // 連想配列と配列以外は末端ノード
+            // keywordsのように連想配列のキーを経由しない文字列(key_listが空)は、イベント全体への部分一致として判定される
             Box::new(selectionnodes::LeafSelectionNode::new(
                 key_list.clone(),
                 yaml.to_owned(),
@@ -381,6 +820,8 @@ pub struct AggResult {
     pub field_values: Vec<String>,
     ///検知したブロックの最初のレコードの時間
     pub start_timedate: DateTime<Utc>,
+    ///検知したブロックの最後のレコードの時間
+    pub end_timedate: DateTime<Utc>,
     ///条件式の情報
     pub condition_op_num: String,
 }
@@ -398,6 +839,7 @@ impl AggResult {
             key: key_name,
             field_values: field_value,
             start_timedate: event_start_timedate,
+            end_timedate: event_start_timedate,
             condition_op_num: condition_op_number,
         }
     }
@@ -419,9 +861,10 @@ mod tests {
         utils,
     };
 
+    use super::selectionnodes::{AndSelectionNode, LeafSelectionNode};
     use super::RuleNode;
 
-    fn create_dummy_stored_static() -> StoredStatic {
+    pub fn create_dummy_stored_static() -> StoredStatic {
         StoredStatic::create_static_data(Some(Config {
             action: Some(Action::CsvTimeline(CsvOutputOption {
                 output_options: OutputOption {
@@ -429,6 +872,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -449,7 +893,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -462,10 +906,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -474,6 +921,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -505,8 +968,23 @@ mod tests {
     }
 
     fn check_select(rule_str: &str, record_str: &str, expect_select: bool) {
-        let mut rule_node = parse_rule_from_str(rule_str);
-        let dummy_stored_static = create_dummy_stored_static();
+        check_select_with_stored_static(
+            rule_str,
+            record_str,
+            expect_select,
+            &create_dummy_stored_static(),
+        );
+    }
+
+    fn check_select_with_stored_static(
+        rule_str: &str,
+        record_str: &str,
+        expect_select: bool,
+        dummy_stored_static: &StoredStatic,
+    ) {
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+        assert!(rule_node.init(dummy_stored_static).is_ok());
         *STORED_EKEY_ALIAS.write().unwrap() = Some(dummy_stored_static.eventkey_alias.clone());
 
         match serde_json::from_str(record_str) {
@@ -531,6 +1009,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_detect_key_case_insensitive() {
+        // aliasが定義されているキーは、ルールとイベントでフィールド名の大文字小文字が異なっていても検知できる。
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                eventid: 4688
+                commandline|contains: 'whoami'
+        details: 'case test'
+        "#;
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4688}, "EventData": {"CommandLine": "whoami /all"}}}"#;
+        check_select(rule_str, record_json_str, true);
+
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4688}, "EventData": {"commandLine": "whoami /all"}}}"#;
+        check_select(rule_str, record_json_str, true);
+
+        let record_json_str =
+            r#"{"Event": {"System": {"EventID": 4688}, "EventData": {"CommandLine": "ipconfig"}}}"#;
+        check_select(rule_str, record_json_str, false);
+    }
+
     #[test]
     fn test_detect_dotkey() {
         // aliasじゃなくて、.区切りでつなげるケースが正しく検知できる。
@@ -885,6 +1385,47 @@ mod tests {
         check_select(rule_str, record_json_str, true);
     }
 
+    #[test]
+    fn test_detect_eventid_json_shapes() {
+        // evtxクレートやJSON入力ではEventIDが数値、文字列、#text付きのオブジェクトのいずれかで出力される
+        let rule_str = r"
+        enabled: true
+        detection:
+            selection:
+                EventID: 4688
+        details: 'test'
+        ";
+        let record_shapes = [
+            "4688",
+            r#""4688""#,
+            r##"{"#attributes": {"Qualifiers": 0}, "#text": 4688}"##,
+            r##"{"#attributes": {"Qualifiers": 0}, "#text": "4688"}"##,
+        ];
+        for eventid in record_shapes {
+            let record_json_str = format!(
+                r#"{{"Event": {{"System": {{"EventID": {eventid}, "Channel": "Security"}}}}}}"#
+            );
+            check_select(rule_str, &record_json_str, true);
+        }
+
+        let rule_str = r"
+        enabled: true
+        detection:
+            selection:
+                EventID: '4688'
+        details: 'test'
+        ";
+        for eventid in record_shapes {
+            let record_json_str = format!(
+                r#"{{"Event": {{"System": {{"EventID": {eventid}, "Channel": "Security"}}}}}}"#
+            );
+            check_select(rule_str, &record_json_str, true);
+        }
+
+        let record_json_str = r##"{"Event": {"System": {"EventID": {"#attributes": {"Qualifiers": 0}, "#text": 4689}, "Channel": "Security"}}}"##;
+        check_select(rule_str, record_json_str, false);
+    }
+
     #[test]
     fn test_notdetect_special_eventdata() {
         // 上記テストケースのEventDataの更に特殊ケースで下記のようにDataタグの中にNameキーがないケースがある。
@@ -999,11 +1540,13 @@ mod tests {
     }
 
     #[test]
-    fn test_detect_not_defined_selection() {
-        // 不明な文字列オプションがルールに書かれていたら警告するテスト
+    fn test_detect_numeric_compare_not_number_value() {
+        // |gtなどの大小比較の値が数値でない場合はエラーにするテスト
         let rule_str = r#"
         enabled: true
         detection:
+            selection:
+                TargetLogonId|gt: abc
         details: 'Rule parse test'
         "#;
         let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
@@ -1011,71 +1554,616 @@ mod tests {
 
         assert_eq!(
             rule_node.init(&create_dummy_stored_static()),
-            Err(vec!["Detection node was not found.".to_string()])
+            Err(vec![
+                "The value of the gt modifier should be a number or a version. key:detection -> selection -> TargetLogonId|gt"
+                    .to_string()
+            ])
         );
     }
 
     #[test]
-    fn test_use_allfeature_() {
-        // allがパイプで入っていた場合は以下の配下の者をAnd条件で扱うようにすできるかのテスト
+    fn test_detect_timestamp_compare_invalid_value() {
+        // |timestampと組み合わせた大小比較の値が時刻でない場合はエラーにするテスト
         let rule_str = r#"
         enabled: true
         detection:
             selection:
-                Channel: 'System'
-                EventID: 7040
-                param1: 'Windows Event Log'
-                param2|contains|all:
-                    - "star"
-                    - "aut"
-        details: 'Service name : %param1%¥nMessage : Event Log Service Stopped¥nResults: Selective event log manipulation may follow this event.'
+                CreationTime|timestamp|gte: abc
+        details: 'Rule parse test'
         "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
 
-        let record_json_str = r#"
-        {
-          "Event": {
-            "System": {
-              "EventID": 7040,
-              "Channel": "System"
-            },
-            "EventData": {
-              "param1": "Windows Event Log",
-              "param2": "auto start"
-            }
-          },
-          "Event_attributes": {
-            "xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"
-          }
-        }"#;
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "The value of the gte modifier should be a timestamp. key:detection -> selection -> CreationTime|timestamp|gte"
+                    .to_string()
+            ])
+        );
+    }
 
-        // case of
-        let record_json_str2 = r#"
-        {
-          "Event": {
-            "System": {
-              "EventID": 7040,
-              "Channel": "System"
-            },
-            "EventData": {
-              "param1": "Windows Event Log",
-              "param2": "auts"
-            }
-          },
-          "Event_attributes": {
-            "xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"
-          }
-        }"#;
+    #[test]
+    fn test_detect_timestamp_without_compare() {
+        // |timestampを|lt, |lte, |gt, |gte以外と組み合わせた場合はエラーにするテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CreationTime|timestamp: '2023-01-01'
+        details: 'Rule parse test'
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
 
-        check_select(rule_str, record_json_str, true);
-        check_select(rule_str, record_json_str2, false);
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "The timestamp modifier must be combined with lt, lte, gt or gte. key:detection -> selection -> CreationTime|timestamp"
+                    .to_string()
+            ])
+        );
     }
 
-    /// countで対象の数値確認を行うためのテスト用関数
-    fn _check_count(rule_str: &str, record_str: &str, key: &str, expect_count: i32) {
+    #[test]
+    fn test_detect_empty_all_list() {
+        // |allに空の配列が指定された場合は、全てのイベントに一致しないようにエラーにするテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine|contains|all: []
+        details: 'Rule parse test'
+        "#;
         let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
-        let test = rule_yaml.next().unwrap();
-        let mut rule_node = create_rule("testpath".to_string(), test);
-        let _init = rule_node.init(&create_dummy_stored_static());
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "An empty list cannot be specified for the all modifier. key:detection -> selection -> CommandLine|contains|all"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_detect_all_modifier_with_scalar_value() {
+        // |allに配列ではなく単一の値が指定された場合はエラーにするテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Image|endswith: '\powershell.exe'
+                CommandLine|contains|windash|all: '-nop'
+            selection2:
+                '|all': 'mimikatz'
+            condition: selection and selection2
+        details: 'Rule parse test'
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "The all modifier requires a list of values. key:detection -> selection -> CommandLine|contains|windash|all"
+                    .to_string(),
+                "The all modifier requires a list of values. key:detection -> selection -> |all"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_detect_empty_keyword_all_list() {
+        // キーワードの|allに空の配列が指定された場合もエラーにするテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                '|all': []
+        details: 'Rule parse test'
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "An empty list cannot be specified for the all modifier. key:detection -> selection -> |all"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_detect_empty_nested_all_list() {
+        // |allの入れ子の配列に空の配列が含まれる場合もエラーにするテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine|contains|all:
+                    - 'whoami'
+                    - []
+        details: 'Rule parse test'
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "An empty list cannot be specified for the all modifier. key:detection -> selection -> CommandLine|contains|all"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_detect_single_element_all_list() {
+        // 要素が1つの|allの配列は、配列ではない値と同じ結果になるテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine|contains|all:
+                    - 'whoami'
+        details: 'Rule parse test'
+        "#;
+        let scalar_rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine|contains: 'whoami'
+        details: 'Rule parse test'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "cmd.exe /c whoami /all"}},
+          "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+        let not_match_record_json_str = r#"
+        {
+          "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"CommandLine": "cmd.exe /c hostname"}},
+          "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        for rule in [rule_str, scalar_rule_str] {
+            check_select(rule, record_json_str, true);
+            check_select(rule, not_match_record_json_str, false);
+        }
+    }
+
+    #[test]
+    fn test_detect_not_defined_selection() {
+        // 不明な文字列オプションがルールに書かれていたら警告するテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+        details: 'Rule parse test'
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec!["Detection node was not found.".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_detect_logsource_service_channel() {
+        // logsourceのserviceに対応するChannel以外のイベントは検知しないテスト
+        let rule_str = r#"
+        enabled: true
+        logsource:
+            product: windows
+            service: security
+        detection:
+            selection:
+                EventID: 4624
+            condition: selection
+        details: 'logsource test'
+        "#;
+        let security_record = r#"{"Event": {"System": {"EventID": 4624, "Channel": "Security"}}}"#;
+        let system_record = r#"{"Event": {"System": {"EventID": 4624, "Channel": "System"}}}"#;
+        let no_channel_record = r#"{"Event": {"System": {"EventID": 4624}}}"#;
+        check_select(rule_str, security_record, true);
+        check_select(rule_str, system_record, false);
+        check_select(rule_str, no_channel_record, true);
+    }
+
+    #[test]
+    fn test_detect_logsource_service_multiple_channels() {
+        // 1つのserviceに複数のChannelが対応する場合はいずれのChannelでも検知するテスト
+        let rule_str = r#"
+        enabled: true
+        logsource:
+            product: windows
+            service: powershell
+        detection:
+            selection:
+                EventID: 4104
+            condition: selection
+        details: 'logsource test'
+        "#;
+        let operational_record = r#"{"Event": {"System": {"EventID": 4104, "Channel": "Microsoft-Windows-PowerShell/Operational"}}}"#;
+        let core_record =
+            r#"{"Event": {"System": {"EventID": 4104, "Channel": "PowerShellCore/Operational"}}}"#;
+        let security_record = r#"{"Event": {"System": {"EventID": 4104, "Channel": "Security"}}}"#;
+        check_select(rule_str, operational_record, true);
+        check_select(rule_str, core_record, true);
+        check_select(rule_str, security_record, false);
+    }
+
+    #[test]
+    fn test_detect_logsource_unknown_service() {
+        // Channelの対応が設定されていないserviceの場合はChannelで絞り込まないテスト
+        let rule_str = r#"
+        enabled: true
+        logsource:
+            product: windows
+            service: unknown-service
+        detection:
+            selection:
+                EventID: 4624
+            condition: selection
+        details: 'logsource test'
+        "#;
+        let system_record = r#"{"Event": {"System": {"EventID": 4624, "Channel": "System"}}}"#;
+        check_select(rule_str, system_record, true);
+    }
+
+    #[test]
+    fn test_get_required_eventids() {
+        // conditionから検知に必須となるEventIDを判定できることを確認するテスト
+        let get_eventids = |detection_str: &str| {
+            let detection_yaml = YamlLoader::load_from_str(detection_str).unwrap();
+            let mut ret: Vec<String> = RuleNode::get_required_eventids(&detection_yaml[0])
+                .into_iter()
+                .collect();
+            ret.sort();
+            ret
+        };
+
+        let single = r#"
+        selection:
+            EventID: 4624
+            LogonType: 3
+        condition: selection
+        "#;
+        assert_eq!(get_eventids(single), vec!["4624"]);
+
+        let list_with_filter = r#"
+        selection:
+            EventID:
+                - 4624
+                - '4625'
+        filter:
+            LogonType: 3
+        condition: selection and not filter | count() by IpAddress > 3
+        "#;
+        assert_eq!(get_eventids(list_with_filter), vec!["4624", "4625"]);
+
+        let no_condition = r#"
+        selection:
+            EventID: 4688
+        "#;
+        assert_eq!(get_eventids(no_condition), vec!["4688"]);
+
+        // orで結合されている場合やEventIDにワイルドカードが含まれる場合は判定しない
+        let with_or = r#"
+        selection:
+            EventID: 4624
+        selection2:
+            EventID: 4625
+        condition: selection or selection2
+        "#;
+        assert!(get_eventids(with_or).is_empty());
+
+        let with_parentheses = r#"
+        selection:
+            EventID: 4624
+        filter1:
+            LogonType: 3
+        filter2:
+            LogonType: 10
+        condition: selection and not (filter1 or filter2)
+        "#;
+        assert!(get_eventids(with_parentheses).is_empty());
+
+        let with_wildcard = r#"
+        selection:
+            EventID: '46*'
+        condition: selection
+        "#;
+        assert!(get_eventids(with_wildcard).is_empty());
+
+        let not_first = r#"
+        selection:
+            EventID: 4624
+        condition: not selection
+        "#;
+        assert!(get_eventids(not_first).is_empty());
+
+        let selection_list = r#"
+        selection:
+            - EventID: 4624
+            - LogonType: 3
+        condition: selection
+        "#;
+        assert!(get_eventids(selection_list).is_empty());
+    }
+
+    #[test]
+    fn test_detect_required_eventid() {
+        // 必須となるEventID以外のイベントは検知しないテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID:
+                    - 4624
+                    - 4625
+                Channel: Security
+            filter:
+                LogonType: 3
+            condition: selection and not filter
+        details: 'eventid test'
+        "#;
+        let record_4625 = r#"{"Event": {"System": {"EventID": 4625, "Channel": "Security"}, "EventData": {"LogonType": 2}}}"#;
+        let record_4634 = r#"{"Event": {"System": {"EventID": 4634, "Channel": "Security"}, "EventData": {"LogonType": 2}}}"#;
+        let record_filtered = r#"{"Event": {"System": {"EventID": 4624, "Channel": "Security"}, "EventData": {"LogonType": 3}}}"#;
+        check_select(rule_str, record_4625, true);
+        check_select(rule_str, record_4634, false);
+        check_select(rule_str, record_filtered, false);
+    }
+
+    #[test]
+    fn test_detect_no_selection_node() {
+        // conditionもselectionもない場合はエラーになるテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            timeframe: 5m
+        details: 'Rule parse test'
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "There is no selection node under detection.".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_detect_without_selection_named_selection() {
+        // selectionという名前のノードがなくても、conditionで参照したノードで検知できるテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            process_creation:
+                EventID: 1
+            suspicious_image:
+                Image|endswith: '\mimikatz.exe'
+            condition: process_creation and suspicious_image
+        details: 'Rule parse test'
+        "#;
+
+        let record_json_str = r#"
+        {
+            "Event": {"System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"}, "EventData": {"Image": "C:\\Temp\\mimikatz.exe"}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+    }
+
+    #[test]
+    fn test_detect_condition_undefined_selection() {
+        // conditionに定義されていないselectionが指定されていたらエラーになるテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 1
+            condition: selection and not filter
+        details: 'Rule parse test'
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+
+        assert_eq!(
+            rule_node.init(&create_dummy_stored_static()),
+            Err(vec![
+                "A condition parse error has occurred. filter is not defined.".to_string()
+            ])
+        );
+    }
+
+    /// global_selections.yamlの共通のselectionを設定したStoredStaticを作成する
+    fn create_global_selections_stored_static() -> StoredStatic {
+        let global_selections_str = r#"
+        global_filter_admin_tools:
+            Image|endswith:
+                - '\psexec.exe'
+                - '\procexp64.exe'
+        global_filter_system_account:
+            SubjectUserSid: 'S-1-5-18'
+        "#;
+        let mut stored_static = create_dummy_stored_static();
+        stored_static.global_selections = YamlLoader::load_from_str(global_selections_str).unwrap()
+            [0]
+        .as_hash()
+        .unwrap()
+        .iter()
+        .map(|(name, selection)| (name.as_str().unwrap().to_string(), selection.clone()))
+        .collect();
+        stored_static
+    }
+
+    #[test]
+    fn test_detect_global_selection() {
+        // conditionから共通のselectionを名前で参照できることを確認するテスト
+        let stored_static = create_global_selections_stored_static();
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 1
+            condition: selection and not global_filter_admin_tools
+        details: 'Rule parse test'
+        "#;
+        let record_json_str = r#"{"Event": {"System": {"EventID": 1}, "EventData": {"Image": "C:\\Tools\\PsExec.exe"}}}"#;
+        check_select_with_stored_static(rule_str, record_json_str, false, &stored_static);
+        let record_json_str = r#"{"Event": {"System": {"EventID": 1}, "EventData": {"Image": "C:\\Windows\\System32\\cmd.exe"}}}"#;
+        check_select_with_stored_static(rule_str, record_json_str, true, &stored_static);
+
+        // ワイルドカードで複数の共通のselectionを参照でき、them には含まれない
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 1
+            condition: all of them and not 1 of global_filter*
+        details: 'Rule parse test'
+        "#;
+        let record_json_str = r#"{"Event": {"System": {"EventID": 1}, "EventData": {"Image": "C:\\Windows\\System32\\cmd.exe", "SubjectUserSid": "S-1-5-18"}}}"#;
+        check_select_with_stored_static(rule_str, record_json_str, false, &stored_static);
+        let record_json_str = r#"{"Event": {"System": {"EventID": 1}, "EventData": {"Image": "C:\\Windows\\System32\\cmd.exe", "SubjectUserSid": "S-1-5-21"}}}"#;
+        check_select_with_stored_static(rule_str, record_json_str, true, &stored_static);
+    }
+
+    #[test]
+    fn test_detect_undefined_global_selection() {
+        // 定義されていない共通のselectionを参照した場合はエラーになるテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 1
+            condition: selection and not global_filter_not_exist
+        details: 'Rule parse test'
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let mut rule_node = create_rule("testpath".to_string(), rule_yaml.next().unwrap());
+        assert_eq!(
+            rule_node.init(&create_global_selections_stored_static()),
+            Err(vec![
+                "The global selection global_filter_not_exist is not defined. Please define it in global_selections.yaml.".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_use_allfeature_() {
+        // allがパイプで入っていた場合は以下の配下の者をAnd条件で扱うようにすできるかのテスト
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Channel: 'System'
+                EventID: 7040
+                param1: 'Windows Event Log'
+                param2|contains|all:
+                    - "star"
+                    - "aut"
+        details: 'Service name : %param1%¥nMessage : Event Log Service Stopped¥nResults: Selective event log manipulation may follow this event.'
+        "#;
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 7040,
+              "Channel": "System"
+            },
+            "EventData": {
+              "param1": "Windows Event Log",
+              "param2": "auto start"
+            }
+          },
+          "Event_attributes": {
+            "xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"
+          }
+        }"#;
+
+        // case of
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {
+              "EventID": 7040,
+              "Channel": "System"
+            },
+            "EventData": {
+              "param1": "Windows Event Log",
+              "param2": "auts"
+            }
+          },
+          "Event_attributes": {
+            "xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"
+          }
+        }"#;
+
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+    }
+
+    #[test]
+    fn test_allfeature_parsed_as_and_node() {
+        // |allが指定された配列はOrSelectionNodeではなくAndSelectionNodeとしてパースされることを確認
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                CommandLine|contains|all:
+                    - "-nop"
+                    - "-w hidden"
+                    - "-enc"
+        details: 'command=%CommandLine%'
+        "#;
+        let rule_node = parse_rule_from_str(rule_str);
+        let selection_node = &rule_node.detection.name_to_selection["selection"];
+        let childs = selection_node.get_childs();
+        assert_eq!(childs.len(), 1);
+        assert!(childs[0].is::<AndSelectionNode>());
+        assert_eq!(childs[0].get_childs().len(), 3);
+        assert!(childs[0]
+            .get_childs()
+            .iter()
+            .all(|node| node.is::<LeafSelectionNode>()));
+
+        let record_json_str = r#"
+        {
+          "Event": {
+            "System": {"EventID": 4104, "Channel": "Microsoft-Windows-PowerShell/Operational"},
+            "EventData": {"CommandLine": "powershell.exe -nop -w hidden -enc SQBFAFgA"}
+          }
+        }"#;
+        let record_json_str2 = r#"
+        {
+          "Event": {
+            "System": {"EventID": 4104, "Channel": "Microsoft-Windows-PowerShell/Operational"},
+            "EventData": {"CommandLine": "powershell.exe -nop -enc SQBFAFgA"}
+          }
+        }"#;
+        check_select(rule_str, record_json_str, true);
+        check_select(rule_str, record_json_str2, false);
+    }
+
+    /// countで対象の数値確認を行うためのテスト用関数
+    fn _check_count(rule_str: &str, record_str: &str, key: &str, expect_count: i32) {
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let test = rule_yaml.next().unwrap();
+        let mut rule_node = create_rule("testpath".to_string(), test);
+        let _init = rule_node.init(&create_dummy_stored_static());
         let dummy_stored_static = create_dummy_stored_static();
         *STORED_EKEY_ALIAS.write().unwrap() = Some(dummy_stored_static.eventkey_alias.clone());
 
@@ -1103,4 +2191,164 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_get_matched_fields() {
+        // 一致したselectionのキーと値のみが取得でき、一致しなかったselectionやORの子要素は含まれない。
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 4688
+                CommandLine|contains:
+                    - 'whoami'
+                    - 'ipconfig'
+            filter:
+                Image|endswith: '\cmd.exe'
+            condition: selection and not filter
+        details: 'matched fields test'
+        "#;
+        let rule_node = parse_rule_from_str(rule_str);
+        let dummy_stored_static = create_dummy_stored_static();
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4688}, "EventData": {"CommandLine": "whoami /all", "Image": "C:\\Windows\\System32\\powershell.exe"}}}"#;
+        let record = serde_json::from_str(record_json_str).unwrap();
+        let keys = detections::rule::get_detection_keys(&rule_node);
+        let recinfo = utils::create_rec_info(record, "testpath".to_owned(), &keys, &false, &false);
+        assert_eq!(
+            rule_node.get_matched_fields(&recinfo, &dummy_stored_static.eventkey_alias),
+            vec![
+                "selection.EventID: 4688".to_string(),
+                "selection.CommandLine|contains: whoami /all".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_matched_fields_skip_negated_selection() {
+        // notで否定された分岐のselectionは、レコードに一致していてもMatchedFieldsに含まれない。
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 4688
+            filter_image:
+                Image|endswith: '\powershell.exe'
+            filter_cmd:
+                CommandLine|contains: 'ipconfig'
+            condition: selection and not (filter_image and filter_cmd)
+        details: 'matched fields test'
+        "#;
+        let mut rule_node = parse_rule_from_str(rule_str);
+        let dummy_stored_static = create_dummy_stored_static();
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4688}, "EventData": {"CommandLine": "whoami /all", "Image": "C:\\Windows\\System32\\powershell.exe"}}}"#;
+        let record = serde_json::from_str(record_json_str).unwrap();
+        let keys = detections::rule::get_detection_keys(&rule_node);
+        let recinfo = utils::create_rec_info(record, "testpath".to_owned(), &keys, &false, &false);
+        assert!(rule_node.select(
+            &recinfo,
+            false,
+            false,
+            false,
+            &dummy_stored_static.eventkey_alias
+        ));
+        assert_eq!(
+            rule_node.get_matched_fields(&recinfo, &dummy_stored_static.eventkey_alias),
+            vec!["selection.EventID: 4688".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_select_with_reason() {
+        // 各selectionの評価結果と、最終的な検知結果が取得できる。
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                EventID: 4688
+                CommandLine|contains: 'whoami'
+            filter:
+                Image|endswith: '\cmd.exe'
+            condition: selection and not filter
+        details: 'select with reason test'
+        "#;
+        let rule_node = parse_rule_from_str(rule_str);
+        let dummy_stored_static = create_dummy_stored_static();
+        let keys = detections::rule::get_detection_keys(&rule_node);
+        let select_with_reason = |record_json_str: &str| {
+            let record = serde_json::from_str(record_json_str).unwrap();
+            let recinfo =
+                utils::create_rec_info(record, "testpath".to_owned(), &keys, &false, &false);
+            rule_node.select_with_reason(&recinfo, &dummy_stored_static.eventkey_alias)
+        };
+
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4688}, "EventData": {"CommandLine": "whoami /all", "Image": "C:\\Windows\\System32\\cmd.exe"}}}"#;
+        let reason = select_with_reason(record_json_str);
+        assert_eq!(
+            reason.selections,
+            vec![
+                ("filter".to_string(), true),
+                ("selection".to_string(), true)
+            ]
+        );
+        assert!(reason.is_target_channel);
+        assert!(reason.is_target_eventid);
+        assert!(!reason.result);
+
+        let record_json_str = r#"{"Event": {"System": {"EventID": 4688}, "EventData": {"CommandLine": "whoami /all", "Image": "C:\\Windows\\System32\\powershell.exe"}}}"#;
+        let reason = select_with_reason(record_json_str);
+        assert_eq!(
+            reason.selections,
+            vec![
+                ("filter".to_string(), false),
+                ("selection".to_string(), true)
+            ]
+        );
+        assert!(reason.result);
+
+        let record_json_str =
+            r#"{"Event": {"System": {"EventID": 1}, "EventData": {"CommandLine": "whoami /all"}}}"#;
+        let reason = select_with_reason(record_json_str);
+        assert!(!reason.is_target_eventid);
+        assert!(!reason.result);
+    }
+
+    #[test]
+    fn test_rule_without_title() {
+        // titleが定義されていないルールは、ファイル名をタイトルとして読み込まれる
+        let rule_path = "test_files/rules/no_title_yaml/no_title.yml";
+        let rule_str = std::fs::read_to_string(rule_path).unwrap();
+        let rule_yaml = YamlLoader::load_from_str(&rule_str).unwrap();
+        let mut rule_node = create_rule(rule_path.to_string(), rule_yaml[0].clone());
+        assert!(rule_node.init(&create_dummy_stored_static()).is_ok());
+        assert_eq!(rule_node.title(), "no_title.yml");
+
+        // titleが定義されている場合はそのまま返す
+        let rule_node = parse_rule_from_str(
+            r#"
+        title: Sample Rule
+        detection:
+            selection:
+                EventID: 4688
+            condition: selection
+        "#,
+        );
+        assert_eq!(rule_node.title(), "Sample Rule");
+    }
+
+    #[test]
+    fn test_rule_without_title_strict() {
+        // --strictが指定された場合、titleが定義されていないルールはエラーとなる
+        let rule_path = "test_files/rules/no_title_yaml/no_title.yml";
+        let rule_str = std::fs::read_to_string(rule_path).unwrap();
+        let rule_yaml = YamlLoader::load_from_str(&rule_str).unwrap();
+        let mut rule_node = create_rule(rule_path.to_string(), rule_yaml[0].clone());
+        let mut stored_static = create_dummy_stored_static();
+        stored_static.strict_flag = true;
+        assert_eq!(
+            rule_node.init(&stored_static),
+            Err(vec![format!(
+                "The title is not defined. (FilePath : {rule_path})"
+            )])
+        );
+    }
 }
@@ -0,0 +1,233 @@
+use std::fmt;
+
+use yaml_rust::Yaml;
+
+/// ルールのlevelに指定できる値
+const RULE_LEVELS: [&str; 5] = ["informational", "low", "medium", "high", "critical"];
+
+/// ルールのトップレベルのフィールドの構造に関するエラー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleSchemaError {
+    /// 必須のフィールドが存在しない場合
+    Missing { field: String },
+    /// フィールドの型が正しくない場合
+    InvalidType {
+        field: String,
+        expected: &'static str,
+        actual: &'static str,
+    },
+    /// フィールドの値が指定できる値ではない場合
+    InvalidValue {
+        field: String,
+        value: String,
+        expected: String,
+    },
+}
+
+impl fmt::Display for RuleSchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleSchemaError::Missing { field } => {
+                write!(f, "The required field '{field}' is not defined.")
+            }
+            RuleSchemaError::InvalidType {
+                field,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The field '{field}' should be {expected}, but it is {actual}."
+            ),
+            RuleSchemaError::InvalidValue {
+                field,
+                value,
+                expected,
+            } => write!(
+                f,
+                "The value '{value}' of the field '{field}' is invalid. Expected one of: {expected}."
+            ),
+        }
+    }
+}
+
+/// エラーメッセージに表示するYAMLの値の型名を返す
+fn type_name(yaml: &Yaml) -> &'static str {
+    match yaml {
+        Yaml::Real(_) => "a number",
+        Yaml::Integer(_) => "an integer",
+        Yaml::String(_) => "a string",
+        Yaml::Boolean(_) => "a boolean",
+        Yaml::Array(_) => "a list",
+        Yaml::Hash(_) => "a map",
+        Yaml::Alias(_) => "an alias",
+        Yaml::Null => "null",
+        Yaml::BadValue => "undefined",
+    }
+}
+
+/// ルールのトップレベルのフィールドが存在し、正しい型であるかを検証する。
+/// 正規表現やconditionなど、フィールドの中身の検証はRuleNode::initで行うので、ここではルールの構造だけを確認する
+pub fn validate_rule_schema(yaml: &Yaml) -> Vec<RuleSchemaError> {
+    let mut errors = vec![];
+    if !matches!(yaml, Yaml::Hash(_)) {
+        errors.push(RuleSchemaError::InvalidType {
+            field: "(root)".to_string(),
+            expected: "a map",
+            actual: type_name(yaml),
+        });
+        return errors;
+    }
+
+    let mut check_type = |field: &str, expected: &'static str, is_valid: fn(&Yaml) -> bool| {
+        let value = &yaml[field];
+        if !value.is_badvalue() && !is_valid(value) {
+            errors.push(RuleSchemaError::InvalidType {
+                field: field.to_string(),
+                expected,
+                actual: type_name(value),
+            });
+        }
+    };
+    check_type("title", "a string", |v| v.as_str().is_some());
+    check_type("id", "a string", |v| v.as_str().is_some());
+    check_type("status", "a string", |v| v.as_str().is_some());
+    check_type("description", "a string", |v| v.as_str().is_some());
+    check_type("author", "a string", |v| v.as_str().is_some());
+    check_type("level", "a string", |v| v.as_str().is_some());
+    check_type("logsource", "a map", |v| v.as_hash().is_some());
+    check_type("tags", "a list of strings", |v| {
+        v.as_vec()
+            .is_some_and(|tags| tags.iter().all(|tag| tag.as_str().is_some()))
+    });
+    check_type("references", "a list", |v| v.as_vec().is_some());
+    check_type("falsepositives", "a list", |v| v.as_vec().is_some());
+
+    if yaml["correlation"].is_badvalue() {
+        // correlationルール以外はdetectionが必須
+        match &yaml["detection"] {
+            Yaml::BadValue => errors.push(RuleSchemaError::Missing {
+                field: "detection".to_string(),
+            }),
+            Yaml::Hash(_) => {
+                let condition = &yaml["detection"]["condition"];
+                if !condition.is_badvalue() && condition.as_str().is_none() {
+                    errors.push(RuleSchemaError::InvalidType {
+                        field: "detection.condition".to_string(),
+                        expected: "a string",
+                        actual: type_name(condition),
+                    });
+                }
+            }
+            detection => errors.push(RuleSchemaError::InvalidType {
+                field: "detection".to_string(),
+                expected: "a map",
+                actual: type_name(detection),
+            }),
+        }
+    } else if yaml["correlation"].as_hash().is_none() {
+        errors.push(RuleSchemaError::InvalidType {
+            field: "correlation".to_string(),
+            expected: "a map",
+            actual: type_name(&yaml["correlation"]),
+        });
+    }
+
+    if let Some(level) = yaml["level"].as_str() {
+        if !RULE_LEVELS.contains(&level.to_lowercase().as_str()) {
+            errors.push(RuleSchemaError::InvalidValue {
+                field: "level".to_string(),
+                value: level.to_string(),
+                expected: RULE_LEVELS.join(", "),
+            });
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_rule_schema, RuleSchemaError};
+    use yaml_rust::YamlLoader;
+
+    fn validate(rule_str: &str) -> Vec<RuleSchemaError> {
+        let yaml = YamlLoader::load_from_str(rule_str).unwrap();
+        validate_rule_schema(&yaml[0])
+    }
+
+    #[test]
+    fn test_valid_rule_schema() {
+        let rule_str = r#"
+        title: test
+        id: 00000000-0000-0000-0000-000000000001
+        level: High
+        tags:
+            - attack.execution
+        logsource:
+            product: windows
+        detection:
+            selection:
+                EventID: 4688
+            condition: selection
+        "#;
+        assert!(validate(rule_str).is_empty());
+
+        // correlationルールはdetectionがなくてもよい
+        let rule_str = r#"
+        title: correlation
+        correlation:
+            type: event_count
+            rules:
+                - rule1
+            timespan: 5m
+        "#;
+        assert!(validate(rule_str).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rule_schema() {
+        let rule_str = r#"
+        title: test
+        level: severe
+        tags: attack.execution
+        detection: selection
+        "#;
+        assert_eq!(
+            validate(rule_str),
+            vec![
+                RuleSchemaError::InvalidType {
+                    field: "tags".to_string(),
+                    expected: "a list of strings",
+                    actual: "a string",
+                },
+                RuleSchemaError::InvalidType {
+                    field: "detection".to_string(),
+                    expected: "a map",
+                    actual: "a string",
+                },
+                RuleSchemaError::InvalidValue {
+                    field: "level".to_string(),
+                    value: "severe".to_string(),
+                    expected: "informational, low, medium, high, critical".to_string(),
+                },
+            ]
+        );
+
+        let errors = validate("title: test\nlogsource:\n    product: windows\n");
+        assert_eq!(
+            errors,
+            vec![RuleSchemaError::Missing {
+                field: "detection".to_string()
+            }]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "The required field 'detection' is not defined."
+        );
+
+        let errors = validate("title: test\ndetection:\n    selection:\n        EventID: 1\n    condition:\n        - selection\n");
+        assert_eq!(
+            errors[0].to_string(),
+            "The field 'detection.condition' should be a string, but it is a list."
+        );
+    }
+}
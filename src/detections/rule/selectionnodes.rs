@@ -237,7 +237,7 @@ impl SelectionNode for OrSelectionNode {
     }
 }
 
-/// conditionでNotを表すノード
+/// conditionでNotを表すノード。selection配下で|notが指定されたキーに配列を指定した場合にも使う
 pub struct NotSelectionNode {
     node: Box<dyn SelectionNode>,
 }
@@ -254,15 +254,18 @@ impl SelectionNode for NotSelectionNode {
     }
 
     fn init(&mut self) -> Result<(), Vec<String>> {
-        Result::Ok(())
+        // |notで配列全体を反転させる場合は、selection配下のノードを子として持つので初期化する
+        self.node.init()
     }
 
     fn get_childs(&self) -> Vec<&dyn SelectionNode> {
-        vec![]
+        vec![self.node.as_ref()]
     }
 
     fn get_descendants(&self) -> Vec<&dyn SelectionNode> {
-        self.get_childs()
+        let mut ret = self.get_childs();
+        ret.extend(self.node.get_descendants());
+        ret
     }
 }
 
@@ -280,6 +283,11 @@ impl RefSelectionNode {
             selection_node: select_node,
         }
     }
+
+    /// 参照しているselectionが指定したselectionと同じものかを返す
+    pub fn is_ref_of(&self, selection_node: &Arc<Box<dyn SelectionNode>>) -> bool {
+        Arc::ptr_eq(&self.selection_node, selection_node)
+    }
 }
 
 impl SelectionNode for RefSelectionNode {
@@ -305,6 +313,8 @@ pub struct LeafSelectionNode {
     key: String,
     key_list: Nested<String>,
     select_value: Yaml,
+    eventid: Option<String>, // 数値のEventIDのみが指定されている場合に、レコードの値と比較する文字列
+    is_negated: bool,        // |notが指定されている場合に、matcherの結果を反転させる
     pub matcher: Option<Box<dyn matchers::LeafMatcher>>,
 }
 
@@ -314,10 +324,25 @@ impl LeafSelectionNode {
             key: String::default(),
             key_list: keys,
             select_value: value_yaml,
+            eventid: Option::None,
+            is_negated: false,
             matcher: Option::None,
         }
     }
 
+    /// キーに|notが指定されている場合は、|notを取り除いたキーのリストを返す。
+    /// |notはmatcherの判定結果を反転させるだけなので、matcherには|notを除いたキーを渡す
+    pub fn remove_not_pipe(key_list: &Nested<String>) -> Option<Nested<String>> {
+        let topkey = key_list.get(0)?;
+        let mut keys: Vec<&str> = topkey.split('|').collect();
+        let not_pos = keys.iter().skip(1).position(|key| *key == "not")? + 1;
+        keys.remove(not_pos);
+        let mut removed_key_list = Nested::<String>::new();
+        removed_key_list.push(keys.join("|"));
+        removed_key_list.extend(key_list.iter().skip(1));
+        Some(removed_key_list)
+    }
+
     pub fn get_key(&self) -> &String {
         &self.key
     }
@@ -349,6 +374,19 @@ impl LeafSelectionNode {
         topkey.split('|').next().unwrap_or_default().to_string()
     }
 
+    /// ルールに記載されたキー(パイプを含む)と、比較に使ったレコードの値を`キー: 値`の形式で取得する。
+    /// keywordsや|allのようにキーがない場合はNoneを返す。
+    pub fn get_matched_field(&self, event_record: &EvtxRecordInfo) -> Option<String> {
+        if self.key_list.is_empty() || self.key_list[0].starts_with('|') {
+            return None;
+        }
+        let event_value = self
+            .get_event_value(event_record)
+            .map(|v| v.as_str())
+            .unwrap_or("n/a");
+        Some(format!("{}: {event_value}", &self.key_list[0]))
+    }
+
     /// JSON形式のEventJSONから値を取得する関数 aliasも考慮されている。
     fn get_event_value<'a>(&self, record: &'a EvtxRecordInfo) -> Option<&'a String> {
         // keyが指定されていない場合はそのままのレコードのデータを取得する
@@ -359,24 +397,12 @@ impl LeafSelectionNode {
         record.get_value(self.get_key())
     }
 
-    /// matchers::LeafMatcherの一覧を取得する。
-    /// 上から順番に調べて、一番始めに一致したMatcherが適用される
-    fn get_matchers(&self) -> Vec<Box<dyn matchers::LeafMatcher>> {
-        vec![
-            Box::new(matchers::MinlengthMatcher::new()),
-            Box::new(matchers::RegexesFileMatcher::new()),
-            Box::new(matchers::AllowlistFileMatcher::new()),
-            Box::new(matchers::DefaultMatcher::new()),
-        ]
-    }
-}
-
-impl SelectionNode for LeafSelectionNode {
-    fn select(&self, event_record: &EvtxRecordInfo, eventkey_alias: &EventKeyAliasConfig) -> bool {
-        if self.matcher.is_none() {
-            return false;
-        }
-
+    /// matcherでイベントの値を判定する。|notによる判定結果の反転はselect()で行う
+    fn is_matcher_match(
+        &self,
+        event_record: &EvtxRecordInfo,
+        eventkey_alias: &EventKeyAliasConfig,
+    ) -> bool {
         // EventDataはXMLが特殊な形式になっているので特別対応。
         //// 元のXMLは下記のような形式
         /*
@@ -397,6 +423,18 @@ impl SelectionNode for LeafSelectionNode {
                     ]
                 }
         */
+        // Data[*]のように[*]を含むキーは、配列のいずれかの要素がmatcherに一致すれば一致とする
+        if utils::contains_str(self.get_key(), utils::ARRAY_WILDCARD) {
+            let matcher = self.matcher.as_ref().unwrap();
+            let values = utils::get_event_values(self.get_key(), &event_record.record);
+            if values.is_empty() {
+                return matcher.is_match(Option::None, event_record);
+            }
+            return values.into_iter().any(|value| {
+                let event_value = utils::value_to_string(value);
+                matcher.is_match(event_value.as_ref(), event_record)
+            });
+        }
         if self.get_key() == "EventData" || self.get_key() == "Data" {
             let values = utils::get_event_value(
                 "Event.EventData.Data",
@@ -447,11 +485,9 @@ impl SelectionNode for LeafSelectionNode {
         }
 
         let mut event_value = self.get_event_value(event_record);
-        if self.get_key() == "EventID" && !self.select_value.is_null() {
-            if let Some(event_id) = self.select_value.as_i64() {
-                // 正規表現は重いので、数値のEventIDのみ文字列完全一致で判定
-                return event_value.unwrap_or(&String::default()) == &event_id.to_string();
-            }
+        if let Some(event_id) = &self.eventid {
+            // 正規表現は重いので、数値のEventIDのみ文字列完全一致で判定
+            return event_value.is_some_and(|event_value| event_value == event_id);
         }
         if !self.key_list.is_empty() && self.key_list[0].eq("|all") {
             event_value = Some(&event_record.data_string);
@@ -463,7 +499,37 @@ impl SelectionNode for LeafSelectionNode {
             .is_match(event_value, event_record);
     }
 
+    /// matchers::LeafMatcherの一覧を取得する。
+    /// 上から順番に調べて、一番始めに一致したMatcherが適用される
+    fn get_matchers(&self) -> Vec<Box<dyn matchers::LeafMatcher>> {
+        vec![
+            Box::new(matchers::MinlengthMatcher::new()),
+            Box::new(matchers::RegexesFileMatcher::new()),
+            Box::new(matchers::AllowlistFileMatcher::new()),
+            Box::new(matchers::DefaultMatcher::new()),
+        ]
+    }
+}
+
+impl SelectionNode for LeafSelectionNode {
+    fn select(&self, event_record: &EvtxRecordInfo, eventkey_alias: &EventKeyAliasConfig) -> bool {
+        if self.matcher.is_none() {
+            return false;
+        }
+        // |notが指定されている場合は、フィールドが存在しない場合も含めてmatcherに一致しなければ検知する
+        self.is_matcher_match(event_record, eventkey_alias) != self.is_negated
+    }
+
     fn init(&mut self) -> Result<(), Vec<String>> {
+        // matcherの初期化(正規表現のコンパイル)はルールの読み込み時に一度だけ行う
+        debug_assert!(
+            self.matcher.is_none(),
+            "LeafSelectionNode must be initialized only once."
+        );
+        if let Some(removed_key_list) = Self::remove_not_pipe(&self.key_list) {
+            self.is_negated = true;
+            self.key_list = removed_key_list;
+        }
         let matchers = self.get_matchers();
         self.matcher = matchers
             .into_iter()
@@ -485,6 +551,14 @@ impl SelectionNode for LeafSelectionNode {
         }
 
         self.key = self._create_key();
+        // |gteなどのパイプが指定されている場合はmatcherで判定する
+        // 検知処理の度に文字列を作成しないよう、比較するEventIDの文字列は初期化時に作成しておく
+        if self.key_list.len() == 1 && self.key_list[0].eq("EventID") {
+            self.eventid = self
+                .select_value
+                .as_i64()
+                .map(|event_id| event_id.to_string());
+        }
         return self
             .matcher
             .as_mut()
@@ -514,6 +588,9 @@ mod tests {
         rule::tests::parse_rule_from_str,
         utils,
     };
+    use nested::Nested;
+
+    use super::LeafSelectionNode;
 
     fn create_dummy_stored_static() -> StoredStatic {
         StoredStatic::create_static_data(Some(Config {
@@ -523,6 +600,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -543,7 +621,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -556,10 +634,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -568,6 +649,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -765,4 +862,62 @@ mod tests {
 
         check_select(rule_str, record_json_str, true);
     }
+
+    #[test]
+    fn test_detect_array_wildcard() {
+        // Data[*]で、複数のDataのいずれかの要素が一致すれば検知することを確認する。
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection:
+                Event.EventData.Data[*]|contains: whoami
+        details: 'command=%CommandLine%'
+        "#;
+
+        let record_json_str = r##"
+        {
+            "Event": {"System": {"EventID": 4688, "Channel": "Security"}, "EventData": {"Data": [
+                {"#attributes": {"Name": "SubjectUserSid"}, "#text": "S-1-5-18"},
+                {"#attributes": {"Name": "CommandLine"}, "#text": "cmd.exe /c whoami"}
+            ]}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"##;
+        check_select(rule_str, record_json_str, true);
+
+        let record_json_str = r##"
+        {
+            "Event": {"System": {"EventID": 4688, "Channel": "Security"}, "EventData": {"Data": [
+                {"#attributes": {"Name": "SubjectUserSid"}, "#text": "S-1-5-18"},
+                {"#attributes": {"Name": "CommandLine"}, "#text": "cmd.exe /c hostname"}
+            ]}},
+            "Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}
+        }"##;
+        check_select(rule_str, record_json_str, false);
+    }
+
+    #[test]
+    fn test_remove_not_pipe() {
+        // |notを取り除いたキーのリストが返ることを確認
+        let create_key_list = |keys: &[&str]| {
+            let mut key_list = Nested::<String>::new();
+            key_list.extend(keys.iter().copied());
+            key_list
+        };
+        assert_eq!(
+            LeafSelectionNode::remove_not_pipe(&create_key_list(&["Image|contains|not"]))
+                .map(|key_list| key_list.iter().map(|k| k.to_string()).collect::<Vec<_>>()),
+            Some(vec!["Image|contains".to_string()])
+        );
+        assert_eq!(
+            LeafSelectionNode::remove_not_pipe(&create_key_list(&["Image|not|endswith"]))
+                .map(|key_list| key_list.iter().map(|k| k.to_string()).collect::<Vec<_>>()),
+            Some(vec!["Image|endswith".to_string()])
+        );
+        // フィールド名がnotの場合や|notがない場合は取り除かない
+        assert!(LeafSelectionNode::remove_not_pipe(&create_key_list(&["not|contains"])).is_none());
+        assert!(
+            LeafSelectionNode::remove_not_pipe(&create_key_list(&["Image|contains"])).is_none()
+        );
+        assert!(LeafSelectionNode::remove_not_pipe(&create_key_list(&[])).is_none());
+    }
 }
@@ -16,6 +16,7 @@ use std::{fs, io};
 use chrono::Local;
 use chrono::{DateTime, TimeZone, Utc};
 use compact_str::{CompactString, ToCompactString};
+use flate2::read::GzDecoder;
 use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
 use memchr::memmem;
@@ -31,7 +32,9 @@ use crate::detections::field_data_map::{convert_field_data, FieldDataMap, FieldD
 use crate::detections::field_extract::extract_fields;
 use crate::options::htmlreport;
 
-use super::configs::{EventKeyAliasConfig, OutputOption, STORED_EKEY_ALIAS};
+use super::configs::{
+    get_default_system_event_key, EventKeyAliasConfig, OutputOption, STORED_EKEY_ALIAS,
+};
 use super::detection::EvtxRecordInfo;
 use super::message::AlertMessage;
 
@@ -98,9 +101,69 @@ pub fn read_txt(filename: &str) -> Result<Nested<String>, String> {
     ))
 }
 
+/// gzipで圧縮されたファイルの先頭のマジックバイト
+const GZIP_MAGIC_BYTES: [u8; 2] = [0x1f, 0x8b];
+
+/// SeekできるReader。gzipを展開したデータとファイルのどちらもEvtxParserに渡せるようにするために使う
+pub trait SeekableReader: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> SeekableReader for T {}
+
+/// ファイルがgzipで圧縮されているかを、拡張子(.gz)またはファイルの先頭のマジックバイトで判定する
+pub fn is_gzip_file(path: &Path) -> bool {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+    {
+        return true;
+    }
+    let mut magic_bytes = [0; 2];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic_bytes))
+        .is_ok_and(|_| magic_bytes == GZIP_MAGIC_BYTES)
+}
+
+/// gzipで圧縮されたファイル(例: Security.evtx.gz)の場合は、.gzを除いたファイル名を返す。
+/// 読み込み対象の拡張子や隠しファイルの判定を、圧縮前のファイル名で行うために使う
+pub fn strip_gz_extension(path: &Path) -> &Path {
+    match path.file_stem() {
+        Some(stem)
+            if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("gz")) =>
+        {
+            Path::new(stem)
+        }
+        _ => path,
+    }
+}
+
+/// ファイルを開く。gzipで圧縮されている場合は展開しながら読み込むReaderを返す
+pub fn open_decompressed_file(path: &str) -> io::Result<Box<dyn Read>> {
+    let f = File::open(path)?;
+    if is_gzip_file(Path::new(path)) {
+        Ok(Box::new(GzDecoder::new(f)))
+    } else {
+        Ok(Box::new(f))
+    }
+}
+
+/// EvtxParserに渡すためのReaderを返す。
+/// EvtxParserはファイル内をSeekする必要があるので、gzipで圧縮されている場合は全体を展開してメモリ上に読み込む
+pub fn open_seekable_evtx_file(path: &Path) -> io::Result<Box<dyn SeekableReader>> {
+    let f = File::open(path)?;
+    if is_gzip_file(path) {
+        let mut buf = vec![];
+        GzDecoder::new(f).read_to_end(&mut buf)?;
+        Ok(Box::new(io::Cursor::new(buf)))
+    } else {
+        Ok(Box::new(f))
+    }
+}
+
 /// convert jsonl fmt string to serde_json Value iterator
 pub fn read_jsonl_to_value(path: &str) -> Result<Box<dyn Iterator<Item = Value>>, String> {
-    let f = File::open(path);
+    let f = open_decompressed_file(path);
     if f.is_err() {
         return Err("Cannot open file. [file:{path}]".to_string());
     }
@@ -126,11 +189,11 @@ pub fn read_jsonl_to_value(path: &str) -> Result<Box<dyn Iterator<Item = Value>>
 
 /// convert json fmt string to serde_json Value iterator
 pub fn read_json_to_value(path: &str) -> Result<Box<dyn Iterator<Item = Value>>, String> {
-    let f = fs::read_to_string(path);
+    let mut contents = String::new();
+    let f = open_decompressed_file(path).and_then(|mut f| f.read_to_string(&mut contents));
     if f.is_err() {
         return Err("Cannot open file. [file:{path}]".to_string());
     }
-    let contents = f.unwrap();
     let json_values: Result<Vec<Value>, Error> = serde_json::from_str(&contents);
     let value_converter = |record: Value| json!({"Event":{"EventData": record}});
     match json_values {
@@ -247,6 +310,10 @@ pub fn get_event_value<'a>(
         return Option::None;
     }
 
+    // イベントのChannel/プロバイダ毎のaliasが定義されている場合はそれを優先し、定義されていない場合は全体のaliasを使う
+    let eventkey_alias = eventkey_alias
+        .get_scoped_alias(key, event_value)
+        .unwrap_or(eventkey_alias);
     let event_key = eventkey_alias.get_event_key(key);
     let mut ret: &Value = event_value;
     if let Some(event_key) = event_key {
@@ -254,37 +321,176 @@ pub fn get_event_value<'a>(
         let splits = eventkey_alias.get_event_key_split(key);
         let mut start_idx = 0;
         for key in splits.unwrap() {
-            if !ret.is_object() {
+            if !ret.is_object() && !ret.is_array() {
                 return Option::None;
             }
 
             let val = &event_key[start_idx..(*key + start_idx)];
-            ret = &ret[val];
+            // aliasの設定で指定されたキーは、イベントのフィールド名と大文字小文字が異なっていても取得する
+            ret = get_child_value(ret, val, true);
             start_idx += *key;
             start_idx += 1;
         }
 
-        Option::Some(ret)
+        Option::Some(get_text_value(ret))
     } else {
-        let event_key = if !contains_str(key, ".") {
-            "Event.EventData.".to_string() + key
-        } else {
-            key.to_string()
-        };
-        for key in event_key.split('.') {
-            if !ret.is_object() {
+        // eventkey_aliasに定義がないEvent.System配下のフィールドは、デフォルトのaliasで取得する
+        // 検知処理の度に文字列を作成しないよう、Event.EventData配下のフィールドはパスを連結せずにたどる
+        let (parent_key, event_key) =
+            if let Some(system_event_key) = get_default_system_event_key(key) {
+                (None, system_event_key)
+            } else if !contains_str(key, ".") {
+                (Some("Event.EventData"), key)
+            } else {
+                (None, key)
+            };
+        for key in parent_key
+            .into_iter()
+            .flat_map(|parent_key| parent_key.split('.'))
+            .chain(event_key.split('.'))
+        {
+            if !ret.is_object() && !ret.is_array() {
                 return Option::None;
             }
-            ret = &ret[key];
+            ret = get_child_value(ret, key, false);
+        }
+
+        Option::Some(get_text_value(ret))
+    }
+}
+
+/// フィールドのパスのうち、配列の全ての要素を対象にする部分を表す文字列
+pub const ARRAY_WILDCARD: &str = "[*]";
+
+/// Data[*]のように[*]を含むパスから、配列の全ての要素を候補として値を取得する関数。
+/// [*]を付けた要素が配列の場合は各要素に残りのパスを適用し、配列でない場合は1つの要素として扱う
+/// ルールではいずれかの候補が一致すれば、そのフィールドの条件に一致したとみなす
+pub fn get_event_values<'a>(key: &str, event_value: &'a Value) -> Vec<&'a Value> {
+    let event_key = if !contains_str(key, ".") {
+        format!("Event.EventData.{key}")
+    } else {
+        key.to_string()
+    };
+    let mut candidates = vec![event_value];
+    for key in event_key.split('.') {
+        let (key, is_wildcard) = match key.strip_suffix(ARRAY_WILDCARD) {
+            Some(stem) => (stem, true),
+            None => (key, false),
+        };
+        let mut next_candidates = vec![];
+        for candidate in candidates {
+            if !candidate.is_object() && !candidate.is_array() {
+                continue;
+            }
+            let child = if key.is_empty() {
+                candidate
+            } else {
+                get_child_value(candidate, key, false)
+            };
+            match child.as_array() {
+                Some(elements) if is_wildcard => next_candidates.extend(elements.iter()),
+                _ if !child.is_null() => next_candidates.push(child),
+                _ => {}
+            }
+        }
+        candidates = next_candidates;
+    }
+    candidates.into_iter().map(get_text_value).collect()
+}
+
+/// 属性付きのXML要素が{"#attributes": {...}, "#text": 値}の形式で変換されている場合は、#textの値を返す関数。
+/// 例えばEventIDは、4688、"4688"、{"#attributes": {"Qualifiers": 0}, "#text": 4688}のいずれの形式でも同じ値として扱えるようにする
+pub fn get_text_value(value: &Value) -> &Value {
+    match value.get("#text") {
+        Some(text) if value.is_object() => text,
+        _ => value,
+    }
+}
+
+/// 指定したキーの子要素を返す関数。配列の場合はキーを0始まりのインデックスとして扱う。
+/// キーが存在せず、Dataが{"#attributes": {"Name": キー名}, "#text": 値}の要素(またはその配列)となっている場合は、Nameが一致する要素の値を返す
+/// キーが存在せず、要素の属性にキーが存在する場合は属性の値を返す
+/// ignore_caseがtrue(aliasの設定で指定されたキー)の場合は、完全一致するキーが存在しなければ大文字小文字を区別せずに一致するキーの値を返す
+/// 検知処理の度に呼ばれるので、キーが存在しない場合も文字列を作成しない
+fn get_child_value<'a>(parent: &'a Value, key: &str, ignore_case: bool) -> &'a Value {
+    if parent.is_array() {
+        return match key.parse::<usize>() {
+            Ok(idx) => &parent[idx],
+            Err(_) => &parent[key],
+        };
+    }
+    let child = &parent[key];
+    if !child.is_null() {
+        return child;
+    }
+    if let Some(named_data) = find_named_data(parent, key, ignore_case) {
+        return named_data;
+    }
+    if let Some(attribute) = get_attribute_value(parent, key) {
+        return attribute;
+    }
+    if !ignore_case {
+        return child;
+    }
+    parent
+        .as_object()
+        .and_then(|obj| obj.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)))
+        .map_or(child, |(_, val)| val)
+}
+
+/// Dataが{"#attributes": {"Name": キー名}, "#text": 値}の形式の場合に、Nameがキーと一致する要素の#textの値を返す関数。
+/// Dataが1つしかない場合は配列ではなくオブジェクトとして変換されるため、どちらの形式にも対応する
+/// ignore_caseがtrueの場合は、完全一致する要素がなければ大文字小文字を区別せずに一致した最初の要素の値を返す
+fn find_named_data<'a>(parent: &'a Value, key: &str, ignore_case: bool) -> Option<&'a Value> {
+    let data = &parent["Data"];
+    let data_list = match data.as_array() {
+        Some(data_list) => data_list.as_slice(),
+        None => std::slice::from_ref(data),
+    };
+    let mut ignore_case_data = None;
+    for data in data_list {
+        let name = data["#attributes"]["Name"].as_str();
+        if name.is_none() {
+            continue;
         }
+        let name = name.unwrap();
+        if name == key {
+            return Some(&data["#text"]);
+        }
+        if ignore_case && ignore_case_data.is_none() && name.eq_ignore_ascii_case(key) {
+            ignore_case_data = Some(&data["#text"]);
+        }
+    }
+    ignore_case_data
+}
 
-        Option::Some(ret)
+/// XMLの属性の値を返す関数。evtxクレートの変換方法によって属性の形式が異なるため、以下のどちらの形式でも同じキーで取得できるようにする
+/// - {"Provider": {"#attributes": {"Name": 値}}} : Provider.Nameでも、Provider_attributes.Nameでも取得できる
+/// - {"Provider_attributes": {"Name": 値}} : Provider_attributes.Nameでも、Provider.Nameでも取得できる
+fn get_attribute_value<'a>(parent: &'a Value, key: &str) -> Option<&'a Value> {
+    let attribute = &parent["#attributes"][key];
+    if !attribute.is_null() {
+        return Some(attribute);
+    }
+    // キー名_attributesの要素は、文字列を連結せずにキーの前方と後方を比較して探す
+    let separated_attributes = parent.as_object()?.iter().find_map(|(k, v)| {
+        k.strip_prefix(key)
+            .filter(|suffix| *suffix == "_attributes" && v.is_object())
+            .map(|_| v)
+    });
+    if separated_attributes.is_some() {
+        return separated_attributes;
     }
+    let attributes = &parent[key.strip_suffix("_attributes")?]["#attributes"];
+    Some(attributes).filter(|attributes| attributes.is_object())
 }
 
+/// 検知処理で使うスレッド数を返す。--threadsが指定されていない場合や0の場合はCPUのコア数を使う
 pub fn get_thread_num(thread_number: Option<usize>) -> usize {
     let cpu_num = available_parallelism().unwrap();
-    thread_number.unwrap_or(cpu_num.into())
+    thread_number
+        .filter(|num| *num > 0)
+        .unwrap_or(cpu_num.into())
 }
 
 pub fn create_tokio_runtime(thread_number: Option<usize>) -> Runtime {
@@ -594,7 +800,10 @@ pub fn format_time(
     date_only: bool,
     output_option: &OutputOption,
 ) -> CompactString {
-    if !(output_option.utc || output_option.iso_8601) {
+    if let Some(tz) = output_option.timezone {
+        // 夏時間の切り替えを含めて、イベントの時刻時点でのタイムゾーンのオフセットで出力する
+        format_rfc(&time.with_timezone(&tz), date_only, output_option)
+    } else if !(output_option.utc || output_option.iso_8601) {
         format_rfc(&time.with_timezone(&Local), date_only, output_option)
     } else {
         format_rfc(time, date_only, output_option)
@@ -663,6 +872,22 @@ pub fn check_file_expect_not_exist(path: &Path, exist_alert_str: String) -> bool
     ret
 }
 
+/// Create the output file. If the parent directory does not exist, create it.
+pub fn create_output_file(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    File::create(path)
+}
+
+/// 出力ファイルを追記モードで開く。ファイルが存在しない場合は作成する
+pub fn append_output_file(path: &Path) -> io::Result<File> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::OpenOptions::new().create(true).append(true).open(path)
+}
+
 pub fn output_and_data_stack_for_html(
     output_str: &str,
     section_name: &str,
@@ -769,15 +994,18 @@ pub fn remove_sp_char(record_value: CompactString) -> CompactString {
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
     use std::path::Path;
 
-    use chrono::NaiveDate;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use clap::Parser;
     use compact_str::CompactString;
     use hashbrown::{HashMap, HashSet};
     use nested::Nested;
     use regex::Regex;
     use serde_json::Value;
 
+    use crate::detections::configs::{load_eventkey_alias, merge_eventkey_alias};
     use crate::detections::field_data_map::FieldDataMapKey;
     use crate::{
         detections::{
@@ -956,6 +1184,330 @@ mod tests {
         assert_eq!(make_ascii_titlecase("β".to_string().as_mut()), "β");
     }
 
+    #[test]
+    /// EventDataの子要素がオブジェクト形式でも、#attributesのName付きのData配列でも値を取得できることを確認
+    fn test_get_event_value_eventdata() {
+        let eventkey_alias = load_eventkey_alias("test_files/config/eventkey_alias.txt");
+        let flattened: Value = serde_json::from_str(
+            r#"{"Event": {"EventData": {"SubjectUserName": "user01", "CommandLine": "cmd.exe /c whoami"}}}"#,
+        )
+        .unwrap();
+        let named_data: Value = serde_json::from_str(
+            r##"{"Event": {"EventData": {"Data": [{"#attributes": {"Name": "SubjectUserName"}, "#text": "user01"}, {"#attributes": {"Name": "CommandLine"}, "#text": "cmd.exe /c whoami"}]}}}"##,
+        )
+        .unwrap();
+        for record in [&flattened, &named_data] {
+            assert_eq!(
+                utils::get_event_value("CommandLine", record, &eventkey_alias),
+                Some(&Value::String("cmd.exe /c whoami".to_string()))
+            );
+            assert_eq!(
+                utils::get_event_value("Event.EventData.SubjectUserName", record, &eventkey_alias),
+                Some(&Value::String("user01".to_string()))
+            );
+            assert_eq!(
+                utils::get_event_value("NotExistField", record, &eventkey_alias),
+                Some(&Value::Null)
+            );
+        }
+    }
+
+    #[test]
+    /// evtxクレートが出力する#attributesと#textの形式の要素から、属性と値を取得できることを確認
+    fn test_get_event_value_attributes_and_text() {
+        let eventkey_alias = load_eventkey_alias("test_files/config/eventkey_alias.txt");
+        // separate_json_attributesを指定しない場合のevtxクレートの出力。Dataが1つの場合は配列にならない
+        let record: Value = serde_json::from_str(
+            r##"{"Event": {"#attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}, "System": {"Provider": {"#attributes": {"Name": "Microsoft-Windows-Security-Auditing", "Guid": "54849625-5478-4994-A5BA-3E3B0328C30D"}}, "EventID": 4688, "Channel": "Security", "Security": {"#attributes": {"UserID": "S-1-5-18"}}}, "EventData": {"Data": {"#attributes": {"Name": "NewProcessName"}, "#text": "C:\\Windows\\System32\\cmd.exe"}}}}"##,
+        )
+        .unwrap();
+        // separate_json_attributesを指定した場合のevtxクレートの出力
+        let separated_record: Value = serde_json::from_str(
+            r##"{"Event": {"Event_attributes": {"xmlns": "http://schemas.microsoft.com/win/2004/08/events/event"}, "System": {"Provider_attributes": {"Name": "Microsoft-Windows-Security-Auditing", "Guid": "54849625-5478-4994-A5BA-3E3B0328C30D"}, "EventID": 4688, "Channel": "Security", "Security_attributes": {"UserID": "S-1-5-18"}}, "EventData": {"NewProcessName": "C:\\Windows\\System32\\cmd.exe"}}}"##,
+        )
+        .unwrap();
+        for record in [&record, &separated_record] {
+            assert_eq!(
+                utils::get_event_value("NewProcessName", record, &eventkey_alias),
+                Some(&Value::String("C:\\Windows\\System32\\cmd.exe".to_string()))
+            );
+            let provider_name = Some(&Value::String(
+                "Microsoft-Windows-Security-Auditing".to_string(),
+            ));
+            assert_eq!(
+                utils::get_event_value("Event.System.Provider.Name", record, &eventkey_alias),
+                provider_name
+            );
+            assert_eq!(
+                utils::get_event_value(
+                    "Event.System.Provider_attributes.Name",
+                    record,
+                    &eventkey_alias
+                ),
+                provider_name
+            );
+            assert_eq!(
+                utils::get_event_value("Event.System.Security.UserID", record, &eventkey_alias),
+                Some(&Value::String("S-1-5-18".to_string()))
+            );
+            assert_eq!(
+                utils::get_event_value("Event.System.Provider.NotExist", record, &eventkey_alias),
+                Some(&Value::Null)
+            );
+        }
+    }
+
+    #[test]
+    /// 配列の要素を0始まりのインデックスで指定して値を取得できることを確認
+    fn test_get_event_value_array_index() {
+        let eventkey_alias = load_eventkey_alias("test_files/config/eventkey_alias.txt");
+        let record: Value = serde_json::from_str(
+            r#"{"Event": {"EventData": {"Data": ["Available", "None", "NewEngineState=Available"]}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            utils::get_event_value("Event.EventData.Data.0", &record, &eventkey_alias),
+            Some(&Value::String("Available".to_string()))
+        );
+        assert_eq!(
+            utils::get_event_value("Event.EventData.Data.2", &record, &eventkey_alias),
+            Some(&Value::String("NewEngineState=Available".to_string()))
+        );
+        assert_eq!(
+            utils::get_event_value("Event.EventData.Data.3", &record, &eventkey_alias),
+            Some(&Value::Null)
+        );
+        assert_eq!(
+            utils::get_event_value("Event.EventData.Data.x", &record, &eventkey_alias),
+            Some(&Value::Null)
+        );
+    }
+
+    #[test]
+    /// Data[*]で配列の全ての要素を候補として取得できることを確認
+    fn test_get_event_values_array_wildcard() {
+        // JSON形式で入力されたSecurityログのように、複数のDataが名前付きの要素の配列になっている場合
+        let record: Value = serde_json::from_str(
+            r##"{"Event": {"System": {"EventID": 4688}, "EventData": {"Data": [
+                {"#attributes": {"Name": "SubjectUserSid"}, "#text": "S-1-5-18"},
+                {"#attributes": {"Name": "NewProcessName"}, "#text": "C:\\Windows\\System32\\cmd.exe"},
+                {"#attributes": {"Name": "CommandLine"}, "#text": "cmd.exe /c whoami"}
+            ]}}}"##,
+        )
+        .unwrap();
+        assert_eq!(
+            utils::get_event_values("Event.EventData.Data[*]", &record),
+            vec![
+                &Value::String("S-1-5-18".to_string()),
+                &Value::String("C:\\Windows\\System32\\cmd.exe".to_string()),
+                &Value::String("cmd.exe /c whoami".to_string()),
+            ]
+        );
+        // .を含まない場合はEvent.EventData配下のパスとして扱う
+        assert_eq!(
+            utils::get_event_values("Data[*].#attributes.Name", &record),
+            vec![
+                &Value::String("SubjectUserSid".to_string()),
+                &Value::String("NewProcessName".to_string()),
+                &Value::String("CommandLine".to_string()),
+            ]
+        );
+        assert!(utils::get_event_values("Event.EventData.Binary[*]", &record).is_empty());
+
+        // Dataが1つしかなく配列になっていない場合は、その要素だけを候補とする
+        let record: Value =
+            serde_json::from_str(r#"{"Event": {"EventData": {"Data": "Available"}}}"#).unwrap();
+        assert_eq!(
+            utils::get_event_values("Event.EventData.Data[*]", &record),
+            vec![&Value::String("Available".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_get_event_value_case_insensitive() {
+        let eventkey_alias = load_eventkey_alias("test_files/config/eventkey_alias_case.txt");
+        let record: Value = serde_json::from_str(
+            r##"{"Event": {"System": {"EventID": 4688}, "EventData": {"commandLine": "cmd.exe", "Data": [{"#attributes": {"Name": "processId"}, "#text": "1234"}]}}}"##,
+        )
+        .unwrap();
+        let command_line = Some(&Value::String("cmd.exe".to_string()));
+        assert_eq!(
+            utils::get_event_value("CommandLine", &record, &eventkey_alias),
+            command_line
+        );
+        assert_eq!(
+            utils::get_event_value("commandline", &record, &eventkey_alias),
+            command_line
+        );
+        // aliasの設定にないキーは、フィールド名の大文字小文字を区別する
+        assert_eq!(
+            utils::get_event_value("event.eventdata.COMMANDLINE", &record, &eventkey_alias),
+            None
+        );
+        assert_eq!(
+            utils::get_event_value("Event.EventData.commandLine", &record, &eventkey_alias),
+            command_line
+        );
+        assert_eq!(
+            utils::get_event_value("ProcessId", &record, &eventkey_alias),
+            Some(&Value::String("1234".to_string()))
+        );
+        assert_eq!(
+            utils::get_event_value("eventid", &record, &eventkey_alias),
+            Some(&Value::from(4688))
+        );
+    }
+
+    #[test]
+    /// EventIDが数値、文字列、#text付きのオブジェクトのいずれの形式でも同じ値として取得できることを確認するテスト
+    fn test_get_event_value_eventid_json_shapes() {
+        let eventkey_alias = load_eventkey_alias("test_files/config/eventkey_alias.txt");
+        for record_str in [
+            r##"{"Event": {"System": {"EventID": 4688}}}"##,
+            r##"{"Event": {"System": {"EventID": "4688"}}}"##,
+            r##"{"Event": {"System": {"EventID": {"#attributes": {"Qualifiers": 0}, "#text": 4688}}}}"##,
+            r##"{"Event": {"System": {"EventID": {"#attributes": {"Qualifiers": 0}, "#text": "4688"}}}}"##,
+        ] {
+            let record: Value = serde_json::from_str(record_str).unwrap();
+            let val = utils::get_event_value("EventID", &record, &eventkey_alias).unwrap();
+            assert_eq!(utils::value_to_string(val), Some("4688".to_string()));
+            assert_eq!(
+                utils::get_serde_number_to_string(
+                    utils::get_text_value(&record["Event"]["System"]["EventID"]),
+                    false
+                ),
+                Some(CompactString::from("4688"))
+            );
+        }
+    }
+
+    #[test]
+    /// eventkey_aliasに定義がなくても、Event.System配下のよく使われるフィールドをフルパスを書かずに取得できることを確認するテスト
+    fn test_get_event_value_default_system_alias() {
+        let eventkey_alias = load_eventkey_alias("test_files/config/eventkey_alias.txt");
+        let record: Value = serde_json::from_str(
+            r#"{
+                "Event": {
+                    "System": {
+                        "Provider_attributes": {"Name": "Microsoft-Windows-Security-Auditing", "Guid": "54849625-5478-4994-A5BA-3E3B0328C30D"},
+                        "EventID": 4624,
+                        "TimeCreated_attributes": {"SystemTime": "2021-12-23T00:00:00.000000Z"},
+                        "Channel": "Security",
+                        "Computer": "DESKTOP-ICHIICHI"
+                    },
+                    "EventData": {"Channel": "EventDataChannel", "TargetUserName": "user01"},
+                    "RenderingInfo": {"Message": "An account was successfully logged on."}
+                }
+            }"#,
+        )
+        .unwrap();
+        let cases = [
+            ("Channel", Value::from("Security")),
+            ("channel", Value::from("Security")),
+            ("Computer", Value::from("DESKTOP-ICHIICHI")),
+            ("EventID", Value::from(4624)),
+            (
+                "Provider",
+                Value::from("Microsoft-Windows-Security-Auditing"),
+            ),
+            (
+                "Provider_Name",
+                Value::from("Microsoft-Windows-Security-Auditing"),
+            ),
+            ("TimeCreated", Value::from("2021-12-23T00:00:00.000000Z")),
+            (
+                "RenderedMessage",
+                Value::from("An account was successfully logged on."),
+            ),
+            // System配下以外のフィールドは今まで通りEventData配下から取得する
+            ("TargetUserName", Value::from("user01")),
+            // フルパスで指定した場合はEventData配下の同名のフィールドも取得できる
+            ("Event.EventData.Channel", Value::from("EventDataChannel")),
+        ];
+        for (key, expected) in cases {
+            assert_eq!(
+                utils::get_event_value(key, &record, &eventkey_alias),
+                Some(&expected),
+                "{key}"
+            );
+        }
+    }
+
+    #[test]
+    /// Channel/プロバイダ毎に定義したaliasで、同じキーがイベントのChannelに応じて異なるフィールドを参照することを確認するテスト
+    fn test_get_event_value_scoped_alias() {
+        let mut eventkey_alias = load_eventkey_alias("test_files/config/eventkey_alias.txt");
+        merge_eventkey_alias(
+            &mut eventkey_alias,
+            Path::new("test_files/config/alias_config/channel_alias.csv"),
+        )
+        .unwrap();
+        let sysmon_record: Value = serde_json::from_str(
+            r#"{
+                "Event": {
+                    "System": {"EventID": 1, "Channel": "Microsoft-Windows-Sysmon/Operational"},
+                    "EventData": {"Image": "C:\\Windows\\System32\\cmd.exe", "ParentImage": "C:\\Windows\\explorer.exe"}
+                }
+            }"#,
+        )
+        .unwrap();
+        let security_record: Value = serde_json::from_str(
+            r#"{
+                "Event": {
+                    "System": {"EventID": 4688, "Channel": "security"},
+                    "EventData": {"NewProcessName": "C:\\Windows\\System32\\whoami.exe", "ParentProcessName": "C:\\Windows\\System32\\cmd.exe"}
+                }
+            }"#,
+        )
+        .unwrap();
+        let session_record: Value = serde_json::from_str(
+            r#"{
+                "Event": {
+                    "System": {
+                        "EventID": 21,
+                        "Channel": "Microsoft-Windows-TerminalServices-LocalSessionManager/Operational",
+                        "Provider_attributes": {"Name": "Microsoft-Windows-TerminalServices-LocalSessionManager"}
+                    },
+                    "UserData": {"EventXML": {"User": "DOMAIN\\user01"}}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            utils::get_event_value("Image", &sysmon_record, &eventkey_alias),
+            Some(&Value::from("C:\\Windows\\System32\\cmd.exe"))
+        );
+        // Channel名は大文字小文字を区別しない
+        assert_eq!(
+            utils::get_event_value("Image", &security_record, &eventkey_alias),
+            Some(&Value::from("C:\\Windows\\System32\\whoami.exe"))
+        );
+        assert_eq!(
+            utils::get_event_value("ParentImage", &security_record, &eventkey_alias),
+            Some(&Value::from("C:\\Windows\\System32\\cmd.exe"))
+        );
+        // プロバイダ名に対して定義したaliasも使える
+        assert_eq!(
+            utils::get_event_value("User", &session_record, &eventkey_alias),
+            Some(&Value::from("DOMAIN\\user01"))
+        );
+        // Channel毎のaliasに定義がないキーは全体のaliasで取得する
+        assert_eq!(
+            utils::get_event_value("EventID", &security_record, &eventkey_alias),
+            Some(&Value::from(4688))
+        );
+    }
+
+    #[test]
+    fn test_get_thread_num() {
+        assert_eq!(utils::get_thread_num(Some(1)), 1);
+        assert_eq!(utils::get_thread_num(Some(3)), 3);
+        // 0を指定した場合は指定しなかった場合と同様にCPUのコア数を使う
+        assert_eq!(utils::get_thread_num(Some(0)), utils::get_thread_num(None));
+        assert!(utils::get_thread_num(None) > 0);
+    }
+
     #[test]
     /// 与えられたパスからファイルの存在確認ができているかのテスト
     fn test_check_setting_path() {
@@ -1036,6 +1588,51 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_gzip_file_to_serde_json_value() {
+        // gzipで圧縮されたJSONL形式とJSON(Array)形式を展開して変換できること
+        let jsonl_records: Vec<Value> = utils::read_jsonl_to_value("test_files/evtx/test.jsonl.gz")
+            .unwrap()
+            .collect();
+        let json_records: Vec<Value> = utils::read_json_to_value("test_files/evtx/test.json.gz")
+            .unwrap()
+            .collect();
+        for records in [&jsonl_records, &json_records] {
+            assert_eq!(records.len(), 2);
+            assert_eq!(
+                records[0]["Event"]["EventData"]["@timestamp"],
+                "2020-05-02T02:55:26.493Z"
+            );
+            assert_eq!(
+                records[1]["Event"]["EventData"]["@timestamp"],
+                "2020-05-02T02:55:30.540Z"
+            );
+        }
+
+        // 拡張子が.gzでなくても、先頭のマジックバイトでgzipと判定すること
+        let path = std::env::temp_dir().join("hayabusa_test_gzip_magic.jsonl");
+        std::fs::copy("test_files/evtx/test.jsonl.gz", &path).unwrap();
+        assert!(utils::is_gzip_file(&path));
+        let records = utils::read_jsonl_to_value(path.to_str().unwrap()).map(|r| r.count());
+        std::fs::remove_file(&path).ok();
+        assert_eq!(records, Ok(2));
+        assert!(!utils::is_gzip_file(Path::new(
+            "test_files/evtx/test.jsonl"
+        )));
+    }
+
+    #[test]
+    fn test_strip_gz_extension() {
+        assert_eq!(
+            utils::strip_gz_extension(Path::new("logs/Security.evtx.gz")),
+            Path::new("Security.evtx")
+        );
+        assert_eq!(
+            utils::strip_gz_extension(Path::new("logs/Security.evtx")),
+            Path::new("logs/Security.evtx")
+        );
+    }
+
     #[test]
     fn test_jq_c_file_to_serde_json_value() {
         // 存在しないパスはErr
@@ -1067,6 +1664,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -1087,7 +1685,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: Some(Path::new("dummy.html").to_path_buf()),
                     no_summary: false,
                     common_options: CommonOptions {
@@ -1100,10 +1698,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -1112,6 +1713,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -1199,6 +1816,106 @@ mod tests {
         ));
     }
 
+    #[test]
+    /// --ISO-8601を指定した場合はローカルのタイムゾーンに関わらずUTCのISO-8601形式で出力されることを確認
+    fn test_format_time_iso_8601() {
+        let time = Utc
+            .with_ymd_and_hms(2021, 12, 23, 1, 2, 3)
+            .unwrap()
+            .checked_add_signed(chrono::Duration::milliseconds(456))
+            .unwrap();
+        let output_option = match Config::try_parse_from([
+            "hayabusa",
+            "json-timeline",
+            "-f",
+            "test.evtx",
+            "-w",
+            "-O",
+        ])
+        .unwrap()
+        .action
+        {
+            Some(Action::JsonTimeline(opt)) => opt.output_options,
+            _ => panic!("json-timeline should be parsed."),
+        };
+        assert_eq!(
+            utils::format_time(&time, false, &output_option),
+            "2021-12-23T01:02:03.456Z"
+        );
+        assert_eq!(
+            utils::format_time(&time, true, &output_option),
+            "2021-12-23"
+        );
+    }
+
+    #[test]
+    /// --timezoneを指定した場合は、夏時間を考慮したそのタイムゾーンの時刻とオフセットで出力されることを確認
+    fn test_format_time_timezone() {
+        let parse_output_option = |args: &[&str]| {
+            let mut cmd = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w"];
+            cmd.extend_from_slice(args);
+            match Config::try_parse_from(cmd).unwrap().action {
+                Some(Action::CsvTimeline(opt)) => opt.output_options,
+                _ => panic!("csv-timeline should be parsed."),
+            }
+        };
+        let output_option = parse_output_option(&["--timezone", "America/New_York"]);
+        // 夏時間(EDT)
+        let summer = Utc.with_ymd_and_hms(2023, 7, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            utils::format_time(&summer, false, &output_option),
+            "2023-07-01 08:00:00.000 -04:00"
+        );
+        // 標準時(EST)
+        let winter = Utc.with_ymd_and_hms(2023, 1, 1, 12, 0, 0).unwrap();
+        assert_eq!(
+            utils::format_time(&winter, false, &output_option),
+            "2023-01-01 07:00:00.000 -05:00"
+        );
+        // 夏時間への切り替え直後(2023-03-12 02:00 EST -> 03:00 EDT)
+        let dst_start = Utc.with_ymd_and_hms(2023, 3, 12, 7, 0, 0).unwrap();
+        assert_eq!(
+            utils::format_time(&dst_start, false, &output_option),
+            "2023-03-12 03:00:00.000 -04:00"
+        );
+        // 日付のみの場合はタイムゾーンでの日付を出力する
+        assert_eq!(
+            utils::format_time(&summer, true, &output_option),
+            "2023-07-01"
+        );
+        let late_night = Utc.with_ymd_and_hms(2023, 7, 1, 2, 0, 0).unwrap();
+        assert_eq!(
+            utils::format_time(&late_night, true, &output_option),
+            "2023-06-30"
+        );
+
+        let output_option = parse_output_option(&["--timezone", "Asia/Tokyo", "--RFC-3339"]);
+        assert_eq!(
+            utils::format_time(&summer, false, &output_option),
+            "2023-07-01 21:00:00.000000+09:00"
+        );
+
+        assert!(Config::try_parse_from([
+            "hayabusa",
+            "csv-timeline",
+            "-f",
+            "test.evtx",
+            "--timezone",
+            "Invalid/Zone",
+        ])
+        .is_err());
+        assert!(Config::try_parse_from([
+            "hayabusa",
+            "csv-timeline",
+            "-f",
+            "test.evtx",
+            "--timezone",
+            "Asia/Tokyo",
+            "-U",
+        ])
+        .is_err());
+    }
+
     #[test]
     /// Durationから出力文字列を作成する関数のテスト
     fn test_output_duration() {
@@ -1221,4 +1938,35 @@ mod tests {
         let ms = duration.num_milliseconds() - 1000 * s;
         assert_eq!(output_duration((s, ms)), "25:11:03.322".to_string());
     }
+
+    #[test]
+    fn test_create_output_file() {
+        // 出力先のディレクトリが存在しない場合は作成してからファイルを作成することを確認
+        let test_dir = std::env::temp_dir().join("hayabusa_test_create_output_file");
+        let _ = std::fs::remove_dir_all(&test_dir);
+        let output_path = test_dir.join("sub").join("results.csv");
+        assert!(utils::create_output_file(&output_path).is_ok());
+        assert!(output_path.exists());
+
+        // 出力先にディレクトリが指定された場合はエラーになることを確認
+        assert!(utils::create_output_file(&test_dir).is_err());
+        std::fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_append_output_file() {
+        // 既存のファイルの内容を残したまま追記することを確認
+        let test_dir = std::env::temp_dir().join("hayabusa_test_append_output_file");
+        let _ = std::fs::remove_dir_all(&test_dir);
+        let output_path = test_dir.join("results.jsonl");
+        for line in ["{\"a\":1}\n", "{\"a\":2}\n"] {
+            let mut file = utils::append_output_file(&output_path).unwrap();
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(
+            std::fs::read_to_string(&output_path).unwrap(),
+            "{\"a\":1}\n{\"a\":2}\n"
+        );
+        std::fs::remove_dir_all(&test_dir).unwrap();
+    }
 }
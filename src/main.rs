@@ -6,19 +6,12 @@ extern crate serde_derive;
 
 use std::borrow::BorrowMut;
 use std::ffi::{OsStr, OsString};
-use std::fmt::Display;
 use std::fmt::Write as _;
-use std::io::{BufWriter, Write};
+use std::io::{self, BufWriter, IsTerminal, Write};
 use std::path::Path;
 use std::ptr::null_mut;
-use std::sync::Arc;
-use std::time::Duration;
-use std::{
-    env,
-    fs::{self, File},
-    path::PathBuf,
-    vec,
-};
+use std::time::{Duration, Instant};
+use std::{env, fs, path::PathBuf, process, vec};
 
 use bytesize::ByteSize;
 use chrono::{DateTime, Datelike, Local, NaiveDateTime, Utc};
@@ -40,28 +33,31 @@ use rust_embed::Embed;
 use serde_json::{Map, Value};
 use termcolor::{BufferWriter, Color, ColorChoice};
 use tokio::runtime::Runtime;
-use tokio::spawn;
-use tokio::task::JoinHandle;
 
 use hayabusa::afterfact::{self, AfterfactInfo, AfterfactWriter};
 use hayabusa::debug::checkpoint_process_timer::CHECKPOINT;
 use hayabusa::detections::configs::{
-    load_pivot_keywords, Action, ConfigReader, EventKeyAliasConfig, StoredStatic, TargetEventTime,
-    TargetIds, CURRENT_EXE_PATH, STORED_EKEY_ALIAS, STORED_STATIC,
+    load_pivot_keywords, Action, ConfigReader, StoredStatic, TargetEventTime, TargetIds,
+    CURRENT_EXE_PATH, STORED_EKEY_ALIAS, STORED_STATIC,
+};
+use hayabusa::detections::detection::{
+    self, DetectionError, RecordFilter, ScanRecord, ScanResult, SkipCorruptRecords,
+    DETECTION_ERROR_REPORT,
 };
-use hayabusa::detections::detection::{self, EvtxRecordInfo};
 use hayabusa::detections::message::{AlertMessage, DetectInfo, ERROR_LOG_STACK};
-use hayabusa::detections::rule::{get_detection_keys, RuleNode};
+use hayabusa::detections::rule::RuleNode;
 use hayabusa::detections::utils;
 use hayabusa::detections::utils::{
     check_setting_path, get_writable_color, output_and_data_stack_for_html, output_profile_name,
+    SeekableReader,
 };
 use hayabusa::filter::create_channel_filter;
 use hayabusa::options::htmlreport::{self, HTML_REPORTER};
+use hayabusa::options::live_channel;
 use hayabusa::options::pivot::create_output;
 use hayabusa::options::pivot::PIVOT_KEYWORD;
 use hayabusa::options::profile::set_default_profile;
-use hayabusa::options::{level_tuning::LevelTuning, update::Update};
+use hayabusa::options::{level_tuning::LevelTuning, update::Update, validate_rules::ValidateRules};
 use hayabusa::timeline::computer_metrics::countup_event_by_computer;
 use hayabusa::{detections::configs, timeline::timelines::Timeline};
 use hayabusa::{detections::utils::write_color_buffer, filter};
@@ -84,27 +80,103 @@ static GLOBAL: MiMalloc = MiMalloc;
 // 一度に読み込んで、スキャンするレコード数
 // The number of records to load and scan at a time. 1000 gave the fastest results and lowest memory usage in test benchmarks.
 const MAX_DETECT_RECORDS: usize = 1000;
+// --streamの場合に一度に読み込んで、スキャンするレコード数。1件ずつではなく、このレコード数ごとにスキャンして検知結果を出力する
+// The number of records to scan at a time with --stream. Detections are not outputted per record but after each batch of this size is scanned.
+const STREAM_DETECT_RECORDS: usize = 100;
+
+/// 一度に読み込んでスキャンするレコード数を返す
+fn get_max_detect_records(stored_static: &StoredStatic) -> usize {
+    if stored_static
+        .output_option
+        .as_ref()
+        .is_some_and(|option| option.stream)
+    {
+        STREAM_DETECT_RECORDS
+    } else {
+        MAX_DETECT_RECORDS
+    }
+}
 
 fn main() {
     let mut config_reader = ConfigReader::new();
+    // -vの指定回数でログの出力レベルが決まるので、コマンドのパース直後にログの出力を初期化する
+    AlertMessage::init_logger(
+        config_reader
+            .config
+            .as_ref()
+            .map_or(0, |config| configs::get_verbose_level(&config.action)),
+    );
     // コマンドのパース情報を作成してstatic変数に格納する
     let mut stored_static = StoredStatic::create_static_data(config_reader.config);
     config_reader.config = None;
     let mut app = App::new(stored_static.thread_number);
     app.exec(&mut config_reader.app, &mut stored_static);
     app.rt.shutdown_background();
+    // --strictオプションが指定された場合は、パースに失敗したレコードがあればエラーコードで終了する
+    if stored_static.strict_flag && DETECTION_ERROR_REPORT.lock().unwrap().evtx_parse_error_cnt > 0
+    {
+        process::exit(1);
+    }
+    // validate-rulesコマンドでは、検証に失敗したルールがあればエラーコードで終了する
+    if matches!(stored_static.config.action, Some(Action::ValidateRules(_)))
+        && DETECTION_ERROR_REPORT.lock().unwrap().rule_error_cnt > 0
+    {
+        process::exit(1);
+    }
 }
 
 pub struct App {
     rt: Runtime,
-    rule_keys: Nested<String>,
+}
+
+/// 解析中の進捗(処理したイベント数と検知数)をプログレスバーに表示するための構造体
+struct ScanProgress {
+    pb: ProgressBar,
+    is_show: bool,
+    filepath: String,
+    event_cnt: usize,
+    detect_cnt: usize,
+}
+
+impl ScanProgress {
+    fn new(pb: ProgressBar, is_show: bool) -> ScanProgress {
+        ScanProgress {
+            pb,
+            is_show,
+            filepath: String::default(),
+            event_cnt: 0,
+            detect_cnt: 0,
+        }
+    }
+
+    fn set_filepath(&mut self, filepath: &Path) {
+        self.filepath = filepath.to_str().unwrap_or_default().replace('\\', "/");
+        self.refresh();
+    }
+
+    fn add_counts(&mut self, event_cnt: usize, detect_cnt: usize) {
+        self.event_cnt += event_cnt;
+        self.detect_cnt += detect_cnt;
+        self.refresh();
+    }
+
+    fn refresh(&self) {
+        if !self.is_show {
+            return;
+        }
+        self.pb.set_message(format!(
+            "{:?}\r\nEvents processed: {} / Detections: {}",
+            self.filepath,
+            self.event_cnt.to_formatted_string(&Locale::en),
+            self.detect_cnt.to_formatted_string(&Locale::en)
+        ));
+    }
 }
 
 impl App {
     pub fn new(thread_number: Option<usize>) -> App {
         App {
             rt: utils::create_tokio_runtime(thread_number),
-            rule_keys: Nested::<String>::new(),
         }
     }
 
@@ -274,10 +346,14 @@ impl App {
         match &stored_static.config.action.as_ref().unwrap() {
             Action::CsvTimeline(_) | Action::JsonTimeline(_) => {
                 // カレントディレクトリ以外からの実行の際にrulesオプションの指定がないとエラーが発生することを防ぐための処理
-                if stored_static.output_option.as_ref().unwrap().rules == Path::new("./rules") {
+                if stored_static.output_option.as_ref().unwrap().rules == [Path::new("./rules")] {
                     stored_static.output_option.as_mut().unwrap().rules =
-                        utils::check_setting_path(&CURRENT_EXE_PATH.to_path_buf(), "rules", true)
-                            .unwrap();
+                        vec![utils::check_setting_path(
+                            &CURRENT_EXE_PATH.to_path_buf(),
+                            "rules",
+                            true,
+                        )
+                        .unwrap()];
                 }
                 // rule configのフォルダ、ファイルを確認してエラーがあった場合は終了とする
                 if let Err(e) = utils::check_rule_config(&stored_static.config_path) {
@@ -303,8 +379,13 @@ impl App {
                         return;
                     }
                 }
+                // --appendの場合は既存のファイルに追記するので、ファイルが存在していても終了しない
+                let is_append = matches!(
+                    &stored_static.config.action,
+                    Some(Action::JsonTimeline(option)) if option.append
+                );
                 if let Some(path) = &stored_static.output_path {
-                    if !(stored_static.output_option.as_ref().unwrap().clobber)
+                    if !(stored_static.output_option.as_ref().unwrap().clobber || is_append)
                         && utils::check_file_expect_not_exist(
                             path.as_path(),
                             format!(
@@ -473,9 +554,9 @@ impl App {
                     //ファイル出力の場合
                     pivot_key_unions.iter().for_each(|(key, pivot_keyword)| {
                         let mut f = BufWriter::new(
-                            fs::File::create(
-                                pivot_file.as_path().display().to_string() + "-" + key + ".txt",
-                            )
+                            utils::create_output_file(Path::new(
+                                &(pivot_file.as_path().display().to_string() + "-" + key + ".txt"),
+                            ))
                             .unwrap(),
                         );
                         f.write_all(
@@ -653,18 +734,12 @@ impl App {
                         .to_string()
                 };
 
-                let rules_path = if stored_static.output_option.as_ref().is_some() {
-                    stored_static
-                        .output_option
-                        .as_ref()
-                        .unwrap()
-                        .rules
-                        .as_os_str()
-                        .to_str()
-                        .unwrap()
-                } else {
-                    "./rules"
-                };
+                let rules_path = stored_static
+                    .output_option
+                    .as_ref()
+                    .and_then(|option| option.rules.first())
+                    .map(|rules| rules.as_os_str().to_str().unwrap())
+                    .unwrap_or("./rules");
 
                 if Path::new(&level_tuning_config_path).exists() {
                     if let Err(err) =
@@ -680,6 +755,27 @@ impl App {
                 }
                 return;
             }
+            Action::ValidateRules(option) => {
+                let rules_path = if option.rules == Path::new("./rules") {
+                    // カレントディレクトリ以外からの実行の際にrulesオプションの指定がないとエラーが発生することを防ぐための処理
+                    utils::check_setting_path(&CURRENT_EXE_PATH.to_path_buf(), "rules", true)
+                        .unwrap()
+                } else {
+                    option.rules.to_owned()
+                };
+                // statusに関わらずすべてのルールを検証する
+                stored_static.include_status.insert("*".into());
+                match ValidateRules::run(rules_path.to_str().unwrap(), stored_static) {
+                    Ok(result) => {
+                        ValidateRules::print_result(&result, stored_static.common_options.no_color)
+                    }
+                    Err(e) => {
+                        AlertMessage::alert(&format!("Failed to validate rules. {e}")).ok();
+                        DETECTION_ERROR_REPORT.lock().unwrap().rule_error_cnt += 1;
+                    }
+                }
+                return;
+            }
             Action::SetDefaultProfile(_) => {
                 if let Err(e) = set_default_profile(
                     check_setting_path(
@@ -760,6 +856,12 @@ impl App {
             _ => {}
         }
 
+        let detection_error_report = DETECTION_ERROR_REPORT.lock().unwrap().clone();
+        if detection_error_report.total() > 0 {
+            println!();
+            println!("{detection_error_report}");
+        }
+
         // Qオプションを付けた場合もしくはパースのエラーがない場合はerrorのstackが0となるのでエラーログファイル自体が生成されない。
         if ERROR_LOG_STACK.lock().unwrap().len() > 0 {
             AlertMessage::create_error_log(stored_static.quiet_errors_flag);
@@ -801,6 +903,21 @@ impl App {
                 time_filter,
                 stored_static.borrow_mut(),
             );
+        } else if let Some(channels) = &stored_static
+            .output_option
+            .as_ref()
+            .unwrap()
+            .input_args
+            .live_channel
+        {
+            if !cfg!(target_os = "windows") {
+                AlertMessage::alert("--live-channel can only be used on Windows.").ok();
+                println!();
+                return;
+            }
+            // 稼働中のイベントログはファイルに書き出さずに、指定されたChannelごとに直接読み込んで解析する
+            let channels = channels.iter().map(PathBuf::from).collect();
+            self.analysis_files(channels, time_filter, stored_static.borrow_mut());
         } else if let Some(directories) = &stored_static
             .output_option
             .as_ref()
@@ -816,6 +933,8 @@ impl App {
                     stored_static,
                 ));
             }
+            // -dで親フォルダとサブフォルダを両方指定した場合などに、同じファイルを2回スキャンしないようにする
+            let evtx_files = Self::dedup_evtxfiles(evtx_files);
             if evtx_files.is_empty() {
                 AlertMessage::alert("No .evtx files were found.").ok();
                 return;
@@ -846,13 +965,15 @@ impl App {
                     .ok();
                     return;
                 }
+                // gzipで圧縮されたファイルは.gzを除いた拡張子で判定する
+                let check_path_without_gz = utils::strip_gz_extension(check_path);
                 if !target_extensions.contains(
-                    check_path
+                    check_path_without_gz
                         .extension()
                         .unwrap_or_else(|| OsStr::new("."))
                         .to_str()
                         .unwrap(),
-                ) || check_path
+                ) || check_path_without_gz
                     .file_stem()
                     .unwrap_or_else(|| OsStr::new("."))
                     .to_str()
@@ -861,7 +982,7 @@ impl App {
                     .starts_with('.')
                 {
                     AlertMessage::alert(
-                        "-f (--filepath) only accepts .evtx files. Hidden files are ignored. If you want to input event logs in JSON format, please specify -J (--JSON-input).",
+                        "-f (--filepath) only accepts .evtx, .json and .jsonl files, and the same files compressed with gzip (.gz). Hidden files are ignored. If you want to input event logs in JSON format with other extensions, please specify -J (--JSON-input) and --target-file-ext.",
                     )
                     .ok();
                     return;
@@ -957,12 +1078,17 @@ impl App {
                     ret.extend(subdir_ret);
                     Option::Some(())
                 });
-            } else if target_extensions.contains(
-                path.extension()
+                continue;
+            }
+            // gzipで圧縮されたファイル(例: Security.evtx.gz)は.gzを除いた拡張子で判定する
+            let path_without_gz = utils::strip_gz_extension(&path);
+            if target_extensions.contains(
+                path_without_gz
+                    .extension()
                     .unwrap_or_else(|| OsStr::new(""))
                     .to_str()
                     .unwrap(),
-            ) && !path
+            ) && !path_without_gz
                 .file_stem()
                 .unwrap_or_else(|| OsStr::new("."))
                 .to_str()
@@ -976,6 +1102,131 @@ impl App {
         ret
     }
 
+    /// 重複したファイルを取り除く。パスの表記が異なっていても同じファイルであれば重複とみなす
+    fn dedup_evtxfiles(evtx_files: Vec<PathBuf>) -> Vec<PathBuf> {
+        let mut loaded_files = HashSet::new();
+        evtx_files
+            .into_iter()
+            .filter(|path| {
+                loaded_files.insert(fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+            })
+            .collect()
+    }
+
+    /// --dry-runが指定された場合に、スキャン対象のファイル、読み込まれたルールの数、有効なオプションを表示する
+    fn print_dry_run_summary(
+        evtx_files: &[PathBuf],
+        rule_files: &[RuleNode],
+        stored_static: &StoredStatic,
+    ) {
+        println!("Dry run: no events were scanned.");
+        println!();
+        println!(
+            "Input files: {}",
+            evtx_files.len().to_formatted_string(&Locale::en)
+        );
+        for evtx_file in evtx_files {
+            println!("  {}", evtx_file.display());
+        }
+        println!();
+        let level_counts = rule_files
+            .iter()
+            .filter_map(|rule| rule.yaml["level"].as_str())
+            .map(|level| level.to_lowercase())
+            .counts();
+        let level_summary = ["critical", "high", "medium", "low", "informational"]
+            .iter()
+            .filter_map(|level| {
+                level_counts
+                    .get(*level)
+                    .map(|cnt| format!("{level}: {}", cnt.to_formatted_string(&Locale::en)))
+            })
+            .join(", ");
+        println!(
+            "Detection rules: {} ({level_summary})",
+            rule_files.len().to_formatted_string(&Locale::en)
+        );
+        println!();
+        println!("Effective options:");
+        for (name, value) in Self::get_dry_run_options(stored_static) {
+            println!("  {name}: {value}");
+        }
+        println!();
+    }
+
+    /// --dry-runで表示する、ルールの読み込みやイベントのフィルタリングに影響するオプションの名前と値を返す。指定されていないオプションは含まない
+    fn get_dry_run_options(stored_static: &StoredStatic) -> Vec<(&'static str, String)> {
+        let mut ret = vec![];
+        let opt = match &stored_static.output_option {
+            Some(opt) => opt,
+            None => return ret,
+        };
+        let join_paths = |paths: &[PathBuf]| paths.iter().map(|p| p.display()).join(", ");
+        ret.push(("Rules", join_paths(&opt.rules)));
+        ret.push(("Minimum level", opt.min_level.to_lowercase()));
+        let list_options = [
+            (
+                "Exact level",
+                opt.exact_level.as_ref().map(|l| vec![l.to_lowercase()]),
+            ),
+            ("Include status", opt.include_status.clone()),
+            ("Exclude status", opt.exclude_status.clone()),
+            ("Include tags", opt.include_tag.clone()),
+            ("Exclude tags", opt.exclude_tag.clone()),
+            ("MITRE ATT&CK", opt.mitre.clone()),
+            ("Include categories", opt.include_category.clone()),
+            ("Exclude categories", opt.exclude_category.clone()),
+            ("Enabled rules", opt.enable_rule.clone()),
+            ("Excluded rules", opt.exclude_rule.clone()),
+            ("Include EIDs", opt.include_eid.clone()),
+            ("Exclude EIDs", opt.exclude_eid.clone()),
+            (
+                "Include computers",
+                opt.detect_common_options.include_computer.clone(),
+            ),
+            (
+                "Exclude computers",
+                opt.detect_common_options.exclude_computer.clone(),
+            ),
+            (
+                "Timeline start",
+                opt.start_timeline.clone().map(|t| vec![t]),
+            ),
+            ("Timeline end", opt.end_timeline.clone().map(|t| vec![t])),
+            (
+                "Timeline offset",
+                opt.input_args.timeline_offset.clone().map(|t| vec![t]),
+            ),
+            ("Profile", opt.profile.clone().map(|p| vec![p])),
+        ];
+        for (name, values) in list_options {
+            if let Some(values) = values {
+                ret.push((name, values.join(", ")));
+            }
+        }
+        let flag_options = [
+            ("Enable noisy rules", opt.enable_noisy_rules),
+            ("Enable deprecated rules", opt.enable_deprecated_rules),
+            ("Enable unsupported rules", opt.enable_unsupported_rules),
+            ("Proven rules only", opt.proven_rules),
+            ("EID filter", opt.eid_filter),
+            ("Scan all evtx files", stored_static.scan_all_evtx_files),
+            ("Enable all rules", stored_static.enable_all_rules),
+        ];
+        for (name, flag) in flag_options {
+            if flag {
+                ret.push((name, "true".to_string()));
+            }
+        }
+        if let Some(suppress) = &opt.suppress {
+            ret.push(("Suppression file", suppress.display().to_string()));
+        }
+        if let Some(output_path) = &stored_static.output_path {
+            ret.push(("Output", output_path.display().to_string()));
+        }
+        ret
+    }
+
     fn print_contributors(&self) {
         let contributors = Contributors::get("contributors.txt").unwrap();
         let content = std::str::from_utf8(contributors.data.as_ref()).unwrap_or_default();
@@ -1015,8 +1266,16 @@ impl App {
             true,
         )
         .ok();
+        let is_live_channel = stored_static
+            .output_option
+            .as_ref()
+            .unwrap()
+            .input_args
+            .live_channel
+            .is_some();
         let mut total_file_size = ByteSize::b(0);
-        for file_path in &evtx_files {
+        // --live-channelの場合は、ファイルではなくChannel名が渡されるのでファイルサイズを集計しない
+        for file_path in evtx_files.iter().filter(|_| !is_live_channel) {
             let file_size = match fs::metadata(file_path) {
                 Ok(res) => res.len(),
                 Err(err) => {
@@ -1049,12 +1308,15 @@ impl App {
                 .unwrap()
                 .rap_checkpoint("Rule Parse Processing Time");
             let mut rule_counter_wizard_map = HashMap::new();
-            yaml::count_rules(
-                &stored_static.output_option.as_ref().unwrap().rules,
-                &filter::exclude_ids(stored_static),
-                stored_static,
-                &mut rule_counter_wizard_map,
-            );
+            let exclude_ids = filter::exclude_ids(stored_static);
+            for rules_path in &stored_static.output_option.as_ref().unwrap().rules {
+                yaml::count_rules(
+                    rules_path,
+                    &exclude_ids,
+                    stored_static,
+                    &mut rule_counter_wizard_map,
+                );
+            }
             let level_map: HashMap<&str, u128> = HashMap::from([
                 ("INFORMATIONAL", 1),
                 ("LOW", 2),
@@ -1436,6 +1698,20 @@ impl App {
                 || stored_static.search_flag
                 || stored_static.computer_metrics_flag
                 || stored_static.metrics_flag;
+            // --debug-ruleで指定されたIDのルールが読み込まれなかった場合は、ルールが見つからない旨を表示して終了する
+            if let Some(debug_rule) = stored_static
+                .output_option
+                .as_ref()
+                .and_then(|opt| opt.debug_rule.as_ref())
+            {
+                if rule_files.is_empty() {
+                    AlertMessage::alert(&format!(
+                        "The rule specified with --debug-rule could not be loaded. (RuleID : {debug_rule})"
+                    ))
+                    .ok();
+                    return;
+                }
+            }
             if !unused_rules_option && rule_files.is_empty() {
                 AlertMessage::alert(
                         "No rules were loaded. Please download the latest rules with the update-rules command.\r\n",
@@ -1443,9 +1719,16 @@ impl App {
                     .ok();
                 return;
             }
+            // --debug-ruleが指定された場合は、全てのイベントに対する評価結果を表示するためにチャンネルフィルタを使用しない
+            let debug_rule_flag = stored_static
+                .output_option
+                .as_ref()
+                .is_some_and(|opt| opt.debug_rule.is_some());
             if !stored_static.json_input_flag
                 && !stored_static.scan_all_evtx_files
                 && !stored_static.enable_all_rules
+                && !debug_rule_flag
+                && !is_live_channel
             {
                 println!("Creating the channel filter. Please wait.");
                 println!();
@@ -1471,6 +1754,16 @@ impl App {
                     println!();
                 }
             }
+            // --dry-runが指定された場合は、スキャンせずに読み込むファイル、ルール数、有効なオプションを表示して終了する
+            if stored_static.output_option.as_ref().unwrap().dry_run {
+                Self::print_dry_run_summary(&evtx_files, &rule_files, stored_static);
+                return;
+            }
+            // --list-rulesが指定された場合は、スキャンせずにフィルタ後のルールの一覧を表示して終了する
+            if stored_static.output_option.as_ref().unwrap().list_rules {
+                detection::Detection::print_rule_list(&rule_files);
+                return;
+            }
             output_profile_name(&stored_static.output_option, true);
             println!();
             println!("Scanning in progress. Please wait.");
@@ -1485,20 +1778,25 @@ impl App {
         let progress_style = ProgressStyle::with_template(template)
             .unwrap()
             .progress_chars("=> ");
-        let pb = ProgressBar::with_draw_target(
-            Some(evtx_files.len() as u64),
-            ProgressDrawTarget::stdout_with_hz(10),
-        )
-        .with_tab_width(55);
+        // --no-progressが指定された場合や標準エラー出力が端末でない場合はプログレスバーを表示しない
+        let draw_target = if stored_static.no_progress_flag || !io::stderr().is_terminal() {
+            ProgressDrawTarget::hidden()
+        } else {
+            ProgressDrawTarget::stderr_with_hz(10)
+        };
+        let pb = ProgressBar::with_draw_target(Some(evtx_files.len() as u64), draw_target)
+            .with_tab_width(55);
         pb.set_style(progress_style);
         // I tried progress bar with low memory option(output log on detection) but it seemts that progress bar didn't go well with low memory option.
         // I disabled progress bar if low memory option is specified.
-        let is_show_progress = !stored_static.is_low_memory || stored_static.output_path.is_some();
+        let is_show_progress = (!stored_static.is_low_memory
+            || stored_static.output_path.is_some())
+            && !pb.is_hidden();
         if is_show_progress {
             pb.enable_steady_tick(Duration::from_millis(300));
         }
+        let mut scan_progress = ScanProgress::new(pb, is_show_progress);
 
-        self.rule_keys = self.get_all_keys(&rule_files);
         let mut detection = detection::Detection::new(rule_files);
         let mut tl = Timeline::new();
 
@@ -1507,23 +1805,29 @@ impl App {
         let mut afterfact_info = AfterfactInfo::default();
         let mut all_detect_infos = vec![];
         let mut afterfact_writer = afterfact::init_writer(stored_static);
+        let scan_start_time = Instant::now();
         for evtx_file in evtx_files {
-            if is_show_progress {
-                let pb_msg = format!(
-                    "{:?}",
-                    &evtx_file.to_str().unwrap_or_default().replace('\\', "/")
-                );
-                pb.set_message(pb_msg);
-            }
+            log::debug!("Scanning {}", evtx_file.display());
+            scan_progress.set_filepath(&evtx_file);
 
             let (detection_tmp, cnt_tmp, tl_tmp, recover_cnt_tmp, mut detect_infos) =
-                if evtx_file.extension().unwrap() == "json" {
+                if is_live_channel {
+                    self.analysis_live_channel(
+                        (evtx_file, time_filter, target_event_ids, stored_static),
+                        detection,
+                        tl.to_owned(),
+                        &mut afterfact_writer,
+                        &mut afterfact_info,
+                        &mut scan_progress,
+                    )
+                } else if stored_static.json_input_flag {
                     self.analysis_json_file(
                         (evtx_file, time_filter, target_event_ids, stored_static),
                         detection,
                         tl.to_owned(),
                         &mut afterfact_writer,
                         &mut afterfact_info,
+                        &mut scan_progress,
                     )
                 } else {
                     self.analysis_file(
@@ -1532,6 +1836,7 @@ impl App {
                         tl.to_owned(),
                         &mut afterfact_writer,
                         &mut afterfact_info,
+                        &mut scan_progress,
                     )
                 };
             detection = detection_tmp;
@@ -1540,10 +1845,18 @@ impl App {
             afterfact_info.recover_record_cnt += recover_cnt_tmp as u128;
             all_detect_infos.append(&mut detect_infos);
             if is_show_progress {
-                pb.inc(1);
+                scan_progress.pb.inc(1);
+            }
+            if stored_static.is_low_memory
+                && afterfact::is_limit_reached(stored_static, &afterfact_info)
+            {
+                afterfact_info.is_truncated_by_limit = true;
+                break;
             }
         }
-        pb.finish_with_message(
+        afterfact_info.suppressed_detect_cnt = detection.suppressed_cnt;
+        let scan_elapsed = scan_start_time.elapsed();
+        scan_progress.pb.finish_with_message(
             "Scanning finished. Please wait while the results are being saved.\r\n",
         );
         CHECKPOINT
@@ -1573,6 +1886,17 @@ impl App {
             || stored_static.computer_metrics_flag)
         {
             println!();
+            // 集計条件の検知処理でdetectionが消費されるので、検知しなかったルールの判定に使うルールの一覧を先に取得しておく
+            let show_unused_rules = stored_static
+                .output_option
+                .as_ref()
+                .unwrap()
+                .show_unused_rules;
+            let rule_list = if show_unused_rules {
+                detection.get_rule_list()
+            } else {
+                vec![]
+            };
             let mut log_records = detection.add_aggcondition_msges(&self.rt, stored_static);
             if stored_static.is_low_memory {
                 let empty_ids = HashSet::new();
@@ -1604,6 +1928,28 @@ impl App {
                     &mut afterfact_info,
                 );
             }
+
+            if show_unused_rules {
+                afterfact::output_unused_rules(
+                    &rule_list,
+                    stored_static,
+                    &afterfact_writer,
+                    &afterfact_info,
+                );
+            }
+
+            // ベンチマークなどで使えるように、スキャンしたイベント数と処理速度を出力する
+            if !stored_static.common_options.quiet {
+                output_and_data_stack_for_html(
+                    &afterfact::create_processing_stats_msg(
+                        afterfact_info.record_cnt,
+                        scan_elapsed,
+                        afterfact_info.total_detect_cnt,
+                    ),
+                    "General Overview {#general_overview}",
+                    &stored_static.html_report_flag,
+                );
+            }
         }
         CHECKPOINT
             .lock()
@@ -1626,10 +1972,11 @@ impl App {
             &TargetIds,
             &StoredStatic,
         ),
-        mut detection: detection::Detection,
-        mut tl: Timeline,
+        detection: detection::Detection,
+        tl: Timeline,
         afterfact_writer: &mut AfterfactWriter,
         afterfact_info: &mut AfterfactInfo,
+        scan_progress: &mut ScanProgress,
     ) -> (
         detection::Detection,
         usize,
@@ -1639,203 +1986,123 @@ impl App {
     ) {
         let path = evtx_filepath.display();
         let parser = self.evtx_to_jsons(&evtx_filepath, stored_static.enable_recover_records);
-        let mut record_cnt = 0;
-        let mut recover_records_cnt = 0;
-        let mut detect_infos: Vec<DetectInfo> = vec![];
         if parser.is_none() {
-            return (detection, record_cnt, tl, 0, detect_infos);
+            return (detection, 0, tl, 0, vec![]);
         }
 
         let mut parser = parser.unwrap();
-        let mut records = parser.records_json_value();
-
-        let verbose_flag = stored_static.verbose_flag;
-        let quiet_errors_flag = stored_static.quiet_errors_flag;
-        loop {
-            let mut records_per_detect = vec![];
-            while records_per_detect.len() < MAX_DETECT_RECORDS {
-                // パースに失敗している場合、エラーメッセージを出力
-                let next_rec = records.next();
-                if next_rec.is_none() {
-                    break;
-                }
-                record_cnt += 1;
-                let record_result = next_rec.unwrap();
-
-                if record_result.is_ok()
-                    && record_result.as_ref().unwrap().allocation == RecordAllocation::EmptyPage
-                {
-                    recover_records_cnt += 1;
-                }
-
-                if record_result.is_err() {
-                    let evtx_filepath = &path;
-                    let errmsg = format!(
-                        "Failed to parse event file.\nEventFile: {}\nError: {}\n",
-                        evtx_filepath,
-                        record_result.unwrap_err()
-                    );
-                    if verbose_flag {
-                        AlertMessage::alert(&errmsg).ok();
-                    }
-                    if !quiet_errors_flag {
-                        ERROR_LOG_STACK
-                            .lock()
-                            .unwrap()
-                            .push(format!("[ERROR] {errmsg}"));
-                    }
-                    continue;
-                }
-
-                let data = &record_result.as_ref().unwrap().data;
-                if stored_static.computer_metrics_flag {
-                    countup_event_by_computer(data, &stored_static.eventkey_alias, &mut tl);
-                    // computer-metricsコマンドでは検知は行わないためカウントのみ行い次のレコードを確認する
-                    continue;
-                }
-
-                // Searchならすべてのフィルタを無視
-                if !stored_static.search_flag {
-                    // Computer名がinclude_computerで指定されたものに合致しないまたはexclude_computerで指定されたものに合致した場合はフィルタリングする。
-                    if utils::is_filtered_by_computer_name(
-                        utils::get_event_value(
-                            "Event.System.Computer",
-                            data,
-                            &stored_static.eventkey_alias,
-                        ),
-                        (
-                            &stored_static.include_computer,
-                            &stored_static.exclude_computer,
-                        ),
-                    ) {
-                        continue;
-                    }
-
-                    // EventIDがinclude_eidで指定されたものに合致しないまたはexclude_eidで指定されたものに合致した場合、target_eventids.txtで指定されたEventIDではない場合はフィルタリングする。
-                    if self.is_filtered_by_eid(
-                        data,
-                        &stored_static.eventkey_alias,
-                        (&stored_static.include_eid, &stored_static.exclude_eid),
-                        stored_static.output_option.as_ref().unwrap().eid_filter,
-                        target_event_ids,
-                    ) {
-                        continue;
-                    }
-
-                    // channelがnullである場合はフィルタリングする。
-                    if !self._is_valid_channel(
-                        data,
-                        &stored_static.eventkey_alias,
-                        "Event.System.Channel",
-                    ) {
-                        continue;
-                    }
-                }
-                // EventID側の条件との条件の混同を防ぐため時間でのフィルタリングの条件分岐を分離した
-                let timestamp = record_result.as_ref().unwrap().timestamp;
-                if !time_filter.is_target(&Some(timestamp)) {
-                    continue;
-                }
-
-                let recover_record_flag = record_result.is_ok()
-                    && record_result.as_ref().unwrap().allocation == RecordAllocation::EmptyPage;
-                records_per_detect.push((data.to_owned(), recover_record_flag));
-            }
-            if records_per_detect.is_empty() {
-                break;
-            }
-
-            let records_per_detect = self.rt.block_on(App::create_rec_infos(
-                records_per_detect,
-                &path,
-                self.rule_keys.to_owned(),
-                stored_static.no_pwsh_field_extraction,
-            ));
+        // パースに失敗したレコードはエラーとして記録して読み飛ばし、残りのレコードの解析を継続する
+        let records = SkipCorruptRecords::new(
+            parser.records_json_value().map(|record| {
+                record.map(|record| ScanRecord {
+                    recovered: record.allocation == RecordAllocation::EmptyPage,
+                    timestamp: Some(record.timestamp),
+                    event: record.data,
+                })
+            }),
+            &path.to_string(),
+            stored_static.verbose_flag,
+            stored_static.quiet_errors_flag,
+        );
 
-            // timeline機能の実行
-            tl.start(&records_per_detect, stored_static);
-            if !(stored_static.metrics_flag
-                || stored_static.logon_summary_flag
-                || stored_static.search_flag)
-            {
-                // detect event record by rule file
-                let (detection_tmp, mut log_records) =
-                    detection.start(&self.rt, records_per_detect);
-                if stored_static.is_low_memory {
-                    let empty_ids = HashSet::new();
-                    afterfact::emit_csv(
-                        &log_records,
-                        &empty_ids,
-                        stored_static,
-                        afterfact_writer,
-                        afterfact_info,
-                    );
-                } else {
-                    detect_infos.append(&mut log_records);
-                }
-                detection = detection_tmp;
-            }
-        }
-        tl.total_record_cnt += record_cnt;
-        (detection, record_cnt, tl, recover_records_cnt, detect_infos)
+        let record_filter = RecordFilter {
+            time_filter,
+            target_event_ids,
+            channel_key: "Event.System.Channel",
+        };
+        // フィルタリングとルールの検知はJSON形式のイベントログと共通のscan_recordsで行う
+        self.analysis_records(
+            records,
+            |records| records.corrupt_cnt,
+            (&path.to_string(), &record_filter, stored_static),
+            detection,
+            tl,
+            (afterfact_writer, afterfact_info, scan_progress),
+        )
     }
 
-    // 時刻およびチャンネルによるフィルタリングを行い、フィルタリングされた場合はtrueを返す。
-    fn is_filtered_record(
+    // 稼働中のWindowsイベントログから、指定したChannelのイベントを読み込んで解析する。
+    fn analysis_live_channel(
         &self,
-        (path, time_filter, target_event_ids, stored_static): (
-            &str,
+        (channel, time_filter, target_event_ids, stored_static): (
+            PathBuf,
             &TargetEventTime,
             &TargetIds,
             &StoredStatic,
         ),
-        (is_splunk_json, is_splunk_api_json): (bool, bool),
-        data: &Value,
-    ) -> bool {
-        // Computer名がinclude_computerで指定されたものに合致しないまたはexclude_computerで指定されたものに合致した場合はフィルタリングする。
-        if utils::is_filtered_by_computer_name(
-            utils::get_event_value("Event.System.Computer", data, &stored_static.eventkey_alias),
-            (
-                &stored_static.include_computer,
-                &stored_static.exclude_computer,
-            ),
-        ) {
-            return true;
-        }
+        detection: detection::Detection,
+        tl: Timeline,
+        afterfact_writer: &mut AfterfactWriter,
+        afterfact_info: &mut AfterfactInfo,
+        scan_progress: &mut ScanProgress,
+    ) -> (
+        detection::Detection,
+        usize,
+        Timeline,
+        usize,
+        Vec<DetectInfo>,
+    ) {
+        let channel = channel.display().to_string();
+        let events = match live_channel::query_channel(&channel) {
+            Ok(events) => events,
+            Err(err) => {
+                AlertMessage::alert(&format!(
+                    "Failed to read the live event log. Channel: {channel} : {err}"
+                ))
+                .ok();
+                return (detection, 0, tl, 0, vec![]);
+            }
+        };
+        // 読み込みやJSONへの変換に失敗したイベントはエラーとして記録して読み飛ばし、残りのイベントの解析を継続する
+        let records = SkipCorruptRecords::new(
+            events.map(|xml| {
+                xml.and_then(|xml| live_channel::xml_to_event_value(&xml))
+                    .map(|event| ScanRecord {
+                        timestamp: live_channel::get_event_timestamp(&event),
+                        event,
+                        recovered: false,
+                    })
+            }),
+            &channel,
+            stored_static.verbose_flag,
+            stored_static.quiet_errors_flag,
+        );
 
-        // EventIDがinclude_eidで指定されたものに合致しないまたはexclude_eidで指定されたものに合致した場合、EventID Filter optionが指定されていないかつtarget_eventids.txtで指定されたEventIDではない場合はフィルタリングする。
-        if self.is_filtered_by_eid(
-            data,
-            &stored_static.eventkey_alias,
-            (&stored_static.include_eid, &stored_static.exclude_eid),
-            stored_static.output_option.as_ref().unwrap().eid_filter,
+        let record_filter = RecordFilter {
+            time_filter,
             target_event_ids,
-        ) {
-            return true;
-        }
-
-        // channelがnullである場合はフィルタリングする。
-        if !self._is_valid_channel(
-            data,
-            &stored_static.eventkey_alias,
-            "Event.EventData.Channel",
-        ) {
-            return true;
-        }
-        let target_timestamp = if data["Event"]["EventData"]["@timestamp"].is_null() {
-            &data["Event"]["EventData"]["TimeGenerated"]
-        } else {
-            &data["Event"]["EventData"]["@timestamp"]
+            channel_key: "Event.System.Channel",
         };
-        let time_fmt = if is_splunk_json {
+        // EVTXファイルと同じscan_recordsで、フィルタリングとルールの検知を行う
+        self.analysis_records(
+            records,
+            |records| records.corrupt_cnt,
+            (&channel, &record_filter, stored_static),
+            detection,
+            tl,
+            (afterfact_writer, afterfact_info, scan_progress),
+        )
+    }
+
+    // JSON形式のイベントログのレコードから、時刻によるフィルタリングに使うイベントの発生時刻を取得する。
+    fn get_json_record_timestamp(
+        path: &str,
+        (is_splunk_json, is_splunk_api_json): (bool, bool),
+        data: &Value,
+    ) -> Option<DateTime<Utc>> {
+        let target_timestamp = if data["Event"]["EventData"]["@timestamp"].is_null() {
+            &data["Event"]["EventData"]["TimeGenerated"]
+        } else {
+            &data["Event"]["EventData"]["@timestamp"]
+        };
+        let time_fmt = if is_splunk_json {
             "%Y-%m-%dT%H:%M:%S%.3f%:z"
         } else if is_splunk_api_json {
             "%Y-%m-%dT%H:%M:%S%.9fZ"
         } else {
             "%Y-%m-%dT%H:%M:%S%.3fZ"
         };
-        // EventID側の条件との条件の混同を防ぐため時間でのフィルタリングの条件分岐を分離した
-        let timestamp = match NaiveDateTime::parse_from_str(
+        match NaiveDateTime::parse_from_str(
             &target_timestamp
                 .to_string()
                 .replace("\\\"", "")
@@ -1859,11 +2126,7 @@ impl App {
                 .ok();
                 None
             }
-        };
-        if !time_filter.is_target(&timestamp) {
-            return true;
         }
-        false
     }
 
     // JSON形式のイベントログファイルを1ファイル分解析する。
@@ -1875,10 +2138,11 @@ impl App {
             &TargetIds,
             &StoredStatic,
         ),
-        mut detection: detection::Detection,
-        mut tl: Timeline,
+        detection: detection::Detection,
+        tl: Timeline,
         afterfact_writer: &mut AfterfactWriter,
         afterfact_info: &mut AfterfactInfo,
+        scan_progress: &mut ScanProgress,
     ) -> (
         detection::Detection,
         usize,
@@ -1886,10 +2150,7 @@ impl App {
         usize,
         Vec<DetectInfo>,
     ) {
-        let path = filepath.display();
-        let mut record_cnt = 0;
-        let recover_records_cnt = 0;
-        let mut is_splunk_api_json;
+        let path = filepath.display().to_string();
         let filename = filepath.to_str().unwrap_or_default();
         let filepath = if filename.starts_with("./") {
             check_setting_path(&CURRENT_EXE_PATH.to_path_buf(), filename, true)
@@ -1901,8 +2162,7 @@ impl App {
             filename.to_string()
         };
         let jsonl_value_iter = utils::read_jsonl_to_value(&filepath);
-        let mut detect_infos: Vec<DetectInfo> = vec![];
-        let mut records = match jsonl_value_iter {
+        let values = match jsonl_value_iter {
             // JSONL形式の場合
             Ok(values) => values,
             // JSONL形式以外(JSON(Array or jq)形式)の場合
@@ -1911,326 +2171,302 @@ impl App {
                 match json_value_iter {
                     Ok(values) => values,
                     Err(e) => {
-                        AlertMessage::alert(&e).ok();
-                        return (detection, record_cnt, tl, recover_records_cnt, detect_infos);
+                        let err = DetectionError::Json {
+                            filepath: filepath.to_string(),
+                            message: e,
+                        };
+                        DETECTION_ERROR_REPORT.lock().unwrap().add(&err);
+                        AlertMessage::alert(&err.to_string()).ok();
+                        return (detection, 0, tl, 0, vec![]);
                     }
                 }
             }
         };
 
-        loop {
-            let mut records_per_detect = vec![];
-            while records_per_detect.len() < MAX_DETECT_RECORDS {
-                // パースに失敗している場合、エラーメッセージを出力
-                let next_rec = records.next();
-                if next_rec.is_none() {
-                    break;
+        // splunk api jsonの場合は1レコードに複数のイベントが含まれるため、イベントごとのScanRecordに展開する
+        let records = values.flat_map(|data| App::json_to_scan_records(&path, &filepath, data));
+
+        let record_filter = RecordFilter {
+            time_filter,
+            target_event_ids,
+            channel_key: "Event.EventData.Channel",
+        };
+        // EVTXファイルと同じscan_recordsで、フィルタリングとルールの検知を行う
+        self.analysis_records(
+            records,
+            |_| 0,
+            (&path, &record_filter, stored_static),
+            detection,
+            tl,
+            (afterfact_writer, afterfact_info, scan_progress),
+        )
+    }
+
+    // JSON形式のイベントログの1レコードを、Event -> Systemなどの形式に変換してScanRecordを作成する。
+    fn json_to_scan_records(path: &str, filepath: &str, mut data: Value) -> Vec<ScanRecord> {
+        let is_splunk_json = data["Event"]["EventData"]["result"].is_object();
+        let is_splunk_api_json = !data["Event"]["EventData"]["rows"].is_null();
+        // ChannelなどのデータはEvent -> Systemに存在する必要があるが、他処理のことも考え、Event -> EventDataのデータをそのまま投入する形にした。cloneを利用しているのはCopy trait実装がserde_json::Valueにないため
+        if is_splunk_api_json {
+            let tmp_data = data["Event"]["EventData"]["rows"].clone();
+            let tmp_data_value = data["Event"]["EventData"]["fields"].clone();
+            let empty = vec![];
+            let field_value = tmp_data.as_array().unwrap_or(&empty);
+            let fields_key = tmp_data_value.as_array().unwrap_or(&empty);
+            let mut records = vec![];
+            for splunk_api_rec in field_value {
+                let mut splunk_api_record = Value::Null;
+                for (v_idx, row_name) in fields_key.iter().enumerate() {
+                    if let Some(row) = row_name.as_str() {
+                        // splunk api jsonの場合はrowsとfieldsのデータをEventDataに投入する。rawデータがはレコード情報がそのまま入っているのでその情報を投入したうえでsplunk api json関連のレコード類を投入する
+                        splunk_api_record["Event"]["System"][row.to_string()] =
+                            splunk_api_rec[v_idx].clone();
+                        splunk_api_record["Event"]["EventData"][row.to_string()] =
+                            splunk_api_rec[v_idx].clone();
+                    }
                 }
-                let mut data = next_rec.unwrap();
-                let is_splunk_json = data["Event"]["EventData"]["result"].is_object();
-                is_splunk_api_json = !data["Event"]["EventData"]["rows"].is_null();
-                // ChannelなどのデータはEvent -> Systemに存在する必要があるが、他処理のことも考え、Event -> EventDataのデータをそのまま投入する形にした。cloneを利用しているのはCopy trait実装がserde_json::Valueにないため
-                if is_splunk_api_json {
-                    let tmp_data = data["Event"]["EventData"]["rows"].clone();
-                    let tmp_data_value = data["Event"]["EventData"]["fields"].clone();
-                    let empty = vec![];
-                    let field_value = tmp_data.as_array().unwrap_or(&empty);
-                    let fields_key = tmp_data_value.as_array().unwrap_or(&empty);
-                    record_cnt += field_value.len();
-                    for splunk_api_rec in field_value {
-                        let mut splunk_api_record = Value::Null;
-                        for (v_idx, row_name) in fields_key.iter().enumerate() {
-                            if let Some(row) = row_name.as_str() {
-                                // splunk api jsonの場合はrowsとfieldsのデータをEventDataに投入する。rawデータがはレコード情報がそのまま入っているのでその情報を投入したうえでsplunk api json関連のレコード類を投入する
-                                splunk_api_record["Event"]["System"][row.to_string()] =
-                                    splunk_api_rec[v_idx].clone();
-                                splunk_api_record["Event"]["EventData"][row.to_string()] =
-                                    splunk_api_rec[v_idx].clone();
-                            }
-                        }
-                        let timestamp = match NaiveDateTime::parse_from_str(
+                let timestamp = match NaiveDateTime::parse_from_str(
+                    &splunk_api_record["Event"]["System"]["SystemTime"]
+                        .to_string()
+                        .replace("\\\"", "")
+                        .replace(['"', '\''], ""),
+                    "%Y-%m-%dT%H:%M:%S%.9fZ",
+                ) {
+                    Ok(without_timezone_datetime) => {
+                        DateTime::<Utc>::from_naive_utc_and_offset(without_timezone_datetime, Utc)
+                            .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+                    }
+                    Err(e) => {
+                        AlertMessage::warn(&format!(
+                            "timestamp parse error. filepath:{},{} {}",
+                            path,
                             &splunk_api_record["Event"]["System"]["SystemTime"]
                                 .to_string()
                                 .replace("\\\"", "")
-                                .replace(['"', '\''], ""),
-                            "%Y-%m-%dT%H:%M:%S%.9fZ",
-                        ) {
-                            Ok(without_timezone_datetime) => {
-                                DateTime::<Utc>::from_naive_utc_and_offset(
-                                    without_timezone_datetime,
-                                    Utc,
-                                )
-                                .format("%Y-%m-%dT%H:%M:%S%.3fZ")
-                            }
-                            Err(e) => {
-                                AlertMessage::warn(&format!(
-                                    "timestamp parse error. filepath:{},{} {}",
-                                    path,
-                                    &splunk_api_record["Event"]["System"]["SystemTime"]
-                                        .to_string()
-                                        .replace("\\\"", "")
-                                        .replace('"', ""),
-                                    e
-                                ))
-                                .ok();
-                                DateTime::<Utc>::default().format("%Y-%m-%dT%H:%M:%S%.3fZ")
-                            }
-                        };
-
-                        splunk_api_record["Event"]["UserData"] =
-                            splunk_api_record["Event"]["EventData"].clone();
-                        splunk_api_record["Event"]["EventData"]["@timestamp"] =
-                            Value::String(timestamp.to_string());
-                        splunk_api_record["Event"]["System"]["@timestamp"] =
-                            splunk_api_record["Event"]["EventData"]["@timestamp"].clone();
-                        splunk_api_record["Event"]["System"]
-                            .as_object_mut()
-                            .unwrap()
-                            .insert(
-                                "Provider_attributes".to_string(),
-                                Value::Object(Map::from_iter(vec![(
-                                    "Name".to_string(),
-                                    Value::from(1),
-                                )])),
-                            );
-                        splunk_api_record["Event"]["System"]["Provider_attributes"]["Name"] =
-                            splunk_api_record["Event"]["EventData"]["Name"].clone();
-                        if stored_static.computer_metrics_flag {
-                            // computer-metricsコマンドでは検知は行わないためカウントのみ行い次のレコードを確認する
-                            countup_event_by_computer(
-                                &splunk_api_record,
-                                &stored_static.eventkey_alias,
-                                &mut tl,
-                            );
-                        }
-                        if !self.is_filtered_record(
-                            (
-                                filepath.as_str(),
-                                time_filter,
-                                target_event_ids,
-                                stored_static,
-                            ),
-                            (is_splunk_json, is_splunk_api_json),
-                            &splunk_api_record,
-                        ) {
-                            records_per_detect.push((splunk_api_record.to_owned(), false));
-                        }
+                                .replace('"', ""),
+                            e
+                        ))
+                        .ok();
+                        DateTime::<Utc>::default().format("%Y-%m-%dT%H:%M:%S%.3fZ")
                     }
-                    continue;
-                } else if is_splunk_json {
-                    data["Event"]["System"] = data["Event"]["EventData"]["result"].clone();
-                    data["Event"]["EventData"] = data["Event"]["EventData"]["result"].clone();
-                }
-                record_cnt += 1;
-                if data["Event"]["EventData"].is_object() {
-                    data["Event"]["System"] = data["Event"]["EventData"].clone();
-                } else if data["Event"]["EventData"].is_array() {
-                    data["Event"]["System"] =
-                        data["Event"]["EventData"].as_array().unwrap()[0].clone();
-                }
-                data["Event"]["System"]
+                };
+
+                splunk_api_record["Event"]["UserData"] =
+                    splunk_api_record["Event"]["EventData"].clone();
+                splunk_api_record["Event"]["EventData"]["@timestamp"] =
+                    Value::String(timestamp.to_string());
+                splunk_api_record["Event"]["System"]["@timestamp"] =
+                    splunk_api_record["Event"]["EventData"]["@timestamp"].clone();
+                splunk_api_record["Event"]["System"]
                     .as_object_mut()
                     .unwrap()
-                    .insert("EventRecordID".to_string(), Value::from(1));
-                data["Event"]["System"]["EventRecordID"] =
-                    data["Event"]["EventData"]["RecordNumber"].clone();
-                data["Event"]["System"].as_object_mut().unwrap().insert(
-                    "Provider_attributes".to_string(),
-                    Value::Object(Map::from_iter(vec![("Name".to_string(), Value::from(1))])),
-                );
-                data["Event"]["UserData"] = data["Event"]["EventData"].clone();
-
-                if is_splunk_json {
-                    data["Event"]["EventData"]["@timestamp"] =
-                        data["Event"]["EventData"]["_time"].clone();
-                    data["Event"]["System"]["@timestamp"] =
-                        data["Event"]["EventData"]["_time"].clone();
-                    data["Event"]["System"]["Provider_attributes"]["Name"] =
-                        data["Event"]["EventData"]["Name"].clone();
-                } else {
-                    data["Event"]["System"]["Provider_attributes"]["Name"] =
-                        data["Event"]["EventData"]["SourceName"].clone();
-                    // Computer名に対応する内容はHostnameであることがわかったためデータをクローンして投入
-                    data["Event"]["System"]["Computer"] =
-                        data["Event"]["EventData"]["Hostname"].clone();
+                    .insert(
+                        "Provider_attributes".to_string(),
+                        Value::Object(Map::from_iter(vec![("Name".to_string(), Value::from(1))])),
+                    );
+                splunk_api_record["Event"]["System"]["Provider_attributes"]["Name"] =
+                    splunk_api_record["Event"]["EventData"]["Name"].clone();
+                records.push(ScanRecord {
+                    timestamp: App::get_json_record_timestamp(
+                        filepath,
+                        (is_splunk_json, is_splunk_api_json),
+                        &splunk_api_record,
+                    ),
+                    event: splunk_api_record,
+                    recovered: false,
+                });
+            }
+            return records;
+        } else if is_splunk_json {
+            data["Event"]["System"] = data["Event"]["EventData"]["result"].clone();
+            data["Event"]["EventData"] = data["Event"]["EventData"]["result"].clone();
+        }
+        if data["Event"]["EventData"].is_object() {
+            data["Event"]["System"] = data["Event"]["EventData"].clone();
+        } else if data["Event"]["EventData"].is_array() {
+            data["Event"]["System"] = data["Event"]["EventData"].as_array().unwrap()[0].clone();
+        }
+        data["Event"]["System"]
+            .as_object_mut()
+            .unwrap()
+            .insert("EventRecordID".to_string(), Value::from(1));
+        data["Event"]["System"]["EventRecordID"] =
+            data["Event"]["EventData"]["RecordNumber"].clone();
+        data["Event"]["System"].as_object_mut().unwrap().insert(
+            "Provider_attributes".to_string(),
+            Value::Object(Map::from_iter(vec![("Name".to_string(), Value::from(1))])),
+        );
+        data["Event"]["UserData"] = data["Event"]["EventData"].clone();
+
+        if is_splunk_json {
+            data["Event"]["EventData"]["@timestamp"] = data["Event"]["EventData"]["_time"].clone();
+            data["Event"]["System"]["@timestamp"] = data["Event"]["EventData"]["_time"].clone();
+            data["Event"]["System"]["Provider_attributes"]["Name"] =
+                data["Event"]["EventData"]["Name"].clone();
+        } else {
+            data["Event"]["System"]["Provider_attributes"]["Name"] =
+                data["Event"]["EventData"]["SourceName"].clone();
+            // Computer名に対応する内容はHostnameであることがわかったためデータをクローンして投入
+            data["Event"]["System"]["Computer"] = data["Event"]["EventData"]["Hostname"].clone();
+        }
+
+        vec![ScanRecord {
+            timestamp: App::get_json_record_timestamp(
+                filepath,
+                (is_splunk_json, is_splunk_api_json),
+                &data,
+            ),
+            event: data,
+            recovered: false,
+        }]
+    }
+
+    /// レコードのイテレータから一定件数ずつレコードを取り出し、scan_recordsでの検知と検知結果の出力を繰り返す。
+    /// EVTXファイル、JSON形式のイベントログ、稼働中のイベントログのいずれもこの関数で解析する。
+    /// corrupt_cntにはイテレータがパースに失敗して読み飛ばしたレコード数を返す関数を渡し、読み込んだレコード数の集計に使う。
+    fn analysis_records<I>(
+        &self,
+        mut records: I,
+        corrupt_cnt: impl Fn(&I) -> usize,
+        (filepath, record_filter, stored_static): (&str, &RecordFilter, &StoredStatic),
+        mut detection: detection::Detection,
+        mut tl: Timeline,
+        (afterfact_writer, afterfact_info, scan_progress): (
+            &mut AfterfactWriter,
+            &mut AfterfactInfo,
+            &mut ScanProgress,
+        ),
+    ) -> (
+        detection::Detection,
+        usize,
+        Timeline,
+        usize,
+        Vec<DetectInfo>,
+    )
+    where
+        I: Iterator<Item = ScanRecord>,
+    {
+        let max_detect_records = get_max_detect_records(stored_static);
+        let mut scan_record_cnt = 0;
+        let mut recover_records_cnt = 0;
+        let mut prev_record_cnt = 0;
+        let mut detect_infos: Vec<DetectInfo> = vec![];
+        loop {
+            let mut records_per_detect = vec![];
+            while records_per_detect.len() < max_detect_records {
+                let next_rec = records.next();
+                if next_rec.is_none() {
+                    break;
+                }
+                let record = next_rec.unwrap();
+                scan_record_cnt += 1;
+                if record.recovered {
+                    recover_records_cnt += 1;
                 }
 
                 if stored_static.computer_metrics_flag {
-                    countup_event_by_computer(&data, &stored_static.eventkey_alias, &mut tl);
+                    countup_event_by_computer(
+                        &record.event,
+                        &stored_static.eventkey_alias,
+                        &mut tl,
+                    );
                     // computer-metricsコマンドでは検知は行わないためカウントのみ行い次のレコードを確認する
                     continue;
                 }
-                if !self.is_filtered_record(
-                    (
-                        filepath.as_str(),
-                        time_filter,
-                        target_event_ids,
-                        stored_static,
-                    ),
-                    (is_splunk_json, is_splunk_api_json),
-                    &data,
-                ) {
-                    records_per_detect.push((data.to_owned(), false));
-                }
+                records_per_detect.push(record);
             }
+            let record_cnt = scan_record_cnt + corrupt_cnt(&records);
+            scan_progress.add_counts(record_cnt - prev_record_cnt, 0);
+            prev_record_cnt = record_cnt;
             if records_per_detect.is_empty() {
                 break;
             }
 
-            let records_per_detect = self.rt.block_on(App::create_rec_infos(
+            let (detection_tmp, scan_result) = detection.scan_records(
+                &self.rt,
                 records_per_detect,
-                &path,
-                self.rule_keys.to_owned(),
-                stored_static.no_pwsh_field_extraction,
-            ));
-
-            // timeline機能の実行
-            tl.start(&records_per_detect, stored_static);
-
-            // 以下のコマンドの際にはルールにかけない
-            if !(stored_static.metrics_flag
-                || stored_static.logon_summary_flag
-                || stored_static.search_flag)
-            {
-                // ruleファイルの検知
-                let (detection_tmp, mut log_records) =
-                    detection.start(&self.rt, records_per_detect);
-                if stored_static.is_low_memory {
-                    let empty_ids = HashSet::new();
-                    afterfact::emit_csv(
-                        &log_records,
-                        &empty_ids,
-                        stored_static,
-                        afterfact_writer,
-                        afterfact_info,
-                    );
-                } else {
-                    detect_infos.append(&mut log_records);
-                }
-                detection = detection_tmp;
+                filepath,
+                record_filter,
+                stored_static,
+            );
+            detection = detection_tmp;
+            if self.emit_scan_result(
+                scan_result,
+                &mut tl,
+                stored_static,
+                (afterfact_writer, afterfact_info, scan_progress),
+                &mut detect_infos,
+            ) {
+                break;
             }
         }
+        let record_cnt = scan_record_cnt + corrupt_cnt(&records);
         tl.total_record_cnt += record_cnt;
         (detection, record_cnt, tl, recover_records_cnt, detect_infos)
     }
 
-    async fn create_rec_infos(
-        records_per_detect: Vec<(Value, bool)>,
-        path: &dyn Display,
-        rule_keys: Nested<String>,
-        no_pwsh_field_extraction: bool,
-    ) -> Vec<EvtxRecordInfo> {
-        let no_pwsh_field_extraction = Arc::new(no_pwsh_field_extraction);
-        let path = Arc::new(path.to_string());
-        let rule_keys = Arc::new(rule_keys);
-        let threads: Vec<JoinHandle<EvtxRecordInfo>> = {
-            let this = records_per_detect.into_iter().map(
-                |(rec, recovered_record_flag)| -> JoinHandle<EvtxRecordInfo> {
-                    let arc_rule_keys = Arc::clone(&rule_keys);
-                    let arc_path = Arc::clone(&path);
-                    let arc_no_pwsh_field_extraction = Arc::clone(&no_pwsh_field_extraction);
-                    spawn(async move {
-                        utils::create_rec_info(
-                            rec,
-                            arc_path.to_string(),
-                            &arc_rule_keys,
-                            &recovered_record_flag,
-                            &arc_no_pwsh_field_extraction,
-                        )
-                    })
-                },
-            );
-            FromIterator::from_iter(this)
-        };
-
-        let mut ret = vec![];
-        for thread in threads.into_iter() {
-            ret.push(thread.await.unwrap());
-        }
-
-        ret
-    }
-
-    fn get_all_keys(&self, rules: &[RuleNode]) -> Nested<String> {
-        let mut key_set = HashSet::new();
-        for rule in rules {
-            let keys = get_detection_keys(rule);
-            key_set.extend(keys.iter().map(|x| x.to_string()));
-        }
-
-        key_set.into_iter().collect::<Nested<String>>()
-    }
-
-    /// target_eventids.txtの設定を元にフィルタする。 trueであれば検知確認対象のEventIDであることを意味する。
-    fn _is_target_event_id(
+    /// scan_recordsの結果をtimeline機能に渡し、検知結果を出力または保持する。
+    /// 低メモリモードで--limitの数の検知結果を出力した場合は、残りのイベントの解析を省略するためにtrueを返す。
+    fn emit_scan_result(
         &self,
-        data: &Value,
-        target_event_ids: &TargetIds,
-        eventkey_alias: &EventKeyAliasConfig,
+        scan_result: ScanResult,
+        tl: &mut Timeline,
+        stored_static: &StoredStatic,
+        (afterfact_writer, afterfact_info, scan_progress): (
+            &mut AfterfactWriter,
+            &mut AfterfactInfo,
+            &mut ScanProgress,
+        ),
+        detect_infos: &mut Vec<DetectInfo>,
     ) -> bool {
-        let eventid = utils::get_event_value(&utils::get_event_id_key(), data, eventkey_alias);
-        if eventid.is_none() {
-            return true;
-        }
+        // timeline機能の実行
+        tl.start(&scan_result.records, stored_static);
 
-        match eventid.unwrap() {
-            Value::String(s) => target_event_ids.is_target(&s.replace('\"', ""), true),
-            Value::Number(n) => target_event_ids.is_target(&n.to_string().replace('\"', ""), true),
-            _ => true, // レコードからEventIdが取得できない場合は、特にフィルタしない
-        }
-    }
-
-    /// レコードのチャンネルの値が正しい(Stringの形でありnullでないもの)ことを判定する関数
-    fn _is_valid_channel(
-        &self,
-        data: &Value,
-        eventkey_alias: &EventKeyAliasConfig,
-        channel_key: &str,
-    ) -> bool {
-        let channel = utils::get_event_value(channel_key, data, eventkey_alias);
-        if channel.is_none() {
+        // 以下のコマンドの際にはルールにかけない
+        if stored_static.metrics_flag
+            || stored_static.logon_summary_flag
+            || stored_static.search_flag
+        {
             return false;
         }
-        match channel.unwrap() {
-            Value::String(s) => s != "null",
-            _ => false, // channelの値は文字列を想定しているため、それ以外のデータが来た場合はfalseを返す
-        }
-    }
-
-    fn is_filtered_by_eid(
-        &self,
-        data: &Value,
-        eventkey_alias: &EventKeyAliasConfig,
-        (include_eid, exclude_eid): (&HashSet<CompactString>, &HashSet<CompactString>),
-        eid_filter: bool,
-        target_event_ids: &TargetIds,
-    ) -> bool {
-        let target_eid = if !include_eid.is_empty() || !exclude_eid.is_empty() {
-            if let Some(eid_record) =
-                utils::get_event_value(&utils::get_event_id_key(), data, eventkey_alias)
-            {
-                utils::get_serde_number_to_string(eid_record, false).unwrap_or_default()
-            } else {
-                CompactString::default()
-            }
+        let mut log_records: Vec<DetectInfo> = scan_result
+            .hits
+            .into_iter()
+            .map(|hit| hit.detect_info)
+            .collect();
+        scan_progress.add_counts(0, log_records.len());
+        if stored_static.is_low_memory {
+            let empty_ids = HashSet::new();
+            afterfact::emit_csv(
+                &log_records,
+                &empty_ids,
+                stored_static,
+                afterfact_writer,
+                afterfact_info,
+            );
         } else {
-            CompactString::default()
-        };
-        // 以下の場合はフィルタリングする。
-        // 1. include_eidが指定されているが、include_eidに含まれていない場合
-        // 2. exclude_eidが指定されていて、exclude_eidに含まれている場合
-        // 3. eid_filterが指定されていて、target_eventids.txtで指定されたEventIDでない場合
-        (!include_eid.is_empty() && !include_eid.contains(&target_eid))
-            || (!exclude_eid.is_empty() && exclude_eid.contains(&target_eid))
-            || (eid_filter && !self._is_target_event_id(data, target_event_ids, eventkey_alias))
+            detect_infos.append(&mut log_records);
+        }
+        // 低メモリモードで--limitの数の検知結果を出力した場合は、残りのイベントの解析を省略する
+        if stored_static.is_low_memory && afterfact::is_limit_reached(stored_static, afterfact_info)
+        {
+            afterfact_info.is_truncated_by_limit = true;
+            return true;
+        }
+        false
     }
 
     fn evtx_to_jsons(
         &self,
         evtx_filepath: &PathBuf,
         enable_recover_records: bool,
-    ) -> Option<EvtxParser<File>> {
-        match EvtxParser::from_path(evtx_filepath) {
+    ) -> Option<EvtxParser<Box<dyn SeekableReader>>> {
+        // gzipで圧縮されたファイル(.evtx.gz)は展開してから解析する
+        let evtx_parser = match utils::open_seekable_evtx_file(evtx_filepath) {
+            Ok(reader) => EvtxParser::from_read_seek(reader).map_err(|e| e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        match evtx_parser {
             Ok(evtx_parser) => {
                 // parserのデフォルト設定を変更
                 let mut parse_config =
@@ -2242,7 +2478,11 @@ impl App {
                 Option::Some(evtx_parser)
             }
             Err(e) => {
-                eprintln!("{e}");
+                AlertMessage::alert(&format!(
+                    "Failed to open the evtx file. {} : {e}",
+                    evtx_filepath.display()
+                ))
+                .ok();
                 Option::None
             }
         }
@@ -2328,11 +2568,12 @@ impl App {
 mod tests {
     use std::{
         fs::{self, remove_file, File},
-        path::Path,
+        path::{Path, PathBuf},
     };
 
     use chrono::Local;
     use hashbrown::HashSet;
+    use indicatif::ProgressBar;
     use itertools::Itertools;
     use yaml_rust::YamlLoader;
 
@@ -2352,7 +2593,9 @@ mod tests {
         timeline::timelines::Timeline,
     };
 
-    use crate::App;
+    use crate::{
+        get_max_detect_records, App, ScanProgress, MAX_DETECT_RECORDS, STREAM_DETECT_RECORDS,
+    };
 
     fn create_dummy_stored_static() -> StoredStatic {
         StoredStatic::create_static_data(Some(Config {
@@ -2362,6 +2605,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -2382,7 +2626,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -2395,10 +2639,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: true,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -2407,6 +2654,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -2446,6 +2709,65 @@ mod tests {
         })
     }
 
+    #[test]
+    fn test_collect_gzip_files() {
+        // gzipで圧縮されたファイルは.gzを除いた拡張子で読み込み対象かを判定する
+        let files: Vec<String> = App::collect_evtxfiles(
+            "test_files/evtx",
+            &HashSet::from(["json".to_string(), "jsonl".to_string()]),
+            &create_dummy_stored_static(),
+        )
+        .iter()
+        .map(|file| file.file_name().unwrap().to_str().unwrap().to_string())
+        .collect();
+        assert!(files.contains(&"test.json.gz".to_string()));
+        assert!(files.contains(&"test.jsonl.gz".to_string()));
+        assert!(files.contains(&"test.jsonl".to_string()));
+    }
+
+    #[test]
+    fn test_dedup_evtxfiles() {
+        // 親フォルダとサブフォルダを両方指定しても同じファイルは1回だけ読み込む
+        // 親フォルダに2ファイル、サブフォルダに2ファイル、対象外の拡張子のファイルを1つ置く
+        let test_dir = std::env::temp_dir().join("hayabusa_test_dedup_evtxfiles");
+        let sub_dir = test_dir.join("sub");
+        fs::remove_dir_all(&test_dir).ok();
+        fs::create_dir_all(&sub_dir).unwrap();
+        for path in [
+            test_dir.join("a.evtx"),
+            test_dir.join("b.evtx"),
+            test_dir.join("c.txt"),
+            sub_dir.join("d.evtx"),
+            sub_dir.join("e.evtx"),
+        ] {
+            fs::write(path, "").unwrap();
+        }
+
+        let target_extensions = HashSet::from(["evtx".to_string()]);
+        let stored_static = create_dummy_stored_static();
+        let collect = |dir: PathBuf| {
+            App::collect_evtxfiles(dir.to_str().unwrap(), &target_extensions, &stored_static)
+        };
+        let mut files = collect(test_dir.clone());
+        assert_eq!(files.len(), 4);
+        files.extend(collect(sub_dir.clone()));
+        // 表記が異なっても同じフォルダを指すパスは重複として扱う
+        files.extend(collect(sub_dir.join("..")));
+        assert_eq!(files.len(), 10);
+
+        let mut actual = App::dedup_evtxfiles(files);
+        actual.sort();
+        let mut expected = vec![
+            test_dir.join("a.evtx"),
+            test_dir.join("b.evtx"),
+            sub_dir.join("d.evtx"),
+            sub_dir.join("e.evtx"),
+        ];
+        expected.sort();
+        assert_eq!(actual, expected);
+        fs::remove_dir_all(&test_dir).ok();
+    }
+
     #[test]
     fn test_exec_none_storedstatic() {
         let mut app = App::new(None);
@@ -2500,13 +2822,13 @@ mod tests {
         let rule_init = rule.init(&stored_static);
         assert!(rule_init.is_ok());
         let rule_files = vec![rule];
-        app.rule_keys = app.get_all_keys(&rule_files);
         let detection = detection::Detection::new(rule_files);
         let target_time_filter = TargetEventTime::new(&stored_static);
         let tl = Timeline::default();
         let target_event_ids = TargetIds::default();
         let mut afterfact_info = AfterfactInfo::default();
         let mut afterfact_writer = afterfact::init_writer(&stored_static);
+        let mut scan_progress = ScanProgress::new(ProgressBar::hidden(), false);
 
         let actual = app.analysis_json_file(
             (
@@ -2519,8 +2841,12 @@ mod tests {
             tl,
             &mut afterfact_writer,
             &mut afterfact_info,
+            &mut scan_progress,
         );
         assert_eq!(actual.1, 2);
+        // プログレスバーに表示する処理済みのイベント数と検知数が更新されていることを確認
+        assert_eq!(scan_progress.event_cnt, 2);
+        assert_eq!(scan_progress.detect_cnt, actual.4.len());
         // TODO add check
         //assert_eq!(MESSAGES.len(), 2);
     }
@@ -2536,6 +2862,7 @@ mod tests {
                     directory: None,
                     filepath: Some(Path::new("test_files/evtx/test.json").to_path_buf()),
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2556,7 +2883,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./test_files/rules/yaml/test_json_detect.yml").to_path_buf(),
+                rules: vec![Path::new("./test_files/rules/yaml/test_json_detect.yml").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -2569,10 +2896,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: true,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -2581,6 +2911,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2624,6 +2970,7 @@ mod tests {
                     directory: None,
                     filepath: Some(Path::new("test_files/evtx/test.json").to_path_buf()),
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2644,7 +2991,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("test_files/rules/yaml/test_json_detect.yml").to_path_buf(),
+                rules: vec![Path::new("test_files/rules/yaml/test_json_detect.yml").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -2657,10 +3004,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: true,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: true,
@@ -2669,6 +3019,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2711,6 +3077,7 @@ mod tests {
                     directory: None,
                     filepath: Some(Path::new("test_files/evtx/test.json").to_path_buf()),
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2731,7 +3098,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./test_files/rules/yaml/test_json_detect.yml").to_path_buf(),
+                rules: vec![Path::new("./test_files/rules/yaml/test_json_detect.yml").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -2744,10 +3111,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: true,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -2756,6 +3126,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2771,6 +3157,8 @@ mod tests {
             geo_ip: None,
             output: Some(Path::new("overwrite.json").to_path_buf()),
             jsonl_timeline: false,
+            json_pretty: false,
+            append: false,
         });
         let config = Some(Config {
             action: Some(action),
@@ -2799,6 +3187,7 @@ mod tests {
                     directory: None,
                     filepath: Some(Path::new("test_files/evtx/test.json").to_path_buf()),
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -2819,7 +3208,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("test_files/rules/yaml/test_json_detect.yml").to_path_buf(),
+                rules: vec![Path::new("test_files/rules/yaml/test_json_detect.yml").to_path_buf()],
                 html_report: None,
                 no_summary: true,
                 common_options: CommonOptions {
@@ -2832,10 +3221,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: true,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: true,
@@ -2844,6 +3236,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -2859,6 +3267,8 @@ mod tests {
             geo_ip: None,
             output: Some(Path::new("overwrite.json").to_path_buf()),
             jsonl_timeline: false,
+            json_pretty: false,
+            append: false,
         });
         let config = Some(Config {
             action: Some(action),
@@ -2886,6 +3296,7 @@ mod tests {
                 directory: None,
                 filepath: Some(Path::new("test_files/evtx/test_metrics.json").to_path_buf()),
                 live_analysis: false,
+                live_channel: None,
                 recover_records: false,
                 timeline_offset: None,
             },
@@ -2899,10 +3310,13 @@ mod tests {
                 thread_number: None,
                 quiet_errors: false,
                 config: Path::new("./rules/config").to_path_buf(),
-                verbose: false,
+                verbose: 0,
+                no_progress: false,
                 json_input: true,
                 include_computer: None,
                 exclude_computer: None,
+                strict: false,
+                alias_config: None,
             },
             european_time: false,
             iso_8601: false,
@@ -2941,6 +3355,7 @@ mod tests {
                 directory: None,
                 filepath: Some(Path::new("test_files/evtx/test_metrics.json").to_path_buf()),
                 live_analysis: false,
+                live_channel: None,
                 recover_records: false,
                 timeline_offset: None,
             },
@@ -2954,10 +3369,13 @@ mod tests {
                 thread_number: None,
                 quiet_errors: false,
                 config: Path::new("./rules/config").to_path_buf(),
-                verbose: false,
+                verbose: 0,
+                no_progress: false,
                 json_input: true,
                 include_computer: None,
                 exclude_computer: None,
+                strict: false,
+                alias_config: None,
             },
             european_time: false,
             iso_8601: false,
@@ -2994,6 +3412,7 @@ mod tests {
                 directory: None,
                 filepath: Some(Path::new("test_files/evtx/test_metrics.json").to_path_buf()),
                 live_analysis: false,
+                live_channel: None,
                 timeline_offset: None,
                 recover_records: false,
             },
@@ -3007,10 +3426,13 @@ mod tests {
                 thread_number: None,
                 quiet_errors: false,
                 config: Path::new("./rules/config").to_path_buf(),
-                verbose: false,
+                verbose: 0,
+                no_progress: false,
                 json_input: true,
                 include_computer: None,
                 exclude_computer: None,
+                strict: false,
+                alias_config: None,
             },
             european_time: false,
             iso_8601: false,
@@ -3050,6 +3472,7 @@ mod tests {
                 directory: None,
                 filepath: Some(Path::new("test_files/evtx/test_metrics.json").to_path_buf()),
                 live_analysis: false,
+                live_channel: None,
                 recover_records: false,
                 timeline_offset: None,
             },
@@ -3063,10 +3486,13 @@ mod tests {
                 thread_number: None,
                 quiet_errors: false,
                 config: Path::new("./rules/config").to_path_buf(),
-                verbose: false,
+                verbose: 0,
+                no_progress: false,
                 json_input: true,
                 include_computer: None,
                 exclude_computer: None,
+                strict: false,
+                alias_config: None,
             },
             european_time: false,
             iso_8601: false,
@@ -3105,6 +3531,7 @@ mod tests {
                 directory: None,
                 filepath: Some(Path::new("test_files/evtx/test_metrics.json").to_path_buf()),
                 live_analysis: false,
+                live_channel: None,
                 recover_records: false,
                 timeline_offset: None,
             },
@@ -3117,7 +3544,8 @@ mod tests {
             thread_number: None,
             quiet_errors: false,
             config: Path::new("./rules/config").to_path_buf(),
-            verbose: false,
+            verbose: 0,
+            no_progress: false,
             json_input: true,
             clobber: false,
         });
@@ -3148,6 +3576,7 @@ mod tests {
                 directory: None,
                 filepath: Some(Path::new("test_files/evtx/test_metrics.json").to_path_buf()),
                 live_analysis: false,
+                live_channel: None,
                 recover_records: false,
                 timeline_offset: None,
             },
@@ -3160,7 +3589,8 @@ mod tests {
             thread_number: None,
             quiet_errors: false,
             config: Path::new("./rules/config").to_path_buf(),
-            verbose: false,
+            verbose: 0,
+            no_progress: false,
             json_input: true,
             clobber: true,
         });
@@ -3201,7 +3631,6 @@ mod tests {
         let rule_init = rule.init(&stored_static);
         assert!(rule_init.is_ok());
         let rule_files = vec![rule];
-        app.rule_keys = app.get_all_keys(&rule_files);
         let detection = detection::Detection::new(rule_files);
         let target_time_filter = TargetEventTime::new(&stored_static);
         let tl = Timeline::default();
@@ -3220,6 +3649,7 @@ mod tests {
             tl,
             &mut afterfact_writer,
             &mut afterfact_info,
+            &mut ScanProgress::new(ProgressBar::hidden(), false),
         );
         assert_eq!(actual.1, 2);
         assert_eq!(actual.4.len(), 1);
@@ -3247,7 +3677,6 @@ mod tests {
         let rule_init = rule.init(&stored_static);
         assert!(rule_init.is_ok());
         let rule_files = vec![rule];
-        app.rule_keys = app.get_all_keys(&rule_files);
         let detection = detection::Detection::new(rule_files);
         let target_time_filter = TargetEventTime::new(&stored_static);
         let tl = Timeline::default();
@@ -3266,11 +3695,60 @@ mod tests {
             tl,
             &mut afterfact_writer,
             &mut afterfact_info,
+            &mut ScanProgress::new(ProgressBar::hidden(), false),
         );
         assert_eq!(actual.1, 2);
         assert_eq!(actual.4.len(), 0);
     }
 
+    #[test]
+    fn test_analysis_json_file_exclude_computer() {
+        // JSON形式のイベントログもEVTXファイルと同じscan_recordsを経由し、Computer名でフィルタリングされることを確認
+        let mut app = App::new(None);
+        let mut stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        stored_static.exclude_computer = HashSet::from_iter(vec!["test.local".into()]);
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection1:
+                Channel: 'Microsoft-Windows-Sysmon/Operational'
+            condition: selection1
+        details: testdata
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let test_yaml_data = rule_yaml.next().unwrap();
+        let mut rule = create_rule("testpath".to_string(), test_yaml_data);
+        let rule_init = rule.init(&stored_static);
+        assert!(rule_init.is_ok());
+        let rule_files = vec![rule];
+        let detection = detection::Detection::new(rule_files);
+        let target_time_filter = TargetEventTime::new(&stored_static);
+        let tl = Timeline::default();
+        let target_event_ids = TargetIds::default();
+        let mut afterfact_info = AfterfactInfo::default();
+        let mut afterfact_writer = afterfact::init_writer(&stored_static);
+
+        let actual = app.analysis_json_file(
+            (
+                Path::new("test_files/evtx/test.jsonl").to_path_buf(),
+                &target_time_filter,
+                &target_event_ids,
+                &stored_static,
+            ),
+            detection,
+            tl,
+            &mut afterfact_writer,
+            &mut afterfact_info,
+            &mut ScanProgress::new(ProgressBar::hidden(), false),
+        );
+        assert_eq!(actual.1, 2);
+        assert_eq!(actual.4.len(), 1);
+        assert_eq!(actual.4[0].computername, "test2.local");
+    }
+
     #[test]
     fn test_analysis_json_file_low_memory_mode() {
         let mut app = App::new(None);
@@ -3294,7 +3772,6 @@ mod tests {
         let rule_init = rule.init(&stored_static);
         assert!(rule_init.is_ok());
         let rule_files = vec![rule];
-        app.rule_keys = app.get_all_keys(&rule_files);
         let detection = detection::Detection::new(rule_files);
         let target_time_filter = TargetEventTime::new(&stored_static);
         let tl = Timeline::default();
@@ -3313,8 +3790,170 @@ mod tests {
             tl,
             &mut afterfact_writer,
             &mut afterfact_info,
+            &mut ScanProgress::new(ProgressBar::hidden(), false),
+        );
+        assert_eq!(actual.1, 2);
+        assert_eq!(actual.4.len(), 0);
+    }
+
+    #[test]
+    fn test_get_max_detect_records() {
+        // --streamの場合は検知結果をすぐに出力するために、一度にスキャンするレコード数を少なくする
+        let mut stored_static = create_dummy_stored_static();
+        assert_eq!(get_max_detect_records(&stored_static), MAX_DETECT_RECORDS);
+        stored_static.output_option.as_mut().unwrap().stream = true;
+        assert_eq!(
+            get_max_detect_records(&stored_static),
+            STREAM_DETECT_RECORDS
+        );
+    }
+
+    #[test]
+    fn test_stream_outputs_detections_per_batch() {
+        // --streamの場合は1件ずつではなくSTREAM_DETECT_RECORDS件ずつスキャンして検知結果を出力するので、
+        // 低メモリモードで--limitに達した場合は、そのバッチまでのレコードのみを読み込んでスキャンを終了することを確認
+        let record_cnt = STREAM_DETECT_RECORDS * 2 + 50;
+        let jsonl_path = std::env::temp_dir().join("hayabusa_test_stream_batch.jsonl");
+        let jsonl = (0..record_cnt)
+            .map(|i| {
+                format!(
+                    r#"{{"@timestamp":"2020-05-01T22:55:23.000Z","EventID":10,"Channel":"Microsoft-Windows-Sysmon/Operational","Hostname":"pc{i}","RecordNumber":{i}}}"#
+                )
+            })
+            .join("\n");
+        fs::write(&jsonl_path, jsonl).unwrap();
+
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection1:
+                Channel: 'Microsoft-Windows-Sysmon/Operational'
+            condition: selection1
+        details: testdata
+        "#;
+        let analysis_with_limit = |stream: bool| {
+            let app = App::new(None);
+            let mut stored_static = create_dummy_stored_static();
+            *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+            stored_static.is_low_memory = true;
+            let output_option = stored_static.output_option.as_mut().unwrap();
+            output_option.stream = stream;
+            output_option.limit = Some(1);
+            *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+
+            let test_yaml_data = YamlLoader::load_from_str(rule_str).unwrap().remove(0);
+            let mut rule = create_rule("testpath".to_string(), test_yaml_data);
+            assert!(rule.init(&stored_static).is_ok());
+            let mut afterfact_info = AfterfactInfo::default();
+            let mut afterfact_writer = afterfact::init_writer(&stored_static);
+            let actual = app.analysis_json_file(
+                (
+                    jsonl_path.clone(),
+                    &TargetEventTime::new(&stored_static),
+                    &TargetIds::default(),
+                    &stored_static,
+                ),
+                detection::Detection::new(vec![rule]),
+                Timeline::default(),
+                &mut afterfact_writer,
+                &mut afterfact_info,
+                &mut ScanProgress::new(ProgressBar::hidden(), false),
+            );
+            (actual.1, afterfact_info.total_detect_cnt)
+        };
+
+        // --streamの場合は最初のバッチで--limitに達するので、残りのレコードは読み込まない
+        assert_eq!(analysis_with_limit(true), (STREAM_DETECT_RECORDS, 1));
+        // --streamを指定しない場合はMAX_DETECT_RECORDS件ずつスキャンするので、全てのレコードを読み込む
+        assert_eq!(analysis_with_limit(false), (record_cnt, 1));
+        remove_file(&jsonl_path).ok();
+    }
+
+    #[test]
+    fn test_get_dry_run_options() {
+        // 指定されたオプションのみ表示され、指定されていないオプションは表示されないことを確認
+        let mut stored_static = create_dummy_stored_static();
+        assert_eq!(
+            App::get_dry_run_options(&stored_static),
+            vec![
+                ("Rules", "./rules".to_string()),
+                ("Minimum level", "informational".to_string())
+            ]
+        );
+
+        let opt = stored_static.output_option.as_mut().unwrap();
+        opt.rules.push(Path::new("../internal-rules").to_path_buf());
+        opt.min_level = "HIGH".to_string();
+        opt.include_tag = Some(vec![
+            "attack.execution".to_string(),
+            "attack.t1059".to_string(),
+        ]);
+        opt.exclude_eid = Some(vec!["4688".to_string()]);
+        opt.start_timeline = Some("2022-02-22 00:00:00 +09:00".to_string());
+        opt.enable_noisy_rules = true;
+        stored_static.output_path = Some(Path::new("results.csv").to_path_buf());
+        assert_eq!(
+            App::get_dry_run_options(&stored_static),
+            vec![
+                ("Rules", "./rules, ../internal-rules".to_string()),
+                ("Minimum level", "high".to_string()),
+                ("Include tags", "attack.execution, attack.t1059".to_string()),
+                ("Exclude EIDs", "4688".to_string()),
+                ("Timeline start", "2022-02-22 00:00:00 +09:00".to_string()),
+                ("Enable noisy rules", "true".to_string()),
+                ("Output", "results.csv".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analysis_json_file_low_memory_mode_with_limit() {
+        let mut app = App::new(None);
+        let mut stored_static = create_dummy_stored_static();
+        *STORED_EKEY_ALIAS.write().unwrap() = Some(stored_static.eventkey_alias.clone());
+        stored_static.include_eid = HashSet::from_iter(vec!["10".into(), "11".into()]);
+        stored_static.is_low_memory = true;
+        stored_static.output_option.as_mut().unwrap().limit = Some(1);
+        *STORED_STATIC.write().unwrap() = Some(stored_static.clone());
+
+        let rule_str = r#"
+        enabled: true
+        detection:
+            selection1:
+                Channel: 'Microsoft-Windows-Sysmon/Operational'
+            condition: selection1
+        details: testdata
+        "#;
+        let mut rule_yaml = YamlLoader::load_from_str(rule_str).unwrap().into_iter();
+        let test_yaml_data = rule_yaml.next().unwrap();
+        let mut rule = create_rule("testpath".to_string(), test_yaml_data);
+        let rule_init = rule.init(&stored_static);
+        assert!(rule_init.is_ok());
+        let rule_files = vec![rule];
+        let detection = detection::Detection::new(rule_files);
+        let target_time_filter = TargetEventTime::new(&stored_static);
+        let tl = Timeline::default();
+        let target_event_ids = TargetIds::default();
+        let mut afterfact_info = AfterfactInfo::default();
+        let mut afterfact_writer = afterfact::init_writer(&stored_static);
+
+        let actual = app.analysis_json_file(
+            (
+                Path::new("test_files/evtx/test.jsonl").to_path_buf(),
+                &target_time_filter,
+                &target_event_ids,
+                &stored_static,
+            ),
+            detection,
+            tl,
+            &mut afterfact_writer,
+            &mut afterfact_info,
+            &mut ScanProgress::new(ProgressBar::hidden(), false),
         );
+        // 2件のイベントが検知されるが、--limit 1により1件だけ出力して打ち切ったことを記録する
         assert_eq!(actual.1, 2);
         assert_eq!(actual.4.len(), 0);
+        assert_eq!(afterfact_info.total_detect_cnt, 1);
+        assert!(afterfact_info.is_truncated_by_limit);
     }
 }
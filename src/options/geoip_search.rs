@@ -10,32 +10,63 @@ use std::{net::IpAddr, str::FromStr};
 lazy_static! {
     pub static ref IP_MAP: Mutex<HashMap<IpAddr, CompactString>> = Mutex::new(HashMap::new());
 }
+/// Readers of MaxMind databases. A reader is None when its database is not specified and the lookup is skipped.
 pub struct GeoIPSearch {
-    pub asn_reader: Reader<Vec<u8>>,
-    pub country_reader: Reader<Vec<u8>>,
-    pub city_reader: Reader<Vec<u8>>,
+    pub asn_reader: Option<Reader<Vec<u8>>>,
+    pub country_reader: Option<Reader<Vec<u8>>>,
+    pub city_reader: Option<Reader<Vec<u8>>>,
 }
 
 impl GeoIPSearch {
     pub fn new(path: &Path, asn_country_city_filename: Vec<&str>) -> GeoIPSearch {
         GeoIPSearch {
-            asn_reader: maxminddb::Reader::open_readfile(path.join(asn_country_city_filename[0]))
-                .unwrap(),
-            country_reader: maxminddb::Reader::open_readfile(
-                path.join(asn_country_city_filename[1]),
-            )
-            .unwrap(),
-            city_reader: maxminddb::Reader::open_readfile(path.join(asn_country_city_filename[2]))
-                .unwrap(),
+            asn_reader: Some(
+                maxminddb::Reader::open_readfile(path.join(asn_country_city_filename[0])).unwrap(),
+            ),
+            country_reader: Some(
+                maxminddb::Reader::open_readfile(path.join(asn_country_city_filename[1])).unwrap(),
+            ),
+            city_reader: Some(
+                maxminddb::Reader::open_readfile(path.join(asn_country_city_filename[2])).unwrap(),
+            ),
         }
     }
 
+    /// open a single .mmdb file specified by GeoIP option. The database type (ASN, Country or City) is read from the metadata of the file.
+    pub fn open_mmdb_file(path: &Path) -> Result<GeoIPSearch, String> {
+        let reader = maxminddb::Reader::open_readfile(path).map_err(|e| {
+            format!("Failed to open the MaxMind GeoIP .mmdb database file. filepath: {path:?} {e}")
+        })?;
+        let database_type = reader.metadata.database_type.clone();
+        let mut geo_ip = GeoIPSearch {
+            asn_reader: None,
+            country_reader: None,
+            city_reader: None,
+        };
+        if database_type.ends_with("ASN") {
+            geo_ip.asn_reader = Some(reader);
+        } else if database_type.ends_with("Country") {
+            geo_ip.country_reader = Some(reader);
+        } else if database_type.ends_with("City") {
+            geo_ip.city_reader = Some(reader);
+        } else {
+            return Err(format!(
+                "Unsupported MaxMind GeoIP database type. Please specify an ASN, Country or City database. filepath: {path:?} type: {database_type}"
+            ));
+        }
+        Ok(geo_ip)
+    }
+
     /// check existence files in specified path by GeoIP option.
+    /// When a single .mmdb file is specified instead of a directory, the file is used as is.
     pub fn check_exist_geo_ip_files(
         geo_ip_dir_path: &Option<PathBuf>,
         check_files: Vec<&str>,
     ) -> Result<Option<PathBuf>, String> {
         if let Some(path) = geo_ip_dir_path {
+            if path.is_file() {
+                return Ok(geo_ip_dir_path.to_owned());
+            }
             let mut combined_err = vec![];
             for file_name in check_files {
                 let mmdb_path = path.join(file_name);
@@ -113,32 +144,42 @@ impl GeoIPSearch {
             return Ok(cached_data.to_string());
         }
 
-        let asn_search: Result<geoip2::Asn, MaxMindDBError> = self.asn_reader.lookup(addr);
-        let country_search: Result<geoip2::Country, MaxMindDBError> =
-            self.country_reader.lookup(addr);
-        let city_search: Result<geoip2::City, MaxMindDBError> = self.city_reader.lookup(addr);
+        // Databases that are not specified or have no entry for the address are output as "-".
+        let asn_search: Option<geoip2::Asn> = self
+            .asn_reader
+            .as_ref()
+            .and_then(|reader| reader.lookup(addr).ok());
+        let country_search: Option<geoip2::Country> = self
+            .country_reader
+            .as_ref()
+            .and_then(|reader| reader.lookup(addr).ok());
+        let city_search: Option<geoip2::City> = self
+            .city_reader
+            .as_ref()
+            .and_then(|reader| reader.lookup(addr).ok());
 
-        let output_asn = if let Ok(asn) = asn_search {
+        let output_asn = if let Some(asn) = asn_search {
             asn.autonomous_system_organization.unwrap_or("-")
         } else {
             "-"
         };
 
-        let output_country = if let Ok(country_data) = country_search {
-            if let Some(country) = country_data.country {
-                let mut ret = "-";
-                if let Some(name_tree) = country.names {
-                    ret = name_tree.get("en").unwrap_or(&"-")
-                }
-                ret
-            } else {
-                "-"
+        // The City database also contains the country, so it is used when the Country database is not specified.
+        let country_data = country_search
+            .as_ref()
+            .and_then(|data| data.country.as_ref())
+            .or_else(|| city_search.as_ref().and_then(|data| data.country.as_ref()));
+        let output_country = if let Some(country) = country_data {
+            let mut ret = "-";
+            if let Some(name_tree) = &country.names {
+                ret = name_tree.get("en").unwrap_or(&"-")
             }
+            ret
         } else {
             "-"
         };
 
-        let output_city = if let Ok(city_data) = city_search {
+        let output_city = if let Some(city_data) = city_search {
             if let Some(city) = city_data.city {
                 let mut ret = "n/-";
                 if let Some(name_tree) = city.names {
@@ -226,6 +267,35 @@ mod tests {
         assert_eq!(expect, actual.unwrap());
     }
 
+    #[test]
+    fn test_convert_ip_to_geo_single_mmdb_file() {
+        // Test files from https://github.com/maxmind/MaxMind-DB/tree/a8ae5b4ac0aa730e2783f708cdaa208aca20e9ec/test-data
+        let city_path = Path::new("test_files/mmdb/GeoLite2-City.mmdb").to_path_buf();
+        assert_eq!(
+            GeoIPSearch::check_exist_geo_ip_files(
+                &Some(city_path.clone()),
+                vec![
+                    "GeoLite2-ASN.mmdb",
+                    "GeoLite2-Country.mmdb",
+                    "GeoLite2-City.mmdb",
+                ]
+            ),
+            Ok(Some(city_path.clone()))
+        );
+        let geo_ip = GeoIPSearch::open_mmdb_file(&city_path).unwrap();
+        assert!(geo_ip.asn_reader.is_none());
+        assert!(geo_ip.country_reader.is_none());
+        // ASN is not looked up and the country is taken from the City database
+        let actual = geo_ip.convert_ip_to_geo("81.2.69.142");
+        assert_eq!("-🦅United Kingdom🦅London", actual.unwrap());
+
+        let geo_ip =
+            GeoIPSearch::open_mmdb_file(Path::new("test_files/mmdb/GeoLite2-ASN.mmdb")).unwrap();
+        assert!(geo_ip.asn_reader.is_some());
+        assert!(geo_ip.city_reader.is_none());
+        assert!(GeoIPSearch::open_mmdb_file(Path::new("test_files/mmdb/NoExist.mmdb")).is_err());
+    }
+
     #[test]
     fn test_already_convert_ip_to_geo() {
         let test_path = Path::new("test_files/mmdb").to_path_buf();
@@ -256,6 +256,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -276,7 +277,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: Some(Path::new("./dummy").to_path_buf()),
                 no_summary: false,
                 common_options: CommonOptions {
@@ -289,10 +290,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -301,6 +305,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -326,6 +346,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -346,7 +367,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -359,10 +380,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -371,6 +395,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -399,6 +439,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -419,7 +460,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: Some(Path::new("./dummy").to_path_buf()),
                 no_summary: false,
                 common_options: CommonOptions {
@@ -432,10 +473,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -444,6 +488,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -457,6 +517,8 @@ mod tests {
                 scan_all_evtx_files: false,
             },
             jsonl_timeline: false,
+            json_pretty: false,
+            append: false,
             geo_ip: None,
             output: None,
         });
@@ -469,6 +531,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -489,7 +552,7 @@ mod tests {
                 us_time: false,
                 utc: false,
                 visualize_timeline: false,
-                rules: Path::new("./rules").to_path_buf(),
+                rules: vec![Path::new("./rules").to_path_buf()],
                 html_report: None,
                 no_summary: false,
                 common_options: CommonOptions {
@@ -502,10 +565,13 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     json_input: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 enable_unsupported_rules: false,
                 clobber: false,
@@ -514,6 +580,22 @@ mod tests {
                 exclude_tag: None,
                 include_category: None,
                 exclude_category: None,
+                stats: false,
+                timezone: None,
+                default_timestamp: None,
+                keep_duplicate_rules: false,
+                matched_fields: false,
+                rule_cache: None,
+                list_rules: false,
+                show_unused_rules: false,
+                mitre: None,
+                debug_rule: None,
+                suppress: None,
+                limit: None,
+                stream: false,
+                dry_run: false,
+                enable_rule: None,
+                exclude_rule: None,
                 include_eid: None,
                 exclude_eid: None,
                 no_field: false,
@@ -527,6 +609,8 @@ mod tests {
                 scan_all_evtx_files: false,
             },
             jsonl_timeline: false,
+            json_pretty: false,
+            append: false,
             geo_ip: None,
             output: None,
         });
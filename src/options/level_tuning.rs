@@ -67,7 +67,7 @@ impl LevelTuning {
         }
 
         // Convert rule files
-        for (path, rule) in rulefile_loader.files {
+        for (path, _, rule) in rulefile_loader.files {
             if let Some(new_level) = tuning_map.get(rule["id"].as_str().unwrap()) {
                 write_color_buffer(
                     &BufferWriter::stdout(ColorChoice::Always),
@@ -0,0 +1,352 @@
+use chrono::{DateTime, Utc};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+
+/// 稼働中のイベントログから読み込んだイベントのXMLのイテレータ。読み込みに失敗したイベントはエラーメッセージを返す
+pub type LiveChannelEvents = Box<dyn Iterator<Item = Result<String, String>>>;
+
+/// 稼働中のWindowsイベントログから、指定したChannelの全てのイベントを古い順に読み込む。
+/// 一時ファイルには書き出さず、EvtQueryで取得したイベントをEvtRenderでXMLにして返す
+#[cfg(target_os = "windows")]
+pub fn query_channel(channel: &str) -> Result<LiveChannelEvents, String> {
+    use windows::core::HSTRING;
+    use windows::Win32::System::EventLog::{
+        EvtQuery, EvtQueryChannelPath, EvtQueryForwardDirection, EVT_HANDLE,
+    };
+
+    let query = unsafe {
+        EvtQuery(
+            EVT_HANDLE::default(),
+            &HSTRING::from(channel),
+            &HSTRING::from("*"),
+            EvtQueryChannelPath.0 | EvtQueryForwardDirection.0,
+        )
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(Box::new(windows_event_log::EventQuery::new(query)))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn query_channel(_channel: &str) -> Result<LiveChannelEvents, String> {
+    Err("--live-channel can only be used on Windows.".to_string())
+}
+
+#[cfg(target_os = "windows")]
+mod windows_event_log {
+    use std::ffi::c_void;
+    use windows::Win32::Foundation::ERROR_NO_MORE_ITEMS;
+    use windows::Win32::System::EventLog::{
+        EvtClose, EvtNext, EvtRender, EvtRenderEventXml, EVT_HANDLE,
+    };
+
+    /// EvtNextで一度に取得するイベント数
+    const EVENT_BATCH_SIZE: usize = 64;
+    /// EvtNextで次のイベントを待つ時間(ミリ秒)
+    const EVENT_NEXT_TIMEOUT: u32 = 1000;
+
+    /// EvtQueryの結果から、イベントを順番にXMLとして取り出すイテレータ
+    pub struct EventQuery {
+        query: EVT_HANDLE,
+        events: Vec<isize>,
+        pos: usize,
+        len: usize,
+        is_finished: bool,
+    }
+
+    impl EventQuery {
+        pub fn new(query: EVT_HANDLE) -> EventQuery {
+            EventQuery {
+                query,
+                events: vec![0; EVENT_BATCH_SIZE],
+                pos: 0,
+                len: 0,
+                is_finished: false,
+            }
+        }
+
+        /// イベントをXMLの文字列に変換する。1回目の呼び出しで必要なバッファサイズを取得してから変換する
+        fn render_xml(event: EVT_HANDLE) -> Result<String, String> {
+            let mut buffer_used = 0;
+            let mut property_count = 0;
+            unsafe {
+                EvtRender(
+                    EVT_HANDLE::default(),
+                    event,
+                    EvtRenderEventXml.0,
+                    0,
+                    None,
+                    &mut buffer_used,
+                    &mut property_count,
+                )
+                .ok();
+            }
+            let mut buffer = vec![0_u16; (buffer_used as usize).div_ceil(2)];
+            unsafe {
+                EvtRender(
+                    EVT_HANDLE::default(),
+                    event,
+                    EvtRenderEventXml.0,
+                    buffer_used,
+                    Some(buffer.as_mut_ptr() as *mut c_void),
+                    &mut buffer_used,
+                    &mut property_count,
+                )
+            }
+            .map_err(|e| e.to_string())?;
+            let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+            Ok(String::from_utf16_lossy(&buffer[..len]))
+        }
+    }
+
+    impl Iterator for EventQuery {
+        type Item = Result<String, String>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.pos == self.len {
+                if self.is_finished {
+                    return None;
+                }
+                let mut returned = 0;
+                let result = unsafe {
+                    EvtNext(
+                        self.query,
+                        &mut self.events,
+                        EVENT_NEXT_TIMEOUT,
+                        0,
+                        &mut returned,
+                    )
+                };
+                self.pos = 0;
+                self.len = returned as usize;
+                if let Err(e) = result {
+                    self.is_finished = true;
+                    // 全てのイベントを読み込んだ場合はERROR_NO_MORE_ITEMSが返る
+                    if e.code() == ERROR_NO_MORE_ITEMS.to_hresult() {
+                        return None;
+                    }
+                    return Some(Err(e.to_string()));
+                }
+                if self.len == 0 {
+                    self.is_finished = true;
+                    return None;
+                }
+            }
+            let event = EVT_HANDLE(self.events[self.pos]);
+            self.pos += 1;
+            let xml = EventQuery::render_xml(event);
+            unsafe {
+                EvtClose(event).ok();
+            }
+            Some(xml)
+        }
+    }
+
+    impl Drop for EventQuery {
+        fn drop(&mut self) {
+            // 取得したが取り出していないイベントとクエリのハンドルを閉じる
+            for event in &self.events[self.pos..self.len] {
+                unsafe {
+                    EvtClose(EVT_HANDLE(*event)).ok();
+                }
+            }
+            unsafe {
+                EvtClose(self.query).ok();
+            }
+        }
+    }
+}
+
+/// 変換中のXMLの要素
+#[derive(Debug, Default)]
+struct XmlElement {
+    name: String,
+    attributes: Map<String, Value>,
+    children: Map<String, Value>,
+    text: String,
+}
+
+impl XmlElement {
+    fn new(start: &BytesStart) -> Result<XmlElement, String> {
+        let mut attributes = Map::new();
+        for attr in start.attributes() {
+            let attr = attr.map_err(|e| e.to_string())?;
+            let value = attr.unescape_value().map_err(|e| e.to_string())?;
+            attributes.insert(
+                String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string(),
+                Value::String(value.to_string()),
+            );
+        }
+        Ok(XmlElement {
+            name: String::from_utf8_lossy(start.local_name().as_ref()).to_string(),
+            attributes,
+            ..Default::default()
+        })
+    }
+
+    /// 子要素を要素名をキーとして追加する。属性は「要素名_attributes」のキーに追加する
+    fn add_child(&mut self, child: XmlElement) {
+        let (key, value, attributes) = child.into_entry();
+        if let Some(attributes) = attributes {
+            insert_value(
+                &mut self.children,
+                format!("{key}_attributes"),
+                Value::Object(attributes),
+            );
+        }
+        if let Some(value) = value {
+            insert_value(&mut self.children, key, value);
+        }
+    }
+
+    /// 親要素に追加するキー、値、属性を返す。
+    /// EventDataの`<Data Name="SubjectUserSid">`のようにName属性を持つData要素は、Name属性の値をキーとする
+    fn into_entry(mut self) -> (String, Option<Value>, Option<Map<String, Value>>) {
+        let key = match self.attributes.get("Name") {
+            Some(Value::String(name)) if self.name == "Data" => {
+                let name = name.to_owned();
+                self.attributes.remove("Name");
+                name
+            }
+            _ => self.name,
+        };
+        let value = if !self.children.is_empty() {
+            Some(Value::Object(self.children))
+        } else if !self.text.trim().is_empty() {
+            Some(to_typed_value(&self.text))
+        } else if self.attributes.is_empty() {
+            Some(Value::Null)
+        } else {
+            None
+        };
+        let attributes = (!self.attributes.is_empty()).then_some(self.attributes);
+        (key, value, attributes)
+    }
+}
+
+/// 同じキーが複数ある場合は配列にまとめる
+fn insert_value(map: &mut Map<String, Value>, key: String, value: Value) {
+    match map.get_mut(&key) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            map.insert(key, value);
+        }
+    }
+}
+
+/// EvtxParserと同様に、10進数の整数は数値として扱う。先頭が0の値や16進数の値は文字列のままとする
+fn to_typed_value(text: &str) -> Value {
+    let is_integer =
+        text.bytes().all(|b| b.is_ascii_digit()) && (text == "0" || !text.starts_with('0'));
+    match text.parse::<u64>() {
+        Ok(number) if is_integer => Value::from(number),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// EvtRenderで取得したイベントのXMLを、EvtxParserでseparate_json_attributesを指定した場合と同じ形式のJSONに変換する
+pub fn xml_to_event_value(xml: &str) -> Result<Value, String> {
+    let mut reader = Reader::from_str(xml);
+    // 変換中の要素のスタック。先頭はEvent要素を子要素として持つためのルート
+    let mut stack = vec![XmlElement::default()];
+    loop {
+        match reader.read_event().map_err(|e| e.to_string())? {
+            Event::Start(start) => stack.push(XmlElement::new(&start)?),
+            Event::Empty(start) => {
+                let element = XmlElement::new(&start)?;
+                stack.last_mut().unwrap().add_child(element);
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|e| e.to_string())?;
+                stack.last_mut().unwrap().text.push_str(&text);
+            }
+            Event::CData(text) => {
+                let text = text.into_inner();
+                stack
+                    .last_mut()
+                    .unwrap()
+                    .text
+                    .push_str(&String::from_utf8_lossy(&text));
+            }
+            Event::End(_) => {
+                let element = stack.pop().unwrap();
+                match stack.last_mut() {
+                    Some(parent) => parent.add_child(element),
+                    None => return Err("Unexpected closing tag in the event XML.".to_string()),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if stack.len() != 1 {
+        return Err("The event XML is not closed.".to_string());
+    }
+    Ok(Value::Object(stack.pop().unwrap().children))
+}
+
+/// 変換したイベントのTimeCreatedのSystemTime属性から、イベントの発生時刻を取得する
+pub fn get_event_timestamp(event: &Value) -> Option<DateTime<Utc>> {
+    event["Event"]["System"]["TimeCreated_attributes"]["SystemTime"]
+        .as_str()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_event_timestamp, xml_to_event_value};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn test_xml_to_event_value() {
+        let xml = r#"<Event xmlns='http://schemas.microsoft.com/win/2004/08/events/event'><System><Provider Name='Microsoft-Windows-Security-Auditing' Guid='{54849625-5478-4994-a5ba-3e3b0328c30d}'/><EventID>4624</EventID><Level>0</Level><Keywords>0x8020000000000000</Keywords><TimeCreated SystemTime='2024-05-01T12:34:56.1234567Z'/><EventRecordID>12345</EventRecordID><Correlation/><Channel>Security</Channel><Computer>WIN-TEST</Computer><Security/></System><EventData><Data Name='SubjectUserSid'>S-1-5-18</Data><Data Name='TargetUserName'>admin &amp; user</Data><Data Name='LogonType'>3</Data><Data Name='IpPort'>-</Data><Data Name='Empty'></Data></EventData></Event>"#;
+        let event = xml_to_event_value(xml).unwrap();
+        let system = &event["Event"]["System"];
+        assert_eq!(
+            system["Provider_attributes"]["Name"],
+            "Microsoft-Windows-Security-Auditing"
+        );
+        assert_eq!(system["EventID"], 4624);
+        assert_eq!(system["Level"], 0);
+        assert_eq!(system["Keywords"], "0x8020000000000000");
+        assert_eq!(system["EventRecordID"], 12345);
+        assert_eq!(system["Channel"], "Security");
+        assert_eq!(system["Computer"], "WIN-TEST");
+        assert!(system["Correlation"].is_null());
+        let event_data = &event["Event"]["EventData"];
+        assert_eq!(event_data["SubjectUserSid"], "S-1-5-18");
+        assert_eq!(event_data["TargetUserName"], "admin & user");
+        assert_eq!(event_data["LogonType"], 3);
+        assert_eq!(event_data["IpPort"], "-");
+        assert!(event_data["Empty"].is_null());
+        assert_eq!(
+            event["Event_attributes"]["xmlns"],
+            "http://schemas.microsoft.com/win/2004/08/events/event"
+        );
+        assert_eq!(
+            get_event_timestamp(&event),
+            Some(
+                Utc.with_ymd_and_hms(2024, 5, 1, 12, 34, 56).unwrap()
+                    + chrono::Duration::nanoseconds(123_456_700)
+            )
+        );
+    }
+
+    #[test]
+    fn test_xml_to_event_value_unnamed_data() {
+        // Name属性のないData要素が複数ある場合は配列にまとめる
+        let xml = "<Event><System><EventID>7036</EventID></System><EventData><Data>Windows Update</Data><Data>running</Data></EventData></Event>";
+        let event = xml_to_event_value(xml).unwrap();
+        assert_eq!(
+            event["Event"]["EventData"]["Data"],
+            serde_json::json!(["Windows Update", "running"])
+        );
+        assert!(get_event_timestamp(&event).is_none());
+        assert!(xml_to_event_value("<Event><System>").is_err());
+    }
+}
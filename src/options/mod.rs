@@ -1,6 +1,10 @@
 pub mod geoip_search;
 pub mod htmlreport;
 pub mod level_tuning;
+pub mod live_channel;
 pub mod pivot;
 pub mod profile;
+pub mod rule_cache;
+pub mod suppression;
 pub mod update;
+pub mod validate_rules;
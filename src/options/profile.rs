@@ -2,10 +2,11 @@ use crate::detections::configs::{Action, StoredStatic, CURRENT_EXE_PATH, GEOIP_D
 use crate::detections::message::AlertMessage;
 use crate::detections::utils::check_setting_path;
 use crate::options::profile::Profile::{
-    AllFieldInfo, Channel, Computer, Details, EventID, EvtxFile, ExtraFieldInfo, Level, Literal,
-    MitreTactics, MitreTags, OtherTags, Provider, RecordID, RecoveredRecord, RenderedMessage,
-    RuleAuthor, RuleCreationDate, RuleFile, RuleID, RuleModifiedDate, RuleTitle, SrcASN, SrcCity,
-    SrcCountry, Status, TgtASN, TgtCity, TgtCountry, Timestamp,
+    AllFieldInfo, Channel, Computer, Details, EventHash, EventID, EvtxFile, ExtraFieldInfo, Level,
+    Literal, MatchedFields, MitreTactics, MitreTags, OtherTags, Provider, RecordID,
+    RecoveredRecord, RenderedMessage, RuleAuthor, RuleCreationDate, RuleFile, RuleID,
+    RuleModifiedDate, RuleTitle, SrcASN, SrcCity, SrcCountry, Status, TgtASN, TgtCity, TgtCountry,
+    Timestamp,
 };
 use crate::yaml;
 use compact_str::CompactString;
@@ -48,6 +49,8 @@ pub enum Profile {
     TgtCity(Cow<'static, str>),
     ExtraFieldInfo(Cow<'static, str>),
     RecoveredRecord(Cow<'static, str>),
+    MatchedFields(Cow<'static, str>),
+    EventHash(Cow<'static, str>),
     Literal(Cow<'static, str>), // profiles.yamlの固定文字列を変換なしでそのまま出力する場合
 }
 
@@ -59,9 +62,8 @@ impl Profile {
             | MitreTags(v) | OtherTags(v) | RuleAuthor(v) | RuleCreationDate(v)
             | RuleModifiedDate(v) | Status(v) | RuleID(v) | Provider(v) | Details(v)
             | RenderedMessage(v) | SrcASN(v) | SrcCountry(v) | SrcCity(v) | TgtASN(v)
-            | TgtCountry(v) | TgtCity(v) | RecoveredRecord(v) | ExtraFieldInfo(v) | Literal(v) => {
-                v.to_string()
-            }
+            | TgtCountry(v) | TgtCity(v) | RecoveredRecord(v) | ExtraFieldInfo(v)
+            | MatchedFields(v) | EventHash(v) | Literal(v) => v.to_string(),
         }
     }
 
@@ -94,6 +96,8 @@ impl Profile {
             TgtCity(_) => TgtCity(converted_string.to_owned().into()),
             ExtraFieldInfo(_) => ExtraFieldInfo(converted_string.to_owned().into()),
             RecoveredRecord(_) => RecoveredRecord(converted_string.to_owned().into()),
+            MatchedFields(_) => MatchedFields(converted_string.to_owned().into()),
+            EventHash(_) => EventHash(converted_string.to_owned().into()),
             Details(_) => Details(converted_string.to_owned().into()),
             AllFieldInfo(_) => AllFieldInfo(converted_string.to_owned().into()),
             p => p.to_owned(),
@@ -127,6 +131,8 @@ impl From<&str> for Profile {
             "%RenderedMessage%" => RenderedMessage(Default::default()),
             "%ExtraFieldInfo%" => ExtraFieldInfo(Default::default()),
             "%RecoveredRecord%" => RecoveredRecord(Default::default()),
+            "%MatchedFields%" => MatchedFields(Default::default()),
+            "%EventHash%" => EventHash(Default::default()),
             s => Literal(s.to_string().into()), // profiles.yamlの固定文字列を変換なしでそのまま出力する場合
         }
     }
@@ -256,6 +262,12 @@ pub fn load_profile(
                 RecoveredRecord(Cow::default()),
             ));
         }
+        if opt.matched_fields && !ret.iter().any(|(_, p)| matches!(p, MatchedFields(_))) {
+            ret.push((
+                CompactString::from("MatchedFields"),
+                MatchedFields(Cow::default()),
+            ));
+        }
     }
     Some(ret)
 }
@@ -484,6 +496,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -504,7 +517,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -517,10 +530,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -529,6 +545,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -565,6 +597,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -585,7 +618,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -598,10 +631,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -610,6 +646,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -676,6 +728,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -696,7 +749,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -709,10 +762,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -721,6 +777,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -0,0 +1,232 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use yaml_rust::Yaml;
+
+/// キャッシュファイルの形式を変更した場合や、パース結果が変わる可能性があるバージョンアップ時にキャッシュを無効にするための値
+const RULE_CACHE_VERSION: &str = concat!("2-", env!("CARGO_PKG_VERSION"));
+
+/// yaml_rust::Yamlをシリアライズするための型。HashはYAMLに記載された順番を保持するため配列で保存する
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+enum CachedYaml {
+    Real(String),
+    Integer(i64),
+    String(String),
+    Boolean(bool),
+    Array(Vec<CachedYaml>),
+    Hash(Vec<(CachedYaml, CachedYaml)>),
+    Alias(usize),
+    Null,
+    BadValue,
+}
+
+impl From<&Yaml> for CachedYaml {
+    fn from(yaml: &Yaml) -> Self {
+        match yaml {
+            Yaml::Real(s) => CachedYaml::Real(s.to_owned()),
+            Yaml::Integer(i) => CachedYaml::Integer(*i),
+            Yaml::String(s) => CachedYaml::String(s.to_owned()),
+            Yaml::Boolean(b) => CachedYaml::Boolean(*b),
+            Yaml::Array(ary) => CachedYaml::Array(ary.iter().map(CachedYaml::from).collect()),
+            Yaml::Hash(hash) => CachedYaml::Hash(
+                hash.iter()
+                    .map(|(k, v)| (CachedYaml::from(k), CachedYaml::from(v)))
+                    .collect(),
+            ),
+            Yaml::Alias(i) => CachedYaml::Alias(*i),
+            Yaml::Null => CachedYaml::Null,
+            Yaml::BadValue => CachedYaml::BadValue,
+        }
+    }
+}
+
+impl From<&CachedYaml> for Yaml {
+    fn from(cached_yaml: &CachedYaml) -> Self {
+        match cached_yaml {
+            CachedYaml::Real(s) => Yaml::Real(s.to_owned()),
+            CachedYaml::Integer(i) => Yaml::Integer(*i),
+            CachedYaml::String(s) => Yaml::String(s.to_owned()),
+            CachedYaml::Boolean(b) => Yaml::Boolean(*b),
+            CachedYaml::Array(ary) => Yaml::Array(ary.iter().map(Yaml::from).collect()),
+            CachedYaml::Hash(hash) => Yaml::Hash(
+                hash.iter()
+                    .map(|(k, v)| (Yaml::from(k), Yaml::from(v)))
+                    .collect(),
+            ),
+            CachedYaml::Alias(i) => Yaml::Alias(*i),
+            CachedYaml::Null => Yaml::Null,
+            CachedYaml::BadValue => Yaml::BadValue,
+        }
+    }
+}
+
+/// 1つのルールファイルのパース結果
+#[derive(Serialize, Deserialize, Debug)]
+struct RuleCacheEntry {
+    modified: u64, // ファイルの更新日時(UNIX時間のナノ秒)
+    size: u64,     // ファイルサイズ
+    docs: Vec<CachedYaml>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct RuleCacheFile {
+    version: String,
+    entries: BTreeMap<String, RuleCacheEntry>, // ルールファイルのパスとパース結果
+}
+
+/// --rule-cacheで指定されたファイルに、ルールファイルをパースした結果を保存する。
+/// 更新日時とファイルサイズが一致するルールファイルは、次回以降の実行時にファイルの読み込みとYAMLのパースを省略する。
+/// ルールの初期化(正規表現のコンパイルを含む)はキャッシュできないので、キャッシュの有無に関わらず毎回行う
+pub struct RuleCache {
+    path: PathBuf,
+    cache: RuleCacheFile,
+    is_updated: bool,
+    pub hit_cnt: usize,
+}
+
+impl RuleCache {
+    /// キャッシュファイルを読み込む。ファイルが存在しない場合や壊れている場合、バージョンが異なる場合は空のキャッシュとする
+    pub fn load(path: &Path) -> RuleCache {
+        let cache = fs::read(path)
+            .ok()
+            .and_then(|contents| serde_json::from_slice::<RuleCacheFile>(&contents).ok())
+            .filter(|cache| cache.version == RULE_CACHE_VERSION)
+            .unwrap_or_else(|| RuleCacheFile {
+                version: RULE_CACHE_VERSION.to_string(),
+                entries: BTreeMap::new(),
+            });
+        RuleCache {
+            path: path.to_path_buf(),
+            cache,
+            is_updated: false,
+            hit_cnt: 0,
+        }
+    }
+
+    /// ルールファイルを読み込まずに、メタデータから更新日時とファイルサイズを取得する。取得できない場合はキャッシュを使わない
+    pub fn get_file_key(filepath: &Path) -> Option<(u64, u64)> {
+        let metadata = fs::metadata(filepath).ok()?;
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos() as u64;
+        Some((modified, metadata.len()))
+    }
+
+    /// 更新日時とファイルサイズが一致する場合のみ、キャッシュされたパース結果を返す
+    pub fn get(&mut self, filepath: &str, file_key: (u64, u64)) -> Option<Vec<Yaml>> {
+        let (modified, size) = file_key;
+        let docs = self
+            .cache
+            .entries
+            .get(filepath)
+            .filter(|entry| entry.modified == modified && entry.size == size)
+            .map(|entry| entry.docs.iter().map(Yaml::from).collect())?;
+        self.hit_cnt += 1;
+        Some(docs)
+    }
+
+    pub fn insert(&mut self, filepath: &str, file_key: (u64, u64), docs: &[Yaml]) {
+        let (modified, size) = file_key;
+        self.cache.entries.insert(
+            filepath.to_string(),
+            RuleCacheEntry {
+                modified,
+                size,
+                docs: docs.iter().map(CachedYaml::from).collect(),
+            },
+        );
+        self.is_updated = true;
+    }
+
+    /// キャッシュに変更があった場合のみ保存する。削除されたルールファイルのパース結果はこの時に取り除く
+    pub fn save(&mut self) -> io::Result<()> {
+        let entry_cnt = self.cache.entries.len();
+        self.cache
+            .entries
+            .retain(|filepath, _| Path::new(filepath).exists());
+        if !self.is_updated && entry_cnt == self.cache.entries.len() {
+            return Ok(());
+        }
+        let contents = serde_json::to_vec(&self.cache)?;
+        fs::write(&self.path, contents)?;
+        self.is_updated = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RuleCache;
+    use std::fs;
+    use yaml_rust::YamlLoader;
+
+    #[test]
+    fn test_rule_cache_roundtrip() {
+        // キャッシュに保存したパース結果が、キャッシュファイルから読み込んだ後も元のYAMLと一致すること
+        let test_dir = std::env::temp_dir().join("hayabusa_test_rule_cache_roundtrip");
+        fs::create_dir_all(&test_dir).unwrap();
+        let rule_path = test_dir.join("rule.yml");
+        let cache_path = test_dir.join("rule_cache.json");
+        let rule_str = "title: test\nid: 1\nlevel: high\ndetection:\n    selection:\n        EventID: [4624, 4625]\n        Rate|gt: 1.5\n        Enabled: true\n        Null: ~\n    condition: selection\n";
+        fs::write(&rule_path, rule_str).unwrap();
+        let _ = fs::remove_file(&cache_path);
+        let yaml_contents = YamlLoader::load_from_str(rule_str).unwrap();
+        let filepath = rule_path.display().to_string();
+        let file_key = RuleCache::get_file_key(&rule_path).unwrap();
+
+        let mut rule_cache = RuleCache::load(&cache_path);
+        assert!(rule_cache.get(&filepath, file_key).is_none());
+        rule_cache.insert(&filepath, file_key, &yaml_contents);
+        rule_cache.save().unwrap();
+
+        let mut rule_cache = RuleCache::load(&cache_path);
+        assert_eq!(rule_cache.get(&filepath, file_key), Some(yaml_contents));
+        assert_eq!(rule_cache.hit_cnt, 1);
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rule_cache_invalidate() {
+        // ルールファイルが更新された場合や、ルールファイルが削除された場合はキャッシュを使わないこと
+        let test_dir = std::env::temp_dir().join("hayabusa_test_rule_cache_invalidate");
+        fs::create_dir_all(&test_dir).unwrap();
+        let rule_path = test_dir.join("rule.yml");
+        let cache_path = test_dir.join("rule_cache.json");
+        fs::write(&rule_path, "title: test1").unwrap();
+        let _ = fs::remove_file(&cache_path);
+        let filepath = rule_path.display().to_string();
+        let file_key = RuleCache::get_file_key(&rule_path).unwrap();
+
+        let mut rule_cache = RuleCache::load(&cache_path);
+        rule_cache.insert(
+            &filepath,
+            file_key,
+            &YamlLoader::load_from_str("title: test1").unwrap(),
+        );
+        rule_cache.save().unwrap();
+
+        fs::write(&rule_path, "title: test22").unwrap();
+        let changed_file_key = RuleCache::get_file_key(&rule_path).unwrap();
+        assert_ne!(changed_file_key, file_key);
+        let mut rule_cache = RuleCache::load(&cache_path);
+        assert!(rule_cache.get(&filepath, changed_file_key).is_none());
+        assert!(rule_cache.get(&filepath, file_key).is_some());
+
+        fs::remove_file(&rule_path).unwrap();
+        rule_cache.save().unwrap();
+        let mut rule_cache = RuleCache::load(&cache_path);
+        assert!(rule_cache.get(&filepath, file_key).is_none());
+
+        // 壊れたキャッシュファイルは空のキャッシュとして扱う
+        fs::write(&cache_path, "{broken").unwrap();
+        let mut rule_cache = RuleCache::load(&cache_path);
+        assert!(rule_cache.get(&filepath, file_key).is_none());
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+}
@@ -0,0 +1,195 @@
+use crate::detections::configs::EventKeyAliasConfig;
+use crate::detections::utils;
+use hashbrown::HashSet;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// 値全体に加えて、値に含まれるハッシュ値のいずれかとも比較するレコードのフィールド
+/// SysmonのHashesは`SHA1=...,MD5=...,SHA256=...,IMPHASH=...`の形式で複数のハッシュ値を持つ
+const HASH_FIELDS: [&str; 2] = ["Hashes", "Hash"];
+
+/// --suppressで指定したファイルに記載された、既知の誤検知を抑制するためのエントリ
+///
+/// ファイルの書式は以下の通り。空行と`#`で始まる行は無視する。
+/// - `フィールド名:値` : ルールが検知したレコードのフィールドの値が一致した場合に抑制する(大文字小文字は区別しない)。
+///   HashesまたはHashフィールドは、含まれるハッシュ値(MD5/SHA1/SHA256/IMPHASH)のいずれかと一致した場合も抑制する
+/// - `イベントハッシュ` : レコード全体のSHA256のハッシュ値(%EventHash%で出力される値)と一致した場合に抑制する
+#[derive(Debug, Clone, Default)]
+pub struct SuppressionList {
+    field_values: Vec<(String, String)>,
+    event_hashes: HashSet<String>,
+}
+
+/// レコード全体をシリアライズした文字列のSHA256のハッシュ値を返す。同じイベントであれば常に同じ値になる
+pub fn event_hash(record: &Value) -> String {
+    hex::encode(Sha256::digest(record.to_string().as_bytes()))
+}
+
+impl SuppressionList {
+    /// 抑制ファイルを読み込む。書式に誤りがある行はエラーメッセージとして返す
+    pub fn load(path: &str) -> Result<SuppressionList, Vec<String>> {
+        let lines = utils::read_txt(path).map_err(|e| vec![e])?;
+        SuppressionList::parse(lines.iter(), path)
+    }
+
+    /// 抑制ファイルの各行をパースする
+    pub fn parse<'a>(
+        lines: impl Iterator<Item = &'a str>,
+        path: &str,
+    ) -> Result<SuppressionList, Vec<String>> {
+        let mut ret = SuppressionList::default();
+        let mut errors = vec![];
+        for (i, line) in lines.enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some((field, value)) = line.split_once(':') {
+                let (field, value) = (field.trim(), value.trim());
+                if field.is_empty() || value.is_empty() {
+                    errors.push(format!(
+                        "Failed to read the suppression file. The field name or value is empty. (FilePath : {path}, Line : {})",
+                        i + 1
+                    ));
+                    continue;
+                }
+                ret.field_values
+                    .push((field.to_string(), value.to_lowercase()));
+            } else if Self::is_event_hash(line) {
+                ret.event_hashes.insert(line.to_lowercase());
+            } else {
+                errors.push(format!(
+                    "Failed to read the suppression file. Please specify a Field:Value pair or an event hash (SHA256). (FilePath : {path}, Line : {})",
+                    i + 1
+                ));
+            }
+        }
+        if errors.is_empty() {
+            Ok(ret)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// SHA256(64文字)の16進数の文字列かどうかを判定する
+    fn is_event_hash(value: &str) -> bool {
+        value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    pub fn len(&self) -> usize {
+        self.field_values.len() + self.event_hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// ルールが検知したレコードが抑制の対象かどうかを判定する
+    pub fn is_suppressed(&self, record: &Value, eventkey_alias: &EventKeyAliasConfig) -> bool {
+        let is_field_match = self.field_values.iter().any(|(field, value)| {
+            let event_value = utils::get_event_value(field, record, eventkey_alias)
+                .and_then(utils::value_to_string)
+                .map(|event_value| event_value.to_lowercase());
+            event_value.is_some_and(|event_value| {
+                event_value == *value
+                    || (HASH_FIELDS.contains(&field.as_str())
+                        && event_value
+                            .split([',', '='])
+                            .any(|hash| hash.trim() == value))
+            })
+        });
+        if is_field_match || self.event_hashes.is_empty() {
+            return is_field_match;
+        }
+        self.event_hashes.contains(&event_hash(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{event_hash, SuppressionList};
+    use crate::detections::configs::EventKeyAliasConfig;
+
+    fn parse(lines: &[&str]) -> Result<SuppressionList, Vec<String>> {
+        SuppressionList::parse(lines.iter().copied(), "test.txt")
+    }
+
+    #[test]
+    fn test_parse_suppression_list() {
+        let suppression_list = parse(&[
+            "# known false positives",
+            "",
+            "CommandLine: C:\\Windows\\system32\\svchost.exe -k netsvcs",
+            "Hashes: 8A2DBDD6A4C2F2D5A7F9C5E1B0F4C0D1",
+            "4C0D7F1A0B25D0B3D7F9E8A6C5B4A3928171605F4E3D2C1B0A99887766554433",
+        ])
+        .unwrap();
+        assert_eq!(suppression_list.len(), 3);
+
+        // イベントハッシュはSHA256のみ。MD5などのファイルのハッシュ値はHashes:の形式で指定する
+        let errors = parse(&[
+            "CommandLine:",
+            "not a hash",
+            "8a2dbdd6a4c2f2d5a7f9c5e1b0f4c0d1",
+        ])
+        .unwrap_err();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].contains("Line : 1"));
+        assert!(errors[1].contains("Line : 2"));
+        assert!(errors[2].contains("Line : 3"));
+    }
+
+    #[test]
+    fn test_is_suppressed() {
+        let suppression_list = parse(&[
+            "CommandLine: C:\\Windows\\system32\\svchost.exe -k netsvcs",
+            "Hashes: 8a2dbdd6a4c2f2d5a7f9c5e1b0f4c0d1",
+        ])
+        .unwrap();
+        let eventkey_alias = EventKeyAliasConfig::new();
+        let create_record = |command_line: &str, hashes: &str| {
+            serde_json::json!({
+                "Event": {
+                    "System": {"EventID": 1},
+                    "EventData": {"CommandLine": command_line, "Hashes": hashes}
+                }
+            })
+        };
+
+        // フィールドの値は大文字小文字を区別せずに比較する
+        let record = create_record("c:\\windows\\System32\\svchost.exe -k netsvcs", "MD5=00");
+        assert!(suppression_list.is_suppressed(&record, &eventkey_alias));
+
+        // Hashesに含まれるハッシュ値のいずれかが一致すれば抑制する
+        let record = create_record(
+            "cmd.exe /c whoami",
+            "SHA1=00,MD5=8A2DBDD6A4C2F2D5A7F9C5E1B0F4C0D1,IMPHASH=00",
+        );
+        assert!(suppression_list.is_suppressed(&record, &eventkey_alias));
+
+        let record = create_record("cmd.exe /c whoami", "MD5=00");
+        assert!(!suppression_list.is_suppressed(&record, &eventkey_alias));
+    }
+
+    #[test]
+    fn test_is_suppressed_by_event_hash() {
+        let create_record = |record_id: u64| {
+            serde_json::json!({
+                "Event": {
+                    "System": {"EventID": 1, "EventRecordID": record_id},
+                    "EventData": {"CommandLine": "cmd.exe /c whoami"}
+                }
+            })
+        };
+        let target = create_record(1);
+        let hash = event_hash(&target);
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, event_hash(&create_record(1)));
+
+        // イベント全体のハッシュ値なので、同じコマンドラインでも別のイベントは抑制しない
+        let suppression_list = parse(&[hash.to_uppercase().as_str()]).unwrap();
+        let eventkey_alias = EventKeyAliasConfig::new();
+        assert!(suppression_list.is_suppressed(&target, &eventkey_alias));
+        assert!(!suppression_list.is_suppressed(&create_record(2), &eventkey_alias));
+    }
+}
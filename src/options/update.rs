@@ -2,7 +2,7 @@ use crate::detections::configs::StoredStatic;
 use crate::detections::message::AlertMessage;
 use crate::detections::utils::write_color_buffer;
 use crate::filter;
-use crate::yaml::ParseYaml;
+use crate::yaml::{format_rule_path, ParseYaml};
 use git2::{ErrorCode, Repository};
 use serde_json::Value;
 use std::fs::{self, create_dir};
@@ -194,20 +194,26 @@ impl Update {
             )
             .ok();
 
-        HashMap::from_iter(rulefile_loader.files.into_iter().map(|(filepath, yaml)| {
-            let yaml_date = yaml["date"].as_str().unwrap_or("-");
-            (
-                filepath.clone(),
-                format!(
-                    "{}|{}|{}|{}|{:?}",
-                    yaml["title"].as_str().unwrap_or(&String::default()),
-                    yaml["modified"].as_str().unwrap_or(yaml_date),
-                    &filepath,
-                    yaml["ruletype"].as_str().unwrap_or("Other"),
-                    yaml
-                ),
-            )
-        }))
+        HashMap::from_iter(
+            rulefile_loader
+                .files
+                .into_iter()
+                .map(|(filepath, doc_index, yaml)| {
+                    let yaml_date = yaml["date"].as_str().unwrap_or("-");
+                    (
+                        // 1つのファイルに複数のルールがある場合もキーが重複しないように、ドキュメントの番号を付与する
+                        format_rule_path(&filepath, doc_index),
+                        format!(
+                            "{}|{}|{}|{}|{:?}",
+                            yaml["title"].as_str().unwrap_or(&String::default()),
+                            yaml["modified"].as_str().unwrap_or(yaml_date),
+                            &filepath,
+                            yaml["ruletype"].as_str().unwrap_or("Other"),
+                            yaml
+                        ),
+                    )
+                }),
+        )
     }
 
     /// print updated rule files.
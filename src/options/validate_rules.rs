@@ -0,0 +1,208 @@
+use crate::detections::configs::StoredStatic;
+use crate::detections::detection::{DetectionError, DETECTION_ERROR_REPORT};
+use crate::detections::rule;
+use crate::detections::rule::schema::validate_rule_schema;
+use crate::detections::utils::{get_writable_color, write_color_buffer};
+use crate::filter::RuleExclude;
+use crate::yaml::ParseYaml;
+use num_format::{Locale, ToFormattedString};
+use termcolor::{BufferWriter, Color, ColorChoice};
+
+/// ルールの検証結果
+#[derive(Debug, Default)]
+pub struct RuleValidationResult {
+    pub valid_cnt: usize,
+    pub errors: Vec<(String, Vec<String>)>, // 検証に失敗したルールファイルのパスとエラーメッセージ
+}
+
+impl RuleValidationResult {
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+pub struct ValidateRules {}
+
+impl ValidateRules {
+    /// イベントログを読み込まずに、指定されたフォルダ配下のすべてのルールをパースして初期化し、エラーがないかを確認する
+    pub fn run(
+        rules_path: &str,
+        stored_static: &StoredStatic,
+    ) -> Result<RuleValidationResult, String> {
+        let mut rulefile_loader = ParseYaml::new(stored_static);
+        // levelやnoisy/excludeの設定に関わらず、すべてのルールを検証対象とする
+        rulefile_loader
+            .read_dir(
+                rules_path,
+                "informational",
+                "",
+                &RuleExclude::new(),
+                stored_static,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let mut result = RuleValidationResult::default();
+        // YAMLとして読み込めなかったルールファイル
+        DETECTION_ERROR_REPORT.lock().unwrap().rule_error_cnt +=
+            rulefile_loader.errorrule_msgs.len();
+        for (filepath, errmsg) in rulefile_loader.errorrule_msgs {
+            result
+                .errors
+                .push((filepath, vec![errmsg.trim().to_string()]));
+        }
+
+        // 必須のフィールドやフィールドの型などのルールの構造と、
+        // 正規表現のコンパイルやconditionのパースなど、ルールの初期化時に発生するエラーを確認する
+        for (filepath, doc_index, yaml) in rulefile_loader.files {
+            let title = yaml["title"].as_str().unwrap_or("-").to_string();
+            let mut errmsgs: Vec<String> = validate_rule_schema(&yaml)
+                .iter()
+                .map(|err| err.to_string())
+                .collect();
+            let mut rule = rule::create_rule(filepath, yaml);
+            rule.doc_index = doc_index;
+            let rulepath = rule.get_rulepath_with_doc_index();
+            if let Err(init_errmsgs) = rule.init(stored_static) {
+                errmsgs.extend(init_errmsgs);
+            }
+            if errmsgs.is_empty() {
+                result.valid_cnt += 1;
+            } else {
+                DETECTION_ERROR_REPORT
+                    .lock()
+                    .unwrap()
+                    .add(&DetectionError::Rule {
+                        rulepath: rulepath.clone(),
+                        title,
+                    });
+                result.errors.push((rulepath, errmsgs));
+            }
+        }
+        result.errors.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(result)
+    }
+
+    /// 検証結果をルールファイル毎に出力する
+    pub fn print_result(result: &RuleValidationResult, no_color: bool) {
+        let stdout = BufferWriter::stdout(ColorChoice::Always);
+        for (filepath, errmsgs) in &result.errors {
+            write_color_buffer(
+                &stdout,
+                get_writable_color(Some(Color::Red), no_color),
+                "[FAIL] ",
+                false,
+            )
+            .ok();
+            write_color_buffer(&stdout, None, filepath, true).ok();
+            for errmsg in errmsgs {
+                write_color_buffer(&stdout, None, &format!("  - {errmsg}"), true).ok();
+            }
+        }
+        if !result.errors.is_empty() {
+            println!();
+        }
+
+        let total_cnt = result.valid_cnt + result.errors.len();
+        write_color_buffer(
+            &stdout,
+            None,
+            &format!(
+                "Validated rules: {} / Passed: {} / Failed: {}",
+                total_cnt.to_formatted_string(&Locale::en),
+                result.valid_cnt.to_formatted_string(&Locale::en),
+                result.errors.len().to_formatted_string(&Locale::en)
+            ),
+            true,
+        )
+        .ok();
+        let (color, msg) = if result.is_success() {
+            (Color::Green, "All rules passed validation.")
+        } else {
+            (Color::Red, "Some rules failed validation.")
+        };
+        write_color_buffer(
+            &stdout,
+            get_writable_color(Some(color), no_color),
+            msg,
+            true,
+        )
+        .ok();
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+    use crate::detections::configs::{Action, CommonOptions, Config, ValidateRulesOption};
+
+    fn create_dummy_stored_static() -> StoredStatic {
+        let mut stored_static = StoredStatic::create_static_data(Some(Config {
+            action: Some(Action::ValidateRules(ValidateRulesOption {
+                common_options: CommonOptions {
+                    no_color: false,
+                    quiet: false,
+                    help: None,
+                },
+                rules: Path::new("./rules").to_path_buf(),
+            })),
+            debug: false,
+        }));
+        stored_static.include_status.insert("*".into());
+        stored_static
+    }
+
+    #[test]
+    fn test_validate_rules() {
+        let dir = &std::env::temp_dir().join("hayabusa_test_validate_rules");
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("valid.yml"),
+            "title: valid\nid: 00000000-0000-0000-0000-000000000001\ndetection:\n    selection:\n        EventID: 4688\n    condition: selection\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("bad_regex.yml"),
+            "title: bad regex\nid: 00000000-0000-0000-0000-000000000002\ndetection:\n    selection:\n        CommandLine|re: '(abc'\n    condition: selection\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("undefined_selection.yml"),
+            "title: undefined selection\nid: 00000000-0000-0000-0000-000000000003\ndetection:\n    selection:\n        EventID: 4688\n    condition: selection and filter\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("bad_schema.yml"),
+            "title: bad schema\nid: 00000000-0000-0000-0000-000000000004\nlevel: severe\ntags: attack.execution\ndetection:\n    selection:\n        EventID: 4688\n    condition: selection\n",
+        )
+        .unwrap();
+        fs::write(dir.join("broken_yaml.yml"), "title: [broken\n").unwrap();
+
+        let result = ValidateRules::run(dir.to_str().unwrap(), &create_dummy_stored_static());
+        fs::remove_dir_all(dir).ok();
+
+        let result = result.unwrap();
+        assert!(!result.is_success());
+        assert_eq!(result.valid_cnt, 1);
+        let failed_files: Vec<&str> = result
+            .errors
+            .iter()
+            .map(|(filepath, _)| Path::new(filepath).file_name().unwrap().to_str().unwrap())
+            .collect();
+        assert_eq!(
+            failed_files,
+            vec![
+                "bad_regex.yml",
+                "bad_schema.yml",
+                "broken_yaml.yml",
+                "undefined_selection.yml"
+            ]
+        );
+        assert!(result.errors.iter().all(|(_, errmsgs)| !errmsgs.is_empty()));
+        // 構造の検証で見つかったエラーはフィールド毎に出力する
+        assert_eq!(result.errors[1].1.len(), 2);
+    }
+}
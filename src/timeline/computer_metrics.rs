@@ -12,7 +12,6 @@ use num::FromPrimitive;
 use num_format::{Locale, ToFormattedString};
 use serde_json::Value;
 use std::cmp::Ordering;
-use std::fs::File;
 use std::io::BufWriter;
 use std::path::PathBuf;
 
@@ -43,7 +42,7 @@ pub fn computer_metrics_dsp_msg(
 ) {
     let mut file_wtr = None;
     if let Some(path) = output {
-        match File::create(path) {
+        match utils::create_output_file(path) {
             Ok(file) => {
                 file_wtr = Some(
                     WriterBuilder::new()
@@ -53,7 +52,8 @@ pub fn computer_metrics_dsp_msg(
                 )
             }
             Err(err) => {
-                AlertMessage::alert(&format!("Failed to open file. {err}")).ok();
+                AlertMessage::alert(&format!("Failed to open file. {} : {err}", path.display()))
+                    .ok();
                 process::exit(1)
             }
         }
@@ -134,6 +134,7 @@ mod tests {
                     directory: None,
                     filepath: Some(Path::new("./dummy.evtx").to_path_buf()),
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -147,7 +148,8 @@ mod tests {
                 thread_number: None,
                 quiet_errors: false,
                 config: Path::new("./rules/config").to_path_buf(),
-                verbose: false,
+                verbose: 0,
+                no_progress: false,
                 output: output.clone(),
                 clobber: true,
             }));
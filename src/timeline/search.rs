@@ -20,7 +20,6 @@ use downcast_rs::__std::process;
 use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
 use regex::Regex;
-use std::fs::File;
 use std::io::BufWriter;
 use std::path::PathBuf;
 use termcolor::{BufferWriter, Color, ColorChoice};
@@ -363,7 +362,7 @@ pub fn search_result_dsp_msg(
     let mut disp_wtr = None;
     let mut file_wtr = None;
     if let Some(path) = output {
-        match File::create(path) {
+        match utils::create_output_file(path) {
             Ok(file) => {
                 if json_output || jsonl_output {
                     file_wtr = Some(
@@ -383,7 +382,8 @@ pub fn search_result_dsp_msg(
                 }
             }
             Err(err) => {
-                AlertMessage::alert(&format!("Failed to open file. {err}")).ok();
+                AlertMessage::alert(&format!("Failed to open file. {} : {err}", path.display()))
+                    .ok();
                 process::exit(1)
             }
         }
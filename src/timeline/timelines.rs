@@ -1,7 +1,6 @@
 use std::cmp;
-use std::fs::File;
 use std::io::BufWriter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::detections::configs::{Action, EventInfoConfig, StoredStatic};
 use crate::detections::detection::EvtxRecordInfo;
@@ -142,13 +141,17 @@ impl Timeline {
                 }
                 wtr = if let Some(csv_path) = option.output.as_ref() {
                     // output to file
-                    match File::create(csv_path) {
+                    match utils::create_output_file(csv_path) {
                         Ok(file) => {
                             target = Box::new(BufWriter::new(file));
                             Some(WriterBuilder::new().from_writer(target))
                         }
                         Err(err) => {
-                            AlertMessage::alert(&format!("Failed to open file. {err}")).ok();
+                            AlertMessage::alert(&format!(
+                                "Failed to open file. {} : {err}",
+                                csv_path.display()
+                            ))
+                            .ok();
                             process::exit(1);
                         }
                     }
@@ -357,13 +360,13 @@ impl Timeline {
         let mut wtr = if let Some(csv_path) = output {
             let file_name = csv_path.as_path().display().to_string() + "-" + logon_res + ".csv";
             // output to file
-            match File::create(file_name) {
+            match utils::create_output_file(Path::new(&file_name)) {
                 Ok(file) => {
                     target = Box::new(BufWriter::new(file));
                     Some(WriterBuilder::new().from_writer(target))
                 }
                 Err(err) => {
-                    AlertMessage::alert(&format!("Failed to open file. {err}")).ok();
+                    AlertMessage::alert(&format!("Failed to open file. {file_name} : {err}")).ok();
                     process::exit(1);
                 }
             }
@@ -531,6 +534,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -545,9 +549,12 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 european_time: false,
                 iso_8601: false,
@@ -719,6 +726,7 @@ mod tests {
                     directory: None,
                     filepath: None,
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -733,9 +741,12 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 european_time: false,
                 iso_8601: false,
@@ -810,6 +821,7 @@ mod tests {
                     directory: None,
                     filepath: Some(Path::new("./dummy.evtx").to_path_buf()),
                     live_analysis: false,
+                    live_channel: None,
                     recover_records: false,
                     timeline_offset: None,
                 },
@@ -824,9 +836,12 @@ mod tests {
                     thread_number: None,
                     quiet_errors: false,
                     config: Path::new("./rules/config").to_path_buf(),
-                    verbose: false,
+                    verbose: 0,
+                    no_progress: false,
                     include_computer: None,
                     exclude_computer: None,
+                    strict: false,
+                    alias_config: None,
                 },
                 european_time: false,
                 iso_8601: false,
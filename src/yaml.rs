@@ -6,24 +6,163 @@ use crate::detections::message::AlertMessage;
 use crate::detections::message::ERROR_LOG_STACK;
 use crate::detections::utils;
 use crate::filter::RuleExclude;
+use crate::options::rule_cache::RuleCache;
 use compact_str::CompactString;
 use hashbrown::{HashMap, HashSet};
 use itertools::Itertools;
+use std::collections::hash_map::DefaultHasher;
 use std::ffi::OsStr;
 use std::fs;
+use std::hash::{Hash, Hasher};
 use std::io::{self, BufReader, Read};
 use std::path::{Path, PathBuf};
-use yaml_rust::YamlLoader;
+use wildmatch::WildMatch;
+use yaml_rust::{Yaml, YamlLoader};
+
+/// Windowsで作成されたBOM付きUTF-8やUTF-16のファイルも読み込めるように、バイト列をUTF-8の文字列に変換する。
+/// BOMがない場合は、YAMLの仕様と同じく先頭2バイトの0x00の位置からUTF-16LE/UTF-16BEを判定する。
+fn decode_yaml_bytes(bytes: &[u8]) -> Result<String, String> {
+    let decode_utf16 = |body: &[u8], is_le: bool| {
+        if body.len() % 2 != 0 {
+            return Err(format!(
+                "Failed to decode as UTF-16{}: odd number of bytes",
+                if is_le { "LE" } else { "BE" }
+            ));
+        }
+        let units: Vec<u16> = body
+            .chunks_exact(2)
+            .map(|c| {
+                if is_le {
+                    u16::from_le_bytes([c[0], c[1]])
+                } else {
+                    u16::from_be_bytes([c[0], c[1]])
+                }
+            })
+            .collect();
+        String::from_utf16(&units).map_err(|e| {
+            format!(
+                "Failed to decode as UTF-16{}: {e}",
+                if is_le { "LE" } else { "BE" }
+            )
+        })
+    };
+    match bytes {
+        [0xEF, 0xBB, 0xBF, body @ ..] => {
+            String::from_utf8(body.to_vec()).map_err(|e| format!("Failed to decode as UTF-8: {e}"))
+        }
+        [0xFF, 0xFE, body @ ..] => decode_utf16(body, true),
+        [0xFE, 0xFF, body @ ..] => decode_utf16(body, false),
+        [first, 0x00, ..] if *first != 0x00 => decode_utf16(bytes, true),
+        [0x00, second, ..] if *second != 0x00 => decode_utf16(bytes, false),
+        _ => {
+            String::from_utf8(bytes.to_vec()).map_err(|e| format!("Failed to decode as UTF-8: {e}"))
+        }
+    }
+}
+
+/// ルールファイルとして読み込む拡張子(.yml/.yaml)のファイルであるかを判定する
+fn is_rule_file(path: &Path) -> bool {
+    let ext = path.extension().unwrap_or_else(|| OsStr::new(""));
+    ext == "yml" || ext == "yaml"
+}
+
+/// 1つのファイルに`---`で区切られた複数のルールが記載されている場合に対応するため、空のドキュメントを除外して、
+/// ファイルパスとファイル内でのドキュメントの番号(0始まり)を付与する。ドキュメントが1つだけの場合は番号をNoneとする。
+fn split_rule_docs(
+    filepath: String,
+    yaml_contents: Vec<Yaml>,
+) -> Vec<(String, Option<usize>, Yaml)> {
+    let is_multi_doc = yaml_contents
+        .iter()
+        .filter(|yaml_content| !yaml_content.is_null() && !yaml_content.is_badvalue())
+        .count()
+        > 1;
+    yaml_contents
+        .into_iter()
+        .enumerate()
+        .filter(|(_, yaml_content)| !yaml_content.is_null() && !yaml_content.is_badvalue())
+        .map(|(i, yaml_content)| {
+            let doc_index = if is_multi_doc { Some(i) } else { None };
+            (filepath.clone(), doc_index, yaml_content)
+        })
+        .collect()
+}
+
+/// エラーメッセージなどに出力するために、ファイル内のドキュメントの番号がある場合はファイルパスに付与する
+pub fn format_rule_path(filepath: &str, doc_index: Option<usize>) -> String {
+    match doc_index {
+        Some(i) => format!("{filepath} (Document: {})", i + 1),
+        None => filepath.to_string(),
+    }
+}
 
 pub struct ParseYaml {
-    pub files: Vec<(String, yaml_rust::Yaml)>,
+    pub files: Vec<(String, Option<usize>, yaml_rust::Yaml)>, // ルールファイルのパス、ファイル内でのドキュメントの番号、ルールの内容
     pub rulecounter: HashMap<CompactString, u128>,
     pub rule_load_cnt: HashMap<CompactString, u128>,
     pub rule_status_cnt: HashMap<CompactString, u128>,
     pub errorrule_count: u128,
+    pub errorrule_msgs: Vec<(String, String)>, // 読み込みに失敗したルールファイルのパスとエラーメッセージ
     pub exclude_status: HashSet<String>,
     pub level_map: HashMap<String, u128>,
-    pub loaded_rule_ids: HashSet<CompactString>,
+    pub loaded_rule_ids: HashMap<CompactString, (usize, String)>, // 読み込んだルールのIDと、そのルールを読み込んだ-rのフォルダの番号とファイルパス
+    pub rule_root_index: usize, // -rで複数のフォルダが指定された場合に、現在読み込んでいるフォルダの番号
+    pub loaded_rule_hashes: HashMap<u64, String>, // 読み込んだルールの内容のハッシュ値と、そのルールのファイルパス
+    pub enable_rule_patterns: Vec<WildMatch>,
+    pub exclude_rule_patterns: Vec<WildMatch>,
+    pub rule_cache: Option<RuleCache>, // --rule-cacheで指定されたルールのパース結果のキャッシュ
+    pub mitre_match_cnt: u128, // --mitreで指定されたタクティクス、テクニックに一致したルールの数
+}
+
+/// ルールIDまたはファイル名を大文字小文字を区別せずに比較するためのパターンを作成する
+fn create_rule_patterns(patterns: Option<&Vec<String>>) -> Vec<WildMatch> {
+    patterns
+        .map(|patterns| {
+            patterns
+                .iter()
+                .map(|pattern| WildMatch::new(&pattern.trim().replace('\\', "/").to_lowercase()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// ルールID、ファイル名、ファイルパスのいずれかが指定されたパターンに合致するかを判定する
+fn is_match_rule_patterns(patterns: &[WildMatch], rule_id: &str, filepath: &str) -> bool {
+    let rule_id = rule_id.to_lowercase();
+    let filepath = filepath.replace('\\', "/").to_lowercase();
+    let filename = filepath.rsplit('/').next().unwrap_or_default();
+    patterns.iter().any(|pattern| {
+        (!rule_id.is_empty() && pattern.matches(&rule_id))
+            || pattern.matches(filename)
+            || pattern.matches(&filepath)
+    })
+}
+
+/// ルールのtagsに記載されたタグの一覧を返す
+pub fn get_rule_tags(yaml_doc: &Yaml) -> Vec<&str> {
+    yaml_doc["tags"]
+        .as_vec()
+        .map(|tags| tags.iter().filter_map(|tag| tag.as_str()).collect())
+        .unwrap_or_default()
+}
+
+/// --mitreで指定されたMITRE ATT&CKのタクティクス名またはテクニックIDのいずれかが、ルールのtagsに含まれるかを判定する。
+/// テクニックIDを指定した場合は、そのサブテクニック(attack.t1059.001など)のタグを持つルールも一致とする
+pub fn is_match_mitre_tags(rule_tags: &[&str], targets: &[String]) -> bool {
+    let normalize = |s: &str| s.trim().to_lowercase().replace(['-', ' '], "_");
+    targets.iter().any(|target| {
+        let target = normalize(target);
+        let target = target.strip_prefix("attack.").unwrap_or(&target);
+        !target.is_empty()
+            && rule_tags.iter().any(|tag| {
+                normalize(tag).strip_prefix("attack.").is_some_and(|tag| {
+                    tag == target
+                        || tag
+                            .strip_prefix(target)
+                            .is_some_and(|sub| sub.starts_with('.'))
+                })
+            })
+    })
 }
 
 impl ParseYaml {
@@ -33,6 +172,22 @@ impl ParseYaml {
         } else {
             &None
         };
+        let rule_cache = stored_static
+            .output_option
+            .as_ref()
+            .and_then(|output_option| output_option.rule_cache.as_ref())
+            .map(|path| RuleCache::load(path));
+        let (enable_rule, exclude_rule) =
+            if let Some(output_option) = stored_static.output_option.as_ref() {
+                // --debug-ruleが指定された場合は、指定されたIDのルールのみを読み込む
+                let enable_rule = match &output_option.debug_rule {
+                    Some(rule_id) => Some(vec![rule_id.to_owned()]),
+                    None => output_option.enable_rule.clone(),
+                };
+                (enable_rule, output_option.exclude_rule.clone())
+            } else {
+                (None, None)
+            };
         ParseYaml {
             files: Vec::new(),
             rulecounter: HashMap::new(),
@@ -42,6 +197,7 @@ impl ParseYaml {
                 ("unsupported".into(), 0_u128),
             ]),
             errorrule_count: 0,
+            errorrule_msgs: Vec::new(),
             exclude_status: configs::convert_option_vecs_to_hs(exclude_status_vec.as_ref()),
             level_map: HashMap::from([
                 ("INFORMATIONAL".to_owned(), 1),
@@ -50,23 +206,97 @@ impl ParseYaml {
                 ("HIGH".to_owned(), 4),
                 ("CRITICAL".to_owned(), 5),
             ]),
-            loaded_rule_ids: HashSet::new(),
+            loaded_rule_ids: HashMap::new(),
+            rule_root_index: 0,
+            loaded_rule_hashes: HashMap::new(),
+            enable_rule_patterns: create_rule_patterns(enable_rule.as_ref()),
+            exclude_rule_patterns: create_rule_patterns(exclude_rule.as_ref()),
+            rule_cache,
+            mitre_match_cnt: 0,
         }
     }
 
+    /// 読み込みに失敗したルールファイルのエラーを記録する
+    fn add_errorrule(
+        &mut self,
+        filepath: &Path,
+        errmsg: String,
+        stored_static: &StoredStatic,
+    ) -> io::Result<()> {
+        if stored_static.verbose_flag {
+            AlertMessage::warn(&errmsg)?;
+        }
+        if !stored_static.quiet_errors_flag {
+            ERROR_LOG_STACK
+                .lock()
+                .unwrap()
+                .push(format!("[WARN] {errmsg}"));
+        }
+        self.errorrule_count += 1;
+        self.errorrule_msgs
+            .push((filepath.display().to_string(), errmsg));
+        Ok(())
+    }
+
     pub fn read_file(path: PathBuf) -> Result<String, String> {
-        let mut file_content = String::new();
+        decode_yaml_bytes(&Self::read_file_bytes(&path)?)
+    }
+
+    fn read_file_bytes(path: &Path) -> Result<Vec<u8>, String> {
+        let mut file_content = vec![];
 
         let mut fr = fs::File::open(path)
             .map(BufReader::new)
             .map_err(|e| e.to_string())?;
 
-        fr.read_to_string(&mut file_content)
+        fr.read_to_end(&mut file_content)
             .map_err(|e| e.to_string())?;
 
         Ok(file_content)
     }
 
+    /// ルールファイルを読み込んでYAMLとしてパースする。
+    /// --rule-cacheが指定されている場合、更新日時とファイルサイズがキャッシュと一致するファイルは読み込みとパースを省略する
+    fn load_rule_file(&mut self, path: &Path) -> Result<Vec<Yaml>, String> {
+        let filepath = format!("{}", path.display());
+        let file_key = self
+            .rule_cache
+            .as_ref()
+            .and_then(|_| RuleCache::get_file_key(path));
+        if let (Some(rule_cache), Some(file_key)) = (self.rule_cache.as_mut(), file_key) {
+            if let Some(yaml_contents) = rule_cache.get(&filepath, file_key) {
+                return Ok(yaml_contents);
+            }
+        }
+
+        let read_err = |e: String| format!("fail to read file: {}\n{} ", path.display(), e);
+        let file_content = Self::read_file_bytes(path).map_err(read_err)?;
+        let read_content = decode_yaml_bytes(&file_content).map_err(read_err)?;
+        let yaml_contents = YamlLoader::load_from_str(&read_content)
+            .map_err(|e| format!("Failed to parse yml: {}\n{} ", path.display(), e))?;
+        if let (Some(rule_cache), Some(file_key)) = (self.rule_cache.as_mut(), file_key) {
+            rule_cache.insert(&filepath, file_key, &yaml_contents);
+        }
+        Ok(yaml_contents)
+    }
+
+    /// -rで指定された複数のルールフォルダまたはファイルを、指定された順に読み込む。
+    /// 同じIDのルールが複数のフォルダにある場合は、先に指定したフォルダのルールを優先する
+    pub fn read_dirs(
+        &mut self,
+        paths: &[PathBuf],
+        min_level: &str,
+        target_level: &str,
+        exclude_ids: &RuleExclude,
+        stored_static: &StoredStatic,
+    ) -> io::Result<String> {
+        for (i, path) in paths.iter().enumerate() {
+            self.rule_root_index = i;
+            self.read_dir(path, min_level, target_level, exclude_ids, stored_static)?;
+        }
+        io::Result::Ok(String::default())
+    }
+
     pub fn read_dir<P: AsRef<Path>>(
         &mut self,
         path: P,
@@ -104,69 +334,32 @@ impl ParseYaml {
         }
         let mut yaml_docs = vec![];
         if metadata.unwrap().file_type().is_file() {
-            // 拡張子がymlでないファイルは無視
-            if path
-                .as_ref()
-                .to_path_buf()
-                .extension()
-                .unwrap_or_else(|| OsStr::new(""))
-                != "yml"
-            {
+            // 拡張子がyml/yamlでないファイルは無視
+            if !is_rule_file(path.as_ref()) {
                 return io::Result::Ok(String::default());
             }
 
-            // 個別のファイルの読み込みは即終了としない。
-            let read_content = Self::read_file(path.as_ref().to_path_buf());
-            if read_content.is_err() {
-                let errmsg = format!(
-                    "fail to read file: {}\n{} ",
-                    path.as_ref().to_path_buf().display(),
-                    read_content.unwrap_err()
-                );
-                if stored_static.verbose_flag {
-                    AlertMessage::warn(&errmsg)?;
-                }
-                if !stored_static.quiet_errors_flag {
-                    ERROR_LOG_STACK
-                        .lock()
-                        .unwrap()
-                        .push(format!("[WARN] {errmsg}"));
-                }
-                self.errorrule_count += 1;
+            // 個別のファイルの読み込みやパースの失敗は即終了としない。
+            let yaml_contents = self.load_rule_file(path.as_ref());
+            if let Err(errmsg) = yaml_contents {
+                self.add_errorrule(path.as_ref(), errmsg, stored_static)?;
                 return io::Result::Ok(String::default());
             }
 
-            // ここも個別のファイルの読み込みは即終了としない。
-            let yaml_contents = YamlLoader::load_from_str(&read_content.unwrap());
-            if yaml_contents.is_err() {
-                let errmsg = format!(
-                    "Failed to parse yml: {}\n{} ",
-                    path.as_ref().to_path_buf().display(),
-                    yaml_contents.unwrap_err()
-                );
-                if stored_static.verbose_flag {
-                    AlertMessage::warn(&errmsg)?;
-                }
-                if !stored_static.quiet_errors_flag {
-                    ERROR_LOG_STACK
-                        .lock()
-                        .unwrap()
-                        .push(format!("[WARN] {errmsg}"));
-                }
-                self.errorrule_count += 1;
-                return io::Result::Ok(String::default());
-            }
-
-            yaml_docs.extend(yaml_contents.unwrap().into_iter().map(|yaml_content| {
-                let filepath = format!("{}", path.as_ref().to_path_buf().display());
-                (filepath, yaml_content)
-            }));
+            yaml_docs.extend(split_rule_docs(
+                format!("{}", path.as_ref().to_path_buf().display()),
+                yaml_contents.unwrap(),
+            ));
         } else {
             let mut entries = fs::read_dir(path)?;
             yaml_docs = entries.try_fold(vec![], |mut ret, entry| {
                 let entry = entry?;
                 // フォルダは再帰的に呼び出す。
                 if entry.file_type()?.is_dir() {
+                    // .gitなどの隠しフォルダは無視
+                    if entry.file_name().to_string_lossy().starts_with('.') {
+                        return io::Result::Ok(ret);
+                    }
                     self.read_dir(
                         entry.path(),
                         min_level,
@@ -181,9 +374,9 @@ impl ParseYaml {
                     return io::Result::Ok(ret);
                 }
 
-                // 拡張子がymlでないファイルは無視
+                // 拡張子がyml/yamlでないファイルは無視
                 let path = entry.path();
-                if path.extension().unwrap_or_else(|| OsStr::new("")) != "yml" {
+                if !is_rule_file(&path) {
                     return io::Result::Ok(ret);
                 }
 
@@ -202,58 +395,27 @@ impl ParseYaml {
                     return io::Result::Ok(ret);
                 }
 
-                // 個別のファイルの読み込みは即終了としない。
-                let read_content = Self::read_file(path);
-                if read_content.is_err() {
-                    let errmsg = format!(
-                        "fail to read file: {}\n{} ",
-                        entry.path().display(),
-                        read_content.unwrap_err()
-                    );
-                    if stored_static.verbose_flag {
-                        AlertMessage::warn(&errmsg)?;
-                    }
-                    if !stored_static.quiet_errors_flag {
-                        ERROR_LOG_STACK
-                            .lock()
-                            .unwrap()
-                            .push(format!("[WARN] {errmsg}"));
-                    }
-                    self.errorrule_count += 1;
-                    return io::Result::Ok(ret);
-                }
-
-                // ここも個別のファイルの読み込みは即終了としない。
-                let yaml_contents = YamlLoader::load_from_str(&read_content.unwrap());
-                if yaml_contents.is_err() {
-                    let errmsg = format!(
-                        "Failed to parse yml: {}\n{} ",
-                        entry.path().display(),
-                        yaml_contents.unwrap_err()
-                    );
-                    if stored_static.verbose_flag {
-                        AlertMessage::warn(&errmsg)?;
-                    }
-                    if !stored_static.quiet_errors_flag {
-                        ERROR_LOG_STACK
-                            .lock()
-                            .unwrap()
-                            .push(format!("[WARN] {errmsg}"));
-                    }
-                    self.errorrule_count += 1;
+                // 個別のファイルの読み込みやパースの失敗は即終了としない。
+                let yaml_contents = self.load_rule_file(&path);
+                if let Err(errmsg) = yaml_contents {
+                    self.add_errorrule(&path, errmsg, stored_static)?;
                     return io::Result::Ok(ret);
                 }
 
-                let yaml_contents = yaml_contents.unwrap().into_iter().map(|yaml_content| {
-                    let filepath = format!("{}", entry.path().display());
-                    (filepath, yaml_content)
-                });
-                ret.extend(yaml_contents);
+                ret.extend(split_rule_docs(
+                    format!("{}", entry.path().display()),
+                    yaml_contents.unwrap(),
+                ));
                 io::Result::Ok(ret)
             })?;
         }
         let exist_output_opt = stored_static.output_option.is_some();
-        let files = yaml_docs.into_iter().filter_map(|(filepath, yaml_doc)| {
+        let keep_duplicate_rules = stored_static
+            .output_option
+            .as_ref()
+            .is_some_and(|o| o.keep_duplicate_rules);
+        let files = yaml_docs.into_iter().filter_map(|(filepath, doc_index, yaml_doc)| {
+            let rulepath = format_rule_path(&filepath, doc_index);
             //除外されたルールは無視する
             let rule_id = &yaml_doc["id"].as_str();
             if rule_id.is_some() {
@@ -290,6 +452,17 @@ impl ParseYaml {
                 }
             }
 
+            // enable-rule optionで指定されたルール以外と、exclude-rule optionで指定されたルールは除外する
+            let rule_id_str = rule_id.unwrap_or_default();
+            if (!self.enable_rule_patterns.is_empty()
+                && !is_match_rule_patterns(&self.enable_rule_patterns, rule_id_str, &filepath))
+                || is_match_rule_patterns(&self.exclude_rule_patterns, rule_id_str, &filepath)
+            {
+                let entry = self.rule_load_cnt.entry("excluded".into()).or_insert(0);
+                *entry += 1;
+                return Option::None;
+            }
+
             let mut up_rule_status_cnt = |status: &str| {
                 let status_cnt = self.rule_status_cnt.entry(status.into()).or_insert(0);
                 *status_cnt += 1;
@@ -305,7 +478,24 @@ impl ParseYaml {
                 .as_str()
                 .unwrap_or("informational")
                 .to_uppercase();
-            let doc_level_num = self.level_map.get(doc_level).unwrap_or(&1);
+            let doc_level_num = self.level_map.get(doc_level).unwrap_or_else(|| {
+                // levelが不正な値の場合はinformationalとして扱い、警告を出力する
+                let errmsg = format!(
+                    "Invalid level is specified. The rule is loaded as informational. (FilePath : {}, Level : {})",
+                    rulepath,
+                    yaml_doc["level"].as_str().unwrap_or("-")
+                );
+                if stored_static.verbose_flag {
+                    AlertMessage::warn(&errmsg).ok();
+                }
+                if !stored_static.quiet_errors_flag {
+                    ERROR_LOG_STACK
+                        .lock()
+                        .unwrap()
+                        .push(format!("[WARN] {errmsg}"));
+                }
+                &1
+            });
             let args_level_num = self.level_map.get(min_level).unwrap_or(&1);
             let target_level_num = self.level_map.get(target_level).unwrap_or(&0);
             if doc_level_num < args_level_num
@@ -443,6 +633,69 @@ impl ParseYaml {
                 }
             }
 
+            // mitre optionで指定されたタクティクス、テクニックのいずれかのタグを持たないルールは除外する
+            if let Some(mitre_targets) = stored_static
+                .output_option
+                .as_ref()
+                .and_then(|opt| opt.mitre.as_ref())
+            {
+                if !is_match_mitre_tags(&get_rule_tags(&yaml_doc), mitre_targets) {
+                    up_rule_load_cnt("excluded");
+                    return Option::None;
+                }
+                self.mitre_match_cnt += 1;
+            }
+
+            // 同じidを持つルールが-rで先に指定した別のフォルダから既に読み込まれている場合は、先に指定したフォルダのルールを優先して読み込まない
+            if let Some(id) = rule_id {
+                if let Some((_, loaded_filepath)) = self
+                    .loaded_rule_ids
+                    .get(*id)
+                    .filter(|(loaded_root_index, _)| *loaded_root_index != self.rule_root_index)
+                {
+                    let errmsg = format!(
+                        "Duplicate rule id is found in another rule directory. The rule in the directory specified first is used. (RuleID : {id}, FilePath : {rulepath}, Loaded FilePath : {loaded_filepath})"
+                    );
+                    if stored_static.verbose_flag {
+                        AlertMessage::warn(&errmsg).ok();
+                    }
+                    if !stored_static.quiet_errors_flag {
+                        ERROR_LOG_STACK
+                            .lock()
+                            .unwrap()
+                            .push(format!("[WARN] {errmsg}"));
+                    }
+                    up_rule_load_cnt("duplicate");
+                    return Option::None;
+                }
+            }
+
+            // 複数のルールフォルダをまとめた場合などで、内容が同じルールが別のパスから既に読み込まれている場合は、
+            // 同じ検知結果が重複して出力されないように読み込まない
+            let mut hasher = DefaultHasher::new();
+            yaml_doc.hash(&mut hasher);
+            let rule_hash = hasher.finish();
+            if let Some(loaded_filepath) = self.loaded_rule_hashes.get(&rule_hash) {
+                let errmsg = format!(
+                    "Duplicate rule is found. (FilePath : {rulepath}, Loaded FilePath : {loaded_filepath})"
+                );
+                if stored_static.verbose_flag {
+                    AlertMessage::warn(&errmsg).ok();
+                }
+                if !stored_static.quiet_errors_flag {
+                    ERROR_LOG_STACK
+                        .lock()
+                        .unwrap()
+                        .push(format!("[WARN] {errmsg}"));
+                }
+                if !keep_duplicate_rules {
+                    up_rule_load_cnt("duplicate");
+                    return Option::None;
+                }
+            } else {
+                self.loaded_rule_hashes.insert(rule_hash, rulepath.clone());
+            }
+
             self.rulecounter.insert(
                 yaml_doc["ruletype"].as_str().unwrap_or("Other").into(),
                 self.rulecounter
@@ -453,11 +706,34 @@ impl ParseYaml {
 
             up_rule_status_cnt(status.unwrap_or("undefined"));
 
+            // 同じidを持つルールが既に読み込まれている場合は、両方のファイルパスを含めて警告を出力する
+            if let Some(id) = rule_id {
+                if let Some((_, loaded_filepath)) = self.loaded_rule_ids.get(*id) {
+                    let errmsg = format!(
+                        "Duplicate rule id is found. (RuleID : {id}, FilePath : {rulepath}, Loaded FilePath : {loaded_filepath})"
+                    );
+                    if stored_static.verbose_flag {
+                        AlertMessage::warn(&errmsg).ok();
+                    }
+                    if !stored_static.quiet_errors_flag {
+                        ERROR_LOG_STACK
+                            .lock()
+                            .unwrap()
+                            .push(format!("[WARN] {errmsg}"));
+                    }
+                } else {
+                    self.loaded_rule_ids.insert(
+                        CompactString::from(*id),
+                        (self.rule_root_index, rulepath.clone()),
+                    );
+                }
+            }
+
             if stored_static.verbose_flag {
-                println!("Loaded rule: {filepath}");
+                println!("Loaded rule: {rulepath}");
             }
 
-            Option::Some((filepath, yaml_doc))
+            Option::Some((filepath, doc_index, yaml_doc))
         });
         self.files.extend(files);
         io::Result::Ok(String::default())
@@ -480,14 +756,8 @@ pub fn count_rules<P: AsRef<Path>>(
     }
     let mut yaml_docs = vec![];
     if metadata.unwrap().file_type().is_file() {
-        // 拡張子がymlでないファイルは無視
-        if path
-            .as_ref()
-            .to_path_buf()
-            .extension()
-            .unwrap_or_else(|| OsStr::new(""))
-            != "yml"
-        {
+        // 拡張子がyml/yamlでないファイルは無視
+        if !is_rule_file(path.as_ref()) {
             return HashMap::default();
         }
 
@@ -503,10 +773,10 @@ pub fn count_rules<P: AsRef<Path>>(
             return HashMap::default();
         }
 
-        yaml_docs.extend(yaml_contents.unwrap().into_iter().map(|yaml_content| {
-            let filepath = format!("{}", path.as_ref().to_path_buf().display());
-            (filepath, yaml_content)
-        }));
+        yaml_docs.extend(split_rule_docs(
+            format!("{}", path.as_ref().to_path_buf().display()),
+            yaml_contents.unwrap(),
+        ));
     } else {
         let entries = fs::read_dir(path);
         if entries.is_err() {
@@ -518,6 +788,10 @@ pub fn count_rules<P: AsRef<Path>>(
                 let entry = entry?;
                 // フォルダは再帰的に呼び出す。
                 if entry.file_type()?.is_dir() {
+                    // .gitなどの隠しフォルダは無視
+                    if entry.file_name().to_string_lossy().starts_with('.') {
+                        return io::Result::Ok(ret);
+                    }
                     count_rules(entry.path(), exclude_ids, stored_static, result_container);
                     return io::Result::Ok(ret);
                 }
@@ -526,9 +800,9 @@ pub fn count_rules<P: AsRef<Path>>(
                     return io::Result::Ok(ret);
                 }
 
-                // 拡張子がymlでないファイルは無視
+                // 拡張子がyml/yamlでないファイルは無視
                 let path = entry.path();
-                if path.extension().unwrap_or_else(|| OsStr::new("")) != "yml" {
+                if !is_rule_file(&path) {
                     return io::Result::Ok(ret);
                 }
 
@@ -573,99 +847,74 @@ pub fn count_rules<P: AsRef<Path>>(
                     return io::Result::Ok(ret);
                 }
 
-                let yaml_contents = yaml_contents.unwrap().into_iter().map(|yaml_content| {
-                    let filepath = format!("{}", entry.path().display());
-                    (filepath, yaml_content)
-                });
-                ret.extend(yaml_contents);
+                ret.extend(split_rule_docs(
+                    format!("{}", entry.path().display()),
+                    yaml_contents.unwrap(),
+                ));
                 io::Result::Ok(ret)
             })
             .unwrap_or_default();
     }
-    yaml_docs.into_iter().for_each(|(_filepath, yaml_doc)| {
-        //除外されたルールは無視する
-        let empty = vec![];
-        let rule_id = &yaml_doc["id"].as_str();
-        let rule_tags_vec = yaml_doc["tags"].as_vec().unwrap_or(&empty);
-        let included_target_tag_vec = {
-            let target_wizard_tags = [
-                "detection.emerging_threats",
-                "detection.threat_hunting",
-                "sysmon",
-            ];
-            rule_tags_vec
-                .iter()
-                .filter(|x| target_wizard_tags.contains(&x.as_str().unwrap_or_default()))
-                .filter_map(|s| s.as_str())
-                .collect_vec()
-        };
-        if rule_id.is_some() {
-            if let Some(v) = exclude_ids
-                .no_use_rule
-                .get(&rule_id.unwrap_or(&String::default()).to_string())
-            {
-                let entry_key = if utils::contains_str(v, "exclude_rule") {
-                    "excluded"
-                } else {
-                    "noisy"
-                };
-                // テスト用のルール(ID:000...0)の場合はexcluded ruleのカウントから除外するようにする
-                if v != "00000000-0000-0000-0000-000000000000" {
-                    let counter = result_container
-                        .entry(entry_key.into())
-                        .or_insert(HashMap::new());
-                    *counter
-                        .entry(
-                            yaml_doc["level"]
-                                .as_str()
-                                .unwrap_or("informational")
-                                .to_uppercase()
-                                .into(),
-                        )
-                        .or_insert(HashMap::new())
-                        .entry(
-                            yaml_doc["status"]
-                                .as_str()
-                                .unwrap_or("undefined")
-                                .to_lowercase()
-                                .into(),
-                        )
-                        .or_insert(0) += 1;
+    yaml_docs
+        .into_iter()
+        .for_each(|(_filepath, _doc_index, yaml_doc)| {
+            //除外されたルールは無視する
+            let empty = vec![];
+            let rule_id = &yaml_doc["id"].as_str();
+            let rule_tags_vec = yaml_doc["tags"].as_vec().unwrap_or(&empty);
+            let included_target_tag_vec = {
+                let target_wizard_tags = [
+                    "detection.emerging_threats",
+                    "detection.threat_hunting",
+                    "sysmon",
+                ];
+                rule_tags_vec
+                    .iter()
+                    .filter(|x| target_wizard_tags.contains(&x.as_str().unwrap_or_default()))
+                    .filter_map(|s| s.as_str())
+                    .collect_vec()
+            };
+            if rule_id.is_some() {
+                if let Some(v) = exclude_ids
+                    .no_use_rule
+                    .get(&rule_id.unwrap_or(&String::default()).to_string())
+                {
+                    let entry_key = if utils::contains_str(v, "exclude_rule") {
+                        "excluded"
+                    } else {
+                        "noisy"
+                    };
+                    // テスト用のルール(ID:000...0)の場合はexcluded ruleのカウントから除外するようにする
+                    if v != "00000000-0000-0000-0000-000000000000" {
+                        let counter = result_container
+                            .entry(entry_key.into())
+                            .or_insert(HashMap::new());
+                        *counter
+                            .entry(
+                                yaml_doc["level"]
+                                    .as_str()
+                                    .unwrap_or("informational")
+                                    .to_uppercase()
+                                    .into(),
+                            )
+                            .or_insert(HashMap::new())
+                            .entry(
+                                yaml_doc["status"]
+                                    .as_str()
+                                    .unwrap_or("undefined")
+                                    .to_lowercase()
+                                    .into(),
+                            )
+                            .or_insert(0) += 1;
+                    }
+                    return;
                 }
-                return;
             }
-        }
 
-        if let Some(s) = yaml_doc["status"].as_str() {
-            // wizard用の初期カウンティングではstatusとlevelの内容を確認したうえで以降の処理は行わないようにする
-            let counter = result_container.entry(s.into()).or_insert(HashMap::new());
-            if included_target_tag_vec.is_empty() {
-                *counter
-                    .entry(
-                        yaml_doc["level"]
-                            .as_str()
-                            .unwrap_or("informational")
-                            .to_uppercase()
-                            .into(),
-                    )
-                    .or_insert(HashMap::new())
-                    .entry("other".into())
-                    .or_insert(0) += 1;
-            } else {
-                if included_target_tag_vec.len() > 1 {
-                    *counter
-                        .entry(
-                            yaml_doc["level"]
-                                .as_str()
-                                .unwrap_or("informational")
-                                .to_uppercase()
-                                .into(),
-                        )
-                        .or_insert(HashMap::new())
-                        .entry("duplicated".into())
-                        .or_insert(0) -= (included_target_tag_vec.len() - 1) as i128;
-                }
-                for tag in included_target_tag_vec {
+            if let Some(s) = yaml_doc["status"].as_str() {
+                // wizard用の初期カウンティングではstatusとlevelの内容を確認したうえで以降の処理は行わないようにする
+                let counter = result_container.entry(s.into()).or_insert(HashMap::new());
+                if included_target_tag_vec.is_empty() {
                     *counter
                         .entry(
                             yaml_doc["level"]
@@ -675,12 +924,38 @@ pub fn count_rules<P: AsRef<Path>>(
                                 .into(),
                         )
                         .or_insert(HashMap::new())
-                        .entry(tag.into())
+                        .entry("other".into())
                         .or_insert(0) += 1;
+                } else {
+                    if included_target_tag_vec.len() > 1 {
+                        *counter
+                            .entry(
+                                yaml_doc["level"]
+                                    .as_str()
+                                    .unwrap_or("informational")
+                                    .to_uppercase()
+                                    .into(),
+                            )
+                            .or_insert(HashMap::new())
+                            .entry("duplicated".into())
+                            .or_insert(0) -= (included_target_tag_vec.len() - 1) as i128;
+                    }
+                    for tag in included_target_tag_vec {
+                        *counter
+                            .entry(
+                                yaml_doc["level"]
+                                    .as_str()
+                                    .unwrap_or("informational")
+                                    .to_uppercase()
+                                    .into(),
+                            )
+                            .or_insert(HashMap::new())
+                            .entry(tag.into())
+                            .or_insert(0) += 1;
+                    }
                 }
             }
-        }
-    });
+        });
     result_container.to_owned()
 }
 
@@ -695,14 +970,16 @@ mod tests {
     use crate::detections::configs::InputOption;
     use crate::detections::configs::OutputOption;
     use crate::detections::configs::StoredStatic;
+    use crate::detections::message::ERROR_LOG_STACK;
     use crate::filter;
     use crate::yaml;
     use crate::yaml::ParseYaml;
     use crate::yaml::RuleExclude;
+    use clap::Parser;
     use compact_str::CompactString;
     use hashbrown::HashMap;
     use hashbrown::HashSet;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
     use yaml_rust::YamlLoader;
 
     fn create_dummy_stored_static() -> StoredStatic {
@@ -713,6 +990,7 @@ mod tests {
                         directory: None,
                         filepath: None,
                         live_analysis: false,
+                        live_channel: None,
                         recover_records: false,
                         timeline_offset: None,
                     },
@@ -733,7 +1011,7 @@ mod tests {
                     us_time: false,
                     utc: false,
                     visualize_timeline: false,
-                    rules: Path::new("./rules").to_path_buf(),
+                    rules: vec![Path::new("./rules").to_path_buf()],
                     html_report: None,
                     no_summary: false,
                     common_options: CommonOptions {
@@ -746,10 +1024,13 @@ mod tests {
                         thread_number: None,
                         quiet_errors: false,
                         config: Path::new("./rules/config").to_path_buf(),
-                        verbose: false,
+                        verbose: 0,
+                        no_progress: false,
                         json_input: false,
                         include_computer: None,
                         exclude_computer: None,
+                        strict: false,
+                        alias_config: None,
                     },
                     enable_unsupported_rules: false,
                     clobber: false,
@@ -758,6 +1039,22 @@ mod tests {
                     exclude_tag: None,
                     include_category: None,
                     exclude_category: None,
+                    stats: false,
+                    timezone: None,
+                    default_timestamp: None,
+                    keep_duplicate_rules: false,
+                    matched_fields: false,
+                    rule_cache: None,
+                    list_rules: false,
+                    show_unused_rules: false,
+                    mitre: None,
+                    debug_rule: None,
+                    suppress: None,
+                    limit: None,
+                    stream: false,
+                    dry_run: false,
+                    enable_rule: None,
+                    exclude_rule: None,
                     include_eid: None,
                     exclude_eid: None,
                     no_field: false,
@@ -810,6 +1107,35 @@ mod tests {
         assert_ne!(yaml.files.len(), 0);
     }
 
+    #[test]
+    fn test_read_nested_dir_yaml() {
+        // サブフォルダのyml/yamlファイルを再帰的に読み込み、隠しフォルダとyml/yaml以外のファイルは無視することを確認
+        let dummy_stored_static = create_dummy_stored_static();
+        let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+        yaml.read_dir(
+            "test_files/rules/nested_yaml",
+            "",
+            "",
+            &RuleExclude::new(),
+            &dummy_stored_static,
+        )
+        .unwrap();
+        let mut loaded_paths: Vec<String> = yaml
+            .files
+            .iter()
+            .map(|(path, _, _)| path.replace('\\', "/"))
+            .collect();
+        loaded_paths.sort();
+        assert_eq!(
+            loaded_paths,
+            vec![
+                "test_files/rules/nested_yaml/top.yml",
+                "test_files/rules/nested_yaml/windows/builtin/security/security.yaml",
+                "test_files/rules/nested_yaml/windows/process_creation/proc_creation.yml",
+            ]
+        );
+    }
+
     #[test]
     fn test_read_yaml() {
         let path = Path::new("test_files/rules/yaml/1.yml");
@@ -851,6 +1177,339 @@ mod tests {
         assert_eq!(yaml.files.len(), 5);
     }
 
+    #[test]
+    /// 不正なlevelのルールはinformationalとして扱われ、警告が出力されることを確認する
+    fn test_invalid_level_read_yaml() {
+        let path = Path::new("test_files/rules/invalid_level_yaml");
+        let dummy_stored_static = create_dummy_stored_static();
+        let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+        yaml.read_dir(
+            path,
+            "INFORMATIONAL",
+            "",
+            &filter::exclude_ids(&dummy_stored_static),
+            &dummy_stored_static,
+        )
+        .unwrap();
+        assert_eq!(yaml.files.len(), 1);
+        assert!(ERROR_LOG_STACK
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|msg| msg.starts_with("[WARN] Invalid level is specified.")
+                && msg.contains("Level : hgih")));
+
+        let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+        yaml.read_dir(
+            path,
+            "LOW",
+            "",
+            &filter::exclude_ids(&dummy_stored_static),
+            &dummy_stored_static,
+        )
+        .unwrap();
+        assert_eq!(yaml.files.len(), 0);
+    }
+
+    #[test]
+    /// 同じidを持つルールが複数存在する場合は両方読み込んだうえで警告が出力されることを確認する
+    fn test_duplicate_rule_id_read_yaml() {
+        let path = Path::new("test_files/rules/duplicate_id_yaml");
+        let dummy_stored_static = create_dummy_stored_static();
+        let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+        yaml.read_dir(
+            path,
+            "",
+            "",
+            &filter::exclude_ids(&dummy_stored_static),
+            &dummy_stored_static,
+        )
+        .unwrap();
+        assert_eq!(yaml.files.len(), 2);
+        assert_eq!(yaml.loaded_rule_ids.len(), 1);
+        assert!(ERROR_LOG_STACK.lock().unwrap().iter().any(|msg| msg
+            .starts_with("[WARN] Duplicate rule id is found.")
+            && msg.contains("RuleID : 11111111-2222-3333-4444-555555555555")
+            && msg.contains("rule1.yml")
+            && msg.contains("rule2.yml")));
+    }
+
+    #[test]
+    /// ---で区切られた複数のルールが記載されたファイルから、空のドキュメントを除いたすべてのルールが読み込まれることを確認する
+    fn test_multi_doc_read_yaml() {
+        let path = Path::new("test_files/rules/multi_doc_yaml");
+        let dummy_stored_static = create_dummy_stored_static();
+        let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+        yaml.read_dir(
+            path,
+            "",
+            "",
+            &filter::exclude_ids(&dummy_stored_static),
+            &dummy_stored_static,
+        )
+        .unwrap();
+        assert_eq!(yaml.errorrule_count, 0);
+        let mut rules: Vec<(Option<usize>, &str)> = yaml
+            .files
+            .iter()
+            .map(|(_, doc_index, rule)| (*doc_index, rule["title"].as_str().unwrap()))
+            .collect();
+        rules.sort();
+        assert_eq!(
+            rules,
+            vec![
+                (Some(0), "Multi document rule 1"),
+                (Some(1), "Multi document rule 2"),
+                (Some(2), "Multi document rule 3")
+            ]
+        );
+        assert_eq!(
+            yaml::format_rule_path("rules.yml", Some(1)),
+            "rules.yml (Document: 2)"
+        );
+        assert_eq!(yaml::format_rule_path("rules.yml", None), "rules.yml");
+    }
+
+    #[test]
+    /// BOM付きUTF-8やUTF-16でエンコードされたルールファイルを読み込めることを確認する
+    fn test_encoding_read_yaml() {
+        let path = Path::new("test_files/rules/encoding_yaml");
+        let dummy_stored_static = create_dummy_stored_static();
+        let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+        yaml.read_dir(
+            path,
+            "",
+            "",
+            &filter::exclude_ids(&dummy_stored_static),
+            &dummy_stored_static,
+        )
+        .unwrap();
+        assert_eq!(yaml.errorrule_count, 0);
+        let mut titles: Vec<&str> = yaml
+            .files
+            .iter()
+            .map(|(_, _, rule)| rule["title"].as_str().unwrap())
+            .collect();
+        titles.sort();
+        assert_eq!(
+            titles,
+            vec![
+                "Encoding test rule 1",
+                "Encoding test rule 2",
+                "Encoding test rule 3",
+                "Encoding test rule 4"
+            ]
+        );
+        assert!(yaml
+            .files
+            .iter()
+            .all(|(_, _, rule)| rule["description"].as_str()
+                == Some("ルールファイルのエンコーディングのテスト")));
+    }
+
+    #[test]
+    /// デコードできないルールファイルはスキップせずにエラーとして記録されることを確認する
+    fn test_invalid_encoding_read_yaml() {
+        let dir = &std::env::temp_dir().join("hayabusa_test_invalid_encoding_read_yaml");
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(
+            dir.join("invalid_utf8.yml"),
+            b"title: \xff\xfeinvalid\n\x80",
+        )
+        .unwrap();
+        // 奇数バイトのUTF-16LE
+        std::fs::write(dir.join("invalid_utf16.yml"), b"\xff\xfet\x00i\x00t").unwrap();
+        let dummy_stored_static = create_dummy_stored_static();
+        let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+        let result = yaml.read_dir(
+            dir,
+            "",
+            "",
+            &filter::exclude_ids(&dummy_stored_static),
+            &dummy_stored_static,
+        );
+        std::fs::remove_dir_all(dir).ok();
+        result.unwrap();
+        assert_eq!(yaml.files.len(), 0);
+        assert_eq!(yaml.errorrule_count, 2);
+        assert!(yaml
+            .errorrule_msgs
+            .iter()
+            .any(|(_, msg)| msg.contains("Failed to decode as UTF-16LE")));
+    }
+
+    #[test]
+    /// -rで複数のフォルダを指定した場合はすべてのルールを読み込み、同じidのルールは先に指定したフォルダのルールを優先することを確認する
+    fn test_read_dirs_multiple_rule_dirs() {
+        let read_rules = |paths: &[&str]| {
+            let dummy_stored_static = create_dummy_stored_static();
+            let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+            let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+            yaml.read_dirs(
+                &paths,
+                "",
+                "",
+                &filter::exclude_ids(&dummy_stored_static),
+                &dummy_stored_static,
+            )
+            .unwrap();
+            yaml
+        };
+        let get_titles = |yaml: &yaml::ParseYaml| {
+            let mut titles: Vec<String> = yaml
+                .files
+                .iter()
+                .map(|(_, _, rule)| rule["title"].as_str().unwrap().to_string())
+                .collect();
+            titles.sort();
+            titles
+        };
+
+        let yaml = read_rules(&[
+            "test_files/rules/multi_dir/core",
+            "test_files/rules/multi_dir/internal",
+        ]);
+        assert_eq!(
+            get_titles(&yaml),
+            vec![
+                "Core logoff rule",
+                "Core logon rule",
+                "Internal special logon rule"
+            ]
+        );
+        assert_eq!(yaml.rule_load_cnt["duplicate"], 1);
+        // 読み込んだルールのパスには、読み込み元のフォルダが含まれる
+        assert!(yaml
+            .files
+            .iter()
+            .any(|(path, _, _)| path.contains("multi_dir/internal")
+                || path.contains("multi_dir\\internal")));
+
+        let yaml = read_rules(&[
+            "test_files/rules/multi_dir/internal",
+            "test_files/rules/multi_dir/core",
+        ]);
+        assert_eq!(
+            get_titles(&yaml),
+            vec![
+                "Core logoff rule",
+                "Internal logon rule",
+                "Internal special logon rule"
+            ]
+        );
+        assert!(ERROR_LOG_STACK.lock().unwrap().iter().any(|msg| msg
+            .starts_with("[WARN] Duplicate rule id is found in another rule directory.")
+            && msg.contains("RuleID : aaaaaaaa-0000-0000-0000-000000000001")));
+    }
+
+    #[test]
+    /// 内容が同じルールが複数のパスにある場合は1つだけ読み込み、keep-duplicate-rules optionを指定した場合はすべて読み込むことを確認する
+    fn test_duplicate_content_rule_read_yaml() {
+        let read_rules = |options: &[&str]| {
+            let mut args = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w"];
+            args.extend(options);
+            let stored_static =
+                StoredStatic::create_static_data(Some(Config::try_parse_from(args).unwrap()));
+            let mut yaml = yaml::ParseYaml::new(&stored_static);
+            yaml.read_dir(
+                Path::new("test_files/rules/duplicate_content_yaml"),
+                "",
+                "",
+                &filter::exclude_ids(&stored_static),
+                &stored_static,
+            )
+            .unwrap();
+            yaml
+        };
+
+        let yaml = read_rules(&[]);
+        assert_eq!(yaml.files.len(), 1);
+        assert_eq!(yaml.rule_load_cnt["duplicate"], 1);
+
+        let yaml = read_rules(&["--keep-duplicate-rules"]);
+        assert_eq!(yaml.files.len(), 2);
+        assert!(!yaml.rule_load_cnt.contains_key("duplicate"));
+        assert!(ERROR_LOG_STACK
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|msg| msg.starts_with("[WARN] Duplicate rule is found.")));
+    }
+
+    #[test]
+    /// enable-rule、exclude-rule optionで指定したルールID、ファイル名でルールが絞り込まれ、除外されたルールがカウントされることを確認する
+    fn test_enable_exclude_rule_read_yaml() {
+        let read_rules = |path: &str, options: &[&str]| {
+            let mut args = vec!["hayabusa", "csv-timeline", "-f", "test.evtx", "-w"];
+            args.extend(options);
+            let stored_static =
+                StoredStatic::create_static_data(Some(Config::try_parse_from(args).unwrap()));
+            let mut yaml = yaml::ParseYaml::new(&stored_static);
+            yaml.read_dir(
+                Path::new(path),
+                "",
+                "",
+                &filter::exclude_ids(&stored_static),
+                &stored_static,
+            )
+            .unwrap();
+            let mut filenames: Vec<String> = yaml
+                .files
+                .iter()
+                .map(|(filepath, _, _)| {
+                    Path::new(filepath)
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string()
+                })
+                .collect();
+            filenames.sort();
+            (filenames, yaml.rule_load_cnt["excluded"])
+        };
+
+        let (filenames, excluded_cnt) = read_rules(
+            "test_files/rules/level_yaml",
+            &["--enable-rule", "*high*,low.yml"],
+        );
+        assert_eq!(filenames, vec!["high.yml", "low.yml"]);
+        assert_eq!(excluded_cnt, 3);
+
+        // enable-ruleで指定したルールからexclude-ruleで指定したルールを除外する
+        let (filenames, excluded_cnt) = read_rules(
+            "test_files/rules/level_yaml",
+            &[
+                "--enable-rule",
+                "*",
+                "--exclude-rule",
+                "CRITICAL.yml,*/level_yaml/info.yml",
+            ],
+        );
+        assert_eq!(filenames, vec!["high.yml", "low.yml", "medium.yml"]);
+        assert_eq!(excluded_cnt, 2);
+
+        let (filenames, excluded_cnt) = read_rules(
+            "test_files/rules/duplicate_id_yaml",
+            &["--exclude-rule", "11111111-2222-3333-4444-555555555555"],
+        );
+        assert!(filenames.is_empty());
+        assert_eq!(excluded_cnt, 2);
+
+        // debug-ruleで指定したルールIDのルールのみ読み込まれる
+        let (filenames, excluded_cnt) = read_rules(
+            "test_files/rules/duplicate_id_yaml",
+            &["--debug-rule", "11111111-2222-3333-4444-555555555555"],
+        );
+        assert_eq!(filenames, vec!["rule1.yml", "rule2.yml"]);
+        assert_eq!(excluded_cnt, 0);
+        let (filenames, excluded_cnt) = read_rules(
+            "test_files/rules/level_yaml",
+            &["--debug-rule", "11111111-2222-3333-4444-555555555555"],
+        );
+        assert!(filenames.is_empty());
+        assert_eq!(excluded_cnt, 5);
+    }
+
     #[test]
     fn test_info_level_read_yaml() {
         let dummy_stored_static = create_dummy_stored_static();
@@ -1098,6 +1757,47 @@ mod tests {
         assert_eq!(yaml.files.len(), 3);
     }
 
+    #[test]
+    fn test_mitre_option() {
+        // attack.testのタグを持つルールのみが読み込まれ、attack.test2やattack.test3はサブテクニックとして扱われない
+        let path = Path::new("test_files/rules/level_yaml");
+        let mut dummy_stored_static = create_dummy_stored_static();
+        dummy_stored_static.output_option.as_mut().unwrap().mitre =
+            Some(vec!["Test".to_string(), "attack.test3".to_string()]);
+        let mut yaml = yaml::ParseYaml::new(&dummy_stored_static);
+        yaml.read_dir(
+            path,
+            "",
+            "",
+            &filter::exclude_ids(&dummy_stored_static),
+            &dummy_stored_static,
+        )
+        .unwrap();
+        assert_eq!(yaml.files.len(), 2);
+        assert_eq!(yaml.mitre_match_cnt, 2);
+    }
+
+    #[test]
+    fn test_is_match_mitre_tags() {
+        let rule_tags = [
+            "attack.execution",
+            "attack.t1059.001",
+            "attack.initial_access",
+        ];
+        let is_match = |targets: &[&str]| {
+            let targets: Vec<String> = targets.iter().map(|t| t.to_string()).collect();
+            yaml::is_match_mitre_tags(&rule_tags, &targets)
+        };
+        assert!(is_match(&["execution"]));
+        assert!(is_match(&["T1059"]));
+        assert!(is_match(&["t1059.001"]));
+        assert!(is_match(&["initial-access"]));
+        assert!(is_match(&["attack.persistence", "attack.t1059"]));
+        assert!(!is_match(&["t1059.003"]));
+        assert!(!is_match(&["t105"]));
+        assert!(!is_match(&["persistence", ""]));
+    }
+
     #[test]
     fn test_include_category_option_1opt() {
         let path = Path::new("test_files/rules/level_yaml");